@@ -154,6 +154,24 @@ impl<T: ResourcePath> Path<T> {
         }
     }
 
+    /// Convert values of matched dynamic segments to ASCII lowercase.
+    ///
+    /// Only ASCII letters are converted, non-ASCII characters and
+    /// percent-encoded sequences are preserved as is.
+    pub fn lowercase_segments(&mut self) {
+        let path = self.path.path();
+        for (_, item) in self.segments.iter_mut() {
+            let value = match item {
+                PathItem::Static(_) => continue,
+                PathItem::Segment(ref s) => s,
+                PathItem::IdxSegment(s, e) => &path[(*s as usize)..(*e as usize)],
+            };
+            if let Some(value) = lowercase(value) {
+                *item = PathItem::Segment(value);
+            }
+        }
+    }
+
     /// Return iterator to items in parameter container
     pub fn iter(&self) -> PathIter<'_, T> {
         PathIter {
@@ -170,6 +188,28 @@ impl<T: ResourcePath> Path<T> {
     }
 }
 
+/// Convert ASCII letters to lowercase, skipping percent-encoded sequences.
+///
+/// Returns `None` if value does not contain uppercase letters.
+fn lowercase(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut result: Option<Vec<u8>> = None;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let b = bytes[idx];
+        if b == b'%' {
+            idx += 3;
+            continue;
+        }
+        if b.is_ascii_uppercase() {
+            result.get_or_insert_with(|| bytes.to_vec())[idx] = b.to_ascii_lowercase();
+        }
+        idx += 1;
+    }
+    // only ascii bytes are changed, so value is valid utf-8
+    result.map(|v| String::from_utf8(v).unwrap())
+}
+
 /// Iterator to items in parameter container
 #[derive(Debug)]
 pub struct PathIter<'a, T> {
@@ -255,4 +295,29 @@ mod tests {
         p.segments.push(("k1", PathItem::IdxSegment(0, 2)));
         assert_eq!(p.get("k1").unwrap(), "te");
     }
+
+    #[test]
+    fn test_lowercase_segments() {
+        let mut p = Path::new("/User/%C3%A9Ab/ÄB".to_string());
+        p.segments.push(("id", PathItem::IdxSegment(1, 5)));
+        p.segments.push(("id", PathItem::IdxSegment(6, 14)));
+        p.segments.push(("name", PathItem::IdxSegment(15, 18)));
+        p.segments.push(("s", PathItem::Static("Static")));
+        p.segments
+            .push(("v", PathItem::Segment("lower".to_string())));
+        p.lowercase_segments();
+
+        let items: Vec<_> = p.iter().collect();
+        assert_eq!(
+            items,
+            vec![
+                ("id", "user"),
+                ("id", "%C3%A9ab"),
+                ("name", "Äb"),
+                ("s", "Static"),
+                ("v", "lower")
+            ]
+        );
+        assert_eq!(p.get_ref(), "/User/%C3%A9Ab/ÄB");
+    }
 }
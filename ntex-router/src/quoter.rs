@@ -40,9 +40,9 @@ pub(super) fn requote(val: &[u8]) -> Option<String> {
         if has_pct > 0 {
             data.extend(&pct[..has_pct]);
         }
-        // Unsafe: we get data from http::Uri, which does utf-8 checks already
-        // this code only decodes valid pct encoded values
-        Some(unsafe { String::from_utf8_unchecked(data) })
+        // decoded values could form invalid utf-8 sequences,
+        // keep original value in that case
+        String::from_utf8(data).ok()
     } else {
         None
     }
@@ -749,6 +749,31 @@ mod tests {
         assert_eq!(resource.get("id").unwrap(), "2345/sdg");
     }
 
+    #[test]
+    fn test_optional_tail() {
+        let re = ResourceDef::new("/files/{path}*");
+        let tree = Tree::new(&re, 1);
+
+        let mut resource = Path::new("/files");
+        assert_eq!(tree.find(&mut resource), Some(1));
+        assert_eq!(resource.get("path").unwrap(), "");
+
+        let mut resource = Path::new("/files/");
+        assert_eq!(tree.find(&mut resource), Some(1));
+        assert_eq!(resource.get("path").unwrap(), "");
+
+        let mut resource = Path::new("/files/a/b/c");
+        assert_eq!(tree.find(&mut resource), Some(1));
+        assert_eq!(resource.get("path").unwrap(), "a/b/c");
+
+        assert_eq!(tree.find(&mut Path::new("/filesx")), None);
+        assert_eq!(tree.find(&mut Path::new("/file")), None);
+
+        // tail with static prefix requires prefix
+        let tree = Tree::new(&ResourceDef::new("/user/-{id}*"), 1);
+        assert_eq!(tree.find(&mut Path::new("/user")), None);
+    }
+
     #[test]
     fn test_static_tail() {
         let re = ResourceDef::new("/*".to_string());
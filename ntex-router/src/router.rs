@@ -91,6 +91,50 @@ impl<T, U> Router<T, U> {
         }
     }
 
+    /// Recognize resource and validate matched path.
+    ///
+    /// `validate` is called with captured path segments after resource
+    /// and `check` match. If validation fails, search continues with
+    /// next matching resource.
+    pub fn recognize_validated<R, P, F, V>(
+        &self,
+        resource: &mut R,
+        check: F,
+        validate: V,
+    ) -> Option<(&T, ResourceId)>
+    where
+        F: Fn(&R, Option<&U>) -> bool,
+        V: Fn(&R, Option<&U>) -> bool,
+        R: Resource<P>,
+        P: ResourcePath,
+    {
+        let path = resource.resource_path();
+        let skip = path.skip;
+        let segments = path.segments.clone();
+        let mut rejected = Vec::new();
+
+        loop {
+            let checker = |idx: usize, res: &R| {
+                !rejected.contains(&idx) && check(res, self.resources[idx].2.as_ref())
+            };
+            let idx = if self.insensitive {
+                self.tree.find_checked_insensitive(resource, &checker)
+            } else {
+                self.tree.find_checked(resource, &checker)
+            }?;
+
+            let item = &self.resources[idx];
+            if validate(resource, item.2.as_ref()) {
+                return Some((&item.1, ResourceId(item.0.id())));
+            }
+
+            rejected.push(idx);
+            let path = resource.resource_path();
+            path.skip = skip;
+            path.segments = segments.clone();
+        }
+    }
+
     pub fn recognize_mut_checked<R, P, F>(
         &mut self,
         resource: &mut R,
@@ -311,6 +355,28 @@ mod tests {
         assert!(router.recognize_mut(&mut path).is_none());
     }
 
+    #[test]
+    fn test_recognizer_optional_tail() {
+        let mut router = Router::<usize>::build();
+        router.path("/files/index", 10);
+        router.path("/files/{path}*", 11);
+        let mut router = router.finish();
+
+        let mut path = Path::new("/files/index");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 10);
+
+        let mut path = Path::new("/files");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 11);
+        assert_eq!(path.get("path").unwrap(), "");
+
+        let mut path = Path::new("/files/a/b/c");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 11);
+        assert_eq!(path.get("path").unwrap(), "a/b/c");
+    }
+
     #[test]
     fn test_recognizer_with_path_skip() {
         let mut router = Router::<usize>::build();
@@ -419,6 +485,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_recognizer_validated() {
+        let mut router = Router::<usize, usize>::build();
+        router.path("/name/{val}", 10).2 = Some(0);
+        router.path("/name/{val}", 11).2 = Some(1);
+        router.path("/name/{tail}*", 12);
+        let router = router.finish();
+
+        let mut p = Path::new("/name/1");
+        let (h, _) = router
+            .recognize_validated(&mut p, |_, _| true, |_, _| true)
+            .unwrap();
+        assert_eq!(*h, 10);
+        assert_eq!(&p["val"], "1");
+
+        let mut p = Path::new("/name/test");
+        let (h, _) = router
+            .recognize_validated(
+                &mut p,
+                |_, _| true,
+                |p, v| v != Some(&0) || p["val"].parse::<usize>().is_ok(),
+            )
+            .unwrap();
+        assert_eq!(*h, 11);
+        assert_eq!(&p["val"], "test");
+
+        let mut p = Path::new("/name/test");
+        let (h, _) = router
+            .recognize_validated(&mut p, |_, _| true, |_, v| v.is_none())
+            .unwrap();
+        assert_eq!(*h, 12);
+        assert_eq!(&p["tail"], "test");
+        assert!(p.get("val").is_none());
+
+        let mut p = Path::new("/name/test");
+        assert!(router
+            .recognize_validated(&mut p, |_, _| true, |_, _| false)
+            .is_none());
+    }
+
     #[test]
     fn test_recognizer_checked_insensitive() {
         let mut router = Router::<usize, usize>::build();
@@ -500,20 +500,44 @@ impl Tree {
                         if key.is_empty() {
                             // checker
                             for val in &self.items {
-                                if let Item::Value(ref val) = val {
-                                    let v = match val {
-                                        Value::Val(v) | Value::Prefix(v) => *v,
-                                        Value::Slash(_) | Value::PrefixSlash(_) => {
-                                            continue
+                                match val {
+                                    Item::Value(ref val) => {
+                                        let v = match val {
+                                            Value::Val(v) | Value::Prefix(v) => *v,
+                                            Value::Slash(_) | Value::PrefixSlash(_) => {
+                                                continue
+                                            }
+                                        };
+                                        if check(v, resource) {
+                                            return Some((v, skip));
+                                        }
+                                    }
+                                    Item::Subtree(ref tree) => {
+                                        let result = find_empty_tail(
+                                            &tree.key,
+                                            &tree.items,
+                                            resource,
+                                            check,
+                                            skip,
+                                            segments,
+                                        );
+                                        if result.is_some() {
+                                            return result;
                                         }
-                                    };
-                                    if check(v, resource) {
-                                        return Some((v, skip));
                                     }
                                 }
                             }
+                            None
+                        } else {
+                            find_empty_tail(
+                                key,
+                                &self.items,
+                                resource,
+                                check,
+                                skip,
+                                segments,
+                            )
                         }
-                        None
                     };
                 } else if key.is_empty() {
                     path = &path[idx..];
@@ -593,6 +617,49 @@ impl Tree {
     }
 }
 
+/// Match optional tail segment against consumed path,
+/// i.e. `/files/{path}*` matches `/files` with empty `path`
+fn find_empty_tail<T, R, F>(
+    key: &[Segment],
+    items: &[Item],
+    resource: &R,
+    check: &F,
+    skip: usize,
+    segments: &mut Vec<(&'static str, PathItem)>,
+) -> Option<(usize, usize)>
+where
+    T: ResourcePath,
+    R: Resource<T>,
+    F: Fn(usize, &R) -> bool,
+{
+    if let [Segment::Dynamic {
+        ref pattern,
+        ref names,
+        tail: true,
+        ..
+    }] = key
+    {
+        if let Some(captures) = pattern.captures("") {
+            if names.iter().any(|name| captures.name(name).is_none()) {
+                return None;
+            }
+            let len = segments.len();
+            segments.extend(names.iter().map(|name| (*name, PathItem::Static(""))));
+
+            for val in items {
+                if let Item::Value(ref val) = val {
+                    let v = val.value();
+                    if check(v, resource) {
+                        return Some((v, skip));
+                    }
+                }
+            }
+            segments.truncate(len);
+        }
+    }
+    None
+}
+
 fn common_prefix(k1: &[Segment], k2: &[Segment]) -> usize {
     k1.iter()
         .zip(k2.iter())
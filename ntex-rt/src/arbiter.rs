@@ -89,7 +89,8 @@ impl Arbiter {
         let handle = thread::Builder::new()
             .name(name.clone())
             .spawn(move || {
-                let rt = Runtime::new().expect("Cannot create Runtime");
+                let rt = Runtime::with_blocking_threads(sys.blocking_threads())
+                    .expect("Cannot create Runtime");
                 let arb = Arbiter::with_sender(arb_tx);
 
                 let (stop, stop_rx) = channel();
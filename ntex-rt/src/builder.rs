@@ -6,7 +6,7 @@ use tokio::sync::oneshot::{channel, Receiver};
 use tokio::task::LocalSet;
 
 use super::arbiter::{Arbiter, SystemArbiter};
-use super::runtime::Runtime;
+use super::runtime::{Runtime, DEFAULT_BLOCKING_THREADS};
 use super::system::System;
 
 /// Builder struct for a ntex runtime.
@@ -20,6 +20,9 @@ pub struct Builder {
 
     /// Whether the Arbiter will stop the whole System on uncaught panic. Defaults to false.
     stop_on_panic: bool,
+
+    /// Max number of threads in blocking pool of each arbiter. Defaults to 512.
+    blocking_threads: usize,
 }
 
 impl Builder {
@@ -27,6 +30,7 @@ impl Builder {
         Builder {
             name: Cow::Borrowed("ntex"),
             stop_on_panic: false,
+            blocking_threads: DEFAULT_BLOCKING_THREADS,
         }
     }
 
@@ -45,6 +49,19 @@ impl Builder {
         self
     }
 
+    /// Sets max number of threads in blocking pool.
+    ///
+    /// Each arbiter has its own pool, blocking pool runs closures submitted
+    /// with `task::spawn_blocking()`. Threads are spawned on demand and
+    /// shut down after a period of inactivity.
+    ///
+    /// Defaults to 512.
+    pub fn blocking_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Number of blocking threads must be positive");
+        self.blocking_threads = threads;
+        self
+    }
+
     /// Create new System.
     ///
     /// This method panics if it can not create tokio runtime
@@ -79,6 +96,7 @@ impl Builder {
             sys_sender,
             Arbiter::new_system(local),
             self.stop_on_panic,
+            self.blocking_threads,
         );
 
         // system arbiter
@@ -97,13 +115,14 @@ impl Builder {
         let (stop_tx, stop) = channel();
         let (sys_sender, sys_receiver) = unbounded_channel();
 
-        let rt = Runtime::new().unwrap();
+        let rt = Runtime::with_blocking_threads(self.blocking_threads).unwrap();
 
         // system arbiter
         let system = System::construct(
             sys_sender,
             Arbiter::new_system(rt.local()),
             self.stop_on_panic,
+            self.blocking_threads,
         );
         let arb = SystemArbiter::new(stop_tx, sys_receiver);
         rt.spawn(arb);
@@ -243,4 +262,39 @@ mod tests {
         let id2 = rx.recv().unwrap();
         assert_eq!(id, id2);
     }
+
+    #[test]
+    fn test_blocking_threads() {
+        let mut sys = System::build().blocking_threads(1).finish();
+        assert_eq!(System::current().blocking_threads(), 1);
+
+        // single blocking thread, closures run one by one
+        let (tx, rx) = mpsc::channel();
+        let active = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        sys.block_on(async move {
+            let mut handles = Vec::new();
+            for _ in 0..3 {
+                let tx = tx.clone();
+                let active = active.clone();
+                handles.push(crate::task::spawn_blocking(move || {
+                    let n = active.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    let _ = tx.send(n);
+                    thread::sleep(std::time::Duration::from_millis(10));
+                    active.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                }));
+            }
+            for h in handles {
+                h.await.unwrap();
+            }
+        });
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 0, 0]);
+
+        // arbiters inherit system settings
+        let arb = Arbiter::new();
+        let threads = sys
+            .block_on(arb.exec(|| System::current().blocking_threads()))
+            .unwrap();
+        assert_eq!(threads, 1);
+        arb.stop();
+    }
 }
@@ -2,6 +2,9 @@ use std::future::Future;
 use std::io;
 use tokio::{runtime, task::LocalSet};
 
+/// Default max number of threads in blocking pool
+pub(super) const DEFAULT_BLOCKING_THREADS: usize = 512;
+
 /// Single-threaded runtime provides a way to start reactor
 /// and runtime on the current thread.
 ///
@@ -18,9 +21,15 @@ impl Runtime {
     #[allow(clippy::new_ret_no_self)]
     /// Returns a new runtime initialized with default configuration values.
     pub fn new() -> io::Result<Runtime> {
+        Runtime::with_blocking_threads(DEFAULT_BLOCKING_THREADS)
+    }
+
+    /// Returns a new runtime with specified max number of blocking threads.
+    pub(super) fn with_blocking_threads(threads: usize) -> io::Result<Runtime> {
         let rt = runtime::Builder::new_current_thread()
             .enable_io()
             .enable_time()
+            .max_blocking_threads(threads)
             .build()?;
 
         Ok(Runtime {
@@ -14,6 +14,7 @@ pub struct System {
     sys: UnboundedSender<SystemCommand>,
     arbiter: Arbiter,
     stop_on_panic: bool,
+    blocking_threads: usize,
 }
 
 thread_local!(
@@ -26,11 +27,13 @@ impl System {
         sys: UnboundedSender<SystemCommand>,
         arbiter: Arbiter,
         stop_on_panic: bool,
+        blocking_threads: usize,
     ) -> Self {
         let sys = System {
             sys,
             arbiter,
             stop_on_panic,
+            blocking_threads,
             id: SYSTEM_COUNT.fetch_add(1, Ordering::SeqCst),
         };
         System::set_current(sys.clone());
@@ -94,6 +97,11 @@ impl System {
         self.stop_on_panic
     }
 
+    /// Return max number of threads in blocking pool of each arbiter.
+    pub fn blocking_threads(&self) -> usize {
+        self.blocking_threads
+    }
+
     /// System arbiter
     pub fn arbiter(&self) -> &Arbiter {
         &self.arbiter
@@ -85,6 +85,20 @@ pub trait Transform<S> {
 
     /// Creates and returns a new Transform component, asynchronously
     fn new_transform(&self, service: S) -> Self::Service;
+
+    /// Name of the transform, used for introspection.
+    ///
+    /// By default type name of the transform is used.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Collect names of the transform and all nested transforms.
+    ///
+    /// Names are collected in order of request processing.
+    fn names(&self, names: &mut Vec<String>) {
+        names.push(self.name().to_string())
+    }
 }
 
 impl<T, S> Transform<S> for Rc<T>
@@ -96,6 +110,14 @@ where
     fn new_transform(&self, service: S) -> T::Service {
         self.as_ref().new_transform(service)
     }
+
+    fn name(&self) -> &str {
+        self.as_ref().name()
+    }
+
+    fn names(&self, names: &mut Vec<String>) {
+        self.as_ref().names(names)
+    }
 }
 
 /// `Apply` transform to new service
@@ -183,6 +205,8 @@ impl<S: Service> Transform<S> for Identity {
     fn new_transform(&self, service: S) -> Self::Service {
         service
     }
+
+    fn names(&self, _: &mut Vec<String>) {}
 }
 
 #[cfg(test)]
@@ -54,6 +54,8 @@ pub(crate) struct IoStateInner {
     read_buf: Cell<Option<BytesMut>>,
     write_buf: Cell<Option<BytesMut>>,
     on_disconnect: RefCell<Slab<Option<LocalWaker>>>,
+    read_bytes: Cell<u64>,
+    written_bytes: Cell<u64>,
 }
 
 thread_local!(static R_BYTES_POOL: RefCell<Vec<BytesMut>> = RefCell::new(Vec::with_capacity(16)));
@@ -80,6 +82,10 @@ fn release_to_w_pool(mut buf: BytesMut) {
 }
 
 impl IoStateInner {
+    fn add_read_bytes(&self, n: usize) {
+        self.read_bytes.set(self.read_bytes.get() + n as u64);
+    }
+
     fn insert_flags(&self, f: Flags) {
         let mut flags = self.flags.get();
         flags.insert(f);
@@ -196,6 +202,8 @@ impl State {
             read_buf: Cell::new(None),
             write_buf: Cell::new(None),
             on_disconnect: RefCell::new(Slab::new()),
+            read_bytes: Cell::new(0),
+            written_bytes: Cell::new(0),
         }))
     }
 
@@ -227,6 +235,8 @@ impl State {
             read_task: LocalWaker::new(),
             write_task: LocalWaker::new(),
             on_disconnect: RefCell::new(Slab::new()),
+            read_bytes: Cell::new(0),
+            written_bytes: Cell::new(0),
         }));
         (parts.io, parts.codec, state)
     }
@@ -252,6 +262,8 @@ impl State {
             write_buf: Cell::new(None),
             write_task: LocalWaker::new(),
             on_disconnect: RefCell::new(Slab::new()),
+            read_bytes: Cell::new(0),
+            written_bytes: Cell::new(0),
         }))
     }
 
@@ -342,6 +354,18 @@ impl State {
         }
     }
 
+    #[inline]
+    /// Get number of bytes read from io stream
+    pub fn read_bytes(&self) -> u64 {
+        self.0.read_bytes.get()
+    }
+
+    #[inline]
+    /// Get number of bytes written to io stream
+    pub fn written_bytes(&self) -> u64 {
+        self.0.written_bytes.get()
+    }
+
     #[inline]
     /// Check if io error occured in read or write task
     pub fn is_io_err(&self) -> bool {
@@ -538,6 +562,7 @@ impl State {
                     if n == 0 {
                         Ok(None)
                     } else {
+                        self.0.add_read_bytes(n);
                         continue;
                     }
                 }
@@ -601,6 +626,7 @@ impl State {
                             if n == 0 {
                                 Poll::Ready(Ok(None))
                             } else {
+                                self.0.add_read_bytes(n);
                                 continue;
                             }
                         }
@@ -643,6 +669,7 @@ impl State {
                         self.set_io_error(None);
                         return false;
                     } else {
+                        inner.add_read_bytes(n);
                         if buf.len() > inner.read_hw.get() as usize {
                             log::trace!(
                                 "buffer is too large {}, enable read back-pressure",
@@ -726,6 +753,9 @@ impl State {
                 }
             }
             // log::trace!("flushed {} bytes", written);
+            inner
+                .written_bytes
+                .set(inner.written_bytes.get() + written as u64);
 
             // remove written data
             if written == len {
@@ -791,6 +821,18 @@ impl<'a> Write<'a> {
         }
     }
 
+    #[inline]
+    /// Check if write buffer is empty
+    pub fn is_empty(&self) -> bool {
+        if let Some(buf) = self.0.write_buf.take() {
+            let result = buf.is_empty();
+            self.0.write_buf.set(Some(buf));
+            result
+        } else {
+            true
+        }
+    }
+
     #[inline]
     /// Wait until write task flushes data to io stream
     ///
@@ -2,6 +2,7 @@ use std::{
     error::Error, fmt, marker::PhantomData, mem, pin::Pin, task::Context, task::Poll,
 };
 
+use crate::http::header::HeaderMap;
 use crate::{util::Bytes, util::BytesMut, Stream};
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -23,10 +24,22 @@ impl BodySize {
 pub trait MessageBody {
     fn size(&self) -> BodySize;
 
+    /// Attempts to pull out the next chunk of the body.
+    ///
+    /// Empty chunk is a flush marker, buffered data is written to the peer
+    /// before next chunk is requested.
     fn poll_next_chunk(
         &mut self,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>>;
+
+    /// Returns trailer headers of the body.
+    ///
+    /// Called once after body is complete. Trailers are sent only with
+    /// HTTP/2 responses, HTTP/1 responses are sent without trailers.
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        None
+    }
 }
 
 impl MessageBody for () {
@@ -53,6 +66,10 @@ impl<T: MessageBody> MessageBody for Box<T> {
     ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
         self.as_mut().poll_next_chunk(cx)
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.as_mut().trailers()
+    }
 }
 
 pub enum ResponseBody<B> {
@@ -112,6 +129,13 @@ impl<B: MessageBody> MessageBody for ResponseBody<B> {
             ResponseBody::Other(ref mut body) => body.poll_next_chunk(cx),
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            ResponseBody::Body(ref mut body) => body.trailers(),
+            ResponseBody::Other(ref mut body) => body.trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody + Unpin> Stream for ResponseBody<B> {
@@ -180,6 +204,13 @@ impl MessageBody for Body {
             Body::Message(ref mut body) => body.poll_next_chunk(cx),
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Body::Message(ref mut body) => body.trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Body {
@@ -463,6 +494,76 @@ where
     }
 }
 
+/// Chunk of the streaming body with explicit flush control.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chunk {
+    /// Body data
+    Data(Bytes),
+    /// Write buffered data to the peer
+    Flush,
+}
+
+impl From<Bytes> for Chunk {
+    fn from(data: Bytes) -> Self {
+        Chunk::Data(data)
+    }
+}
+
+/// Type represent streaming body with explicit flush control.
+///
+/// Stream yields `Chunk::Data` for body data and `Chunk::Flush` for writing
+/// buffered data to the peer before next chunk is requested.
+/// Response does not contain `content-length` header and appropriate transfer encoding is used.
+pub struct FlushableStream<S, E> {
+    stream: S,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> FlushableStream<S, E>
+where
+    S: Stream<Item = Result<Chunk, E>> + Unpin,
+    E: Error,
+{
+    pub fn new(stream: S) -> Self {
+        FlushableStream {
+            stream,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<S, E> MessageBody for FlushableStream<S, E>
+where
+    S: Stream<Item = Result<Chunk, E>> + Unpin,
+    E: Error + 'static,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    /// Attempts to pull out the next value of the underlying [`Stream`].
+    ///
+    /// Empty data chunks are skipped, `Chunk::Flush` is converted to
+    /// flush marker.
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        loop {
+            return Poll::Ready(match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(Chunk::Data(ref bytes)))) if bytes.is_empty() => {
+                    continue
+                }
+                Poll::Ready(Some(Ok(Chunk::Data(bytes)))) => Some(Ok(bytes)),
+                Poll::Ready(Some(Ok(Chunk::Flush))) => Some(Ok(Bytes::new())),
+                Poll::Ready(Some(Err(e))) => Some(Err(e.into())),
+                Poll::Ready(None) => None,
+                Poll::Pending => return Poll::Pending,
+            });
+        }
+    }
+}
+
 /// Type represent streaming body. This body implementation should be used
 /// if total size of stream is known. Data get sent as is without using transfer encoding.
 pub struct SizedStream<S> {
@@ -506,6 +607,88 @@ where
     }
 }
 
+/// Body wrapper that sends `Digest` trailer (RFC 3230) computed over body.
+///
+/// Digest is computed while body is streamed and is sent as `digest`
+/// trailer, i.e. `digest: sha-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=`.
+/// Trailers are supported only by HTTP/2 responses, response should
+/// announce trailer with `Trailer: digest` header. Requires `digest` feature.
+///
+/// ```rust
+/// use ntex::http::body::{Body, BodyStream, DigestBody};
+/// use ntex::http::{header, Response};
+/// use ntex::util::Bytes;
+///
+/// fn response() -> Response {
+///     let stream = futures::stream::iter(vec![
+///         Ok::<_, std::io::Error>(Bytes::from_static(b"hello ")),
+///         Ok(Bytes::from_static(b"world")),
+///     ]);
+///     Response::Ok()
+///         .header(header::TRAILER, "digest")
+///         .body(Body::from_message(DigestBody::sha256(BodyStream::new(stream))))
+/// }
+/// ```
+#[cfg(feature = "digest")]
+pub struct DigestBody<B> {
+    body: B,
+    hasher: Option<sha2::Sha256>,
+    digest: Option<Bytes>,
+}
+
+#[cfg(feature = "digest")]
+impl<B: MessageBody> DigestBody<B> {
+    /// Create body wrapper that computes `SHA-256` digest.
+    pub fn sha256(body: B) -> Self {
+        DigestBody {
+            body,
+            hasher: Some(sha2::Digest::new()),
+            digest: None,
+        }
+    }
+}
+
+#[cfg(feature = "digest")]
+impl<B: MessageBody> MessageBody for DigestBody<B> {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        match self.body.poll_next_chunk(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Some(ref mut hasher) = self.hasher {
+                    sha2::Digest::update(hasher, &chunk);
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(None) => {
+                if let Some(hasher) = self.hasher.take() {
+                    let digest = sha2::Digest::finalize(hasher);
+                    self.digest = Some(Bytes::copy_from_slice(&digest));
+                }
+                Poll::Ready(None)
+            }
+            res => res,
+        }
+    }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        let digest = self.digest.take()?;
+        let value = format!("sha-256={}", base64::encode(&digest));
+
+        let mut trailers = self.body.trailers().unwrap_or_default();
+        trailers.insert(
+            crate::http::header::HeaderName::from_static("digest"),
+            crate::http::header::HeaderValue::try_from(value).ok()?,
+        );
+        Some(trailers)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use futures::stream;
@@ -732,4 +915,30 @@ mod tests {
             Some(Bytes::from("2")),
         );
     }
+
+    #[cfg(feature = "digest")]
+    #[crate::rt_test]
+    async fn test_digest_body() {
+        let mut body = DigestBody::sha256(BodyStream::new(stream::iter(
+            ["hello ", "world"]
+                .iter()
+                .map(|&v| Ok(Bytes::from(v)) as Result<Bytes, io::Error>),
+        )));
+        assert!(body.trailers().is_none());
+        assert_eq!(
+            poll_fn(|cx| body.poll_next_chunk(cx)).await.unwrap().ok(),
+            Some(Bytes::from("hello ")),
+        );
+        assert_eq!(
+            poll_fn(|cx| body.poll_next_chunk(cx)).await.unwrap().ok(),
+            Some(Bytes::from("world")),
+        );
+        assert!(poll_fn(|cx| body.poll_next_chunk(cx)).await.is_none());
+
+        let trailers = body.trailers().unwrap();
+        assert_eq!(
+            trailers.get("digest").unwrap(),
+            "sha-256=uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek="
+        );
+    }
 }
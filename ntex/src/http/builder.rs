@@ -2,10 +2,13 @@ use std::{cell::RefCell, error::Error, fmt, marker::PhantomData, rc::Rc};
 
 use crate::framed::State;
 use crate::http::body::MessageBody;
-use crate::http::config::{KeepAlive, OnRequest, ServiceConfig};
+use crate::http::config::{
+    KeepAlive, MapRequest, OnRequest, ServerHeader, ServiceConfig,
+};
 use crate::http::error::ResponseError;
-use crate::http::h1::{Codec, ExpectHandler, H1Service, UpgradeHandler};
+use crate::http::h1::{Codec, ExpectHandler, H1Service, HeadersLimits, UpgradeHandler};
 use crate::http::h2::H2Service;
+use crate::http::header::HeaderValue;
 use crate::http::helpers::{Data, DataFactory};
 use crate::http::request::Request;
 use crate::http::response::Response;
@@ -25,6 +28,16 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler<T>> {
     lw: u16,
     read_hw: u16,
     write_hw: u16,
+    headers_limits: HeadersLimits,
+    server_header: ServerHeader,
+    date_header: bool,
+    title_case_headers: bool,
+    write_timeout: Millis,
+    write_stall_timeout: Millis,
+    coalesce_small_writes: usize,
+    map_request: Option<MapRequest>,
+    h2_initial_window_size: Option<u32>,
+    h2_initial_connection_window_size: Option<u32>,
     expect: X,
     upgrade: Option<U>,
     on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
@@ -43,6 +56,16 @@ impl<T, S> HttpServiceBuilder<T, S, ExpectHandler, UpgradeHandler<T>> {
             lw: 1024,
             read_hw: 8 * 1024,
             write_hw: 8 * 1024,
+            headers_limits: HeadersLimits::default(),
+            server_header: ServerHeader::Keep,
+            date_header: true,
+            title_case_headers: false,
+            write_timeout: Millis::ZERO,
+            write_stall_timeout: Millis::ZERO,
+            coalesce_small_writes: 0,
+            map_request: None,
+            h2_initial_window_size: None,
+            h2_initial_connection_window_size: None,
             expect: ExpectHandler,
             upgrade: None,
             on_connect: None,
@@ -132,6 +155,146 @@ where
         self
     }
 
+    /// Set limits for incoming request headers.
+    ///
+    /// `max_headers` is a max number of distinct header names, `max_values`
+    /// is a max number of values per header name. Requests that exceed limits
+    /// are rejected with the 431 (Request Header Fields Too Large) error.
+    ///
+    /// By default limits are 96 headers and 32 values per header. Limits
+    /// apply to http/1 and http/2 requests. `max_headers` could not exceed 96,
+    /// http/1 parser does not accept requests with more than 96 header lines.
+    pub fn headers_limits(mut self, max_headers: usize, max_values: usize) -> Self {
+        self.headers_limits = HeadersLimits::new(max_headers, max_values);
+        self
+    }
+
+    /// Set `Server` header for all responses.
+    ///
+    /// `Some(value)` sets fixed value of the header, `None` removes
+    /// the header from all responses. By default responses are sent as is.
+    ///
+    /// Panics if value is not a valid header value.
+    pub fn server_header(mut self, value: Option<&str>) -> Self {
+        self.server_header = match value {
+            Some(val) => ServerHeader::Set(
+                HeaderValue::from_str(val).expect("Cannot create header value"),
+            ),
+            None => ServerHeader::Remove,
+        };
+        self
+    }
+
+    /// Set default `Server` header for all responses.
+    ///
+    /// Header is added to every response that does not have one, handlers
+    /// could override it by setting the header explicitly. Use
+    /// `server_header()` to replace handler provided values. Value could
+    /// include application version, i.e.
+    /// `concat!("my-svc/", env!("CARGO_PKG_VERSION"))`.
+    ///
+    /// Panics if value is not a valid header value.
+    pub fn default_server_header(mut self, value: &str) -> Self {
+        self.server_header = ServerHeader::Default(
+            HeaderValue::from_str(value).expect("Cannot create header value"),
+        );
+        self
+    }
+
+    /// Enable or disable automatic `Date` header.
+    ///
+    /// By default `Date` header is added to every response that does not
+    /// have one, handlers could override it by setting the header explicitly.
+    /// If disabled, only handler provided `Date` headers are sent.
+    ///
+    /// Note: RFC 7231 requires an origin server with a clock to send `Date`
+    /// header, disable it only if a proxy in front of the server adds it
+    /// or the server has no reliable clock.
+    ///
+    /// By default automatic `Date` header is enabled.
+    pub fn date_header(mut self, enabled: bool) -> Self {
+        self.date_header = enabled;
+        self
+    }
+
+    /// Write HTTP/1 response header names in title case.
+    ///
+    /// Header names are stored and usually sent in lowercase, some
+    /// clients expect canonical casing, i.e. `Content-Type`. Only
+    /// HTTP/1 responses are affected, HTTP/2 requires lowercase names.
+    ///
+    /// By default header names are sent in lowercase.
+    pub fn title_case_headers(mut self, enabled: bool) -> Self {
+        self.title_case_headers = enabled;
+        self
+    }
+
+    /// Set response write timeout.
+    ///
+    /// Defines a max time for writing response to the peer, including
+    /// flushing of the write buffer. If response is not fully written within
+    /// this time, the connection is aborted. Applies to http/1 connections only.
+    ///
+    /// To disable timeout set value to 0.
+    ///
+    /// By default write timeout is disabled.
+    pub fn write_timeout(mut self, timeout: Seconds) -> Self {
+        self.write_timeout = timeout.into();
+        self
+    }
+
+    /// Set response write stall timeout.
+    ///
+    /// Defines a max time without write progress while response is being
+    /// written, i.e. peer does not read any data. If peer does not read
+    /// within this time, the connection is aborted. Applies to http/1
+    /// connections only.
+    ///
+    /// To disable timeout set value to 0.
+    ///
+    /// By default write stall timeout is disabled.
+    pub fn write_stall_timeout(mut self, timeout: Seconds) -> Self {
+        self.write_stall_timeout = timeout.into();
+        self
+    }
+
+    /// Coalesce response head and small response body into one write.
+    ///
+    /// If response body size is known and does not exceed `size` bytes,
+    /// dispatcher keeps response head until whole body is available and
+    /// writes response to the peer with a single write call. Applies to
+    /// http/1 connections only.
+    ///
+    /// To disable coalescing set value to 0.
+    ///
+    /// By default coalescing is disabled.
+    pub fn coalesce_small_writes(mut self, size: usize) -> Self {
+        self.coalesce_small_writes = size;
+        self
+    }
+
+    /// Set http/2 initial stream flow-control window size.
+    ///
+    /// Defines how many bytes of request's body peer could send on each
+    /// stream before it has to wait for window update.
+    ///
+    /// By default window size is set to 65,535 bytes.
+    pub fn h2_initial_window_size(mut self, size: u32) -> Self {
+        self.h2_initial_window_size = Some(size);
+        self
+    }
+
+    /// Set http/2 initial connection flow-control window size.
+    ///
+    /// Defines how many bytes peer could send on all streams of the
+    /// connection before it has to wait for window update.
+    ///
+    /// By default window size is set to 65,535 bytes.
+    pub fn h2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.h2_initial_connection_window_size = Some(size);
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -158,6 +321,16 @@ where
             lw: self.lw,
             read_hw: self.read_hw,
             write_hw: self.write_hw,
+            headers_limits: self.headers_limits,
+            server_header: self.server_header,
+            date_header: self.date_header,
+            title_case_headers: self.title_case_headers,
+            write_timeout: self.write_timeout,
+            write_stall_timeout: self.write_stall_timeout,
+            coalesce_small_writes: self.coalesce_small_writes,
+            map_request: self.map_request,
+            h2_initial_window_size: self.h2_initial_window_size,
+            h2_initial_connection_window_size: self.h2_initial_connection_window_size,
             _t: PhantomData,
         }
     }
@@ -191,6 +364,16 @@ where
             lw: self.lw,
             read_hw: self.read_hw,
             write_hw: self.write_hw,
+            headers_limits: self.headers_limits,
+            server_header: self.server_header,
+            date_header: self.date_header,
+            title_case_headers: self.title_case_headers,
+            write_timeout: self.write_timeout,
+            write_stall_timeout: self.write_stall_timeout,
+            coalesce_small_writes: self.coalesce_small_writes,
+            map_request: self.map_request,
+            h2_initial_window_size: self.h2_initial_window_size,
+            h2_initial_connection_window_size: self.h2_initial_connection_window_size,
             _t: PhantomData,
         }
     }
@@ -208,6 +391,18 @@ where
         self
     }
 
+    /// Set request transformer.
+    ///
+    /// Transformer get called for each request before request is passed
+    /// to the service, it could rewrite or replace the request.
+    pub fn map_request<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Request) -> Request + 'static,
+    {
+        self.map_request = Some(Rc::new(f));
+        self
+    }
+
     /// Set req request callback.
     ///
     /// It get called once per request.
@@ -243,6 +438,17 @@ where
             self.read_hw,
             self.write_hw,
         );
+        cfg.set_headers_limits(self.headers_limits);
+        cfg.set_server_header(self.server_header);
+        cfg.set_date_header(self.date_header);
+        cfg.set_title_case_headers(self.title_case_headers);
+        cfg.set_write_timeout(self.write_timeout, self.write_stall_timeout);
+        cfg.set_coalesce_small_writes(self.coalesce_small_writes);
+        cfg.set_map_request(self.map_request);
+        cfg.set_h2_window_sizes(
+            self.h2_initial_window_size,
+            self.h2_initial_connection_window_size,
+        );
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
@@ -269,6 +475,17 @@ where
             self.read_hw,
             self.write_hw,
         );
+        cfg.set_headers_limits(self.headers_limits);
+        cfg.set_server_header(self.server_header);
+        cfg.set_date_header(self.date_header);
+        cfg.set_title_case_headers(self.title_case_headers);
+        cfg.set_write_timeout(self.write_timeout, self.write_stall_timeout);
+        cfg.set_coalesce_small_writes(self.coalesce_small_writes);
+        cfg.set_map_request(self.map_request);
+        cfg.set_h2_window_sizes(
+            self.h2_initial_window_size,
+            self.h2_initial_connection_window_size,
+        );
         H2Service::with_config(cfg, service.into_factory()).on_connect(self.on_connect)
     }
 
@@ -292,6 +509,17 @@ where
             self.read_hw,
             self.write_hw,
         );
+        cfg.set_headers_limits(self.headers_limits);
+        cfg.set_server_header(self.server_header);
+        cfg.set_date_header(self.date_header);
+        cfg.set_title_case_headers(self.title_case_headers);
+        cfg.set_write_timeout(self.write_timeout, self.write_stall_timeout);
+        cfg.set_coalesce_small_writes(self.coalesce_small_writes);
+        cfg.set_map_request(self.map_request);
+        cfg.set_h2_window_sizes(
+            self.h2_initial_window_size,
+            self.h2_initial_connection_window_size,
+        );
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
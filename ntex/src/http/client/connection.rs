@@ -4,7 +4,7 @@ use h2::client::SendRequest;
 
 use crate::codec::{AsyncRead, AsyncWrite, Framed};
 use crate::http::body::MessageBody;
-use crate::http::h1::ClientCodec;
+use crate::http::h1::{ClientCodec, HeadersLimits};
 use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::Payload;
 use crate::http::Protocol;
@@ -97,6 +97,13 @@ where
         }
     }
 
+    fn headers_limits(&self) -> HeadersLimits {
+        self.pool
+            .as_ref()
+            .map(|pool| pool.headers_limits())
+            .unwrap_or_default()
+    }
+
     pub(super) fn into_inner(self) -> (ConnectionType<T>, time::Instant) {
         (self.io.unwrap(), self.created)
     }
@@ -123,6 +130,7 @@ where
         head: H,
         body: B,
     ) -> Self::Future {
+        let limits = self.headers_limits();
         match self.io.take().unwrap() {
             ConnectionType::H1(io) => Box::pin(h1proto::send_request(
                 io,
@@ -130,6 +138,7 @@ where
                 body,
                 self.created,
                 self.pool,
+                limits,
             )),
             ConnectionType::H2(io) => Box::pin(h2proto::send_request(
                 io,
@@ -137,6 +146,7 @@ where
                 body,
                 self.created,
                 self.pool,
+                limits,
             )),
         }
     }
@@ -158,9 +168,11 @@ where
     /// Send request, returns Response and Framed
     fn open_tunnel<H: Into<RequestHeadType>>(mut self, head: H) -> Self::TunnelFuture {
         match self.io.take().unwrap() {
-            ConnectionType::H1(io) => {
-                Either::Left(Box::pin(h1proto::open_tunnel(io, head.into())))
-            }
+            ConnectionType::H1(io) => Either::Left(Box::pin(h1proto::open_tunnel(
+                io,
+                head.into(),
+                self.headers_limits(),
+            ))),
             ConnectionType::H2(io) => {
                 if let Some(mut pool) = self.pool.take() {
                     pool.release(IoConnection::new(
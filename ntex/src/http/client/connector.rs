@@ -2,7 +2,7 @@ use std::{rc::Rc, task::Context, task::Poll, time::Duration};
 
 use crate::codec::{AsyncRead, AsyncWrite};
 use crate::connect::{Connect as TcpConnect, Connector as TcpConnector};
-use crate::http::{Protocol, Uri};
+use crate::http::{h1::HeadersLimits, Protocol, Uri};
 use crate::service::{apply_fn, boxed, Service};
 use crate::time::{Millis, Seconds};
 use crate::util::timeout::{TimeoutError, TimeoutService};
@@ -43,6 +43,7 @@ pub struct Connector {
     conn_keep_alive: Duration,
     disconnect_timeout: Millis,
     limit: usize,
+    headers_limits: HeadersLimits,
     connector: BoxedConnector,
     ssl_connector: Option<BoxedConnector>,
 }
@@ -70,6 +71,7 @@ impl Connector {
             conn_keep_alive: Duration::from_secs(15),
             disconnect_timeout: Millis(3_000),
             limit: 100,
+            headers_limits: HeadersLimits::default(),
         };
 
         #[cfg(feature = "openssl")]
@@ -193,6 +195,20 @@ impl Connector {
         self
     }
 
+    /// Set limits for response headers.
+    ///
+    /// `max_headers` is a max number of distinct header names, `max_values`
+    /// is a max number of values per header name. Responses that exceed
+    /// limits fail with `ParseError::TooManyHeaders` error. Limits apply to
+    /// http/1 and http/2 responses.
+    ///
+    /// By default limits are 96 headers and 32 values per header.
+    /// `max_headers` could not exceed 96.
+    pub fn headers_limits(mut self, max_headers: usize, max_values: usize) -> Self {
+        self.headers_limits = HeadersLimits::new(max_headers, max_values);
+        self
+    }
+
     /// Use custom connector to open un-secured connections.
     pub fn connector<T, U>(mut self, connector: T) -> Self
     where
@@ -246,6 +262,7 @@ impl Connector {
                 self.conn_keep_alive,
                 self.disconnect_timeout,
                 self.limit,
+                self.headers_limits,
             ))
         } else {
             None
@@ -258,6 +275,7 @@ impl Connector {
                 self.conn_keep_alive,
                 self.disconnect_timeout,
                 self.limit,
+                self.headers_limits,
             ),
             ssl_pool,
         })
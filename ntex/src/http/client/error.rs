@@ -34,6 +34,10 @@ pub enum WsClientError {
     /// Invalid challenge response
     #[display(fmt = "Invalid challenge response")]
     InvalidChallengeResponse(String, HeaderValue),
+    /// Invalid SEC-WEBSOCKET-EXTENSIONS header
+    #[display(fmt = "Invalid SEC-WEBSOCKET-EXTENSIONS header")]
+    #[from(ignore)]
+    InvalidExtensionsHeader(HeaderValue),
     /// Protocol error
     #[display(fmt = "{}", _0)]
     Protocol(ProtocolError),
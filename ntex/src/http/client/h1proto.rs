@@ -3,7 +3,7 @@ use std::{io, io::Write, pin::Pin, task::Context, task::Poll, time};
 use crate::codec::{AsyncRead, AsyncWrite, Framed, ReadBuf};
 use crate::http::body::{BodySize, MessageBody};
 use crate::http::error::PayloadError;
-use crate::http::h1;
+use crate::http::h1::{self, HeadersLimits};
 use crate::http::header::{HeaderMap, HeaderValue, HOST};
 use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::{Payload, PayloadStream};
@@ -20,6 +20,7 @@ pub(super) async fn send_request<T, B>(
     body: B,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    limits: HeadersLimits,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
@@ -59,7 +60,7 @@ where
     };
 
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut framed = Framed::new(io, h1::ClientCodec::default().headers_limits(limits));
     send(&mut framed, (head, body.size()).into()).await?;
 
     // send request body
@@ -91,12 +92,13 @@ where
 pub(super) async fn open_tunnel<T>(
     io: T,
     head: RequestHeadType,
+    limits: HeadersLimits,
 ) -> Result<(ResponseHead, Framed<T, h1::ClientCodec>), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut framed = Framed::new(io, h1::ClientCodec::default().headers_limits(limits));
     send(&mut framed, (head, BodySize::None).into()).await?;
 
     // read response
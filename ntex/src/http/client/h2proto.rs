@@ -6,6 +6,8 @@ use http::{request::Request, Method, Version};
 
 use crate::codec::{AsyncRead, AsyncWrite};
 use crate::http::body::{BodySize, MessageBody};
+use crate::http::error::ParseError;
+use crate::http::h1::HeadersLimits;
 use crate::http::header::HeaderMap;
 use crate::http::message::{RequestHeadType, ResponseHead};
 use crate::http::payload::Payload;
@@ -21,6 +23,7 @@ pub(super) async fn send_request<T, B>(
     body: B,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    limits: HeadersLimits,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
@@ -106,6 +109,9 @@ where
     };
 
     let (parts, body) = resp.into_parts();
+    if !limits.check(&parts.headers) {
+        return Err(SendRequestError::Response(ParseError::TooManyHeaders));
+    }
     let payload = if head_req { Payload::None } else { body.into() };
 
     let mut head = ResponseHead::new(parts.status);
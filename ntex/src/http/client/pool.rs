@@ -7,7 +7,7 @@ use http::uri::Authority;
 
 use crate::channel::pool;
 use crate::codec::{AsyncRead, AsyncWrite, ReadBuf};
-use crate::http::Protocol;
+use crate::http::{h1::HeadersLimits, Protocol};
 use crate::rt::spawn;
 use crate::service::Service;
 use crate::task::LocalWaker;
@@ -49,6 +49,7 @@ where
         conn_keep_alive: Duration,
         disconnect_timeout: Millis,
         limit: usize,
+        headers_limits: HeadersLimits,
     ) -> Self {
         let connector = Rc::new(connector);
         let inner = Rc::new(RefCell::new(Inner {
@@ -56,6 +57,7 @@ where
             conn_keep_alive,
             disconnect_timeout,
             limit,
+            headers_limits,
             acquired: 0,
             waiters: VecDeque::new(),
             available: HashMap::default(),
@@ -182,6 +184,7 @@ pub(super) struct Inner<Io> {
     conn_keep_alive: Duration,
     disconnect_timeout: Millis,
     limit: usize,
+    headers_limits: HeadersLimits,
     acquired: usize,
     available: HashMap<Key, VecDeque<AvailableConnection<Io>>>,
     waiters: VecDeque<(Key, Connect, Waiter<Io>)>,
@@ -579,6 +582,14 @@ where
         }
     }
 
+    /// Limits for response headers of pool's connections
+    pub(super) fn headers_limits(&self) -> HeadersLimits {
+        self.1
+            .as_ref()
+            .map(|inner| inner.borrow().headers_limits)
+            .unwrap_or_default()
+    }
+
     pub(super) fn release(&mut self, conn: IoConnection<T>) {
         if let Some(inner) = self.1.take() {
             let (io, created) = conn.into_inner();
@@ -623,6 +634,7 @@ mod tests {
             Duration::from_secs(10),
             Millis::ZERO,
             1,
+            HeadersLimits::default(),
         )
         .clone();
 
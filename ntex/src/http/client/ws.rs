@@ -30,6 +30,7 @@ pub struct WsRequest {
     addr: Option<SocketAddr>,
     max_size: usize,
     server_mode: bool,
+    deflate: Option<ws::DeflateConfig>,
     #[cfg(feature = "cookie")]
     cookies: Option<CookieJar>,
     config: Rc<ClientConfig>,
@@ -62,6 +63,7 @@ impl WsRequest {
             protocols: None,
             max_size: 65_536,
             server_mode: false,
+            deflate: None,
             #[cfg(feature = "cookie")]
             cookies: None,
         }
@@ -130,6 +132,14 @@ impl WsRequest {
         self
     }
 
+    /// Offer per-message deflate compression.
+    ///
+    /// Compression is used only if server accepts offer.
+    pub fn deflate(mut self, config: ws::DeflateConfig) -> Self {
+        self.deflate = Some(config);
+        self
+    }
+
     /// Append a header.
     ///
     /// Header gets appended to existing header.
@@ -292,6 +302,13 @@ impl WsRequest {
             );
         }
 
+        if let Some(ref deflate) = self.deflate {
+            self.head.headers.insert(
+                header::SEC_WEBSOCKET_EXTENSIONS,
+                HeaderValue::try_from(deflate.to_string()).unwrap(),
+            );
+        }
+
         // Generate a random key for the `Sec-WebSocket-Key` header.
         // a base64-encoded (see Section 4 of [RFC4648]) value that,
         // when decoded, is 16 bytes in length (RFC 6455)
@@ -307,6 +324,7 @@ impl WsRequest {
         let head = self.head;
         let max_size = self.max_size;
         let server_mode = self.server_mode;
+        let deflate = self.deflate;
 
         let fut = self.config.connector.open_tunnel(head.into(), self.addr);
 
@@ -374,14 +392,36 @@ impl WsRequest {
             return Err(WsClientError::MissingWebSocketAcceptHeader);
         };
 
+        // Check negotiated per-message deflate parameters
+        let deflate =
+            match (deflate, head.headers.get(&header::SEC_WEBSOCKET_EXTENSIONS)) {
+                (Some(deflate), Some(hdr)) => {
+                    match hdr.to_str().ok().and_then(|s| deflate.accept(s)) {
+                        Some(deflate) => Some(deflate),
+                        None => {
+                            log::trace!("Invalid extensions header: {:?}", hdr);
+                            return Err(WsClientError::InvalidExtensionsHeader(
+                                hdr.clone(),
+                            ));
+                        }
+                    }
+                }
+                _ => None,
+            };
+
         // response and ws io
         Ok(WsConnection::new(
             ClientResponse::new(head, Payload::None),
             framed.map_codec(|_| {
-                if server_mode {
+                let codec = if server_mode {
                     ws::Codec::new().max_size(max_size)
                 } else {
                     ws::Codec::new().max_size(max_size).client_mode()
+                };
+                if let Some(deflate) = deflate {
+                    codec.deflate(deflate)
+                } else {
+                    codec
                 }
             }),
         ))
@@ -1,6 +1,10 @@
 use std::{cell::Cell, cell::RefCell, ptr::copy_nonoverlapping, rc::Rc, time};
 
+use h2::server;
+
 use crate::framed::Timer;
+use crate::http::h1::HeadersLimits;
+use crate::http::header::{HeaderMap, HeaderValue, SERVER};
 use crate::http::{Request, Response};
 use crate::service::boxed::BoxService;
 use crate::time::{sleep, Millis, Seconds, Sleep};
@@ -33,6 +37,34 @@ impl From<Option<usize>> for KeepAlive {
     }
 }
 
+#[derive(Debug, Clone)]
+/// `Server` response header setting
+pub(super) enum ServerHeader {
+    /// Keep response headers as is
+    Keep,
+    /// Set fixed value for all responses
+    Set(HeaderValue),
+    /// Set value for responses without `Server` header
+    Default(HeaderValue),
+    /// Remove header from all responses
+    Remove,
+}
+
+impl ServerHeader {
+    pub(super) fn apply(&self, headers: &mut HeaderMap) {
+        match self {
+            ServerHeader::Keep => (),
+            ServerHeader::Set(val) => headers.insert(SERVER, val.clone()),
+            ServerHeader::Default(val) => {
+                if !headers.contains_key(SERVER) {
+                    headers.insert(SERVER, val.clone())
+                }
+            }
+            ServerHeader::Remove => headers.remove(SERVER),
+        }
+    }
+}
+
 /// Http service configuration
 pub struct ServiceConfig(pub(super) Rc<Inner>);
 
@@ -47,6 +79,16 @@ pub(super) struct Inner {
     pub(super) lw: u16,
     pub(super) read_hw: u16,
     pub(super) write_hw: u16,
+    pub(super) headers_limits: Cell<HeadersLimits>,
+    pub(super) server_header: RefCell<ServerHeader>,
+    pub(super) date_header: Cell<bool>,
+    pub(super) title_case_headers: Cell<bool>,
+    pub(super) write_timeout: Cell<Millis>,
+    pub(super) write_stall_timeout: Cell<Millis>,
+    pub(super) coalesce_small_writes: Cell<usize>,
+    pub(super) map_request: RefCell<Option<MapRequest>>,
+    pub(super) h2_initial_window_size: Cell<Option<u32>>,
+    pub(super) h2_initial_connection_window_size: Cell<Option<u32>>,
 }
 
 impl Clone for ServiceConfig {
@@ -98,12 +140,66 @@ impl ServiceConfig {
             write_hw,
             timer: DateService::new(),
             timer_h1: Timer::default(),
+            headers_limits: Cell::new(HeadersLimits::default()),
+            server_header: RefCell::new(ServerHeader::Keep),
+            date_header: Cell::new(true),
+            title_case_headers: Cell::new(false),
+            write_timeout: Cell::new(Millis::ZERO),
+            write_stall_timeout: Cell::new(Millis::ZERO),
+            coalesce_small_writes: Cell::new(0),
+            map_request: RefCell::new(None),
+            h2_initial_window_size: Cell::new(None),
+            h2_initial_connection_window_size: Cell::new(None),
         }))
     }
+
+    /// Set limits for incoming request headers
+    pub(super) fn set_headers_limits(&self, limits: HeadersLimits) {
+        self.0.headers_limits.set(limits);
+    }
+
+    /// Set `Server` response header
+    pub(super) fn set_server_header(&self, header: ServerHeader) {
+        *self.0.server_header.borrow_mut() = header;
+    }
+
+    /// Enable or disable automatic `Date` response header
+    pub(super) fn set_date_header(&self, enabled: bool) {
+        self.0.date_header.set(enabled);
+    }
+
+    /// Enable or disable title case response header names
+    pub(super) fn set_title_case_headers(&self, enabled: bool) {
+        self.0.title_case_headers.set(enabled);
+    }
+
+    /// Set response write timeouts
+    pub(super) fn set_write_timeout(&self, total: Millis, stall: Millis) {
+        self.0.write_timeout.set(total);
+        self.0.write_stall_timeout.set(stall);
+    }
+
+    /// Set max size of response body that is coalesced with response head
+    pub(super) fn set_coalesce_small_writes(&self, size: usize) {
+        self.0.coalesce_small_writes.set(size);
+    }
+
+    /// Set request transformer
+    pub(super) fn set_map_request(&self, f: Option<MapRequest>) {
+        *self.0.map_request.borrow_mut() = f;
+    }
+
+    /// Set http/2 flow-control window sizes
+    pub(super) fn set_h2_window_sizes(&self, stream: Option<u32>, conn: Option<u32>) {
+        self.0.h2_initial_window_size.set(stream);
+        self.0.h2_initial_connection_window_size.set(conn);
+    }
 }
 
 pub(super) type OnRequest<T> = BoxService<(Request, Rc<RefCell<T>>), Request, Response>;
 
+pub(super) type MapRequest = Rc<dyn Fn(Request) -> Request>;
+
 pub(super) struct DispatcherConfig<T, S, X, U> {
     pub(super) service: S,
     pub(super) expect: X,
@@ -118,6 +214,16 @@ pub(super) struct DispatcherConfig<T, S, X, U> {
     pub(super) read_hw: u16,
     pub(super) write_hw: u16,
     pub(super) on_request: Option<OnRequest<T>>,
+    pub(super) headers_limits: HeadersLimits,
+    pub(super) server_header: ServerHeader,
+    pub(super) date_header: bool,
+    pub(super) title_case_headers: bool,
+    pub(super) write_timeout: Millis,
+    pub(super) write_stall_timeout: Millis,
+    pub(super) coalesce_small_writes: usize,
+    pub(super) map_request: Option<MapRequest>,
+    pub(super) h2_initial_window_size: Option<u32>,
+    pub(super) h2_initial_connection_window_size: Option<u32>,
 }
 
 impl<T, S, X, U> DispatcherConfig<T, S, X, U> {
@@ -142,6 +248,19 @@ impl<T, S, X, U> DispatcherConfig<T, S, X, U> {
             lw: cfg.0.lw,
             read_hw: cfg.0.read_hw,
             write_hw: cfg.0.write_hw,
+            headers_limits: cfg.0.headers_limits.get(),
+            server_header: cfg.0.server_header.borrow().clone(),
+            date_header: cfg.0.date_header.get(),
+            title_case_headers: cfg.0.title_case_headers.get(),
+            write_timeout: cfg.0.write_timeout.get(),
+            write_stall_timeout: cfg.0.write_stall_timeout.get(),
+            coalesce_small_writes: cfg.0.coalesce_small_writes.get(),
+            map_request: cfg.0.map_request.borrow().clone(),
+            h2_initial_window_size: cfg.0.h2_initial_window_size.get(),
+            h2_initial_connection_window_size: cfg
+                .0
+                .h2_initial_connection_window_size
+                .get(),
         }
     }
 
@@ -164,6 +283,18 @@ impl<T, S, X, U> DispatcherConfig<T, S, X, U> {
     pub(super) fn now(&self) -> time::Instant {
         self.timer.now()
     }
+
+    /// Create http/2 connection builder
+    pub(super) fn h2_builder(&self) -> server::Builder {
+        let mut builder = server::Builder::new();
+        if let Some(size) = self.h2_initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.h2_initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        builder
+    }
 }
 
 const DATE_VALUE_LENGTH_HDR: usize = 39;
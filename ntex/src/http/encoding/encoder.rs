@@ -5,7 +5,7 @@ use brotli2::write::BrotliEncoder;
 use flate2::write::{GzEncoder, ZlibEncoder};
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
-use crate::http::header::{ContentEncoding, HeaderValue, CONTENT_ENCODING};
+use crate::http::header::{ContentEncoding, HeaderMap, HeaderValue, CONTENT_ENCODING};
 use crate::http::{ResponseHead, StatusCode};
 use crate::rt::task::{spawn_blocking, JoinHandle};
 use crate::util::Bytes;
@@ -13,6 +13,7 @@ use crate::util::Bytes;
 use super::Writer;
 
 const INPLACE: usize = 1024;
+const DIGEST: &str = "digest";
 
 pub struct Encoder<B> {
     eof: bool,
@@ -159,6 +160,22 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
             }
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        let mut trailers = match self.body {
+            EncoderBody::Bytes(_) => None,
+            EncoderBody::Stream(ref mut b) => b.trailers(),
+            EncoderBody::BoxedStream(ref mut b) => b.trailers(),
+        }?;
+        // `digest` is computed over unencoded body, it does not match
+        // encoded content
+        trailers.remove(DIGEST);
+        if trailers.is_empty() {
+            None
+        } else {
+            Some(trailers)
+        }
+    }
 }
 
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
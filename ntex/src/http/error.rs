@@ -75,6 +75,9 @@ pub enum ParseError {
     /// A message head is too large to be reasonable.
     #[display(fmt = "Message head is too large")]
     TooLarge,
+    /// A message contains too many headers or header values.
+    #[display(fmt = "Too many headers")]
+    TooManyHeaders,
     /// A message reached EOF, but is not complete.
     #[display(fmt = "Message is incomplete")]
     Incomplete,
@@ -189,6 +192,14 @@ pub enum DispatchError {
     #[display(fmt = "The first request did not complete within the specified timeout")]
     SlowRequestTimeout,
 
+    /// Response is not written within the specified timeout.
+    #[display(fmt = "Response is not written within the specified timeout")]
+    WriteTimeout,
+
+    /// Response writing does not make progress within the specified timeout.
+    #[display(fmt = "Response writing is stalled")]
+    WriteStallTimeout,
+
     /// Disconnect timeout. Makes sense for ssl streams.
     #[display(fmt = "Connection shutdown timeout")]
     DisconnectTimeout,
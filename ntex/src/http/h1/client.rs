@@ -10,7 +10,7 @@ use crate::http::message::{ConnectionType, RequestHeadType, ResponseHead};
 use crate::http::{Method, Version};
 use crate::util::{Bytes, BytesMut};
 
-use super::decoder::{PayloadDecoder, PayloadItem, PayloadType};
+use super::decoder::{HeadersLimits, PayloadDecoder, PayloadItem, PayloadType};
 use super::{decoder, encoder, reserve_readbuf, Message, MessageType};
 
 bitflags! {
@@ -72,6 +72,12 @@ impl ClientCodec {
         }
     }
 
+    /// Set limits for incoming response headers
+    pub(crate) fn headers_limits(mut self, limits: HeadersLimits) -> Self {
+        self.inner.decoder = decoder::MessageDecoder::new(limits);
+        self
+    }
+
     /// Check if request is upgrade
     pub fn upgrade(&self) -> bool {
         self.inner.ctype.get() == ConnectionType::Upgrade
@@ -221,7 +227,7 @@ impl Encoder for ClientCodec {
                     inner.version.get(),
                     length,
                     inner.ctype.get(),
-                    &inner.timer,
+                    Some(&inner.timer),
                 )?;
             }
             Message::Chunk(Some(bytes)) => {
@@ -12,13 +12,15 @@ use crate::http::response::Response;
 use crate::http::{Method, Version};
 use crate::util::BytesMut;
 
-use super::{decoder, decoder::PayloadType, encoder, Message};
+use super::decoder::{HeadersLimits, PayloadType};
+use super::{decoder, encoder, Message};
 
 bitflags! {
     struct Flags: u8 {
         const HEAD              = 0b0000_0001;
         const STREAM            = 0b0000_0010;
         const KEEPALIVE_ENABLED = 0b0000_0100;
+        const NO_DATE           = 0b0000_1000;
     }
 }
 
@@ -80,6 +82,26 @@ impl Codec {
         }
     }
 
+    /// Set limits for incoming request headers
+    pub(super) fn headers_limits(mut self, limits: HeadersLimits) -> Self {
+        self.decoder = decoder::MessageDecoder::new(limits);
+        self
+    }
+
+    /// Enable or disable automatic `Date` header
+    pub(super) fn date_header(self, enabled: bool) -> Self {
+        let mut flags = self.flags.get();
+        flags.set(Flags::NO_DATE, !enabled);
+        self.flags.set(flags);
+        self
+    }
+
+    /// Enable or disable title case response header names
+    pub(super) fn title_case_headers(mut self, enabled: bool) -> Self {
+        self.encoder.title_case = enabled;
+        self
+    }
+
     #[inline]
     /// Check if request is upgrade
     pub fn upgrade(&self) -> bool {
@@ -165,7 +187,11 @@ impl Encoder for Codec {
                     self.version.get(),
                     length,
                     self.ctype.get(),
-                    &self.timer,
+                    if self.flags.get().contains(Flags::NO_DATE) {
+                        None
+                    } else {
+                        Some(&self.timer)
+                    },
                 )?;
                 // self.headers_size = (dst.len() - len) as u32;
             }
@@ -234,4 +260,44 @@ mod tests {
         assert!(codec.upgrade());
         assert!(!codec.keepalive_enabled());
     }
+
+    #[crate::rt_test]
+    async fn test_title_case_headers() {
+        let codec = Codec::default().title_case_headers(true);
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\n\r\n");
+        let _item = codec.decode(&mut buf).unwrap().unwrap();
+
+        let res = Response::Ok()
+            .header("content-type", "text/plain")
+            .header("x-request-id", "some-id")
+            .finish()
+            .drop_body();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(Message::Item((res, BodySize::Sized(0))), &mut buf)
+            .unwrap();
+        let data = String::from_utf8(buf.to_vec()).unwrap();
+        assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(data.contains("\r\nContent-Type: text/plain\r\n"));
+        assert!(data.contains("\r\nX-Request-Id: some-id\r\n"));
+        assert!(data.contains("\r\nContent-Length: 0\r\n"));
+        assert!(data.contains("\r\nDate: "));
+        assert!(!data.contains("content-type"));
+
+        // lowercase by default
+        let codec = Codec::default();
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\n\r\n");
+        let _item = codec.decode(&mut buf).unwrap().unwrap();
+
+        let res = Response::Ok()
+            .header("content-type", "text/plain")
+            .finish()
+            .drop_body();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(Message::Item((res, BodySize::Sized(0))), &mut buf)
+            .unwrap();
+        let data = String::from_utf8(buf.to_vec()).unwrap();
+        assert!(data.contains("\r\ncontent-type: text/plain\r\n"));
+    }
 }
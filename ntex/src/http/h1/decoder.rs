@@ -15,9 +15,49 @@ use crate::util::{Buf, Bytes, BytesMut};
 use super::MAX_BUFFER_SIZE;
 
 const MAX_HEADERS: usize = 96;
+const MAX_HEADER_VALUES: usize = 32;
 
 /// Incoming messagd decoder
-pub(super) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(super) struct MessageDecoder<T: MessageType> {
+    limits: HeadersLimits,
+    _t: PhantomData<T>,
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Limits for incoming message headers
+pub(crate) struct HeadersLimits {
+    /// Max number of distinct header names
+    pub(crate) max_headers: usize,
+    /// Max number of values per header name
+    pub(crate) max_values: usize,
+}
+
+impl Default for HeadersLimits {
+    fn default() -> Self {
+        HeadersLimits {
+            max_headers: MAX_HEADERS,
+            max_values: MAX_HEADER_VALUES,
+        }
+    }
+}
+
+impl HeadersLimits {
+    /// Create limits, max number of headers could not exceed 96
+    pub(crate) fn new(max_headers: usize, max_values: usize) -> Self {
+        HeadersLimits {
+            max_headers: std::cmp::min(max_headers, MAX_HEADERS),
+            max_values,
+        }
+    }
+
+    /// Check that headers of h2 message do not exceed limits
+    pub(crate) fn check(&self, headers: &http::HeaderMap) -> bool {
+        headers.keys_len() <= self.max_headers
+            && headers
+                .keys()
+                .all(|name| headers.get_all(name).iter().count() <= self.max_values)
+    }
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -29,13 +69,22 @@ pub enum PayloadType {
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder::new(HeadersLimits::default())
     }
 }
 
 impl<T: MessageType> Clone for MessageDecoder<T> {
     fn clone(&self) -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder::new(self.limits)
+    }
+}
+
+impl<T: MessageType> MessageDecoder<T> {
+    pub(super) fn new(limits: HeadersLimits) -> Self {
+        MessageDecoder {
+            limits,
+            _t: PhantomData,
+        }
     }
 }
 
@@ -44,7 +93,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, &self.limits)
     }
 }
 
@@ -61,18 +110,23 @@ pub(super) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadersLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
+        limits: &HeadersLimits,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade = false;
         let mut expect = false;
         let mut chunked = false;
         let mut seen_te = false;
+        let mut seen_cl = false;
         let mut content_length = None;
 
         {
@@ -89,7 +143,7 @@ pub(super) trait MessageType: Sized {
                     )
                 };
                 match name {
-                    header::CONTENT_LENGTH if content_length.is_some() || chunked => {
+                    header::CONTENT_LENGTH if seen_cl || chunked => {
                         log::debug!("multiple Content-Length not allowed");
                         return Err(ParseError::Header);
                     }
@@ -99,6 +153,7 @@ pub(super) trait MessageType: Sized {
                             return Err(ParseError::Header);
                         }
                         Ok(s) => {
+                            seen_cl = true;
                             if let Ok(len) = s.parse::<u64>() {
                                 if len != 0 {
                                     content_length = Some(len);
@@ -121,9 +176,7 @@ pub(super) trait MessageType: Sized {
                     header::TRANSFER_ENCODING => {
                         seen_te = true;
                         if let Ok(s) = value.to_str().map(str::trim) {
-                            if s.eq_ignore_ascii_case("chunked")
-                                && content_length.is_none()
-                            {
+                            if s.eq_ignore_ascii_case("chunked") && !seen_cl {
                                 chunked = true
                             } else if s.eq_ignore_ascii_case("identity") {
                                 // allow silently since multiple TE headers are already checked
@@ -170,6 +223,17 @@ pub(super) trait MessageType: Sized {
                     _ => (),
                 }
 
+                match headers.get_all(&name).count() {
+                    0 if headers.len() >= limits.max_headers => {
+                        log::debug!("max number of headers reached");
+                        return Err(ParseError::TooManyHeaders);
+                    }
+                    n if n >= limits.max_values => {
+                        log::debug!("max number of values for {:?} reached", name);
+                        return Err(ParseError::TooManyHeaders);
+                    }
+                    _ => (),
+                }
                 headers.append(name, value);
             }
         }
@@ -213,7 +277,10 @@ impl MessageType for Request {
     }
 
     #[allow(clippy::uninit_assumed_init)]
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadersLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         // Unsafe: we read this data only after httparse parses headers into.
         // performance bump for pipeline benchmarks.
         let mut headers: [HeaderIndex; MAX_HEADERS] =
@@ -251,7 +318,8 @@ impl MessageType for Request {
         let mut msg = Request::new();
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length =
+            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], limits)?;
 
         // payload decoder
         let decoder = match length {
@@ -292,7 +360,10 @@ impl MessageType for ResponseHead {
     }
 
     #[allow(clippy::uninit_assumed_init)]
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadersLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         // Unsafe: we read this data only after httparse parses headers into.
         // performance bump for pipeline benchmarks.
         let mut headers: [HeaderIndex; MAX_HEADERS] =
@@ -331,7 +402,8 @@ impl MessageType for ResponseHead {
         msg.version = ver;
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length =
+            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], limits)?;
 
         // message payload
         let decoder = if let PayloadLength::Payload(pl) = length {
@@ -862,8 +934,66 @@ mod tests {
             .get_all(SET_COOKIE)
             .map(|v| v.to_str().unwrap().to_owned())
             .collect();
-        assert_eq!(val[1], "c1=cookie1");
-        assert_eq!(val[0], "c2=cookie2");
+        assert_eq!(val[0], "c1=cookie1");
+        assert_eq!(val[1], "c2=cookie2");
+    }
+
+    #[test]
+    fn test_headers_limits() {
+        let flood = "X-Flood: 1\r\n".repeat(MAX_HEADER_VALUES + 1);
+        let mut buf = BytesMut::from(format!("GET /test HTTP/1.1\r\n{}\r\n", flood));
+        let reader = MessageDecoder::<Request>::default();
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::TooManyHeaders)
+        ));
+
+        let flood = "X-Flood: 1\r\n".repeat(MAX_HEADER_VALUES);
+        let mut buf = BytesMut::from(format!("GET /test HTTP/1.1\r\n{}\r\n", flood));
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().get_all("x-flood").count(), MAX_HEADER_VALUES);
+
+        let reader = MessageDecoder::<Request>::new(HeadersLimits {
+            max_headers: 2,
+            max_values: 2,
+        });
+        let mut buf =
+            BytesMut::from("GET /test HTTP/1.1\r\nX-A: 1\r\nX-A: 2\r\nX-B: 3\r\n\r\n");
+        assert!(reader.decode(&mut buf).unwrap().is_some());
+
+        let mut buf =
+            BytesMut::from("GET /test HTTP/1.1\r\nX-A: 1\r\nX-A: 2\r\nX-A: 3\r\n\r\n");
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::TooManyHeaders)
+        ));
+
+        let mut buf =
+            BytesMut::from("GET /test HTTP/1.1\r\nX-A: 1\r\nX-B: 2\r\nX-C: 3\r\n\r\n");
+        assert!(matches!(
+            reader.decode(&mut buf),
+            Err(ParseError::TooManyHeaders)
+        ));
+    }
+
+    #[test]
+    fn test_headers_limits_check() {
+        let limits = HeadersLimits::new(1000, 2);
+        assert_eq!(limits.max_headers, MAX_HEADERS);
+
+        let mut headers = http::HeaderMap::new();
+        headers.append("x-a", HeaderValue::from_static("1"));
+        headers.append("x-a", HeaderValue::from_static("2"));
+        assert!(limits.check(&headers));
+        headers.append("x-a", HeaderValue::from_static("3"));
+        assert!(!limits.check(&headers));
+
+        let limits = HeadersLimits::new(1, 2);
+        let mut headers = http::HeaderMap::new();
+        headers.append("x-a", HeaderValue::from_static("1"));
+        assert!(limits.check(&headers));
+        headers.append("x-b", HeaderValue::from_static("2"));
+        assert!(!limits.check(&headers));
     }
 
     #[test]
@@ -1330,6 +1460,30 @@ mod tests {
         expect_parse_err!(&mut buf);
     }
 
+    #[test]
+    fn test_transfer_encoding_zero_content_length() {
+        let mut buf = BytesMut::from(
+            "POST /test HTTP/1.1\r\n\
+             Host: example.com\r\n\
+             Content-Length: 0\r\n\
+             Transfer-Encoding: chunked\r\n\
+             \r\n\
+             5\r\n\
+             hello\r\n\
+             0\r\n\r\n",
+        );
+        expect_parse_err!(&mut buf);
+
+        let mut buf = BytesMut::from(
+            "POST /test HTTP/1.1\r\n\
+             Host: example.com\r\n\
+             Content-Length: 0\r\n\
+             Content-Length: 0\r\n\
+             \r\n",
+        );
+        expect_parse_err!(&mut buf);
+    }
+
     #[test]
     fn test_transfer_encoding_content_length() {
         let mut buf = BytesMut::from(
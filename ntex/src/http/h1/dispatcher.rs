@@ -1,14 +1,15 @@
 //! Framed transport dispatcher
 use std::task::{Context, Poll};
 use std::{
-    cell::RefCell, error::Error, fmt, future::Future, marker, net, pin::Pin, rc::Rc,
+    cell::RefCell, error::Error, fmt, future::Future, io, marker, net, pin::Pin, rc::Rc,
     time,
 };
 
-use crate::codec::{AsyncRead, AsyncWrite};
+use crate::codec::{AsyncRead, AsyncWrite, Encoder};
 use crate::framed::{ReadTask, State as IoState, WriteTask};
 use crate::service::Service;
-use crate::util::Bytes;
+use crate::time::{sleep, Sleep};
+use crate::util::{Bytes, BytesMut};
 
 use crate::http;
 use crate::http::body::{BodySize, MessageBody, ResponseBody};
@@ -19,8 +20,11 @@ use crate::http::request::Request;
 use crate::http::response::Response;
 
 use super::decoder::{PayloadDecoder, PayloadItem, PayloadType};
+use super::extensions::ConnExtensions;
+use super::options::SocketOptions;
 use super::payload::{Payload, PayloadSender, PayloadStatus};
-use super::{codec::Codec, Message};
+use super::stats::ConnStats;
+use super::{codec::Codec, ConnInfo, Message};
 
 bitflags::bitflags! {
     pub struct Flags: u16 {
@@ -76,9 +80,19 @@ struct DispatcherInner<T, S, B, X, U> {
     payload: Option<(PayloadDecoder, PayloadSender)>,
     peer_addr: Option<net::SocketAddr>,
     on_connect_data: Option<Box<dyn DataFactory>>,
+    conn: ConnInfo,
+    write_timer: Option<WriteTimer>,
+    coalesce: Option<BytesMut>,
     _t: marker::PhantomData<(S, B)>,
 }
 
+/// Response write timers
+struct WriteTimer {
+    total: Option<Sleep>,
+    stall: Option<Sleep>,
+    written: u64,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum ReadPayloadStatus {
     Done,
@@ -112,7 +126,10 @@ where
         peer_addr: Option<net::SocketAddr>,
         on_connect_data: Option<Box<dyn DataFactory>>,
     ) -> Self {
-        let codec = Codec::new(config.timer.clone(), config.keep_alive_enabled());
+        let codec = Codec::new(config.timer.clone(), config.keep_alive_enabled())
+            .headers_limits(config.headers_limits)
+            .date_header(config.date_header)
+            .title_case_headers(config.title_case_headers);
         let state = IoState::with_params(
             config.read_hw,
             config.write_hw,
@@ -137,6 +154,13 @@ where
             call: CallState::None,
             st: State::ReadRequest,
             inner: DispatcherInner {
+                conn: ConnInfo {
+                    options: SocketOptions::new(&io),
+                    stats: ConnStats::new(state.clone()),
+                    extensions: ConnExtensions::new(),
+                },
+                write_timer: None,
+                coalesce: None,
                 io: Some(io),
                 flags: Flags::empty(),
                 error: None,
@@ -168,6 +192,38 @@ where
     type Output = Result<(), DispatchError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let result = self.as_mut().poll_dispatcher(cx);
+            if result.is_pending() {
+                // response write timeouts
+                let this = self.as_mut().project();
+                let sending = matches!(this.st, State::SendPayload { .. });
+                if this.inner.poll_write_timer(cx, sending) {
+                    *this.st = State::Stop;
+                    continue;
+                }
+            }
+            return result;
+        }
+    }
+}
+
+impl<T, S, B, X, U> Dispatcher<T, S, B, X, U>
+where
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
+    S: Service<Request = Request>,
+    S::Error: ResponseError + 'static,
+    S::Response: Into<Response<B>>,
+    B: MessageBody,
+    X: Service<Request = Request, Response = Request>,
+    X::Error: ResponseError + 'static,
+    U: Service<Request = (Request, T, IoState, Codec), Response = ()>,
+    U::Error: Error + fmt::Display + 'static,
+{
+    fn poll_dispatcher(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), DispatchError>> {
         let mut this = self.as_mut().project();
 
         loop {
@@ -297,6 +353,15 @@ where
                         continue;
                     }
 
+                    // previous response closed connection,
+                    // pipelined requests are not processed
+                    if this.inner.flags.contains(Flags::STARTED)
+                        && !this.inner.flags.contains(Flags::KEEPALIVE)
+                    {
+                        *this.st = State::Stop;
+                        continue;
+                    }
+
                     let read = this.inner.state.read();
 
                     // decode incoming bytes stream
@@ -347,6 +412,16 @@ where
                                     on_connect.set(&mut req.extensions_mut());
                                 }
 
+                                // connection socket options, statistics
+                                // and connection-level extensions
+                                this.inner.conn.stats.request_received();
+                                req.conn = Some(this.inner.conn.clone());
+
+                                // request transformer
+                                if let Some(ref f) = this.inner.config.map_request {
+                                    req = (*f)(req);
+                                }
+
                                 if upgrade {
                                     // Handle UPGRADE request
                                     log::trace!("prep io for upgrade handler");
@@ -401,10 +476,15 @@ where
                                 return Poll::Pending;
                             }
                             Err(err) => {
-                                // Malformed requests, respond with 400
+                                // Malformed requests, respond with 400,
+                                // or with 431 if request has too many headers
                                 log::trace!("malformed request: {:?}", err);
-                                let (res, body) =
-                                    Response::BadRequest().finish().into_parts();
+                                let res = if let ParseError::TooManyHeaders = err {
+                                    Response::RequestHeaderFieldsTooLarge().finish()
+                                } else {
+                                    Response::BadRequest().finish()
+                                };
+                                let (res, body) = res.into_parts();
                                 this.inner.error = Some(DispatchError::Parse(err));
                                 *this.st =
                                     this.inner.send_response(res, body.into_body());
@@ -549,6 +629,80 @@ where
         }
     }
 
+    fn start_write_timer(&mut self) {
+        let total = self.config.write_timeout;
+        let stall = self.config.write_stall_timeout;
+
+        if let Some(ref mut timer) = self.write_timer {
+            // every response gets full write time
+            if let Some(ref sleep) = timer.total {
+                sleep.reset(total);
+            }
+            if let Some(ref sleep) = timer.stall {
+                sleep.reset(stall);
+            }
+            timer.written = self.state.written_bytes();
+        } else if total.non_zero() || stall.non_zero() {
+            self.write_timer = Some(WriteTimer {
+                total: total.map(sleep),
+                stall: stall.map(sleep),
+                written: self.state.written_bytes(),
+            });
+        }
+    }
+
+    /// Check if response body could be coalesced with response head
+    fn is_coalesced(&self, size: BodySize) -> bool {
+        match size {
+            BodySize::Sized(size) => {
+                size != 0 && size <= self.config.coalesce_small_writes as u64
+            }
+            _ => false,
+        }
+    }
+
+    /// Check response write timers, returns true if timer is expired
+    fn poll_write_timer(&mut self, cx: &mut Context<'_>, sending: bool) -> bool {
+        if let Some(ref mut timer) = self.write_timer {
+            if self.state.is_io_err() || (!sending && self.state.write().is_empty()) {
+                // response is written
+                self.write_timer = None;
+                return false;
+            }
+
+            if let Some(ref total) = timer.total {
+                if total.poll_elapsed(cx).is_ready() {
+                    log::trace!("response write timeout, abort connection");
+                    self.error = Some(DispatchError::WriteTimeout);
+                    self.state.set_io_error(Some(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "response write timeout",
+                    )));
+                    return true;
+                }
+            }
+
+            if let Some(ref stall) = timer.stall {
+                let written = self.state.written_bytes();
+                if written != timer.written {
+                    // write makes progress, restart stall timer
+                    timer.written = written;
+                    stall.reset(self.config.write_stall_timeout);
+                    let _ = stall.poll_elapsed(cx);
+                } else if stall.poll_elapsed(cx).is_ready() {
+                    log::trace!("response write is stalled, abort connection");
+                    self.error = Some(DispatchError::WriteStallTimeout);
+                    self.state.set_io_error(Some(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "response write stall timeout",
+                    )));
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     fn handle_error<E>(&mut self, err: E, critical: bool) -> State<B>
     where
         E: ResponseError + 'static,
@@ -571,27 +725,42 @@ where
         }
     }
 
-    fn send_response(&mut self, msg: Response<()>, body: ResponseBody<B>) -> State<B> {
+    fn send_response(
+        &mut self,
+        mut msg: Response<()>,
+        body: ResponseBody<B>,
+    ) -> State<B> {
+        self.config.server_header.apply(msg.headers_mut());
         trace!("Sending response: {:?} body: {:?}", msg, body.size());
         // we dont need to process responses if socket is disconnected
         // but we still want to handle requests with app service
         // so we skip response processing for droppped connection
         if !self.state.is_io_err() {
-            let result = self
-                .state
-                .write()
-                .encode(Message::Item((msg, body.size())), &self.codec)
-                .map_err(|err| {
-                    if let Some(mut payload) = self.payload.take() {
-                        payload.1.set_error(PayloadError::Incomplete(None));
-                    }
-                    err
-                });
+            let size = body.size();
+            let result = if self.is_coalesced(size) {
+                // keep response head until small body is available
+                let mut buf = BytesMut::new();
+                let result = self.codec.encode(Message::Item((msg, size)), &mut buf);
+                self.coalesce = Some(buf);
+                result
+            } else {
+                self.state
+                    .write()
+                    .encode(Message::Item((msg, size)), &self.codec)
+                    .map(|_| ())
+            }
+            .map_err(|err| {
+                if let Some(mut payload) = self.payload.take() {
+                    payload.1.set_error(PayloadError::Incomplete(None));
+                }
+                err
+            });
 
             if result.is_err() {
                 State::Stop
             } else {
                 self.flags.set(Flags::KEEPALIVE, self.codec.keepalive());
+                self.start_write_timer();
 
                 match body.size() {
                     BodySize::None | BodySize::Empty => {
@@ -617,8 +786,30 @@ where
         item: Option<Result<Bytes, Box<dyn Error>>>,
     ) -> WritePayloadStatus<B> {
         match item {
+            Some(Ok(item)) if item.is_empty() => {
+                trace!("Got response flush marker");
+                if let Some(buf) = self.coalesce.take() {
+                    self.state.write().with_buf(|b| b.extend_from_slice(&buf));
+                }
+                // wait until write task flushes buffered data
+                if self.state.write().is_empty() {
+                    WritePayloadStatus::Continue
+                } else {
+                    WritePayloadStatus::Pause
+                }
+            }
             Some(Ok(item)) => {
                 trace!("Got response chunk: {:?}", item.len());
+                if let Some(ref mut buf) = self.coalesce {
+                    return match self.codec.encode(Message::Chunk(Some(item)), buf) {
+                        Err(err) => {
+                            self.coalesce = None;
+                            self.error = Some(DispatchError::Encode(err));
+                            WritePayloadStatus::Next(State::Stop)
+                        }
+                        Ok(_) => WritePayloadStatus::Continue,
+                    };
+                }
                 match self
                     .state
                     .write()
@@ -639,9 +830,18 @@ where
             }
             None => {
                 trace!("Response payload eof");
-                if let Err(err) =
-                    self.state.write().encode(Message::Chunk(None), &self.codec)
-                {
+                let result = if let Some(mut buf) = self.coalesce.take() {
+                    // write response head and body at once
+                    self.codec.encode(Message::Chunk(None), &mut buf).map(|_| {
+                        self.state.write().with_buf(|b| b.extend_from_slice(&buf))
+                    })
+                } else {
+                    self.state
+                        .write()
+                        .encode(Message::Chunk(None), &self.codec)
+                        .map(|_| ())
+                };
+                if let Err(err) = result {
                     self.error = Some(DispatchError::Encode(err));
                     WritePayloadStatus::Next(State::Stop)
                 } else if self.flags.contains(Flags::SENDPAYLOAD_AND_STOP) {
@@ -655,6 +855,7 @@ where
             }
             Some(Err(e)) => {
                 trace!("Error during response body poll: {:?}", e);
+                self.coalesce = None;
                 self.error = Some(DispatchError::ResponsePayload(e));
                 WritePayloadStatus::Next(State::Stop)
             }
@@ -859,6 +1060,31 @@ mod tests {
         assert!(h1.inner.state.is_io_err());
     }
 
+    #[crate::rt_test]
+    async fn test_req_headers_flood() {
+        let (client, server) = Io::create();
+        client.remote_buffer_cap(4096);
+        client.write("GET /test HTTP/1.1\r\n");
+        client.write("Cookie: c=1\r\n".repeat(64));
+        client.write("\r\n");
+
+        let mut h1 = h1(server, |_| {
+            Box::pin(async { Ok::<_, io::Error>(Response::Ok().finish()) })
+        });
+        sleep(Millis(50)).await;
+
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_ready());
+        assert!(!h1.inner.state.is_open());
+        sleep(Millis(50)).await;
+
+        client.local_buffer(|buf| {
+            assert_eq!(
+                &buf[..46],
+                b"HTTP/1.1 431 Request Header Fields Too Large\r\n"
+            )
+        });
+    }
+
     #[crate::rt_test]
     async fn test_pipeline() {
         let (client, server) = Io::create();
@@ -1105,6 +1331,137 @@ mod tests {
         assert_eq!(num.load(Ordering::Relaxed), 65_536 * 2);
     }
 
+    #[crate::rt_test]
+    async fn test_coalesce_small_writes() {
+        struct Stream(Rc<Cell<bool>>, bool);
+
+        impl body::MessageBody for Stream {
+            fn size(&self) -> body::BodySize {
+                body::BodySize::Sized(4)
+            }
+            fn poll_next_chunk(
+                &mut self,
+                _: &mut Context<'_>,
+            ) -> Poll<Option<Result<Bytes, Box<dyn std::error::Error>>>> {
+                if self.1 {
+                    Poll::Ready(None)
+                } else if self.0.get() {
+                    self.1 = true;
+                    Poll::Ready(Some(Ok(Bytes::from_static(b"test"))))
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+
+        // response head is written before body
+        let ready = Rc::new(Cell::new(false));
+        let ready2 = ready.clone();
+        let (client, server) = Io::create();
+        let mut h1 = h1(server, move |_| {
+            let ready = ready2.clone();
+            Box::pin(async move {
+                Ok::<_, io::Error>(Response::Ok().message_body(Stream(ready, false)))
+            })
+        });
+        let state = h1.inner.state.clone();
+
+        client.remote_buffer_cap(0);
+        client.write("GET /test HTTP/1.1\r\n\r\n");
+        sleep(Millis(50)).await;
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_pending());
+        assert!(state.write().with_buf(|buf| buf.len()) > 0);
+
+        // response head and body are written at once
+        let ready = Rc::new(Cell::new(false));
+        let ready2 = ready.clone();
+        let (client, server) = Io::create();
+        let cfg = ServiceConfig::default();
+        cfg.set_coalesce_small_writes(1024);
+        let mut h1 = Dispatcher::<_, _, _, ExpectHandler, UpgradeHandler<Io>>::new(
+            server,
+            Rc::new(DispatcherConfig::new(
+                cfg,
+                (move |_| {
+                    let ready = ready2.clone();
+                    Box::pin(async move {
+                        Ok::<_, io::Error>(
+                            Response::Ok().message_body(Stream(ready, false)),
+                        )
+                    })
+                })
+                .into_service(),
+                ExpectHandler,
+                None,
+                None,
+            )),
+            None,
+            None,
+        );
+        let state = h1.inner.state.clone();
+
+        client.remote_buffer_cap(0);
+        client.write("GET /test HTTP/1.1\r\n\r\n");
+        sleep(Millis(50)).await;
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_pending());
+        assert_eq!(state.write().with_buf(|buf| buf.len()), 0);
+
+        ready.set(true);
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_pending());
+        let buf = state.write().with_buf(|buf| buf.clone());
+        assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(buf.ends_with(b"\r\n\r\ntest"));
+
+        client.remote_buffer_cap(1024);
+        let buf = client.read().await.unwrap();
+        assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(buf.ends_with(b"\r\n\r\ntest"));
+    }
+
+    #[crate::rt_test]
+    async fn test_flush_marker() {
+        let (tx, rx) = crate::channel::mpsc::channel::<Result<body::Chunk, io::Error>>();
+        let rx = Cell::new(Some(rx));
+        let (client, server) = Io::create();
+        let mut h1 = h1(server, move |_| {
+            let rx = rx.take().unwrap();
+            Box::pin(async move {
+                Ok::<_, io::Error>(
+                    Response::Ok().message_body(body::FlushableStream::new(rx)),
+                )
+            })
+        });
+        let state = h1.inner.state.clone();
+
+        client.remote_buffer_cap(0);
+        client.write("GET /test HTTP/1.1\r\n\r\n");
+        tx.send(Ok(body::Chunk::Data(Bytes::from_static(b"hello"))))
+            .unwrap();
+        tx.send(Ok(body::Chunk::Flush)).unwrap();
+        tx.send(Ok(body::Chunk::Data(Bytes::from_static(b"world"))))
+            .unwrap();
+        sleep(Millis(50)).await;
+
+        // dispatcher waits for write task at flush marker
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_pending());
+        let buf = state.write().with_buf(|buf| buf.clone());
+        assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(buf.ends_with(b"\r\n\r\n5\r\nhello\r\n"));
+
+        client.remote_buffer_cap(1024);
+        let buf = client.read().await.unwrap();
+        assert!(buf.ends_with(b"\r\n\r\n5\r\nhello\r\n"));
+
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_pending());
+        let buf = client.read().await.unwrap();
+        assert_eq!(&buf[..], b"5\r\nworld\r\n");
+
+        drop(tx);
+        assert!(lazy(|cx| Pin::new(&mut h1).poll(cx)).await.is_pending());
+        let buf = client.read().await.unwrap();
+        assert_eq!(&buf[..], b"0\r\n\r\n");
+    }
+
     #[crate::rt_test]
     async fn test_disconnect_during_response_body_pending() {
         struct Stream(bool);
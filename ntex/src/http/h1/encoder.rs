@@ -16,6 +16,7 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(super) struct MessageEncoder<T: MessageType> {
     pub(super) length: BodySize,
     pub(super) te: Cell<TransferEncoding>,
+    pub(super) title_case: bool,
     _t: PhantomData<T>,
 }
 
@@ -24,6 +25,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: Cell::new(TransferEncoding::empty()),
+            title_case: false,
             _t: PhantomData,
         }
     }
@@ -34,6 +36,7 @@ impl<T: MessageType> Clone for MessageEncoder<T> {
         MessageEncoder {
             length: self.length,
             te: self.te.clone(),
+            title_case: self.title_case,
             _t: PhantomData,
         }
     }
@@ -56,7 +59,7 @@ pub(super) trait MessageType: Sized {
         version: Version,
         mut length: BodySize,
         ctype: ConnectionType,
-        timer: &DateService,
+        timer: Option<&DateService>,
     ) -> io::Result<()> {
         let chunked = self.chunked();
         let mut skip_len = length != BodySize::Stream;
@@ -108,7 +111,7 @@ pub(super) trait MessageType: Sized {
             .headers()
             .inner
             .iter()
-            .filter(|(name, _)| !extra_headers.contains_key(*name))
+            .filter(|(name, _)| !extra_headers.contains_key(name))
             .chain(extra_headers.inner.iter());
 
         // write headers
@@ -188,7 +191,7 @@ pub(super) trait MessageType: Sized {
         }
 
         // optimized date header, set_date writes \r\n
-        if !has_date {
+        if let (false, Some(timer)) = (has_date, timer) {
             timer.set_date_header(dst);
         } else {
             // msg eof
@@ -299,7 +302,7 @@ impl<T: MessageType> MessageEncoder<T> {
         version: Version,
         length: BodySize,
         ctype: ConnectionType,
-        timer: &DateService,
+        timer: Option<&DateService>,
     ) -> io::Result<()> {
         // transfer encoding
         if !head {
@@ -320,7 +323,35 @@ impl<T: MessageType> MessageEncoder<T> {
         }
 
         message.encode_status(dst)?;
-        message.encode_headers(dst, version, length, ctype, timer)
+        let start = dst.len();
+        message.encode_headers(dst, version, length, ctype, timer)?;
+        if self.title_case {
+            title_case_headers(&mut dst[start..]);
+        }
+        Ok(())
+    }
+}
+
+/// Convert encoded header names to title case, i.e. `content-type` to `Content-Type`
+fn title_case_headers(buf: &mut [u8]) {
+    let mut in_name = false;
+    let mut upper = false;
+    let mut prev = 0;
+    for b in buf.iter_mut() {
+        if in_name {
+            if *b == b':' {
+                in_name = false;
+            } else if upper {
+                b.make_ascii_uppercase();
+                upper = false;
+            } else if *b == b'-' {
+                upper = true;
+            }
+        } else if prev == b'\r' && *b == b'\n' {
+            in_name = true;
+            upper = true;
+        }
+        prev = *b;
     }
 }
 
@@ -596,7 +627,7 @@ mod tests {
     use std::rc::Rc;
 
     use super::*;
-    use crate::http::header::{HeaderValue, AUTHORIZATION};
+    use crate::http::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
     use crate::http::RequestHead;
     use crate::util::Bytes;
 
@@ -638,7 +669,7 @@ mod tests {
             Version::HTTP_11,
             BodySize::Empty,
             ConnectionType::Close,
-            &DateService::default(),
+            Some(&DateService::default()),
         );
         let data =
             String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
@@ -648,6 +679,36 @@ mod tests {
         assert!(data.contains("date: date\r\n"));
     }
 
+    #[test]
+    fn test_headers_order() {
+        let mut bytes = BytesMut::with_capacity(2048);
+
+        let res = Response::Ok()
+            .header("x-c", "1")
+            .header(DATE, "date")
+            .header("x-a", "2")
+            .header(CONTENT_TYPE, "text/plain")
+            .header("x-b", "3")
+            .header("x-a", "4")
+            .finish()
+            .drop_body();
+
+        let _ = res.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::KeepAlive,
+            Some(&DateService::default()),
+        );
+        assert_eq!(
+            bytes.split().freeze(),
+            Bytes::from_static(
+                b"\r\ncontent-length: 0\r\nx-c: 1\r\ndate: date\r\nx-a: 2\r\n\
+                  x-a: 4\r\ncontent-type: text/plain\r\nx-b: 3\r\n\r\n"
+            )
+        );
+    }
+
     #[test]
     fn test_write_content_length() {
         let mut bytes = BytesMut::new();
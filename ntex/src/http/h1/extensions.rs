@@ -0,0 +1,68 @@
+use std::{cell::Ref, cell::RefCell, cell::RefMut, fmt, rc::Rc};
+
+use crate::util::Extensions;
+
+/// Connection-level extensions of the http/1 connection.
+///
+/// `ConnExtensions` is available via `Request::conn_extensions()` or
+/// `HttpRequest::conn_extensions()`. Values are shared across all requests
+/// on the same connection, and dropped when connection is closed.
+///
+/// ```rust
+/// use ntex::web::{self, HttpRequest, HttpResponse};
+///
+/// struct Session(String);
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     if let Some(ext) = req.conn_extensions() {
+///         ext.insert(Session("user".to_string()));
+///     }
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ConnExtensions(Rc<RefCell<Extensions>>);
+
+impl ConnExtensions {
+    pub(super) fn new() -> Self {
+        ConnExtensions::default()
+    }
+
+    /// Insert a value into connection extensions.
+    ///
+    /// If a value of this type already exists, it will be replaced.
+    pub fn insert<T: 'static>(&self, val: T) {
+        self.0.borrow_mut().insert(val)
+    }
+
+    /// Check if connection extensions contains value of specified type.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.0.borrow().contains::<T>()
+    }
+
+    /// Get a clone of the value of specified type.
+    pub fn get<T: Clone + 'static>(&self) -> Option<T> {
+        self.0.borrow().get::<T>().cloned()
+    }
+
+    /// Remove a value of specified type from connection extensions.
+    pub fn remove<T: 'static>(&self) -> Option<T> {
+        self.0.borrow_mut().remove::<T>()
+    }
+
+    /// Immutable reference to the extensions container.
+    pub fn extensions(&self) -> Ref<'_, Extensions> {
+        self.0.borrow()
+    }
+
+    /// Mutable reference to the extensions container.
+    pub fn extensions_mut(&self) -> RefMut<'_, Extensions> {
+        self.0.borrow_mut()
+    }
+}
+
+impl fmt::Debug for ConnExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnExtensions").finish()
+    }
+}
@@ -7,22 +7,37 @@ mod decoder;
 mod dispatcher;
 mod encoder;
 mod expect;
+mod extensions;
+mod options;
 mod payload;
 mod service;
+mod stats;
 mod upgrade;
 
 pub use self::client::{ClientCodec, ClientPayloadCodec};
 pub use self::codec::Codec;
 pub use self::decoder::{PayloadDecoder, PayloadItem, PayloadType};
 pub use self::expect::ExpectHandler;
+pub use self::extensions::ConnExtensions;
+pub use self::options::SocketOptions;
 pub use self::payload::Payload;
 pub use self::service::{H1Service, H1ServiceHandler};
+pub use self::stats::ConnStats;
 pub use self::upgrade::UpgradeHandler;
 
+pub(super) use self::decoder::HeadersLimits;
 pub(super) use self::dispatcher::Dispatcher;
 
 const MAX_BUFFER_SIZE: usize = 32_768;
 
+/// Connection data of the http/1 connection, shared by all requests
+#[derive(Clone)]
+pub(crate) struct ConnInfo {
+    pub(crate) options: SocketOptions,
+    pub(crate) stats: ConnStats,
+    pub(crate) extensions: ConnExtensions,
+}
+
 #[derive(Debug)]
 /// Codec message
 pub enum Message<T> {
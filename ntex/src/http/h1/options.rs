@@ -0,0 +1,72 @@
+use std::{any::Any, cell::RefCell, io, rc::Rc, rc::Weak};
+
+use crate::rt::net::TcpStream;
+
+/// Socket options of the current http/1 connection.
+///
+/// `SocketOptions` is available via `Request::socket_options()` or
+/// `HttpRequest::socket_options()`, it could be used for changing
+/// connection's socket options from within handler.
+///
+/// ```rust
+/// use ntex::web::{self, HttpRequest, HttpResponse};
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     if let Some(opts) = req.socket_options() {
+///         let _ = opts.set_nodelay(true);
+///     }
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SocketOptions(Weak<dyn Any>);
+
+impl SocketOptions {
+    pub(super) fn new<T: 'static>(io: &Rc<RefCell<T>>) -> Self {
+        let io: Weak<RefCell<T>> = Rc::downgrade(io);
+        SocketOptions(io)
+    }
+
+    /// Sets the value of the `TCP_NODELAY` option on connection's socket.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.with_stream(|io| io.set_nodelay(nodelay))
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option on connection's socket.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.with_stream(|io| io.nodelay())
+    }
+
+    fn with_stream<F, R>(&self, f: F) -> io::Result<R>
+    where
+        F: FnOnce(&TcpStream) -> io::Result<R>,
+    {
+        let io = self.0.upgrade().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotConnected, "Connection is closed")
+        })?;
+
+        if let Some(io) = io.downcast_ref::<RefCell<TcpStream>>() {
+            return f(&*io.borrow());
+        }
+        #[cfg(feature = "openssl")]
+        {
+            use crate::server::openssl::SslStream;
+
+            if let Some(io) = io.downcast_ref::<RefCell<SslStream<TcpStream>>>() {
+                return f(io.borrow().get_ref());
+            }
+        }
+        #[cfg(feature = "rustls")]
+        {
+            use crate::server::rustls::TlsStream;
+
+            if let Some(io) = io.downcast_ref::<RefCell<TlsStream<TcpStream>>>() {
+                return f(io.borrow().get_ref().0);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Socket options are not supported",
+        ))
+    }
+}
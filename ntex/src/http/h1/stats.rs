@@ -0,0 +1,83 @@
+use std::{cell::Cell, fmt, rc::Rc, time::Duration, time::Instant};
+
+use crate::framed::{OnDisconnect, State as IoState};
+
+/// Statistics of the current http/1 connection.
+///
+/// `ConnStats` is available via `Request::conn_stats()` or
+/// `HttpRequest::conn_stats()`, counters are updated by dispatcher
+/// while connection is alive.
+///
+/// ```rust
+/// use ntex::web::{self, HttpRequest, HttpResponse};
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     if let Some(stats) = req.conn_stats() {
+///         println!("requests on connection: {}", stats.requests());
+///     }
+///     HttpResponse::Ok().finish()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ConnStats(Rc<Inner>);
+
+struct Inner {
+    state: IoState,
+    created: Instant,
+    requests: Cell<u64>,
+}
+
+impl ConnStats {
+    pub(super) fn new(state: IoState) -> Self {
+        ConnStats(Rc::new(Inner {
+            state,
+            created: Instant::now(),
+            requests: Cell::new(0),
+        }))
+    }
+
+    pub(super) fn request_received(&self) {
+        self.0.requests.set(self.0.requests.get() + 1);
+    }
+
+    /// Number of bytes read from connection so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.0.state.read_bytes()
+    }
+
+    /// Number of bytes written to connection so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.0.state.written_bytes()
+    }
+
+    /// Time since connection is established.
+    pub fn age(&self) -> Duration {
+        self.0.created.elapsed()
+    }
+
+    /// Number of requests received on connection, including current request.
+    pub fn requests(&self) -> u64 {
+        self.0.requests.get()
+    }
+
+    /// Check if connection is closed or reset by peer.
+    pub fn is_disconnected(&self) -> bool {
+        self.0.state.is_io_err()
+    }
+
+    /// Future that resolves when connection get disconnected.
+    pub fn on_disconnect(&self) -> OnDisconnect {
+        self.0.state.on_disconnect()
+    }
+}
+
+impl fmt::Debug for ConnStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnStats")
+            .field("bytes_read", &self.bytes_read())
+            .field("bytes_written", &self.bytes_written())
+            .field("age", &self.age())
+            .field("requests", &self.requests())
+            .finish()
+    }
+}
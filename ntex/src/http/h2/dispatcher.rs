@@ -10,7 +10,7 @@ use log::{error, trace};
 
 use crate::codec::{AsyncRead, AsyncWrite};
 use crate::http::body::{BodySize, MessageBody, ResponseBody};
-use crate::http::config::{DateService, DispatcherConfig};
+use crate::http::config::{DateService, DispatcherConfig, ServerHeader};
 use crate::http::error::{DispatchError, ResponseError};
 use crate::http::helpers::DataFactory;
 use crate::http::message::ResponseHead;
@@ -95,9 +95,20 @@ where
             match Pin::new(&mut this.connection).poll_accept(cx) {
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err.into())),
-                Poll::Ready(Some(Ok((req, res)))) => {
+                Poll::Ready(Some(Ok((req, mut res)))) => {
                     trace!("h2 message is received: {:?}", req);
 
+                    if !this.config.headers_limits.check(req.headers()) {
+                        trace!("h2 request headers exceed limits");
+                        let mut h2_res = http::Response::new(());
+                        *h2_res.status_mut() =
+                            http::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE;
+                        if let Err(e) = res.send_response(h2_res, true) {
+                            trace!("Error sending h2 response: {:?}", e);
+                        }
+                        continue;
+                    }
+
                     // update keep-alive expire
                     if this.ka_timer.is_some() {
                         if let Some(expire) = this.config.keep_alive_expire() {
@@ -122,12 +133,19 @@ where
                         on_connect.set(&mut req.extensions_mut());
                     }
 
+                    // request transformer
+                    if let Some(ref f) = this.config.map_request {
+                        req = (*f)(req);
+                    }
+
                     crate::rt::spawn(ServiceResponse {
                         state: ServiceResponseState::ServiceCall {
                             call: this.config.service.call(req),
                             send: Some(res),
                         },
                         timer: this.config.timer.clone(),
+                        server_header: this.config.server_header.clone(),
+                        date_header: this.config.date_header,
                         buffer: None,
                         _t: PhantomData,
                     });
@@ -143,6 +161,8 @@ pin_project_lite::pin_project! {
         #[pin]
         state: ServiceResponseState<F, B>,
         timer: DateService,
+        server_header: ServerHeader,
+        date_header: bool,
         buffer: Option<Bytes>,
         _t: PhantomData<(I, E)>,
     }
@@ -209,7 +229,7 @@ where
         }
 
         // set date header
-        if !has_date {
+        if !has_date && self.date_header {
             let mut bytes = BytesMut::with_capacity(29);
             self.timer.set_date(|date| bytes.extend_from_slice(date));
             res.headers_mut().insert(DATE, unsafe {
@@ -237,7 +257,8 @@ where
             ServiceResponseStateProject::ServiceCall { call, send } => {
                 match call.poll(cx) {
                     Poll::Ready(Ok(res)) => {
-                        let (res, body) = res.into().replace_body(());
+                        let (mut res, body) = res.into().replace_body(());
+                        this.server_header.apply(res.headers_mut());
 
                         let mut send = send.take().unwrap();
                         let mut size = body.size();
@@ -321,11 +342,23 @@ where
                         match body.poll_next_chunk(cx) {
                             Poll::Pending => return Poll::Pending,
                             Poll::Ready(None) => {
-                                if let Err(e) = stream.send_data(Bytes::new(), true) {
+                                let res = if let Some(trailers) = body.trailers() {
+                                    let mut map =
+                                        http::HeaderMap::with_capacity(trailers.len());
+                                    for (key, value) in trailers.iter() {
+                                        map.append(key.clone(), value.clone());
+                                    }
+                                    stream.send_trailers(map)
+                                } else {
+                                    stream.send_data(Bytes::new(), true)
+                                };
+                                if let Err(e) = res {
                                     warn!("{:?}", e);
                                 }
                                 return Poll::Ready(());
                             }
+                            // flush marker, data frames are sent by connection
+                            Poll::Ready(Some(Ok(chunk))) if chunk.is_empty() => (),
                             Poll::Ready(Some(Ok(chunk))) => {
                                 stream.reserve_capacity(std::cmp::min(
                                     chunk.len(),
@@ -1,7 +1,7 @@
 use std::task::{Context, Poll};
 use std::{future::Future, marker::PhantomData, net, pin::Pin, rc::Rc};
 
-use h2::server::{self, Handshake};
+use h2::server::Handshake;
 use log::error;
 
 use crate::codec::{AsyncRead, AsyncWrite};
@@ -266,7 +266,7 @@ where
                 self.config.clone(),
                 addr,
                 self.on_connect.as_ref().map(|f| f(&io)),
-                server::Builder::new().handshake(io),
+                self.config.h2_builder().handshake(io),
             ),
         }
     }
@@ -1,5 +1,4 @@
-use std::collections::hash_map::{self, Entry};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, fmt, slice};
 
 use http::header::{HeaderName, HeaderValue};
 
@@ -8,11 +7,13 @@ use crate::util::{Either, HashMap};
 /// A set of HTTP headers
 ///
 /// `HeaderMap` is an multimap of [`HeaderName`] to values.
+/// Map preserves insertion order of header names.
 ///
 /// [`HeaderName`]: struct.HeaderName.html
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct HeaderMap {
-    pub(crate) inner: HashMap<HeaderName, Value>,
+    pub(crate) inner: Vec<(HeaderName, Value)>,
+    index: HashMap<HeaderName, usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,9 +40,9 @@ impl Value {
     fn append(&mut self, val: HeaderValue) {
         match self {
             Value::One(_) => {
-                let data = std::mem::replace(self, Value::Multi(vec![val]));
+                let data = std::mem::replace(self, Value::Multi(Vec::new()));
                 match data {
-                    Value::One(val) => self.append(val),
+                    Value::One(old) => *self = Value::Multi(vec![old, val]),
                     Value::Multi(_) => unreachable!(),
                 }
             }
@@ -63,7 +64,8 @@ impl HeaderMap {
     /// allocate.
     pub fn new() -> Self {
         HeaderMap {
-            inner: HashMap::default(),
+            inner: Vec::new(),
+            index: HashMap::default(),
         }
     }
 
@@ -77,7 +79,8 @@ impl HeaderMap {
     /// More capacity than requested may be allocated.
     pub fn with_capacity(capacity: usize) -> HeaderMap {
         HeaderMap {
-            inner: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+            inner: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity_and_hasher(capacity, Default::default()),
         }
     }
 
@@ -98,6 +101,7 @@ impl HeaderMap {
     /// for reuse.
     pub fn clear(&mut self) {
         self.inner.clear();
+        self.index.clear();
     }
 
     /// Returns the number of headers the map can hold without reallocating.
@@ -117,7 +121,8 @@ impl HeaderMap {
     /// patterns could cause additional allocations before the number is
     /// reached.
     pub fn reserve(&mut self, additional: usize) {
-        self.inner.reserve(additional)
+        self.inner.reserve(additional);
+        self.index.reserve(additional)
     }
 
     /// Returns a reference to the value associated with the key.
@@ -130,11 +135,15 @@ impl HeaderMap {
     }
 
     fn get2<N: AsName>(&self, name: N) -> Option<&Value> {
+        self.position(name).map(|idx| &self.inner[idx].1)
+    }
+
+    fn position<N: AsName>(&self, name: N) -> Option<usize> {
         match name.as_name() {
-            Either::Left(name) => self.inner.get(name),
+            Either::Left(name) => self.index.get(name).copied(),
             Either::Right(s) => {
                 if let Ok(name) = HeaderName::try_from(s) {
-                    self.inner.get(&name)
+                    self.index.get(&name).copied()
                 } else {
                     None
                 }
@@ -162,48 +171,32 @@ impl HeaderMap {
     /// is returned. Use `entry` to get all values associated with a given
     /// key. Returns `None` if there are no values associated with the key.
     pub fn get_mut<N: AsName>(&mut self, name: N) -> Option<&mut HeaderValue> {
-        match name.as_name() {
-            Either::Left(name) => self.inner.get_mut(name).map(|v| v.get_mut()),
-            Either::Right(s) => {
-                if let Ok(name) = HeaderName::try_from(s) {
-                    self.inner.get_mut(&name).map(|v| v.get_mut())
-                } else {
-                    None
-                }
-            }
+        match self.position(name) {
+            Some(idx) => Some(self.inner[idx].1.get_mut()),
+            None => None,
         }
     }
 
     /// Returns true if the map contains a value for the specified key.
     pub fn contains_key<N: AsName>(&self, key: N) -> bool {
-        match key.as_name() {
-            Either::Left(name) => self.inner.contains_key(name),
-            Either::Right(s) => {
-                if let Ok(name) = HeaderName::try_from(s) {
-                    self.inner.contains_key(&name)
-                } else {
-                    false
-                }
-            }
-        }
+        self.position(key).is_some()
     }
 
     /// An iterator visiting all key-value pairs.
     ///
-    /// The iteration order is arbitrary, but consistent across platforms for
-    /// the same crate version. Each key will be yielded once per associated
-    /// value. So, if a key has 3 associated values, it will be yielded 3 times.
+    /// The iteration order is the insertion order of the keys. Each key will be
+    /// yielded once per associated value. So, if a key has 3 associated values,
+    /// it will be yielded 3 times.
     pub fn iter(&self) -> Iter<'_> {
         Iter::new(self.inner.iter())
     }
 
     /// An iterator visiting all keys.
     ///
-    /// The iteration order is arbitrary, but consistent across platforms for
-    /// the same crate version. Each key will be yielded only once even if it
-    /// has multiple associated values.
+    /// The iteration order is the insertion order of the keys. Each key will be
+    /// yielded only once even if it has multiple associated values.
     pub fn keys(&self) -> Keys<'_> {
-        Keys(self.inner.keys())
+        Keys(self.inner.iter())
     }
 
     /// Inserts a key-value pair into the map.
@@ -219,9 +212,14 @@ impl HeaderMap {
     /// all values.
     ///
     /// The key is not updated, though; this matters for types that can be `==`
-    /// without being identical.
+    /// without being identical. Key keeps its original position in the map.
     pub fn insert(&mut self, key: HeaderName, val: HeaderValue) {
-        let _ = self.inner.insert(key, Value::One(val));
+        if let Some(idx) = self.index.get(&key) {
+            self.inner[*idx].1 = Value::One(val);
+        } else {
+            self.index.insert(key.clone(), self.inner.len());
+            self.inner.push((key, Value::One(val)));
+        }
     }
 
     /// Inserts a key-value pair into the map.
@@ -234,23 +232,22 @@ impl HeaderMap {
     /// updated, though; this matters for types that can be `==` without being
     /// identical.
     pub fn append(&mut self, key: HeaderName, value: HeaderValue) {
-        match self.inner.entry(key) {
-            Entry::Occupied(mut entry) => entry.get_mut().append(value),
-            Entry::Vacant(entry) => {
-                entry.insert(Value::One(value));
-            }
+        if let Some(idx) = self.index.get(&key) {
+            self.inner[*idx].1.append(value);
+        } else {
+            self.index.insert(key.clone(), self.inner.len());
+            self.inner.push((key, Value::One(value)));
         }
     }
 
     /// Removes all headers for a particular header name from the map.
     pub fn remove<N: AsName>(&mut self, key: N) {
-        match key.as_name() {
-            Either::Left(name) => {
-                let _ = self.inner.remove(name);
-            }
-            Either::Right(s) => {
-                if let Ok(name) = HeaderName::try_from(s) {
-                    let _ = self.inner.remove(&name);
+        if let Some(pos) = self.position(key) {
+            let (name, _) = self.inner.remove(pos);
+            self.index.remove(&name);
+            for idx in self.index.values_mut() {
+                if *idx > pos {
+                    *idx -= 1;
                 }
             }
         }
@@ -325,14 +322,20 @@ impl<'a> Iterator for GetAll<'a> {
     }
 }
 
-pub struct Keys<'a>(hash_map::Keys<'a, HeaderName, Value>);
+pub struct Keys<'a>(slice::Iter<'a, (HeaderName, Value)>);
 
 impl<'a> Iterator for Keys<'a> {
     type Item = &'a HeaderName;
 
     #[inline]
     fn next(&mut self) -> Option<&'a HeaderName> {
-        self.0.next()
+        self.0.next().map(|item| &item.0)
+    }
+}
+
+impl fmt::Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
     }
 }
 
@@ -348,11 +351,11 @@ impl<'a> IntoIterator for &'a HeaderMap {
 pub struct Iter<'a> {
     idx: usize,
     current: Option<(&'a HeaderName, &'a Vec<HeaderValue>)>,
-    iter: hash_map::Iter<'a, HeaderName, Value>,
+    iter: slice::Iter<'a, (HeaderName, Value)>,
 }
 
 impl<'a> Iter<'a> {
-    fn new(iter: hash_map::Iter<'a, HeaderName, Value>) -> Self {
+    fn new(iter: slice::Iter<'a, (HeaderName, Value)>) -> Self {
         Self {
             iter,
             idx: 0,
@@ -378,9 +381,9 @@ impl<'a> Iterator for Iter<'a> {
         }
         if let Some(item) = self.iter.next() {
             match item.1 {
-                Value::One(ref value) => Some((item.0, value)),
+                Value::One(ref value) => Some((&item.0, value)),
                 Value::Multi(ref vec) => {
-                    self.current = Some((item.0, vec));
+                    self.current = Some((&item.0, vec));
                     self.next()
                 }
             }
@@ -393,7 +396,7 @@ impl<'a> Iterator for Iter<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http::header::CONTENT_TYPE;
+    use crate::http::header::{CONTENT_TYPE, DATE};
 
     #[test]
     fn test_basics() {
@@ -424,4 +427,32 @@ mod tests {
         m.remove("content-type");
         assert!(m.is_empty());
     }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut m = HeaderMap::new();
+        m.insert(CONTENT_TYPE, HeaderValue::from_static("text"));
+        m.append(DATE, HeaderValue::from_static("date"));
+        m.insert(
+            HeaderName::from_static("x-b"),
+            HeaderValue::from_static("1"),
+        );
+        m.append(
+            HeaderName::from_static("x-a"),
+            HeaderValue::from_static("2"),
+        );
+        m.append(DATE, HeaderValue::from_static("date2"));
+        m.insert(CONTENT_TYPE, HeaderValue::from_static("json"));
+
+        let keys: Vec<_> = m.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["content-type", "date", "x-b", "x-a"]);
+        let values: Vec<_> = m.iter().map(|(_, v)| v.to_str().unwrap()).collect();
+        assert_eq!(values, vec!["json", "date", "date2", "1", "2"]);
+
+        m.remove(DATE);
+        let keys: Vec<_> = m.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["content-type", "x-b", "x-a"]);
+        assert_eq!(m.get("x-a").unwrap(), "2");
+        assert_eq!(m.get("x-b").unwrap(), "1");
+    }
 }
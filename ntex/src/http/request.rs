@@ -2,6 +2,7 @@ use std::{cell::Ref, cell::RefMut, fmt, mem, net};
 
 use http::{header, Method, Uri, Version};
 
+use crate::http::h1::{ConnExtensions, ConnInfo, ConnStats, SocketOptions};
 use crate::http::header::HeaderMap;
 use crate::http::httpmessage::HttpMessage;
 use crate::http::message::{Message, RequestHead};
@@ -12,6 +13,7 @@ use crate::util::Extensions;
 pub struct Request {
     pub(crate) payload: Payload,
     pub(crate) head: Message<RequestHead>,
+    pub(crate) conn: Option<ConnInfo>,
 }
 
 impl HttpMessage for Request {
@@ -38,6 +40,7 @@ impl From<Message<RequestHead>> for Request {
         Request {
             head,
             payload: Payload::None,
+            conn: None,
         }
     }
 }
@@ -48,6 +51,7 @@ impl Request {
         Request {
             head: Message::new(),
             payload: Payload::None,
+            conn: None,
         }
     }
 }
@@ -58,6 +62,7 @@ impl Request {
         Request {
             payload,
             head: Message::new(),
+            conn: None,
         }
     }
 
@@ -162,6 +167,24 @@ impl Request {
         self.head.extensions_mut()
     }
 
+    /// Socket options of the http/1 connection
+    #[inline]
+    pub fn socket_options(&self) -> Option<&SocketOptions> {
+        self.conn.as_ref().map(|conn| &conn.options)
+    }
+
+    /// Statistics of the http/1 connection
+    #[inline]
+    pub fn conn_stats(&self) -> Option<&ConnStats> {
+        self.conn.as_ref().map(|conn| &conn.stats)
+    }
+
+    /// Connection-level extensions of the http/1 connection
+    #[inline]
+    pub fn conn_extensions(&self) -> Option<&ConnExtensions> {
+        self.conn.as_ref().map(|conn| &conn.extensions)
+    }
+
     #[allow(dead_code)]
     /// Split request into request head and payload
     pub(crate) fn into_parts(self) -> (Message<RequestHead>, Payload) {
@@ -159,6 +159,15 @@ impl<B> Response<B> {
         self.head.keep_alive()
     }
 
+    /// Close connection after this response, even if it is keep-alive.
+    ///
+    /// `Connection: close` header is sent and connection is closed
+    /// once response is flushed, pipelined requests are not processed.
+    #[inline]
+    pub fn close_connection(&mut self) {
+        self.head.set_connection_type(ConnectionType::Close);
+    }
+
     /// Responses extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -443,6 +452,37 @@ impl ResponseBuilder {
         self.header(header::CONTENT_LENGTH, len)
     }
 
+    /// Add a `Link` header entry (RFC 8288).
+    ///
+    /// Multiple links are coalesced into one comma separated `Link` header.
+    ///
+    /// ```rust
+    /// use ntex::http::{Request, Response};
+    ///
+    /// fn index(req: Request) -> Response {
+    ///     Response::Ok()
+    ///         .link("next", "/items?page=3")
+    ///         .link("prev", "/items?page=1")
+    ///         .finish()
+    /// }
+    /// ```
+    pub fn link(&mut self, rel: &str, url: &str) -> &mut Self {
+        if let Some(parts) = parts(&mut self.head, &self.err) {
+            let link = format!("<{}>; rel=\"{}\"", url, rel);
+            let value = match parts.headers.get(header::LINK).map(|v| v.to_str()) {
+                Some(Ok(prev)) => format!("{}, {}", prev, link),
+                _ => link,
+            };
+            match HeaderValue::try_from(value) {
+                Ok(value) => {
+                    parts.headers.insert(header::LINK, value);
+                }
+                Err(e) => self.err = Some(log_error(e)),
+            };
+        }
+        self
+    }
+
     #[cfg(feature = "cookie")]
     /// Set a cookie
     ///
@@ -852,7 +892,7 @@ mod tests {
                     .max_age(time::Duration::days(1))
                     .finish(),
             )
-            .del_cookie(&cookies[1])
+            .del_cookie(&cookies[0])
             .finish();
 
         let mut val: Vec<_> = resp
@@ -887,9 +927,9 @@ mod tests {
 
         let mut iter = r.cookies();
         let v = iter.next().unwrap();
-        assert_eq!((v.name(), v.value()), ("cookie3", "val300"));
-        let v = iter.next().unwrap();
         assert_eq!((v.name(), v.value()), ("original", "val100"));
+        let v = iter.next().unwrap();
+        assert_eq!((v.name(), v.value()), ("cookie3", "val300"));
     }
 
     #[test]
@@ -924,6 +964,31 @@ mod tests {
         assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/plain")
     }
 
+    #[test]
+    fn test_link() {
+        let resp = Response::Ok().link("next", "/items?page=3").finish();
+        assert_eq!(
+            resp.headers().get(header::LINK).unwrap(),
+            "</items?page=3>; rel=\"next\""
+        );
+
+        let resp = Response::Ok()
+            .link("first", "/items?page=1")
+            .link("prev", "/items?page=1")
+            .link("next", "/items?page=3")
+            .link("last", "/items?page=10")
+            .finish();
+        assert_eq!(resp.headers().get_all(header::LINK).count(), 1);
+        assert_eq!(
+            resp.headers().get(header::LINK).unwrap(),
+            "</items?page=1>; rel=\"first\", </items?page=1>; rel=\"prev\", \
+             </items?page=3>; rel=\"next\", </items?page=10>; rel=\"last\""
+        );
+
+        let resp = Response::Ok().link("next", "/items\n").finish();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     #[test]
     fn test_json() {
         let resp = Response::build(StatusCode::OK).json(&vec!["v1", "v2", "v3"]);
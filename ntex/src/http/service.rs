@@ -3,7 +3,7 @@ use std::{
     task::Poll,
 };
 
-use h2::server::{self, Handshake};
+use h2::server::Handshake;
 
 use crate::codec::{AsyncRead, AsyncWrite};
 use crate::framed::State;
@@ -516,7 +516,7 @@ where
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: ResponseState::H2Handshake {
                     data: Some((
-                        server::Builder::new().handshake(io),
+                        self.config.h2_builder().handshake(io),
                         self.config.clone(),
                         on_connect,
                         peer_addr,
@@ -1,6 +1,5 @@
-use std::{
-    io, sync::mpsc as sync_mpsc, sync::Arc, thread, time::Duration, time::Instant,
-};
+use std::sync::{atomic::AtomicUsize, atomic::Ordering, mpsc as sync_mpsc, Arc};
+use std::{io, thread, time::Duration, time::Instant};
 
 use log::{error, info};
 use slab::Slab;
@@ -48,10 +47,47 @@ impl AcceptNotify {
     }
 }
 
+/// Server-wide counter of open connections
+#[derive(Debug, Clone)]
+pub(super) struct Connections {
+    max: usize,
+    count: Arc<AtomicUsize>,
+}
+
+impl Connections {
+    fn new(max: usize) -> Self {
+        Connections {
+            max,
+            count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Acquire connection guard, returns `None` if limit is reached
+    fn acquire(&self) -> Option<ConnectionGuard> {
+        if self.count.fetch_add(1, Ordering::AcqRel) < self.max {
+            Some(ConnectionGuard(self.count.clone()))
+        } else {
+            self.count.fetch_sub(1, Ordering::AcqRel);
+            None
+        }
+    }
+}
+
+/// Releases connection on drop
+#[derive(Debug)]
+pub(super) struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
 pub(super) struct AcceptLoop {
     notify: AcceptNotify,
     inner: Option<(sync_mpsc::Receiver<Command>, mio::Poll, Server)>,
     status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+    connections: Option<Connections>,
 }
 
 impl AcceptLoop {
@@ -73,6 +109,7 @@ impl AcceptLoop {
             notify,
             inner: Some((rx, poll, srv)),
             status_handler: None,
+            connections: None,
         }
     }
 
@@ -91,6 +128,10 @@ impl AcceptLoop {
         self.status_handler = Some(Box::new(f));
     }
 
+    pub(super) fn set_max_connections(&mut self, max: usize) {
+        self.connections = Some(Connections::new(max));
+    }
+
     pub(super) fn start(
         &mut self,
         socks: Vec<(Token, Listener)>,
@@ -110,6 +151,7 @@ impl AcceptLoop {
             workers,
             self.notify.clone(),
             status_handler,
+            self.connections.take(),
         );
     }
 }
@@ -124,6 +166,7 @@ struct Accept {
     next: usize,
     backpressure: bool,
     status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+    connections: Option<Connections>,
 }
 
 /// This function defines errors that are per-connection. Which basically
@@ -148,6 +191,7 @@ impl Accept {
         workers: Vec<WorkerClient>,
         notify: AcceptNotify,
         status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+        connections: Option<Connections>,
     ) {
         let sys = System::current();
 
@@ -156,7 +200,17 @@ impl Accept {
             .name("ntex-server accept loop".to_owned())
             .spawn(move || {
                 System::set_current(sys);
-                Accept::new(rx, poll, socks, workers, srv, notify, status_handler).poll()
+                Accept::new(
+                    rx,
+                    poll,
+                    socks,
+                    workers,
+                    srv,
+                    notify,
+                    status_handler,
+                    connections,
+                )
+                .poll()
             });
     }
 
@@ -168,6 +222,7 @@ impl Accept {
         srv: Server,
         notify: AcceptNotify,
         status_handler: Option<Box<dyn FnMut(ServerStatus) + Send>>,
+        connections: Option<Connections>,
     ) -> Accept {
         // Start accept
         let mut sockets = Slab::new();
@@ -201,6 +256,7 @@ impl Accept {
             notify,
             srv,
             status_handler,
+            connections,
             next: 0,
             backpressure: false,
         }
@@ -442,10 +498,23 @@ impl Accept {
         loop {
             let msg = if let Some(info) = self.sockets.get_mut(token) {
                 match info.sock.accept() {
-                    Ok(Some(io)) => Connection {
-                        io,
-                        token: info.token,
-                    },
+                    Ok(Some(io)) => {
+                        let guard = if let Some(ref conns) = self.connections {
+                            if let Some(guard) = conns.acquire() {
+                                Some(guard)
+                            } else {
+                                trace!("Max connections reached, closing {:?}", io);
+                                continue;
+                            }
+                        } else {
+                            None
+                        };
+                        Connection {
+                            io,
+                            guard,
+                            token: info.token,
+                        }
+                    }
                     Ok(None) => return,
                     Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return,
                     Err(ref e) if connection_error(e) => continue,
@@ -1,4 +1,6 @@
-use std::{future::Future, io, mem, net, pin::Pin, task::Context, task::Poll};
+use std::{
+    future::Future, io, mem, net, pin::Pin, sync::Arc, task::Context, task::Poll,
+};
 
 use async_channel::{unbounded, Receiver};
 use async_oneshot as oneshot;
@@ -7,13 +9,15 @@ use log::{error, info};
 use socket2::{Domain, SockAddr, Socket, Type};
 
 use crate::rt::{net::TcpStream, spawn, System};
-use crate::{time::sleep, time::Millis, util::join_all};
+use crate::time::{sleep, Millis, Seconds};
+use crate::util::join_all;
 
 use super::accept::{AcceptLoop, AcceptNotify, Command};
 use super::config::{ConfiguredService, ServiceConfig};
 use super::service::{Factory, InternalServiceFactory, StreamServiceFactory};
 use super::signals::{Signal, Signals};
 use super::socket::Listener;
+use super::stats::StatsCollector;
 use super::worker::{self, Worker, WorkerAvailability, WorkerClient};
 use super::{Server, ServerCommand, ServerStatus, Token};
 
@@ -24,6 +28,7 @@ pub struct ServerBuilder {
     threads: usize,
     token: Token,
     backlog: i32,
+    fail_fast_on_bind: bool,
     workers: Vec<(usize, WorkerClient)>,
     services: Vec<Box<dyn InternalServiceFactory>>,
     sockets: Vec<(Token, String, Listener)>,
@@ -31,6 +36,8 @@ pub struct ServerBuilder {
     exit: bool,
     shutdown_timeout: Millis,
     no_signals: bool,
+    on_drain: Vec<Box<dyn Fn() + Send>>,
+    stats: Option<(Seconds, Arc<StatsCollector>)>,
     cmd: Receiver<ServerCommand>,
     server: Server,
     notify: Vec<oneshot::Sender<()>>,
@@ -56,9 +63,12 @@ impl ServerBuilder {
             sockets: Vec::new(),
             accept: AcceptLoop::new(server.clone()),
             backlog: 2048,
+            fail_fast_on_bind: false,
             exit: false,
             shutdown_timeout: Millis::from_secs(30),
             no_signals: false,
+            on_drain: Vec::new(),
+            stats: None,
             cmd: rx,
             notify: Vec::new(),
             server,
@@ -89,6 +99,21 @@ impl ServerBuilder {
         self
     }
 
+    /// Fail `bind()` if any of resolved addresses could not be bound.
+    ///
+    /// Address could resolve to multiple socket addresses, i.e. `localhost`
+    /// resolves to ipv4 and ipv6 addresses. By default `bind()` fails only
+    /// if none of the addresses could be bound, failed addresses are logged.
+    /// If enabled, `bind()` returns error for the first address that could
+    /// not be bound, for example if address is already in use, and no
+    /// sockets are bound. Errors contain failed address.
+    ///
+    /// This method should be called before `bind()` method call.
+    pub fn fail_fast_on_bind(mut self, enabled: bool) -> Self {
+        self.fail_fast_on_bind = enabled;
+        self
+    }
+
     /// Sets the maximum per-worker number of concurrent connections.
     ///
     /// All socket listeners will stop accepting connections when this limit is
@@ -100,6 +125,17 @@ impl ServerBuilder {
         self
     }
 
+    /// Sets the maximum server-wide number of concurrent connections.
+    ///
+    /// Limit is shared across all workers. Connections accepted past the
+    /// limit are closed immediately.
+    ///
+    /// By default server-wide limit is not set.
+    pub fn max_connections(mut self, num: usize) -> Self {
+        self.accept.set_max_connections(num);
+        self
+    }
+
     /// Stop ntex runtime when server get dropped.
     ///
     /// By default "stop runtime" is disabled.
@@ -116,6 +152,21 @@ impl ServerBuilder {
         self
     }
 
+    /// Register callback for switching server to draining mode.
+    ///
+    /// Callback is invoked when server receives `SIGUSR1` signal or
+    /// when `Server::drain()` get called. Draining does not stop server,
+    /// callback could be used for rejecting new requests, for example with
+    /// `web::middleware::Drain`, so load balancer stops routing traffic to
+    /// the server before shutdown with `SIGTERM`.
+    pub fn on_drain<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + 'static,
+    {
+        self.on_drain.push(Box::new(f));
+        self
+    }
+
     /// Timeout for graceful workers shutdown.
     ///
     /// After receiving a stop signal, workers have this much time to finish
@@ -128,6 +179,25 @@ impl ServerBuilder {
         self
     }
 
+    /// Log aggregated request statistics periodically.
+    ///
+    /// Server logs number of responses sent by web applications of all
+    /// workers during the interval, with breakdown by status class, and
+    /// total number of requests. Responses of services that do not use
+    /// `web::App` are not counted. Statistics are logged at `info` level,
+    /// latest totals are also available with `Server::stats()`. Zero
+    /// interval disables statistics.
+    ///
+    /// By default statistics are disabled.
+    pub fn stats_interval(mut self, interval: Seconds) -> Self {
+        self.stats = if interval.is_zero() {
+            None
+        } else {
+            Some((interval, Arc::new(StatsCollector::default())))
+        };
+        self
+    }
+
     /// Set server status handler.
     ///
     /// Server calls this handler on every inner status update.
@@ -148,7 +218,8 @@ impl ServerBuilder {
     where
         F: Fn(&mut ServiceConfig) -> io::Result<()>,
     {
-        let mut cfg = ServiceConfig::new(self.threads, self.backlog);
+        let mut cfg =
+            ServiceConfig::new(self.threads, self.backlog, self.fail_fast_on_bind);
 
         f(&mut cfg)?;
 
@@ -177,7 +248,7 @@ impl ServerBuilder {
         F: StreamServiceFactory<TcpStream>,
         U: net::ToSocketAddrs,
     {
-        let sockets = bind_addr(addr, self.backlog)?;
+        let sockets = bind_addr(addr, self.backlog, self.fail_fast_on_bind)?;
 
         for lst in sockets {
             let token = self.token.next();
@@ -305,6 +376,11 @@ impl ServerBuilder {
                 spawn(Signals::new(self.server.clone()));
             }
 
+            // log request statistics
+            if let Some((interval, ref collector)) = self.stats {
+                spawn(StatsCollector::run(Arc::downgrade(collector), interval));
+            }
+
             // start http server actor
             let server = self.server.clone();
             spawn(self);
@@ -317,7 +393,18 @@ impl ServerBuilder {
         let services: Vec<Box<dyn InternalServiceFactory>> =
             self.services.iter().map(|v| v.clone_factory()).collect();
 
-        Worker::start(idx, services, avail, self.shutdown_timeout)
+        let stats = self
+            .stats
+            .as_ref()
+            .map(|(_, collector)| collector.register());
+
+        Worker::start(idx, services, avail, self.shutdown_timeout, stats)
+    }
+
+    fn drain(&self) {
+        for f in &self.on_drain {
+            f();
+        }
     }
 
     fn handle_cmd(&mut self, item: ServerCommand) {
@@ -332,7 +419,8 @@ impl ServerBuilder {
             }
             ServerCommand::Signal(sig) => {
                 // Signals support
-                // Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and stop ntex system
+                // Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and stop ntex system,
+                // `SIGUSR1` switches server to draining mode
                 match sig {
                     Signal::Int => {
                         info!("SIGINT received, exiting");
@@ -358,20 +446,32 @@ impl ServerBuilder {
                             completion: None,
                         })
                     }
+                    Signal::Usr1 => {
+                        info!("SIGUSR1 received, draining");
+                        self.drain();
+                    }
                     _ => (),
                 }
             }
+            ServerCommand::Drain(mut tx) => {
+                self.drain();
+                let _ = tx.send(());
+            }
             ServerCommand::Notify(tx) => {
                 self.notify.push(tx);
             }
+            ServerCommand::Stats(mut tx) => {
+                let _ = tx.send(self.stats.as_ref().map(|(_, c)| c.last()));
+            }
             ServerCommand::Stop {
                 graceful,
                 completion,
             } => {
                 let exit = self.exit;
 
-                // stop accept thread
+                // stop accept thread and statistics
                 self.accept.send(Command::Stop);
+                self.stats = None;
                 let notify = std::mem::take(&mut self.notify);
 
                 // stop workers
@@ -462,6 +562,7 @@ impl Future for ServerBuilder {
 pub(super) fn bind_addr<S: net::ToSocketAddrs>(
     addr: S,
     backlog: i32,
+    fail_fast: bool,
 ) -> io::Result<Vec<net::TcpListener>> {
     let mut err = None;
     let mut succ = false;
@@ -472,7 +573,12 @@ pub(super) fn bind_addr<S: net::ToSocketAddrs>(
                 succ = true;
                 sockets.push(lst);
             }
-            Err(e) => err = Some(e),
+            // already bound sockets get closed on drop
+            Err(e) if fail_fast => return Err(e),
+            Err(e) => {
+                error!("{}", e);
+                err = Some(e)
+            }
         }
     }
 
@@ -490,10 +596,17 @@ pub(super) fn bind_addr<S: net::ToSocketAddrs>(
     }
 }
 
+/// Create tcp listener, error contains address
 pub(crate) fn create_tcp_listener(
     addr: net::SocketAddr,
     backlog: i32,
 ) -> io::Result<net::TcpListener> {
+    tcp_listener(addr, backlog).map_err(|e| {
+        io::Error::new(e.kind(), format!("Cannot bind to address {}: {}", addr, e))
+    })
+}
+
+fn tcp_listener(addr: net::SocketAddr, backlog: i32) -> io::Result<net::TcpListener> {
     let builder = match addr {
         net::SocketAddr::V4(_) => Socket::new(Domain::IPV4, Type::STREAM, None)?,
         net::SocketAddr::V6(_) => Socket::new(Domain::IPV6, Type::STREAM, None)?,
@@ -559,9 +672,85 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[crate::rt_test]
+    async fn test_drain() {
+        use std::sync::{atomic::AtomicUsize, atomic::Ordering, mpsc, Arc};
+        use std::{net, thread};
+
+        let (tx, rx) = mpsc::channel();
+        let counter = Arc::new(AtomicUsize::new(0));
+        let counter2 = counter.clone();
+        let h = thread::spawn(move || {
+            let mut sys = crate::rt::System::new("test");
+            let addr = TestServer::unused_addr();
+            let srv = sys.exec(|| {
+                crate::server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .on_drain(move || {
+                        counter2.fetch_add(1, Ordering::Relaxed);
+                    })
+                    .bind("test", addr, move || {
+                        fn_service(|_| async { Ok::<_, ()>(()) })
+                    })
+                    .unwrap()
+                    .start()
+            });
+            let _ = tx.send((srv, addr));
+            let _ = sys.run();
+        });
+        let (srv, addr) = rx.recv().unwrap();
+
+        srv.drain().await;
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+
+        srv.signal(signals::Signal::Usr1);
+        crate::time::sleep(Millis(300)).await;
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+
+        // draining server still accepts connections
+        assert!(net::TcpStream::connect(addr).is_ok());
+
+        srv.signal(signals::Signal::Term);
+        let _ = h.join();
+    }
+
     #[test]
     fn test_bind_addr() {
         let addrs: Vec<net::SocketAddr> = Vec::new();
-        assert!(bind_addr(&addrs[..], 10).is_err());
+        assert!(bind_addr(&addrs[..], 10, false).is_err());
+    }
+
+    #[test]
+    fn test_bind_addr_in_use() {
+        let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let used = lst.local_addr().unwrap();
+        let free = TestServer::unused_addr();
+
+        let err = Server::build()
+            .bind("test", used, || fn_service(|_| async { Ok::<_, ()>(()) }))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert!(err.to_string().contains(&used.to_string()));
+
+        // partial binding
+        let addrs = [free, used];
+        let sockets = bind_addr(&addrs[..], 10, false).unwrap();
+        assert_eq!(sockets.len(), 1);
+        drop(sockets);
+
+        let err = Server::build()
+            .fail_fast_on_bind(true)
+            .bind("test", &addrs[..], || {
+                fn_service(|_| async { Ok::<_, ()>(()) })
+            })
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert!(err.to_string().contains(&used.to_string()));
+        // first address is released
+        assert!(net::TcpListener::bind(free).is_ok());
     }
 }
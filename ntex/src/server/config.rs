@@ -20,13 +20,15 @@ pub struct ServiceConfig {
     pub(super) apply: Option<Box<dyn ServiceRuntimeConfiguration + Send>>,
     pub(super) threads: usize,
     pub(super) backlog: i32,
+    pub(super) fail_fast: bool,
 }
 
 impl ServiceConfig {
-    pub(super) fn new(threads: usize, backlog: i32) -> ServiceConfig {
+    pub(super) fn new(threads: usize, backlog: i32, fail_fast: bool) -> ServiceConfig {
         ServiceConfig {
             threads,
             backlog,
+            fail_fast,
             services: Vec::new(),
             apply: None,
         }
@@ -37,7 +39,7 @@ impl ServiceConfig {
     where
         U: net::ToSocketAddrs,
     {
-        let sockets = bind_addr(addr, self.backlog)?;
+        let sockets = bind_addr(addr, self.backlog, self.fail_fast)?;
 
         for lst in sockets {
             self.listen(name.as_ref(), lst);
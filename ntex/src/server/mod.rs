@@ -15,6 +15,7 @@ mod config;
 mod service;
 mod signals;
 mod socket;
+mod stats;
 mod test;
 mod worker;
 
@@ -28,6 +29,8 @@ pub(crate) use self::builder::create_tcp_listener;
 pub use self::builder::ServerBuilder;
 pub use self::config::{ServiceConfig, ServiceRuntime};
 pub use self::service::StreamServiceFactory;
+pub(crate) use self::stats::record_status;
+pub use self::stats::RequestStats;
 pub use self::test::{build_test_server, test_server, TestServer};
 
 #[doc(hidden)]
@@ -90,6 +93,7 @@ enum ServerCommand {
     Pause(oneshot::Sender<()>),
     Resume(oneshot::Sender<()>),
     Signal(signals::Signal),
+    Drain(oneshot::Sender<()>),
     /// Whether to try and shut down gracefully
     Stop {
         graceful: bool,
@@ -97,6 +101,8 @@ enum ServerCommand {
     },
     /// Notify of server stop
     Notify(oneshot::Sender<()>),
+    /// Latest request statistics
+    Stats(oneshot::Sender<Option<RequestStats>>),
 }
 
 /// Server controller
@@ -142,8 +148,37 @@ impl Server {
         }
     }
 
+    /// Switch server to draining mode.
+    ///
+    /// Invokes callbacks registered with `ServerBuilder::on_drain()`, same as
+    /// `SIGUSR1` signal. Server continues to accept connections.
+    pub fn drain(&self) -> impl Future<Output = ()> {
+        let (tx, rx) = oneshot::oneshot();
+        let _ = self.0.try_send(ServerCommand::Drain(tx));
+        async move {
+            let _ = rx.await;
+        }
+    }
+
+    /// Get request statistics.
+    ///
+    /// Returns totals collected at the last interval, or `None` if
+    /// statistics are not enabled with `ServerBuilder::stats_interval()`
+    /// or server is stopped.
+    pub fn stats(&self) -> impl Future<Output = Option<RequestStats>> {
+        let (tx, rx) = oneshot::oneshot();
+        let _ = self.0.try_send(ServerCommand::Stats(tx));
+        async move { rx.await.ok().flatten() }
+    }
+
     /// Stop incoming connection processing, stop all workers and exit.
     ///
+    /// If `graceful` is set, workers wait for in-flight requests to complete
+    /// within `ServerBuilder::shutdown_timeout()`, connections that are still
+    /// active after the timeout get aborted. Otherwise all connections
+    /// get closed immediately. Returned future resolves once all workers
+    /// are stopped.
+    ///
     /// If server starts with `spawn()` method, then spawned thread get terminated.
     pub fn stop(&self, graceful: bool) -> impl Future<Output = ()> {
         let (tx, rx) = oneshot::oneshot();
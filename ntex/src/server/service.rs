@@ -9,13 +9,14 @@ use crate::service::{Service, ServiceFactory};
 use crate::util::{counter::CounterGuard, Ready};
 use crate::{rt::spawn, time::Millis};
 
+use super::accept::ConnectionGuard;
 use super::socket::{FromStream, Stream};
 use super::Token;
 
 /// Server message
 pub(super) enum ServerMessage {
     /// New stream
-    Connect(Stream),
+    Connect(Stream, Option<ConnectionGuard>),
     /// Gracefull shutdown in millis
     Shutdown(Millis),
     /// Force shutdown
@@ -81,7 +82,7 @@ where
 
     fn call(&self, (guard, req): (Option<CounterGuard>, ServerMessage)) -> Self::Future {
         match req {
-            ServerMessage::Connect(stream) => {
+            ServerMessage::Connect(stream, conn) => {
                 let stream = FromStream::from_stream(stream).map_err(|e| {
                     error!("Cannot convert to an async io stream: {}", e);
                 });
@@ -91,6 +92,7 @@ where
                     spawn(async move {
                         let _ = f.await;
                         drop(guard);
+                        drop(conn);
                     });
                     Ready::Ok(())
                 } else {
@@ -14,6 +14,8 @@ pub(crate) enum Signal {
     Term,
     /// SIGQUIT
     Quit,
+    /// SIGUSR1
+    Usr1,
 }
 
 pub(super) struct Signals {
@@ -43,6 +45,7 @@ impl Signals {
                 (unix::SignalKind::hangup(), Signal::Hup),
                 (unix::SignalKind::terminate(), Signal::Term),
                 (unix::SignalKind::quit(), Signal::Quit),
+                (unix::SignalKind::user_defined1(), Signal::Usr1),
             ];
 
             let mut signals = Vec::new();
@@ -0,0 +1,148 @@
+//! Server-wide request statistics
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::{cell::RefCell, fmt};
+
+use log::info;
+
+use crate::time::{sleep, Seconds};
+
+thread_local! {
+    static WORKER_STATS: RefCell<Option<Arc<WorkerStats>>> = RefCell::new(None);
+}
+
+/// Record response status for server statistics.
+///
+/// Status is recorded only if current thread is a server worker and
+/// statistics are enabled with `ServerBuilder::stats_interval()`.
+pub(crate) fn record_status<F: FnOnce() -> u16>(f: F) {
+    WORKER_STATS.with(|stats| {
+        if let Some(ref stats) = *stats.borrow() {
+            stats.record(f());
+        }
+    })
+}
+
+/// Set statistics counters of the current worker thread
+pub(super) fn set_worker_stats(stats: Arc<WorkerStats>) {
+    WORKER_STATS.with(|s| *s.borrow_mut() = Some(stats));
+}
+
+/// Aggregated request statistics of all workers.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestStats {
+    requests: u64,
+    classes: [u64; 5],
+}
+
+impl RequestStats {
+    /// Number of responses sent by the server.
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// Number of responses with status of specified class, i.e.
+    /// `status_class(2)` returns number of 2xx responses.
+    pub fn status_class(&self, class: u16) -> u64 {
+        match class {
+            1..=5 => self.classes[class as usize - 1],
+            _ => 0,
+        }
+    }
+
+    fn since(&self, prev: &RequestStats) -> RequestStats {
+        let mut stats = RequestStats {
+            requests: self.requests - prev.requests,
+            classes: [0; 5],
+        };
+        for (idx, val) in stats.classes.iter_mut().enumerate() {
+            *val = self.classes[idx] - prev.classes[idx];
+        }
+        stats
+    }
+}
+
+impl fmt::Display for RequestStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} requests (1xx: {}, 2xx: {}, 3xx: {}, 4xx: {}, 5xx: {})",
+            self.requests,
+            self.classes[0],
+            self.classes[1],
+            self.classes[2],
+            self.classes[3],
+            self.classes[4]
+        )
+    }
+}
+
+/// Request counters of a worker
+#[derive(Default)]
+pub(super) struct WorkerStats {
+    requests: AtomicU64,
+    classes: [AtomicU64; 5],
+}
+
+impl WorkerStats {
+    fn record(&self, status: u16) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let 100..=599 = status {
+            self.classes[status as usize / 100 - 1].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Collects counters of all workers
+#[derive(Default)]
+pub(super) struct StatsCollector {
+    workers: Mutex<Vec<Arc<WorkerStats>>>,
+    last: Mutex<RequestStats>,
+}
+
+impl StatsCollector {
+    /// Create counters for a new worker.
+    ///
+    /// Counters of stopped workers are kept, so totals include
+    /// requests handled by restarted workers.
+    pub(super) fn register(&self) -> Arc<WorkerStats> {
+        let stats = Arc::new(WorkerStats::default());
+        self.workers.lock().unwrap().push(stats.clone());
+        stats
+    }
+
+    /// Totals at last tick
+    pub(super) fn last(&self) -> RequestStats {
+        *self.last.lock().unwrap()
+    }
+
+    fn tick(&self, interval: Seconds) {
+        let mut totals = RequestStats::default();
+        for stats in self.workers.lock().unwrap().iter() {
+            totals.requests += stats.requests.load(Ordering::Relaxed);
+            for (idx, val) in totals.classes.iter_mut().enumerate() {
+                *val += stats.classes[idx].load(Ordering::Relaxed);
+            }
+        }
+
+        let mut last = self.last.lock().unwrap();
+        info!(
+            "Server stats for last {}s: {}, total: {}",
+            interval.seconds(),
+            totals.since(&last),
+            totals.requests
+        );
+        *last = totals;
+    }
+
+    /// Log statistics periodically, until collector is dropped
+    pub(super) async fn run(collector: Weak<StatsCollector>, interval: Seconds) {
+        loop {
+            sleep(interval).await;
+            match collector.upgrade() {
+                Some(collector) => collector.tick(interval),
+                None => break,
+            }
+        }
+    }
+}
@@ -9,9 +9,10 @@ use crate::rt::{spawn, Arbiter};
 use crate::time::{sleep, Millis, Sleep};
 use crate::util::{counter::Counter, join_all};
 
-use super::accept::{AcceptNotify, Command};
+use super::accept::{AcceptNotify, Command, ConnectionGuard};
 use super::service::{BoxedServerService, InternalServiceFactory, ServerMessage};
 use super::socket::Stream;
+use super::stats::{self, WorkerStats};
 use super::Token;
 
 #[derive(Debug)]
@@ -28,6 +29,7 @@ pub(super) struct StopCommand {
 #[derive(Debug)]
 pub(super) struct Connection {
     pub(super) io: Stream,
+    pub(super) guard: Option<ConnectionGuard>,
     pub(super) token: Token,
 }
 
@@ -163,12 +165,16 @@ impl Worker {
         factories: Vec<Box<dyn InternalServiceFactory>>,
         availability: WorkerAvailability,
         shutdown_timeout: Millis,
+        stats: Option<Arc<WorkerStats>>,
     ) -> WorkerClient {
         let (tx1, rx1) = unbounded();
         let (tx2, rx2) = unbounded();
         let avail = availability.clone();
 
         Arbiter::default().exec_fn(move || {
+            if let Some(stats) = stats {
+                stats::set_worker_stats(stats);
+            }
             let _ = spawn(async move {
                 match Worker::create(rx1, rx2, factories, availability, shutdown_timeout)
                     .await
@@ -423,6 +429,10 @@ impl Future for Worker {
                 match t2.poll_elapsed(cx) {
                     Poll::Pending => (),
                     Poll::Ready(_) => {
+                        warn!(
+                            "Graceful worker shutdown timeout, aborting {} connections",
+                            num
+                        );
                         let _ = tx.take().unwrap().send(false);
                         self.shutdown(true);
                         Arbiter::current().stop();
@@ -479,9 +489,10 @@ impl Future for Worker {
                                     self.factories[srv.factory].name(msg.token)
                                 );
                             }
-                            let _ = srv
-                                .service
-                                .call((Some(guard), ServerMessage::Connect(msg.io)));
+                            let _ = srv.service.call((
+                                Some(guard),
+                                ServerMessage::Connect(msg.io, msg.guard),
+                            ));
                         }
                         Poll::Pending => return Poll::Pending,
                         Poll::Ready(None) => return Poll::Ready(()),
@@ -1,28 +1,54 @@
 use std::{
-    cell::RefCell, fmt, future::Future, marker::PhantomData, pin::Pin, rc::Rc, task,
+    cell::RefCell, convert::Infallible, fmt, fmt::Write, future::Future,
+    marker::PhantomData, panic, pin::Pin, rc::Rc, sync::Arc, task,
 };
 
-use crate::http::Request;
+use crate::http::{Request, StatusCode, Uri};
 use crate::router::ResourceDef;
 use crate::service::boxed::{self, BoxServiceFactory};
 use crate::service::{map_config, pipeline_factory, PipelineFactory};
 use crate::service::{Identity, IntoServiceFactory, Service, ServiceFactory, Transform};
-use crate::util::{Extensions, Ready};
+use crate::time::{sleep, Millis};
+use crate::util::{timeout::TimeoutError, Extensions, Ready};
 
-use super::app_service::{AppFactory, AppService};
+use super::app_service::{
+    AppFactory, AppService, CatchPanic, MapTarget, MethodNotAllowed, NotFound,
+    OnResponse, OnStart, Probe,
+};
 use super::config::{AppConfig, ServiceConfig};
+use super::middleware::{Next, OnContentType, WrapFn};
 use super::request::WebRequest;
 use super::resource::Resource;
-use super::response::WebResponse;
-use super::route::Route;
-use super::service::{AppServiceFactory, ServiceFactoryWrapper, WebServiceFactory};
+use super::response::{ErrorLogLevel, ErrorRendererFn, WebResponse};
+use super::route::{DefaultTimeout, Route};
+use super::service::{
+    AppServiceFactory, ServiceFactoryWrapper, WebServiceAdapter, WebServiceFactory,
+};
 use super::types::data::{Data, DataFactory};
-use super::{DefaultError, ErrorRenderer};
+use super::types::payload::PayloadLimit;
+use super::{DefaultError, ErrorRenderer, HttpRequest, HttpResponse};
+
+/// Trailing slash handling mode, see `App::trailing_slash()`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// Match paths as is
+    #[default]
+    Strict,
+    /// Resources match paths with or without trailing slash
+    Merge,
+    /// Redirect paths with trailing slash to path without it,
+    /// with *308 Permanent Redirect* response
+    RedirectToNoSlash,
+    /// Redirect paths without trailing slash to path with it,
+    /// with *308 Permanent Redirect* response
+    RedirectToSlash,
+}
 
 type HttpNewService<Err: ErrorRenderer> =
     BoxServiceFactory<(), WebRequest<Err>, WebResponse, Err::Container, ()>;
-type FnDataFactory =
-    Box<dyn Fn() -> Pin<Box<dyn Future<Output = Result<Box<dyn DataFactory>, ()>>>>>;
+type FnDataFactory = Box<
+    dyn Fn() -> Pin<Box<dyn Future<Output = Result<Option<Box<dyn DataFactory>>, ()>>>>,
+>;
 
 /// Application builder - structure that follows the builder pattern
 /// for building application instances.
@@ -33,10 +59,21 @@ pub struct App<M, F, Err: ErrorRenderer = DefaultError> {
     default: Option<Rc<HttpNewService<Err>>>,
     data: Vec<Box<dyn DataFactory>>,
     data_factories: Vec<FnDataFactory>,
+    data_factory_concurrency: usize,
     external: Vec<ResourceDef>,
     extensions: Extensions,
     error_renderer: Err,
     case_insensitive: bool,
+    case_insensitive_capture: bool,
+    automatic_options: bool,
+    allow_malformed_uri: bool,
+    reject_malformed_query: bool,
+    trailing_slash: TrailingSlash,
+    on_response: Option<OnResponse>,
+    catch_panic: Option<CatchPanic>,
+    probes: Vec<(String, Probe)>,
+    on_start: Vec<OnStart>,
+    map_target: Option<MapTarget>,
 }
 
 impl App<Identity, Filter<DefaultError>, DefaultError> {
@@ -47,12 +84,23 @@ impl App<Identity, Filter<DefaultError>, DefaultError> {
             filter: pipeline_factory(Filter::new()),
             data: Vec::new(),
             data_factories: Vec::new(),
+            data_factory_concurrency: 1,
             services: Vec::new(),
             default: None,
             external: Vec::new(),
             extensions: Extensions::new(),
             error_renderer: DefaultError,
             case_insensitive: false,
+            case_insensitive_capture: false,
+            automatic_options: false,
+            allow_malformed_uri: false,
+            reject_malformed_query: false,
+            trailing_slash: TrailingSlash::Strict,
+            on_response: None,
+            catch_panic: None,
+            probes: Vec::new(),
+            on_start: Vec::new(),
+            map_target: None,
         }
     }
 }
@@ -65,12 +113,23 @@ impl<Err: ErrorRenderer> App<Identity, Filter<Err>, Err> {
             filter: pipeline_factory(Filter::new()),
             data: Vec::new(),
             data_factories: Vec::new(),
+            data_factory_concurrency: 1,
             services: Vec::new(),
             default: None,
             external: Vec::new(),
             extensions: Extensions::new(),
             error_renderer: err,
             case_insensitive: false,
+            case_insensitive_capture: false,
+            automatic_options: false,
+            allow_malformed_uri: false,
+            reject_malformed_query: false,
+            trailing_slash: TrailingSlash::Strict,
+            on_response: None,
+            catch_panic: None,
+            probes: Vec::new(),
+            on_start: Vec::new(),
+            map_target: None,
         }
     }
 }
@@ -122,9 +181,42 @@ where
         self
     }
 
+    /// Set application data from existing `Arc`.
+    ///
+    /// This method is similar to `.data()`, but data is not wrapped to
+    /// a new `Arc`. All app instances that are registered with clones of
+    /// the same `Arc` share the same data. Data is accessible via
+    /// `Data<T>` extractor.
+    ///
+    /// ```rust
+    /// use std::sync::{atomic::AtomicUsize, atomic::Ordering, Arc};
+    /// use ntex::web::{self, App, HttpResponse};
+    ///
+    /// async fn index(data: web::types::Data<AtomicUsize>) -> HttpResponse {
+    ///     data.fetch_add(1, Ordering::Relaxed);
+    ///     HttpResponse::Ok().into()
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let app = App::new()
+    ///     .data_arc(counter.clone())
+    ///     .service(
+    ///         web::resource("/index.html").route(web::get().to(index))
+    ///     );
+    /// ```
+    pub fn data_arc<U: 'static>(mut self, data: Arc<U>) -> Self {
+        self.data.push(Box::new(Data::from_arc(data)));
+        self
+    }
+
     /// Set application data factory. This function is
     /// similar to `.data()` but it accepts data factory. Data object get
     /// constructed asynchronously during application initialization.
+    ///
+    /// If factory returns error, application initialization fails.
+    /// Use `.data_factory_optional()` for data that application
+    /// could work without.
     pub fn data_factory<F, Out, D, E>(mut self, data: F) -> Self
     where
         F: Fn() -> Out + 'static,
@@ -142,7 +234,145 @@ where
                     }
                     Ok(data) => {
                         let data: Box<dyn DataFactory> = Box::new(Data::new(data));
-                        Ok(data)
+                        Ok(Some(data))
+                    }
+                }
+            })
+        }));
+        self
+    }
+
+    /// Set application data factory with retries.
+    ///
+    /// This function is similar to `.data_factory()`, but factory is
+    /// called again if it returns error, up to `attempts` times in total.
+    /// Retries are delayed, delay is doubled after each failed attempt.
+    /// If the last attempt fails, application initialization fails.
+    ///
+    /// ```rust
+    /// use ntex::time::Seconds;
+    /// use ntex::web::{self, types::Data, App, HttpResponse};
+    ///
+    /// struct Db;
+    ///
+    /// async fn connect() -> Result<Db, std::io::Error> {
+    ///     Ok(Db)
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         // 3 attempts, retries after 1 and 2 seconds
+    ///         .data_factory_retry(3, Seconds(1), connect)
+    ///         .route("/index.html", web::get().to(|_: Data<Db>| async { HttpResponse::Ok() }));
+    /// }
+    /// ```
+    pub fn data_factory_retry<F, Out, D, E, U>(
+        mut self,
+        attempts: usize,
+        delay: U,
+        data: F,
+    ) -> Self
+    where
+        F: Fn() -> Out + 'static,
+        Out: Future<Output = Result<D, E>> + 'static,
+        D: 'static,
+        E: std::fmt::Debug,
+        U: Into<Millis>,
+    {
+        let data = Rc::new(data);
+        let attempts = attempts.max(1);
+        let delay = delay.into();
+
+        self.data_factories.push(Box::new(move || {
+            let data = data.clone();
+            Box::pin(async move {
+                let mut delay = delay;
+                let mut attempt = 1;
+                loop {
+                    match (*data)().await {
+                        Ok(data) => {
+                            let data: Box<dyn DataFactory> = Box::new(Data::new(data));
+                            return Ok(Some(data));
+                        }
+                        Err(e) if attempt < attempts => {
+                            log::warn!(
+                                "Cannot construct data instance, attempt {} of {}: {:?}",
+                                attempt,
+                                attempts,
+                                e
+                            );
+                            sleep(delay).await;
+                            delay = Millis(delay.0.saturating_mul(2));
+                            attempt += 1;
+                        }
+                        Err(e) => {
+                            log::error!(
+                                "Cannot construct data instance after {} attempts: {:?}",
+                                attempts,
+                                e
+                            );
+                            return Err(());
+                        }
+                    }
+                }
+            })
+        }));
+        self
+    }
+
+    /// Set optional application data factory.
+    ///
+    /// This function is similar to `.data_factory()`, but factory error
+    /// does not fail application initialization. Error is logged and
+    /// data is not available, `Data<T>` extractor fails for each request
+    /// with *Internal Server Error* response. `Option<Data<T>>` could be
+    /// used for routes that could work without data.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, types::Data, App, HttpResponse};
+    ///
+    /// struct Cache;
+    ///
+    /// async fn connect() -> Result<Cache, std::io::Error> {
+    ///     Ok(Cache)
+    /// }
+    ///
+    /// async fn index(cache: Option<Data<Cache>>) -> HttpResponse {
+    ///     if cache.is_some() {
+    ///         HttpResponse::Ok().body("cached")
+    ///     } else {
+    ///         HttpResponse::Ok().body("not cached")
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .data_factory_optional(connect)
+    ///         .route("/index.html", web::get().to(index));
+    /// }
+    /// ```
+    pub fn data_factory_optional<F, Out, D, E>(mut self, data: F) -> Self
+    where
+        F: Fn() -> Out + 'static,
+        Out: Future<Output = Result<D, E>> + 'static,
+        D: 'static,
+        E: std::fmt::Debug,
+    {
+        self.data_factories.push(Box::new(move || {
+            let fut = data();
+            Box::pin(async move {
+                match fut.await {
+                    Err(e) => {
+                        log::warn!(
+                            "Cannot construct optional data instance {}: {:?}",
+                            std::any::type_name::<D>(),
+                            e
+                        );
+                        Ok(None)
+                    }
+                    Ok(data) => {
+                        let data: Box<dyn DataFactory> = Box::new(Data::new(data));
+                        Ok(Some(data))
                     }
                 }
             })
@@ -150,6 +380,49 @@ where
         self
     }
 
+    /// Set max number of data factories that initialize concurrently.
+    ///
+    /// At most `num` data factories run at the same time, next factory
+    /// starts as soon as any running factory completes. Data is added
+    /// to application in registration order. By default data factories
+    /// are resolved one by one.
+    pub fn data_factory_concurrency(mut self, num: usize) -> Self {
+        self.data_factory_concurrency = num;
+        self
+    }
+
+    /// Register callback that runs when application service is built.
+    ///
+    /// Application service is built once per worker thread, callback runs
+    /// on the worker thread after app data and data factories are
+    /// initialized. Callback could modify app data, values inserted to
+    /// extensions are available via `HttpRequest::app_data()`.
+    /// Callbacks run in registration order.
+    ///
+    /// ```rust
+    /// use std::cell::Cell;
+    /// use ntex::web::{self, App, HttpRequest};
+    ///
+    /// struct Requests(Cell<usize>);
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .on_start(|ext| ext.insert(Requests(Cell::new(0))))
+    ///         .route("/index.html", web::get().to(|req: HttpRequest| async move {
+    ///             let requests = req.app_data::<Requests>().unwrap();
+    ///             requests.0.set(requests.0.get() + 1);
+    ///             format!("{} requests", requests.0.get())
+    ///         }));
+    /// }
+    /// ```
+    pub fn on_start<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut Extensions) + 'static,
+    {
+        self.on_start.push(Box::new(f));
+        self
+    }
+
     /// Set application level arbitrary data item.
     ///
     /// Application data stored with `App::app_data()` method is available
@@ -193,8 +466,8 @@ where
     {
         let mut cfg = ServiceConfig::new();
         f(&mut cfg);
+        self.services.extend(cfg.take_services());
         self.data.extend(cfg.data);
-        self.services.extend(cfg.services);
         self.external.extend(cfg.external);
         self
     }
@@ -226,6 +499,58 @@ where
         )
     }
 
+    /// Configure named route for a specific path.
+    ///
+    /// This is same as `App::route()` method, but registered resource
+    /// gets a name, so it could be used in `HttpRequest::url_for()`.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .route_named("user", "/user/{id}", web::get().to(|| async { HttpResponse::Ok() }));
+    /// }
+    /// ```
+    pub fn route_named(self, name: &str, path: &str, mut route: Route<Err>) -> Self {
+        self.service(
+            Resource::new(path)
+                .name(name)
+                .add_guards(route.take_guards())
+                .route(route),
+        )
+    }
+
+    /// Configure service for a specific path.
+    ///
+    /// This is a simplified version of the `App::service()` method,
+    /// same as `web::service(path).finish(factory)`.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, DefaultError, Error, HttpResponse};
+    ///
+    /// async fn index(req: web::WebRequest<DefaultError>) -> Result<web::WebResponse, Error> {
+    ///     Ok(req.into_response(HttpResponse::Ok().finish()))
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().route_service("/test", index);
+    /// }
+    /// ```
+    pub fn route_service<S, U>(self, path: &str, factory: U) -> Self
+    where
+        U: IntoServiceFactory<S>,
+        S: ServiceFactory<
+                Config = (),
+                Request = WebRequest<Err>,
+                Response = WebResponse,
+                Error = Err::Container,
+                InitError = (),
+            > + 'static,
+    {
+        self.service(WebServiceAdapter::new(path).finish(factory.into_factory()))
+    }
+
     /// Register http service.
     ///
     /// Http service is any type that implements `WebServiceFactory` trait.
@@ -244,6 +569,34 @@ where
         self
     }
 
+    /// Register multiple http services.
+    ///
+    /// This method is similar to `.service()`, services are registered
+    /// in iteration order.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let resources: Vec<_> = ["/a", "/b", "/c"]
+    ///         .iter()
+    ///         .map(|path| web::resource(*path).to(|| async { HttpResponse::Ok() }))
+    ///         .collect();
+    ///
+    ///     let app = App::new().services(resources);
+    /// }
+    /// ```
+    pub fn services<I, F>(mut self, factories: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: WebServiceFactory<Err> + 'static,
+    {
+        for factory in factories {
+            self = self.service(factory);
+        }
+        self
+    }
+
     /// Default service to be used if no matching resource could be found.
     ///
     /// It is possible to use services like `Resource`, `Route`.
@@ -319,17 +672,61 @@ where
     ///         .external_resource("youtube", "https://youtube.com/watch/{video_id}");
     /// }
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if url pattern is malformed, i.e. contains unclosed or
+    /// unnamed dynamic segment.
     pub fn external_resource<N, U>(mut self, name: N, url: U) -> Self
     where
         N: AsRef<str>,
         U: AsRef<str>,
     {
-        let mut rdef = ResourceDef::new(url.as_ref());
+        let url = url.as_ref();
+        let mut rdef =
+            panic::catch_unwind(|| ResourceDef::new(url)).unwrap_or_else(|_| {
+                panic!(
+                    "External resource {:?} has malformed url {:?}",
+                    name.as_ref(),
+                    url
+                )
+            });
         *rdef.name_mut() = name.as_ref().to_string();
         self.external.push(rdef);
         self
     }
 
+    /// Register multiple external resources.
+    ///
+    /// Iterator yields `(name, url)` pairs, see `App::external_resource()`.
+    ///
+    /// ```rust
+    /// use ntex::web::App;
+    ///
+    /// fn main() {
+    ///     let app = App::new().external_resources(vec![
+    ///         ("youtube", "https://youtube.com/watch/{video_id}"),
+    ///         ("github", "https://github.com/{user}/{repo}"),
+    ///     ]);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics on first url pattern with malformed dynamic segment,
+    /// panic message contains name of the resource.
+    pub fn external_resources<I, N, U>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = (N, U)>,
+        N: AsRef<str>,
+        U: AsRef<str>,
+    {
+        for (name, url) in iter {
+            self = self.external_resource(name, url);
+        }
+        self
+    }
+
     /// Register request filter.
     ///
     /// Filter runs during inbound processing in the request
@@ -339,7 +736,8 @@ where
     /// Use filter when you need to read or modify *every* request in some way.
     /// If filter returns request object then pipeline execution continues
     /// to the next service in pipeline. In case of response, it get returned
-    /// immediately.
+    /// immediately. Values stored with `WebRequest::insert_extension()`
+    /// are available to handlers via `HttpRequest::extensions()`.
     ///
     /// ```rust
     /// use ntex::http::header::{CONTENT_TYPE, HeaderValue};
@@ -384,12 +782,23 @@ where
             middleware: self.middleware,
             data: self.data,
             data_factories: self.data_factories,
+            data_factory_concurrency: self.data_factory_concurrency,
             services: self.services,
             default: self.default,
             external: self.external,
             extensions: self.extensions,
             error_renderer: self.error_renderer,
             case_insensitive: self.case_insensitive,
+            case_insensitive_capture: self.case_insensitive_capture,
+            automatic_options: self.automatic_options,
+            allow_malformed_uri: self.allow_malformed_uri,
+            reject_malformed_query: self.reject_malformed_query,
+            trailing_slash: self.trailing_slash,
+            on_response: self.on_response,
+            catch_panic: self.catch_panic,
+            probes: self.probes,
+            on_start: self.on_start,
+            map_target: self.map_target,
         }
     }
 
@@ -425,93 +834,563 @@ where
             filter: self.filter,
             data: self.data,
             data_factories: self.data_factories,
+            data_factory_concurrency: self.data_factory_concurrency,
             services: self.services,
             default: self.default,
             external: self.external,
             extensions: self.extensions,
             error_renderer: self.error_renderer,
             case_insensitive: self.case_insensitive,
+            case_insensitive_capture: self.case_insensitive_capture,
+            automatic_options: self.automatic_options,
+            allow_malformed_uri: self.allow_malformed_uri,
+            reject_malformed_query: self.reject_malformed_query,
+            trailing_slash: self.trailing_slash,
+            on_response: self.on_response,
+            catch_panic: self.catch_panic,
+            probes: self.probes,
+            on_start: self.on_start,
+            map_target: self.map_target,
         }
     }
 
-    /// Use ascii case-insensitive routing.
+    /// Registers middleware, in the form of a closure.
     ///
-    /// Only static segments could be case-insensitive.
-    pub fn case_insensitive_routing(mut self) -> Self {
-        self.case_insensitive = true;
-        self
-    }
-}
-
-impl<M, F, Err> App<M, F, Err>
-where
-    M: Transform<AppService<F::Service, Err>> + 'static,
-    M::Service: Service<
-        Request = WebRequest<Err>,
-        Response = WebResponse,
-        Error = Err::Container,
-    >,
-    F: ServiceFactory<
-        Config = (),
-        Request = WebRequest<Err>,
-        Response = WebRequest<Err>,
-        Error = Err::Container,
-        InitError = (),
-    >,
-    F::Future: 'static,
-    Err: ErrorRenderer,
-{
-    /// Construct service factory with default `AppConfig`, suitable for `http::HttpService`.
+    /// Closure receives request and inner service, it could be used for
+    /// small cross-cutting concerns without defining `Transform` type.
+    /// Future returned by `Next::call()` does not borrow inner service and
+    /// could be moved to `async` block.
     ///
-    /// ```rust,no_run
-    /// use ntex::{web, http, server};
+    /// ```rust
+    /// use ntex::http::header::{HeaderValue, CONTENT_TYPE};
+    /// use ntex::web::{self, App};
     ///
-    /// #[ntex::main]
-    /// async fn main() -> std::io::Result<()> {
-    ///     server::build().bind("http", "127.0.0.1:0", ||
-    ///         http::HttpService::build().finish(
-    ///             web::App::new()
-    ///                 .route("/index.html", web::get().to(|| async { "hello_world" }))
-    ///                 .finish()
-    ///         ).tcp()
-    ///     )?
-    ///     .run()
-    ///     .await
+    /// async fn index() -> &'static str {
+    ///     "Welcome!"
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .wrap_fn(|req, next| {
+    ///             let fut = next.call(req);
+    ///             async move {
+    ///                 let mut res = fut.await?;
+    ///                 res.headers_mut()
+    ///                     .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    ///                 Ok(res)
+    ///             }
+    ///         })
+    ///         .route("/index.html", web::get().to(index));
     /// }
     /// ```
-    pub fn finish(
-        self,
-    ) -> impl ServiceFactory<
-        Config = (),
-        Request = Request,
-        Response = WebResponse,
-        Error = Err::Container,
-        InitError = (),
-    > {
-        map_config(self.into_factory(), move |_| Default::default())
+    pub fn wrap_fn<F, R>(self, f: F) -> App<Stack<M, WrapFn<F, Err>>, T, Err>
+    where
+        F: Fn(WebRequest<Err>, &Next<Err>) -> R,
+        R: Future<Output = Result<WebResponse, Err::Container>>,
+    {
+        self.wrap(WrapFn::new(f))
     }
 
-    /// Construct service factory suitable for `http::HttpService`.
+    /// Registers middleware that is applied only to responses with
+    /// matching content type.
     ///
-    /// ```rust,no_run
-    /// use ntex::{web, http, server};
+    /// Inner middleware is called after response is produced, responses
+    /// with other content types are passed as is.
+    /// See [`OnContentType`](super::middleware::OnContentType) for details.
     ///
-    /// #[ntex::main]
-    /// async fn main() -> std::io::Result<()> {
-    ///     server::build().bind("http", "127.0.0.1:0", ||
-    ///         http::HttpService::build().finish(
-    ///             web::App::new()
-    ///                 .route("/index.html", web::get().to(|| async { "hello_world" }))
-    ///                 .with_config(web::dev::AppConfig::default())
-    ///         ).tcp()
-    ///     )?
-    ///     .run()
-    ///     .await
+    /// ```rust
+    /// use ntex::util::Bytes;
+    /// use ntex::web::{self, middleware, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .wrap_on_content_type(
+    ///             mime::TEXT_HTML,
+    ///             middleware::MapBody::new(|_| true, |body: Bytes| body),
+    ///         )
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
     /// }
     /// ```
-    pub fn with_config(
+    pub fn wrap_on_content_type<U>(
         self,
-        cfg: AppConfig,
+        mime: mime::Mime,
+        mw: U,
+    ) -> App<Stack<M, OnContentType<U>>, T, Err> {
+        self.wrap(OnContentType::new(mime, mw))
+    }
+
+    /// Register a hook that is called for every response.
+    ///
+    /// Hook runs at the outermost layer, after all middlewares, right before
+    /// response is passed to the http service. Errors returned by handlers
+    /// and by middlewares are rendered to responses before hook is called,
+    /// so hook sees every response, including responses of default
+    /// service. Request's head is copied for each request if hook is set.
+    ///
+    /// ```rust
+    /// use ntex::http::header::{HeaderName, HeaderValue};
+    /// use ntex::web::{self, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .on_response(|res| {
+    ///             res.headers_mut().insert(
+    ///                 HeaderName::from_static("x-version"),
+    ///                 HeaderValue::from_static("1.0"),
+    ///             );
+    ///         })
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn on_response<U>(mut self, f: U) -> Self
+    where
+        U: Fn(&mut WebResponse) + 'static,
+    {
+        self.on_response = Some(Rc::new(f));
+        self
+    }
+
+    /// Convert handler panics to responses.
+    ///
+    /// By default panic in a handler drops connection without response.
+    /// If handler is set, panics in services are caught and response,
+    /// usually *500 Internal Server Error*, is rendered by `f`. Request
+    /// passed to `f` contains copy of the original request's head, so
+    /// request's head is copied for each request.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpRequest, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .catch_panic(|req: &HttpRequest| {
+    ///             HttpResponse::InternalServerError()
+    ///                 .body(format!("{} is broken", req.path()))
+    ///         })
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn catch_panic<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + 'static,
+    {
+        self.catch_panic = Some(Rc::new(f));
+        self
+    }
+
+    /// Rewrite request target before routing.
+    ///
+    /// Function receives parsed request uri and returns uri that is used
+    /// for routing, it runs before probes, middlewares and resource matching.
+    /// Rewritten uri is available to handlers via `HttpRequest::uri()`,
+    /// original uri is not preserved. This could be used for uri
+    /// canonicalization in a single place.
+    ///
+    /// ```rust
+    /// use ntex::http::Uri;
+    /// use ntex::web::{self, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         // "/Index.html" and "/index.html" are the same path
+    ///         .map_target(|uri: Uri| {
+    ///             let path = uri.path().to_ascii_lowercase();
+    ///             match uri.query() {
+    ///                 Some(q) => format!("{}?{}", path, q).parse().unwrap_or(uri),
+    ///                 None => path.parse().unwrap_or(uri),
+    ///             }
+    ///         })
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn map_target<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Uri) -> Uri + 'static,
+    {
+        self.map_target = Some(Rc::new(f));
+        self
+    }
+
+    /// Register health or readiness probe.
+    ///
+    /// Requests with path equal to `path` are handled by `f` before
+    /// application's middlewares and routing, so probes are cheap and are
+    /// not affected by authentication or logging middlewares. Probe takes
+    /// precedence over resources registered for the same path. Probe
+    /// responses are not passed to `on_response` hook.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpRequest, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .probe("/healthz", |_: &HttpRequest| HttpResponse::Ok().finish())
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn probe<F>(mut self, path: &str, f: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + 'static,
+    {
+        self.probes.push((path.to_string(), Rc::new(f)));
+        self
+    }
+
+    /// Set log level selector for errors rendered to responses.
+    ///
+    /// Selector receives response status code and returns log level,
+    /// `None` disables logging for specified status. By default internal
+    /// server errors (500) are logged at `error` level and all other
+    /// errors at `debug` level.
+    ///
+    /// ```rust
+    /// use ntex::http::StatusCode;
+    /// use ntex::web::{self, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .error_log_level(|status: StatusCode| {
+    ///             if status == StatusCode::NOT_FOUND {
+    ///                 None
+    ///             } else if status.is_server_error() {
+    ///                 Some(log::Level::Error)
+    ///             } else {
+    ///                 Some(log::Level::Info)
+    ///             }
+    ///         })
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn error_log_level<U>(mut self, f: U) -> Self
+    where
+        U: Fn(StatusCode) -> Option<log::Level> + 'static,
+    {
+        self.extensions.insert(ErrorLogLevel(Rc::new(f)));
+        self
+    }
+
+    /// Set runtime error renderer for the application.
+    ///
+    /// Renderer receives request, error and response rendered by
+    /// application's `Err` renderer, and returns final response.
+    /// Renderer could be overridden for a subtree with
+    /// `Scope::error_renderer()`.
+    pub fn error_renderer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest, &dyn fmt::Display, HttpResponse) -> HttpResponse + 'static,
+    {
+        self.extensions.insert(ErrorRendererFn(Rc::new(f)));
+        self
+    }
+
+    /// Set renderer for *404 Not Found* responses.
+    ///
+    /// Renderer is used if request path does not match any resource and
+    /// default service is not set. It is not used if resource matched the
+    /// request path but rejected request by method guard.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpRequest, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .not_found(|req: &HttpRequest| {
+    ///             HttpResponse::NotFound().body(format!("{} is not found", req.path()))
+    ///         })
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn not_found<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + 'static,
+    {
+        self.extensions.insert(NotFound(Rc::new(f)));
+        self
+    }
+
+    /// Set renderer for *405 Method Not Allowed* responses.
+    ///
+    /// Renderer is used if resource matched the request path but rejected
+    /// request by method guards, `HttpRequest::matched_methods()` returns
+    /// methods of the matched resource. Renderer takes precedence over
+    /// app's and scope's default service, resource's default service takes
+    /// precedence over renderer.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpRequest, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .method_not_allowed(|req: &HttpRequest| {
+    ///             HttpResponse::MethodNotAllowed()
+    ///                 .body(format!("{} is not allowed", req.method()))
+    ///         })
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn method_not_allowed<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + 'static,
+    {
+        self.extensions.insert(MethodNotAllowed(Rc::new(f)));
+        self
+    }
+
+    /// Set application-wide max size of request's payload.
+    ///
+    /// Limit is used by `Bytes`, `String`, `Json` and `Form` extractors
+    /// unless more specific config, i.e. `PayloadConfig`, `JsonConfig`
+    /// or `FormConfig`, is set. `Payload` stream is limited only by this
+    /// setting, it is not limited by default. Extractors fail with
+    /// overflow error if payload is larger than limit.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .payload_limit(1024 * 1024)
+    ///         .route("/index.html", web::post().to(|body: String| async move { body }));
+    /// }
+    /// ```
+    pub fn payload_limit(mut self, limit: usize) -> Self {
+        self.extensions.insert(PayloadLimit(limit));
+        self
+    }
+
+    /// Set default handler timeout for all routes of the application.
+    ///
+    /// Timeout is applied to routes without own timeout, see
+    /// `Route::timeout()`. If handler does not complete within timeout,
+    /// handler's future is dropped and *504 Gateway Timeout* response is
+    /// returned. Clients could shorten timeout of the request with
+    /// `Request-Timeout` header, value is a number of seconds, i.e. `2.5`.
+    /// Header value larger than default timeout is ignored, so clients
+    /// cannot extend timeout. Zero duration disables default timeout.
+    ///
+    /// ```rust
+    /// use ntex::{time::Seconds, web::{self, App}};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .default_timeout(Seconds(30))
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn default_timeout<U>(mut self, timeout: U) -> Self
+    where
+        U: Into<Millis>,
+        TimeoutError<Infallible>: Into<Err::Container>,
+    {
+        let timeout = timeout.into();
+        if timeout.is_zero() {
+            self.extensions.remove::<DefaultTimeout<Err>>();
+        } else {
+            self.extensions.insert(DefaultTimeout::<Err> {
+                timeout,
+                err: || TimeoutError::<Infallible>::Timeout.into(),
+            });
+        }
+        self
+    }
+
+    /// Use ascii case-insensitive routing.
+    ///
+    /// Only static segments could be case-insensitive.
+    pub fn case_insensitive_routing(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Convert values of dynamic segments to ascii lowercase.
+    ///
+    /// Matched values are lowercased before they are available to
+    /// `Path` extractor and `HttpRequest::match_info()`, for all
+    /// resources including nested scopes. Only ascii letters are converted,
+    /// non-ascii characters and percent-encoded sequences are preserved.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, types::Path, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .case_insensitive_routing()
+    ///         .case_insensitive_capture()
+    ///         .route("/users/{name}", web::get().to(|name: Path<String>| async move {
+    ///             // "/Users/John" and "/users/john" get the same value
+    ///             name.into_inner()
+    ///         }));
+    /// }
+    /// ```
+    pub fn case_insensitive_capture(mut self) -> Self {
+        self.case_insensitive_capture = true;
+        self
+    }
+
+    /// Respond to `OPTIONS` requests automatically for all resources.
+    ///
+    /// If matched resource has no route for `OPTIONS` method, *200*
+    /// response with `Allow` header is returned. Header lists methods of
+    /// resource's routes. Resources configured with
+    /// `Resource::auto_options()` respond with *204*.
+    pub fn automatic_options(mut self) -> Self {
+        self.automatic_options = true;
+        self
+    }
+
+    /// Pass requests with malformed percent-encoding to services.
+    ///
+    /// By default requests with malformed percent-encoded sequences in
+    /// path, like `%zz` or `%2`, and with sequences that do not decode
+    /// to valid utf-8 are rejected with *400 Bad Request*. Query string
+    /// is not checked unless `App::reject_malformed_query()` is set.
+    pub fn allow_malformed_uri(mut self) -> Self {
+        self.allow_malformed_uri = true;
+        self
+    }
+
+    /// Reject requests with malformed percent-encoding in query string.
+    ///
+    /// Query string is checked with the same rules as request path.
+    /// It is not checked by default, because query values like `q=100%`
+    /// or non utf-8 form values are common in real traffic.
+    pub fn reject_malformed_query(mut self) -> Self {
+        self.reject_malformed_query = true;
+        self
+    }
+
+    /// Set handling of trailing slash in request paths.
+    ///
+    /// By default paths are matched as is, `/users` and `/users/` are
+    /// different paths. Root path `/` is never changed.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, TrailingSlash};
+    ///
+    /// fn main() {
+    ///     // "/users/" is redirected to "/users"
+    ///     let app = App::new()
+    ///         .trailing_slash(TrailingSlash::RedirectToNoSlash)
+    ///         .route("/users", web::get().to(|| async { "users" }));
+    /// }
+    /// ```
+    pub fn trailing_slash(mut self, mode: TrailingSlash) -> Self {
+        self.trailing_slash = mode;
+        self
+    }
+}
+
+impl<M, F, Err> App<M, F, Err>
+where
+    M: Transform<AppService<F::Service, Err>> + 'static,
+    M::Service: Service<
+        Request = WebRequest<Err>,
+        Response = WebResponse,
+        Error = Err::Container,
+    >,
+    F: ServiceFactory<
+        Config = (),
+        Request = WebRequest<Err>,
+        Response = WebRequest<Err>,
+        Error = Err::Container,
+        InitError = (),
+    >,
+    F::Future: 'static,
+    Err: ErrorRenderer,
+{
+    /// Printable description of the application.
+    ///
+    /// Description contains app middlewares in order of request processing
+    /// and registered resources with methods of resource's routes. Middleware
+    /// name is provided by `Transform::name()` method.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, middleware, App};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .wrap(middleware::Logger::default())
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    ///     println!("{}", app.debug_routes());
+    /// }
+    /// ```
+    pub fn debug_routes(&self) -> String {
+        let mut names = Vec::new();
+        self.middleware.names(&mut names);
+
+        let mut s = String::from("middleware:\n");
+        for name in names {
+            let _ = writeln!(s, "    {}", name);
+        }
+        s.push_str("resources:\n");
+        for srv in self.services.iter() {
+            for (path, methods) in srv.describe() {
+                let methods = if methods.is_empty() {
+                    "*".to_string()
+                } else {
+                    methods
+                        .iter()
+                        .map(|m| m.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let _ = writeln!(s, "    {} {}", methods, path);
+            }
+        }
+        s
+    }
+
+    /// Construct service factory with default `AppConfig`, suitable for `http::HttpService`.
+    ///
+    /// ```rust,no_run
+    /// use ntex::{web, http, server};
+    ///
+    /// #[ntex::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     server::build().bind("http", "127.0.0.1:0", ||
+    ///         http::HttpService::build().finish(
+    ///             web::App::new()
+    ///                 .route("/index.html", web::get().to(|| async { "hello_world" }))
+    ///                 .finish()
+    ///         ).tcp()
+    ///     )?
+    ///     .run()
+    ///     .await
+    /// }
+    /// ```
+    pub fn finish(
+        self,
+    ) -> impl ServiceFactory<
+        Config = (),
+        Request = Request,
+        Response = WebResponse,
+        Error = Err::Container,
+        InitError = (),
+    > {
+        map_config(self.into_factory(), move |_| Default::default())
+    }
+
+    /// Construct service factory suitable for `http::HttpService`.
+    ///
+    /// ```rust,no_run
+    /// use ntex::{web, http, server};
+    ///
+    /// #[ntex::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     server::build().bind("http", "127.0.0.1:0", ||
+    ///         http::HttpService::build().finish(
+    ///             web::App::new()
+    ///                 .route("/index.html", web::get().to(|| async { "hello_world" }))
+    ///                 .with_config(web::dev::AppConfig::default())
+    ///         ).tcp()
+    ///     )?
+    ///     .run()
+    ///     .await
+    /// }
+    /// ```
+    pub fn with_config(
+        self,
+        cfg: AppConfig,
     ) -> impl ServiceFactory<
         Config = (),
         Request = Request,
@@ -547,11 +1426,22 @@ where
             middleware: Rc::new(self.middleware),
             data: Rc::new(self.data),
             data_factories: Rc::new(self.data_factories),
+            data_factory_concurrency: self.data_factory_concurrency,
             services: Rc::new(RefCell::new(self.services)),
             external: RefCell::new(self.external),
             default: self.default,
             extensions: RefCell::new(Some(self.extensions)),
             case_insensitive: self.case_insensitive,
+            case_insensitive_capture: self.case_insensitive_capture,
+            automatic_options: self.automatic_options,
+            allow_malformed_uri: self.allow_malformed_uri,
+            reject_malformed_query: self.reject_malformed_query,
+            trailing_slash: self.trailing_slash,
+            on_response: self.on_response,
+            catch_panic: self.catch_panic,
+            probes: Rc::new(self.probes),
+            on_start: Rc::new(self.on_start),
+            map_target: self.map_target,
         }
     }
 }
@@ -577,6 +1467,11 @@ where
     fn new_transform(&self, service: S) -> Self::Service {
         self.outer.new_transform(self.inner.new_transform(service))
     }
+
+    fn names(&self, names: &mut Vec<String>) {
+        self.outer.names(names);
+        self.inner.names(names);
+    }
 }
 
 pub struct Filter<Err>(PhantomData<Err>);
@@ -628,17 +1523,38 @@ mod tests {
     use crate::http::header::{self, HeaderValue};
     use crate::http::{Method, StatusCode};
     use crate::service::{fn_service, Service};
-    use crate::util::{Ready};
-    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::util::{Bytes, Ready};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
     use crate::web::{
         self, middleware::DefaultHeaders, request::WebRequest, DefaultError,
         HttpRequest, HttpResponse,
     };
 
     #[crate::rt_test]
-    async fn test_default_resource() {
-        let srv = init_service(
-            App::new()
+    async fn test_services() {
+        let srv = init_service(App::new().services(vec![
+            web::resource("/a").to(|| async { "a" }),
+            web::resource("/b").to(|| async { "b" }),
+            web::resource("/c").to(|| async { "c" }),
+        ]))
+        .await;
+
+        for (path, body) in &[("/a", b"a"), ("/b", b"b"), ("/c", b"c")] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(read_body(resp).await, Bytes::from_static(*body));
+        }
+
+        let req = TestRequest::with_uri("/d").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[crate::rt_test]
+    async fn test_default_resource() {
+        let srv = init_service(
+            App::new()
                 .service(web::resource("/test").to(|| async { HttpResponse::Ok() })),
         )
         .await;
@@ -710,6 +1626,421 @@ mod tests {
         assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[crate::rt_test]
+    async fn test_not_found() {
+        let srv = init_service(
+            App::new()
+                .not_found(|req: &HttpRequest| {
+                    HttpResponse::NotFound()
+                        .header("x-not-found", "1")
+                        .body(format!("{} is missing", req.path()))
+                })
+                .route("/test", web::get().to(|| async { HttpResponse::Ok() }))
+                .service(
+                    web::scope("/scope")
+                        .route("/test", web::get().to(|| async { HttpResponse::Ok() })),
+                ),
+        )
+        .await;
+
+        for path in &["/missing", "/scope/missing"] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+            assert_eq!(resp.headers().get("x-not-found").unwrap(), "1");
+            assert_eq!(
+                read_body(resp).await,
+                Bytes::from(format!("{} is missing", path))
+            );
+        }
+
+        // resource matched but method guard rejected request
+        let req = TestRequest::with_uri("/test")
+            .method(Method::POST)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert!(resp.headers().get("x-not-found").is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_method_not_allowed() {
+        let not_found = Rc::new(std::cell::Cell::new(0));
+        let not_found2 = not_found.clone();
+        let srv = init_service(
+            App::new()
+                .method_not_allowed(|req: &HttpRequest| {
+                    HttpResponse::MethodNotAllowed()
+                        .header("x-allowed", format!("{:?}", req.matched_methods()))
+                        .finish()
+                })
+                .default_service(move |req: WebRequest<DefaultError>| {
+                    not_found2.set(not_found2.get() + 1);
+                    async move { Ok(req.into_response(HttpResponse::NotFound())) }
+                })
+                .service(
+                    web::resource("/x")
+                        .route(web::get().to(|| async { HttpResponse::Ok() })),
+                )
+                .route("/y", web::get().to(|| async { HttpResponse::Ok() })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/x").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        for path in &["/x", "/y"] {
+            let req = TestRequest::with_uri(path)
+                .method(Method::POST)
+                .to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(resp.headers().get("x-allowed").unwrap(), "Some([GET])");
+        }
+        assert_eq!(not_found.get(), 0);
+
+        let req = TestRequest::with_uri("/missing").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(not_found.get(), 1);
+    }
+
+    #[crate::rt_test]
+    async fn test_payload_limit() {
+        use crate::web::types::{Form, Json, Payload, PayloadConfig};
+        use std::collections::HashMap;
+
+        let srv = init_service(
+            App::new()
+                .payload_limit(1024 * 1024)
+                .route("/bytes", web::post().to(|body: Bytes| async move { body }))
+                .route(
+                    "/string",
+                    web::post().to(|body: String| async move { body }),
+                )
+                .route(
+                    "/json",
+                    web::post().to(|body: Json<serde_json::Value>| async move {
+                        body.to_string()
+                    }),
+                )
+                .route(
+                    "/form",
+                    web::post().to(|body: Form<HashMap<String, String>>| async move {
+                        body.len().to_string()
+                    }),
+                )
+                .route(
+                    "/stream",
+                    web::post().to(|mut body: Payload| async move {
+                        let mut size = 0;
+                        while let Some(chunk) = crate::util::next(&mut body).await {
+                            size += chunk.map_err(web::error::PayloadError::from)?.len();
+                        }
+                        Ok::<_, web::error::PayloadError>(size.to_string())
+                    }),
+                )
+                .service(
+                    web::resource("/override")
+                        .app_data(PayloadConfig::new(4 * 1024 * 1024))
+                        .route(
+                            web::post()
+                                .to(|body: Bytes| async move { body.len().to_string() }),
+                        ),
+                ),
+        )
+        .await;
+
+        let big = "a".repeat(2 * 1024 * 1024);
+        for path in &["/bytes", "/string", "/stream"] {
+            let req = TestRequest::post()
+                .uri(path)
+                .set_payload(big.clone())
+                .to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE, "{}", path);
+
+            let req = TestRequest::post()
+                .uri(path)
+                .set_payload("data")
+                .to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", path);
+        }
+
+        let req = TestRequest::post()
+            .uri("/json")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(format!("\"{}\"", big))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::post()
+            .uri("/form")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload(format!("a={}", big))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // resource config overrides app limit
+        let req = TestRequest::post()
+            .uri("/override")
+            .set_payload(big.clone())
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from((2 * 1024 * 1024).to_string())
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_data_arc() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let app = || {
+            init_service(App::new().data_arc(counter.clone()).service(
+                web::resource("/").to(
+                    |data: web::types::Data<AtomicUsize>| async move {
+                        data.fetch_add(1, Ordering::Relaxed).to_string()
+                    },
+                ),
+            ))
+        };
+        let srv1 = app().await;
+        let srv2 = app().await;
+
+        let res = srv1
+            .call(TestRequest::default().to_request())
+            .await
+            .unwrap();
+        assert_eq!(read_body(res).await, Bytes::from_static(b"0"));
+        let res = srv2
+            .call(TestRequest::default().to_request())
+            .await
+            .unwrap();
+        assert_eq!(read_body(res).await, Bytes::from_static(b"1"));
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+    }
+
+    #[crate::rt_test]
+    async fn test_data_factory_retry() {
+        use std::cell::Cell;
+
+        let calls = Rc::new(Cell::new(0));
+        let calls2 = calls.clone();
+        let srv = init_service(
+            App::new()
+                .data_factory_retry(3, crate::time::Millis(10), move || {
+                    let calls = calls2.clone();
+                    async move {
+                        calls.set(calls.get() + 1);
+                        if calls.get() < 3 {
+                            Err("not ready")
+                        } else {
+                            Ok(10usize)
+                        }
+                    }
+                })
+                .service(web::resource("/").to(
+                    |data: web::types::Data<usize>| async move {
+                        HttpResponse::Ok().body(format!("{}", data.get_ref()))
+                    },
+                )),
+        )
+        .await;
+        assert_eq!(calls.get(), 3);
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"10"));
+
+        // all attempts fail
+        let calls = Rc::new(Cell::new(0));
+        let calls2 = calls.clone();
+        let app = App::new().data_factory_retry(2, crate::time::Millis(10), move || {
+            calls2.set(calls2.get() + 1);
+            async { Err::<usize, _>("not ready") }
+        });
+        let res = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(res.is_err());
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[crate::rt_test]
+    async fn test_data_factory_concurrency() {
+        use std::cell::Cell;
+
+        let current = Rc::new(Cell::new(0));
+        let max = Rc::new(Cell::new(0));
+
+        let mut app = App::new().data_factory_concurrency(2);
+        for i in 0..5usize {
+            let current = current.clone();
+            let max = max.clone();
+            app = app.data_factory(move || {
+                let current = current.clone();
+                let max = max.clone();
+                async move {
+                    current.set(current.get() + 1);
+                    max.set(std::cmp::max(max.get(), current.get()));
+                    crate::time::sleep(crate::time::Millis(25)).await;
+                    current.set(current.get() - 1);
+                    Ok::<_, ()>(i)
+                }
+            });
+        }
+        let srv = init_service(app.service(web::resource("/").to(
+            |data: web::types::Data<usize>| async move {
+                HttpResponse::Ok().body(format!("{}", data.get_ref()))
+            },
+        )))
+        .await;
+        assert_eq!(max.get(), 2);
+        assert_eq!(current.get(), 0);
+
+        // data is created in registration order
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"0"));
+    }
+
+    #[crate::rt_test]
+    async fn test_data_factory_concurrency_slow() {
+        use std::cell::RefCell;
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let mut app = App::new().data_factory_concurrency(2);
+        for i in 0..5usize {
+            let log = log.clone();
+            app = app.data_factory(move || {
+                let log = log.clone();
+                async move {
+                    log.borrow_mut().push(format!("start {}", i));
+                    let delay = if i == 0 { 200 } else { 10 };
+                    crate::time::sleep(crate::time::Millis(delay)).await;
+                    log.borrow_mut().push(format!("end {}", i));
+                    Ok::<_, ()>(i)
+                }
+            });
+        }
+        let _ =
+            init_service(app.service(web::resource("/").to(|| async { "ok" }))).await;
+
+        // slow factory does not block next factories
+        let log = log.borrow();
+        let pos = |s: &str| log.iter().position(|item| item == s).unwrap();
+        assert!(pos("start 4") < pos("end 0"));
+        assert_eq!(log.last().unwrap(), "end 0");
+    }
+
+    #[crate::rt_test]
+    async fn test_on_start() {
+        use std::cell::Cell;
+
+        struct Seeded(usize);
+
+        let calls = Rc::new(Cell::new(0));
+        let app = |calls: Rc<Cell<usize>>| {
+            App::new()
+                .data_factory(|| async { Ok::<_, ()>(10usize) })
+                .on_start(move |ext| {
+                    calls.set(calls.get() + 1);
+                    // data factories are initialized
+                    let data = *ext.get::<web::types::Data<usize>>().unwrap().get_ref();
+                    ext.insert(Seeded(data + calls.get()));
+                })
+                .service(web::resource("/").to(|req: HttpRequest| async move {
+                    HttpResponse::Ok()
+                        .body(format!("{}", req.app_data::<Seeded>().unwrap().0))
+                }))
+        };
+
+        let srv = init_service(app(calls.clone())).await;
+        assert_eq!(calls.get(), 1);
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"11"));
+
+        let srv = init_service(app(calls.clone())).await;
+        assert_eq!(calls.get(), 2);
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"12"));
+    }
+
+    #[crate::rt_test]
+    async fn test_data_factory_panic() {
+        let app = App::new()
+            .data_factory(|| async { Ok::<_, ()>(10usize) })
+            .data_factory(|| async {
+                if true {
+                    panic!("cannot connect");
+                }
+                Ok::<_, ()>(10u32)
+            })
+            .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+        let res = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(res.is_err());
+
+        // panic in factory's closure
+        let app = App::new()
+            .data_factory(|| -> Ready<usize, ()> { panic!("cannot create") })
+            .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+        let res = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(res.is_err());
+    }
+
+    #[crate::rt_test]
+    async fn test_data_factory_error() {
+        let app = App::new()
+            .data_factory(|| async { Err::<usize, _>("cannot connect") })
+            .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+        let res = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(res.is_err());
+    }
+
+    #[crate::rt_test]
+    async fn test_data_factory_optional() {
+        let srv = init_service(
+            App::new()
+                .data_factory(|| async { Ok::<_, ()>(10usize) })
+                .data_factory_optional(|| async { Err::<u32, _>("cannot connect") })
+                .route(
+                    "/",
+                    web::get()
+                        .to(|_: web::types::Data<usize>| async { HttpResponse::Ok() }),
+                )
+                .route(
+                    "/cache",
+                    web::get()
+                        .to(|_: web::types::Data<u32>| async { HttpResponse::Ok() }),
+                ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/cache").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // optional factories do not change behavior of regular factories
+        let app = App::new()
+            .data_factory_optional(|| async { Err::<u32, _>("cannot connect") })
+            .data_factory(|| async { Err::<usize, _>("cannot connect") })
+            .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+        let res = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(res.is_err());
+    }
+
     #[crate::rt_test]
     async fn test_extension() {
         let srv = init_service(App::new().app_data(10usize).service(
@@ -743,6 +2074,39 @@ mod tests {
         assert!(filter.get());
     }
 
+    #[crate::rt_test]
+    async fn test_filter_extensions() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct TraceId(String);
+
+        let srv = init_service(
+            App::new()
+                .filter(fn_service(|mut req: WebRequest<_>| {
+                    let id = req
+                        .headers()
+                        .get("x-trace-id")
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("none")
+                        .to_string();
+                    req.insert_extension(TraceId(id));
+                    assert!(req.get_extension::<TraceId>().is_some());
+                    Ready::Ok(req)
+                }))
+                .route(
+                    "/test",
+                    web::get().to(|req: HttpRequest| async move {
+                        req.extensions().get::<TraceId>().unwrap().0.clone()
+                    }),
+                ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test")
+            .header("x-trace-id", "abc")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"abc"));
+    }
+
     #[crate::rt_test]
     async fn test_wrap() {
         let srv = init_service(
@@ -763,6 +2127,33 @@ mod tests {
         );
     }
 
+    #[crate::rt_test]
+    async fn test_wrap_fn() {
+        let srv = init_service(
+            App::new()
+                .wrap_fn(|req, next| {
+                    let fut = next.call(req);
+                    async move {
+                        let mut res = fut.await?;
+                        res.headers_mut().insert(
+                            header::CONTENT_TYPE,
+                            HeaderValue::from_static("0001"),
+                        );
+                        Ok(res)
+                    }
+                })
+                .route("/test", web::get().to(|| async { HttpResponse::Ok() })),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("0001")
+        );
+    }
+
     #[crate::rt_test]
     async fn test_router_wrap() {
         let srv = init_service(
@@ -783,6 +2174,91 @@ mod tests {
         );
     }
 
+    #[crate::rt_test]
+    async fn test_on_response() {
+        let srv = init_service(
+            App::new()
+                .on_response(|res| {
+                    res.headers_mut().insert(
+                        header::HeaderName::from_static("x-version"),
+                        HeaderValue::from_static("0001"),
+                    );
+                })
+                .wrap(
+                    DefaultHeaders::new()
+                        .header("x-version", HeaderValue::from_static("0002")),
+                )
+                .route("/test", web::get().to(|| async { HttpResponse::Ok() }))
+                .route(
+                    "/error",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(web::error::ErrorBadRequest("error"))
+                    }),
+                ),
+        )
+        .await;
+
+        for (path, status) in &[
+            ("/test", StatusCode::OK),
+            ("/error", StatusCode::BAD_REQUEST),
+            ("/unknown", StatusCode::NOT_FOUND),
+        ] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), *status);
+            assert_eq!(
+                resp.headers().get("x-version").unwrap(),
+                HeaderValue::from_static("0001")
+            );
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_on_response_errors() {
+        let counter = Rc::new(std::cell::Cell::new(0));
+        let counter2 = counter.clone();
+        let srv = init_service(
+            App::new()
+                .on_response(move |_| counter2.set(counter2.get() + 1))
+                .wrap_fn(|req, next| {
+                    let fail = req.path() == "/middleware";
+                    let fut = next.call(req);
+                    async move {
+                        let res = fut.await?;
+                        if fail {
+                            Err(web::error::ErrorBadRequest("middleware").into())
+                        } else {
+                            Ok(res)
+                        }
+                    }
+                })
+                .route(
+                    "/handler",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(web::error::ErrorConflict("handler"))
+                    }),
+                )
+                .route(
+                    "/middleware",
+                    web::get().to(|| async { HttpResponse::Ok() }),
+                ),
+        )
+        .await;
+
+        // handler error is rendered to response
+        let req = TestRequest::with_uri("/handler").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert_eq!(counter.get(), 1);
+
+        // middleware error is rendered and passed to hook
+        let req = TestRequest::with_uri("/middleware").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"middleware"));
+        assert_eq!(counter.get(), 2);
+    }
+
     #[crate::rt_test]
     async fn test_case_insensitive_router() {
         let srv = init_service(
@@ -800,6 +2276,351 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[crate::rt_test]
+    async fn test_automatic_options() {
+        let srv = init_service(
+            App::new()
+                .automatic_options()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(|| async { HttpResponse::Ok() }))
+                        .route(web::put().to(|| async { HttpResponse::Ok() })),
+                )
+                .service(
+                    web::resource("/custom")
+                        .route(web::get().to(|| async { HttpResponse::Ok() }))
+                        .route(
+                            web::method(Method::OPTIONS)
+                                .to(|| async { HttpResponse::NoContent() }),
+                        ),
+                )
+                .service(web::scope("/scope").service(
+                    web::resource("/test").route(web::post().to(|| async { "post" })),
+                )),
+        )
+        .await;
+
+        for (path, allow) in &[
+            ("/test", "GET, PUT, OPTIONS"),
+            ("/scope/test", "POST, OPTIONS"),
+        ] {
+            let req = TestRequest::with_uri(path)
+                .method(Method::OPTIONS)
+                .to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(resp.headers().get(header::ALLOW).unwrap(), *allow);
+        }
+
+        // user defined OPTIONS route
+        let req = TestRequest::with_uri("/custom")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(!resp.headers().contains_key(header::ALLOW));
+
+        let req = TestRequest::with_uri("/missing")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[crate::rt_test]
+    async fn test_case_insensitive_capture() {
+        async fn params(req: HttpRequest) -> String {
+            let params: Vec<_> = req.match_info().iter().map(|(_, v)| v).collect();
+            params.join(",")
+        }
+
+        let srv = init_service(
+            App::new()
+                .case_insensitive_routing()
+                .case_insensitive_capture()
+                .route("/users/{id}/{id}", web::get().to(params))
+                .service(
+                    web::scope("/{org}").route("/teams/{team}", web::get().to(params)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/Users/AbC/dEf").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"abc,def"));
+
+        let req = TestRequest::with_uri("/Org/teams/%C3%89Team").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from("org,Éteam"));
+
+        let req = TestRequest::with_uri("/users/%C3%89A/B").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from("Éa,b"));
+    }
+
+    #[crate::rt_test]
+    async fn test_catch_panic() {
+        let srv = init_service(
+            App::new()
+                .catch_panic(|req: &HttpRequest| {
+                    HttpResponse::InternalServerError().body(format!(
+                        "panic: {} {}",
+                        req.method(),
+                        req.path()
+                    ))
+                })
+                .wrap_fn(|req, srv| {
+                    if req.path() == "/sync" {
+                        panic!("sync panic");
+                    }
+                    srv.call(req)
+                })
+                .route(
+                    "/test",
+                    web::get().to(|| async {
+                        if true {
+                            panic!("handler panic");
+                        }
+                        HttpResponse::Ok().finish()
+                    }),
+                )
+                .route("/ok", web::get().to(|| async { "ok" })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"panic: GET /test")
+        );
+
+        let req = TestRequest::with_uri("/sync")
+            .method(Method::POST)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"panic: POST /sync")
+        );
+
+        let req = TestRequest::with_uri("/ok").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"ok"));
+    }
+
+    #[crate::rt_test]
+    async fn test_malformed_uri() {
+        let srv =
+            init_service(App::new().route(
+                "/{name}",
+                web::get().to(|name: web::types::Path<String>| async move {
+                    name.into_inner()
+                }),
+            ))
+            .await;
+
+        for uri in &[
+            "/test%", "/test%2", "/test%zz", "/%2z",
+            // overlong utf-8 encoding of '/'
+            "/%C0%AF", "/%FF",
+        ] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+
+        // query string is not checked by default
+        for uri in &["/test?q=100%", "/test?q=%zz", "/test?info_hash=%FF%C0%AF"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+        }
+
+        let req = TestRequest::with_uri("/test%zz").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"Malformed percent-encoded sequence")
+        );
+        let req = TestRequest::with_uri("/%C0%AF").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"Percent-encoded sequence is not valid utf-8")
+        );
+
+        let req = TestRequest::with_uri("/%C3%89a?q=%20").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from("Éa"));
+
+        let srv =
+            init_service(App::new().allow_malformed_uri().route(
+                "/{name}",
+                web::get().to(|name: web::types::Path<String>| async move {
+                    name.into_inner()
+                }),
+            ))
+            .await;
+        let req = TestRequest::with_uri("/test%zz").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"test%zz"));
+
+        let req = TestRequest::with_uri("/%C0%AF").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"%C0%AF"));
+
+        let srv =
+            init_service(App::new().reject_malformed_query().route(
+                "/{name}",
+                web::get().to(|name: web::types::Path<String>| async move {
+                    name.into_inner()
+                }),
+            ))
+            .await;
+        for uri in &["/test?q=%", "/test?q=%2", "/test?q=%zz", "/test?q=%C0%AF"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+        let req = TestRequest::with_uri("/test?q=%20").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_trailing_slash() {
+        async fn status(
+            mode: TrailingSlash,
+            path: &str,
+            uri: &str,
+        ) -> (StatusCode, String) {
+            let srv = init_service(
+                App::new()
+                    .trailing_slash(mode)
+                    .route(path, web::get().to(|| async { HttpResponse::Ok() }))
+                    .service(
+                        web::scope("/s")
+                            .route("/", web::get().to(|| async { HttpResponse::Ok() })),
+                    ),
+            )
+            .await;
+            let req = TestRequest::with_uri(uri).to_request();
+            let resp = call_service(&srv, req).await;
+            let location = resp
+                .headers()
+                .get(header::LOCATION)
+                .map(|v| v.to_str().unwrap().to_string())
+                .unwrap_or_default();
+            (resp.status(), location)
+        }
+        let ok = (StatusCode::OK, String::new());
+        let not_found = (StatusCode::NOT_FOUND, String::new());
+
+        assert_eq!(status(TrailingSlash::Strict, "/a", "/a").await, ok);
+        assert_eq!(status(TrailingSlash::Strict, "/a", "/a/").await, not_found);
+
+        assert_eq!(status(TrailingSlash::Merge, "/a", "/a").await, ok);
+        assert_eq!(status(TrailingSlash::Merge, "/a", "/a/").await, ok);
+        assert_eq!(status(TrailingSlash::Merge, "/a/", "/a").await, ok);
+        assert_eq!(status(TrailingSlash::Strict, "/a", "/s/").await, ok);
+        assert_eq!(status(TrailingSlash::Merge, "/a", "/s").await, ok);
+        assert_eq!(status(TrailingSlash::Merge, "/a", "/b/").await, not_found);
+
+        let redirect =
+            |location: &str| (StatusCode::PERMANENT_REDIRECT, location.to_string());
+        let mode = TrailingSlash::RedirectToNoSlash;
+        assert_eq!(status(mode, "/a", "/a").await, ok);
+        assert_eq!(status(mode, "/a", "/a/").await, redirect("/a"));
+        assert_eq!(status(mode, "/a", "/a//?q=1").await, redirect("/a?q=1"));
+        assert_eq!(status(mode, "/", "/").await, ok);
+        assert_eq!(
+            status(mode, "/a", "//evil.com/").await,
+            redirect("/evil.com")
+        );
+        assert_eq!(
+            status(mode, "/a", "/%5Cevil.com/").await,
+            redirect("/%5Cevil.com")
+        );
+        assert_eq!(
+            status(mode, "/a", "/\\evil.com/").await,
+            redirect("/evil.com")
+        );
+        assert_eq!(status(mode, "/a", "///").await, redirect("/"));
+
+        let mode = TrailingSlash::RedirectToSlash;
+        assert_eq!(status(mode, "/a/", "/a/").await, ok);
+        assert_eq!(status(mode, "/a/", "/a").await, redirect("/a/"));
+        assert_eq!(status(mode, "/a/", "/a?q=1").await, redirect("/a/?q=1"));
+        assert_eq!(
+            status(mode, "/a/", "//evil.com").await,
+            redirect("/evil.com/")
+        );
+        assert_eq!(
+            status(mode, "/a/", "/\\evil.com").await,
+            redirect("/evil.com/")
+        );
+
+        // resource pattern and dynamic segments are preserved
+        let srv = init_service(App::new().trailing_slash(TrailingSlash::Merge).route(
+            "/users/{id}",
+            web::get().to(|req: HttpRequest| async move {
+                format!(
+                    "{} {}",
+                    req.match_pattern().unwrap(),
+                    req.match_info().get("id").unwrap()
+                )
+            }),
+        ))
+        .await;
+        let req = TestRequest::with_uri("/users/5/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"/users/{id} 5"));
+    }
+
+    #[test]
+    fn test_debug_routes() {
+        struct Named;
+
+        impl<S> Transform<S> for Named {
+            type Service = S;
+
+            fn new_transform(&self, service: S) -> S {
+                service
+            }
+
+            fn name(&self) -> &str {
+                "named"
+            }
+        }
+
+        let app = App::new()
+            .wrap(DefaultHeaders::new())
+            .wrap(Named)
+            .service(
+                web::resource("/test")
+                    .route(web::get().to(|| async { HttpResponse::Ok() }))
+                    .route(web::post().to(|| async { HttpResponse::Ok() })),
+            )
+            .service(web::scope("/app").service(
+                web::resource("/user/{id}").to(|| async { HttpResponse::Ok() }),
+            ));
+
+        let dump = app.debug_routes();
+        assert!(dump.starts_with(
+            "middleware:\n    named\n    \
+             ntex::web::middleware::defaultheaders::DefaultHeaders\n"
+        ));
+        assert!(dump.contains("    GET, POST /test\n"), "{}", dump);
+        assert!(dump.contains("    * /app/user/{id}\n"), "{}", dump);
+    }
+
     #[cfg(feature = "url")]
     #[crate::rt_test]
     async fn test_external_resource() {
@@ -823,4 +2644,227 @@ mod tests {
         let body = read_body(resp).await;
         assert_eq!(body, Bytes::from_static(b"https://youtube.com/watch/12345"));
     }
+
+    #[cfg(feature = "url")]
+    #[crate::rt_test]
+    async fn test_external_resources() {
+        let srv = init_service(
+            App::new()
+                .external_resources(vec![
+                    ("youtube", "https://youtube.com/watch/{video_id}"),
+                    ("github", "https://github.com/{user}/{repo}"),
+                    ("docs", "https://docs.rs/{name:[a-z-]+}"),
+                ])
+                .route(
+                    "/test",
+                    web::get().to(|req: HttpRequest| async move {
+                        HttpResponse::Ok().body(format!(
+                            "{} {} {}",
+                            req.url_for("youtube", &["12345"]).unwrap(),
+                            req.url_for("github", &["ntex-rs", "ntex"]).unwrap(),
+                            req.url_for("docs", &["ntex"]).unwrap(),
+                        ))
+                    }),
+                ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(
+                b"https://youtube.com/watch/12345 \
+                  https://github.com/ntex-rs/ntex https://docs.rs/ntex"
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "External resource \"broken\" has malformed url")]
+    fn test_external_resources_malformed() {
+        let _ = App::new().external_resources(vec![
+            ("youtube", "https://youtube.com/watch/{video_id}"),
+            ("broken", "https://example.com/{id"),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "External resource \"empty\" has malformed url")]
+    fn test_external_resource_unnamed_segment() {
+        let _ = App::new().external_resource("empty", "https://example.com/{:\\d+}");
+    }
+
+    #[cfg(feature = "url")]
+    #[crate::rt_test]
+    async fn test_route_named() {
+        let srv = init_service(
+            App::new()
+                .route_named("user", "/user/{id}", web::get().to(|| async { "" }))
+                .route(
+                    "/test",
+                    web::get().to(|req: HttpRequest| async move {
+                        HttpResponse::Ok().body(format!(
+                            "{}",
+                            req.url_for("user", &["12345"]).unwrap()
+                        ))
+                    }),
+                ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(b"http://localhost:8080/user/12345")
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_default_timeout() {
+        use crate::time::{sleep, Millis, Seconds};
+
+        let srv = init_service(
+            App::new()
+                .default_timeout(Seconds(1))
+                .route(
+                    "/slow",
+                    web::get().to(|| async {
+                        sleep(Millis(1500)).await;
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route(
+                    "/own",
+                    web::get().timeout(Seconds(2)).to(|| async {
+                        sleep(Millis(1500)).await;
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route("/fast", web::get().to(|| async { "fast" })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/slow").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        // header cannot extend default timeout
+        let req = TestRequest::with_uri("/slow")
+            .header("request-timeout", "5")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        // route's own timeout
+        let req = TestRequest::with_uri("/own").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/fast").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_request_timeout_header() {
+        use crate::time::{sleep, Millis, Seconds};
+
+        let srv = init_service(App::new().default_timeout(Seconds(5)).route(
+            "/",
+            web::get().to(|| async {
+                sleep(Millis(500)).await;
+                HttpResponse::Ok()
+            }),
+        ))
+        .await;
+
+        let start = std::time::Instant::now();
+        let req = TestRequest::default()
+            .header("request-timeout", "0.1")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(start.elapsed() < std::time::Duration::from_millis(500));
+
+        // invalid value is ignored
+        let req = TestRequest::default()
+            .header("request-timeout", "soon")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_map_target() {
+        let srv = init_service(
+            App::new()
+                .map_target(|uri: Uri| {
+                    let path = uri.path().to_ascii_lowercase();
+                    match uri.query() {
+                        Some(q) => format!("{}?{}", path, q).parse().unwrap(),
+                        None => path.parse().unwrap(),
+                    }
+                })
+                .route(
+                    "/users/{name}",
+                    web::get().to(|req: HttpRequest| async move {
+                        format!(
+                            "{} {} {}",
+                            req.match_info().get("name").unwrap(),
+                            req.path(),
+                            req.query_string()
+                        )
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/Users/John?Q=1").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"john /users/john Q=1")
+        );
+
+        let req = TestRequest::with_uri("/USERS/john").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"john /users/john ")
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_probe() {
+        let srv = init_service(
+            App::new()
+                .probe("/healthz", |req: &HttpRequest| {
+                    HttpResponse::Ok().body(format!("ok {}", req.method()))
+                })
+                .wrap_fn(|req, _| async move {
+                    Ok(req.into_response(HttpResponse::Unauthorized().finish()))
+                })
+                .route("/healthz", web::get().to(|| async { "resource" }))
+                .route("/", web::get().to(|| async { "index" })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/healthz").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"ok GET"));
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::with_uri("/healthz/deep").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
 }
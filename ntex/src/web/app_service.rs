@@ -1,18 +1,23 @@
+use std::panic::{self, AssertUnwindSafe};
 use std::task::{Context, Poll};
-use std::{cell::RefCell, future::Future, marker::PhantomData, pin::Pin, rc::Rc};
+use std::{
+    any::Any, cell::RefCell, future::Future, marker::PhantomData, pin::Pin, rc::Rc,
+    time::Instant,
+};
 
-use crate::http::{Request, Response};
+use crate::http::{header, Method, Request, Response, Uri};
 use crate::router::{Path, ResourceDef, ResourceInfo, Router};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{fn_service, PipelineFactory, Service, ServiceFactory, Transform};
-use crate::util::Extensions;
+use crate::util::{poll_fn, Extensions};
 
+use super::app::TrailingSlash;
 use super::config::AppConfig;
-use super::error::ErrorRenderer;
+use super::error::{ErrorContainer, ErrorRenderer};
 use super::guard::Guard;
-use super::httprequest::{HttpRequest, HttpRequestPool};
+use super::httprequest::{HttpRequest, HttpRequestPool, RequestCopy};
 use super::request::WebRequest;
-use super::response::WebResponse;
+use super::response::{log_error, render_error, WebResponse};
 use super::rmap::ResourceMap;
 use super::service::{AppServiceFactory, WebServiceConfig};
 use super::types::data::DataFactory;
@@ -24,8 +29,14 @@ type HttpNewService<Err: ErrorRenderer> =
     BoxServiceFactory<(), WebRequest<Err>, WebResponse, Err::Container, ()>;
 type BoxResponse<Err: ErrorRenderer> =
     Pin<Box<dyn Future<Output = Result<WebResponse, Err::Container>>>>;
-type FnDataFactory =
-    Box<dyn Fn() -> Pin<Box<dyn Future<Output = Result<Box<dyn DataFactory>, ()>>>>>;
+type FnDataFactory = Box<
+    dyn Fn() -> Pin<Box<dyn Future<Output = Result<Option<Box<dyn DataFactory>>, ()>>>>,
+>;
+pub(super) type OnResponse = Rc<dyn Fn(&mut WebResponse)>;
+pub(super) type CatchPanic = Rc<dyn Fn(&HttpRequest) -> Response>;
+pub(super) type Probe = Rc<dyn Fn(&HttpRequest) -> Response>;
+pub(super) type OnStart = Box<dyn Fn(&mut Extensions)>;
+pub(super) type MapTarget = Rc<dyn Fn(Uri) -> Uri>;
 
 /// Service factory to convert `Request` to a `WebRequest<S>`.
 /// It also executes data factories.
@@ -46,10 +57,21 @@ where
     pub(super) extensions: RefCell<Option<Extensions>>,
     pub(super) data: Rc<Vec<Box<dyn DataFactory>>>,
     pub(super) data_factories: Rc<Vec<FnDataFactory>>,
+    pub(super) data_factory_concurrency: usize,
     pub(super) services: Rc<RefCell<Vec<Box<dyn AppServiceFactory<Err>>>>>,
     pub(super) default: Option<Rc<HttpNewService<Err>>>,
     pub(super) external: RefCell<Vec<ResourceDef>>,
     pub(super) case_insensitive: bool,
+    pub(super) case_insensitive_capture: bool,
+    pub(super) automatic_options: bool,
+    pub(super) allow_malformed_uri: bool,
+    pub(super) reject_malformed_query: bool,
+    pub(super) trailing_slash: TrailingSlash,
+    pub(super) on_response: Option<OnResponse>,
+    pub(super) catch_panic: Option<CatchPanic>,
+    pub(super) probes: Rc<Vec<(String, Probe)>>,
+    pub(super) on_start: Rc<Vec<OnStart>>,
+    pub(super) map_target: Option<MapTarget>,
 }
 
 impl<T, F, Err> ServiceFactory for AppFactory<T, F, Err>
@@ -83,7 +105,14 @@ where
         let default = self.default.clone().unwrap_or_else(|| {
             Rc::new(boxed::factory(fn_service(
                 |req: WebRequest<Err>| async move {
-                    Ok(req.into_response(Response::NotFound().finish()))
+                    let (req, _) = req.into_parts();
+                    // renderer is not used if method guard of matched
+                    // resource rejected request
+                    let res = match req.app_data::<NotFound>() {
+                        Some(f) if req.matched_methods().is_none() => (f.0)(&req),
+                        _ => Response::NotFound().finish(),
+                    };
+                    Ok(WebResponse::new(res, req))
                 },
             )))
         });
@@ -91,6 +120,9 @@ where
         // App config
         let mut config =
             WebServiceConfig::new(config, default.clone(), self.data.clone());
+        config.set_case_insensitive_capture(self.case_insensitive_capture);
+        config.set_automatic_options(self.automatic_options);
+        config.set_merge_trailing_slash(self.trailing_slash == TrailingSlash::Merge);
 
         // register services
         std::mem::take(&mut *self.services.borrow_mut())
@@ -126,24 +158,40 @@ where
         let filter_fut = self.filter.new_service(());
         let data = self.data.clone();
         let data_factories = self.data_factories.clone();
+        let concurrency = self.data_factory_concurrency.max(1);
         let mut extensions = self
             .extensions
             .borrow_mut()
             .take()
             .unwrap_or_else(Extensions::new);
         let middleware = self.middleware.clone();
+        let on_response = self.on_response.clone();
+        let catch_panic = self.catch_panic.clone();
+        let probes = self.probes.clone();
+        let on_start = self.on_start.clone();
+        let map_target = self.map_target.clone();
+        let case_insensitive_capture = self.case_insensitive_capture;
+        let allow_malformed_uri = self.allow_malformed_uri;
+        let reject_malformed_query = self.reject_malformed_query;
+        let trailing_slash = self.trailing_slash;
 
         Box::pin(async move {
             // create http services
             for (path, factory, guards) in &mut services.iter() {
                 let service = factory.new_service(()).await?;
-                router.rdef(path.clone(), service).2 = guards.borrow_mut().take();
+                let pattern = path.pattern().to_string();
+                router.rdef(path.clone(), (service, pattern)).2 =
+                    guards.borrow_mut().take();
             }
 
             let routing = AppRouting {
                 ready: None,
                 router: router.finish(),
                 default: Some(default_fut.await?),
+                case_insensitive_capture,
+                allow_malformed_uri,
+                reject_malformed_query,
+                trailing_slash,
             };
 
             // main service
@@ -157,11 +205,44 @@ where
                 f.create(&mut extensions);
             }
 
-            // async data factories
-            for fut in data_factories.iter() {
-                if let Ok(f) = fut().await {
-                    f.create(&mut extensions);
+            // async data factories, next factory starts as soon as
+            // any of running factories completes
+            let mut results: Vec<_> = data_factories.iter().map(|_| None).collect();
+            let mut running: Vec<CatchUnwind> = Vec::with_capacity(concurrency);
+            let mut next = 0;
+            poll_fn(|cx| loop {
+                while running.len() < concurrency && next < data_factories.len() {
+                    running.push(CatchUnwind::new(next, &data_factories[next]));
+                    next += 1;
                 }
+
+                let mut completed = false;
+                let mut idx = 0;
+                while idx < running.len() {
+                    if let Poll::Ready(res) = Pin::new(&mut running[idx]).poll(cx) {
+                        let fut = running.swap_remove(idx);
+                        results[fut.idx] = Some(res??);
+                        completed = true;
+                    } else {
+                        idx += 1;
+                    }
+                }
+
+                if running.is_empty() && next == data_factories.len() {
+                    return Poll::Ready(Ok::<_, ()>(()));
+                } else if !completed {
+                    return Poll::Pending;
+                }
+            })
+            .await?;
+
+            // create data in registration order
+            for f in results.into_iter().flatten().flatten() {
+                f.create(&mut extensions);
+            }
+
+            for f in on_start.iter() {
+                f(&mut extensions);
             }
 
             Ok(AppFactoryService {
@@ -170,12 +251,70 @@ where
                 service: middleware.new_transform(service),
                 data: Rc::new(extensions),
                 pool: HttpRequestPool::create(),
+                on_response,
+                catch_panic,
+                probes,
+                map_target,
                 _t: PhantomData,
             })
         })
     }
 }
 
+/// Data factory future that converts panics to init errors
+struct CatchUnwind {
+    idx: usize,
+    fut: Option<Pin<Box<dyn Future<Output = Result<Option<Box<dyn DataFactory>>, ()>>>>>,
+}
+
+impl CatchUnwind {
+    fn new(idx: usize, f: &FnDataFactory) -> Self {
+        let fut = match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(fut) => Some(fut),
+            Err(e) => {
+                log_panic(idx, e);
+                None
+            }
+        };
+        CatchUnwind { idx, fut }
+    }
+}
+
+impl Future for CatchUnwind {
+    type Output = Result<Result<Option<Box<dyn DataFactory>>, ()>, ()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let idx = self.idx;
+        if let Some(ref mut fut) = self.fut {
+            match panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+                Ok(Poll::Ready(res)) => Poll::Ready(Ok(res)),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(e) => {
+                    self.fut = None;
+                    log_panic(idx, e);
+                    Poll::Ready(Err(()))
+                }
+            }
+        } else {
+            Poll::Ready(Err(()))
+        }
+    }
+}
+
+fn log_panic(idx: usize, e: Box<dyn Any + Send>) {
+    log::error!("Data factory #{} panicked: {}", idx, panic_message(&e));
+}
+
+fn panic_message(e: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = e.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = e.downcast_ref::<String>() {
+        msg.as_str()
+    } else {
+        "unknown panic"
+    }
+}
+
 /// Service to convert `Request` to a `WebRequest<Err>`
 pub struct AppFactoryService<T, Err>
 where
@@ -191,6 +330,10 @@ where
     config: AppConfig,
     data: Rc<Extensions>,
     pool: &'static HttpRequestPool,
+    on_response: Option<OnResponse>,
+    catch_panic: Option<CatchPanic>,
+    probes: Rc<Vec<(String, Probe)>>,
+    map_target: Option<MapTarget>,
     _t: PhantomData<Err>,
 }
 
@@ -206,7 +349,7 @@ where
     type Request = Request;
     type Response = WebResponse;
     type Error = T::Error;
-    type Future = T::Future;
+    type Future = AppFactoryServiceResponse<T::Future>;
 
     #[inline]
     fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -218,8 +361,13 @@ where
         self.service.poll_shutdown(cx, is_error)
     }
 
-    fn call(&self, req: Request) -> Self::Future {
-        let (head, payload) = req.into_parts();
+    fn call(&self, mut req: Request) -> Self::Future {
+        let conn = req.conn.take();
+        let (mut head, payload) = req.into_parts();
+        if let Some(ref f) = self.map_target {
+            let uri = std::mem::take(&mut head.uri);
+            head.uri = (*f)(uri);
+        }
 
         let req = if let Some(mut req) = self.pool.get_request() {
             let inner = Rc::get_mut(&mut req.0).unwrap();
@@ -227,9 +375,13 @@ where
             inner.head = head;
             inner.payload = payload;
             inner.app_data = self.data.clone();
+            inner.match_pattern.clear();
+            inner.matched_methods = None;
+            inner.received_at = Instant::now();
+            inner.conn = conn;
             req
         } else {
-            HttpRequest::new(
+            let mut req = HttpRequest::new(
                 Path::new(head.uri.clone()),
                 head,
                 payload,
@@ -237,9 +389,153 @@ where
                 self.config.clone(),
                 self.data.clone(),
                 self.pool,
-            )
+            );
+            Rc::get_mut(&mut req.0).unwrap().conn = conn;
+            req
+        };
+
+        // probes bypass middlewares and routing
+        if let Some((_, f)) = self.probes.iter().find(|(path, _)| path == req.path()) {
+            let res = (*f)(&req);
+            return AppFactoryServiceResponse {
+                fut: None,
+                probe: Some(WebResponse::new(res, req)),
+                on_response: None,
+                catch_panic: None,
+            };
+        }
+
+        // copy of request's head for rendering responses after
+        // request is consumed by service
+        let copy = if self.catch_panic.is_some() || self.on_response.is_some() {
+            Some(Rc::new(RequestCopy::new(&req)))
+        } else {
+            None
+        };
+        let catch_panic = self.catch_panic.as_ref().map(|f| PanicHandler {
+            f: f.clone(),
+            req: copy.clone().unwrap(),
+        });
+        let on_response = self
+            .on_response
+            .as_ref()
+            .map(|f| (f.clone(), copy.unwrap()));
+
+        let fut = if let Some(ref handler) = catch_panic {
+            let req = WebRequest::new(req);
+            match panic::catch_unwind(AssertUnwindSafe(|| self.service.call(req))) {
+                Ok(fut) => Some(fut),
+                Err(e) => {
+                    handler.log(&e);
+                    None
+                }
+            }
+        } else {
+            Some(self.service.call(WebRequest::new(req)))
+        };
+
+        AppFactoryServiceResponse {
+            fut,
+            probe: None,
+            on_response,
+            catch_panic,
+        }
+    }
+}
+
+/// Panic handler with copy of the request's head
+struct PanicHandler {
+    f: CatchPanic,
+    req: Rc<RequestCopy>,
+}
+
+impl PanicHandler {
+    fn log(&self, e: &(dyn Any + Send)) {
+        log::error!(
+            "Handler panicked: {} (path: {:?})",
+            panic_message(e),
+            self.req.path()
+        );
+    }
+
+    fn response(&self) -> WebResponse {
+        let req = self.req.request();
+        WebResponse::new((*self.f)(&req), req)
+    }
+}
+
+pin_project_lite::pin_project! {
+    pub struct AppFactoryServiceResponse<F> {
+        #[pin]
+        fut: Option<F>,
+        probe: Option<WebResponse>,
+        on_response: Option<(OnResponse, Rc<RequestCopy>)>,
+        catch_panic: Option<PanicHandler>,
+    }
+}
+
+impl<F, E> Future for AppFactoryServiceResponse<F>
+where
+    F: Future<Output = Result<WebResponse, E>>,
+    E: ErrorContainer,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        if let Some(res) = this.probe.take() {
+            return Poll::Ready(Ok(res));
+        }
+
+        let result = if let Some(ref handler) = this.catch_panic {
+            let fut = &mut this.fut;
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                fut.as_mut().as_pin_mut().map(|fut| fut.poll(cx))
+            }));
+            match result {
+                Ok(Some(res)) => res,
+                Ok(None) => Poll::Ready(Ok(handler.response())),
+                Err(e) => {
+                    handler.log(&e);
+                    this.fut.set(None);
+                    Poll::Ready(Ok(handler.response()))
+                }
+            }
+        } else if let Some(fut) = this.fut.as_pin_mut() {
+            fut.poll(cx)
+        } else {
+            Poll::Pending
         };
-        self.service.call(WebRequest::new(req))
+
+        match result {
+            Poll::Ready(Ok(mut res)) => {
+                if let Some((ref f, _)) = this.on_response {
+                    (*f)(&mut res);
+                }
+                crate::server::record_status(|| res.status().as_u16());
+                Poll::Ready(Ok(res))
+            }
+            Poll::Ready(Err(e)) => {
+                // render error, so hook could inspect response
+                if let Some((ref f, ref req)) = this.on_response {
+                    let req = req.request();
+                    let res =
+                        render_error(&req, &e, ErrorContainer::error_response(&e, &req));
+                    log_error(&req, res.status(), &e);
+                    let mut res = WebResponse::new(res, req);
+                    (*f)(&mut res);
+                    crate::server::record_status(|| res.status().as_u16());
+                    return Poll::Ready(Ok(res));
+                }
+                crate::server::record_status(|| {
+                    crate::http::ResponseError::error_response(&e)
+                        .status()
+                        .as_u16()
+                });
+                Poll::Ready(Err(e))
+            }
+            Poll::Pending => Poll::Pending,
+        }
     }
 }
 
@@ -258,9 +554,13 @@ where
 }
 
 struct AppRouting<Err: ErrorRenderer> {
-    router: Router<HttpService<Err>, Guards>,
+    router: Router<(HttpService<Err>, String), Guards>,
     ready: Option<(WebRequest<Err>, ResourceInfo)>,
     default: Option<HttpService<Err>>,
+    case_insensitive_capture: bool,
+    allow_malformed_uri: bool,
+    reject_malformed_query: bool,
+    trailing_slash: TrailingSlash,
 }
 
 impl<Err: ErrorRenderer> Service for AppRouting<Err> {
@@ -279,21 +579,61 @@ impl<Err: ErrorRenderer> Service for AppRouting<Err> {
     }
 
     fn call(&self, mut req: WebRequest<Err>) -> Self::Future {
-        let res = self.router.recognize_checked(&mut req, |req, guards| {
-            if let Some(guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
+        let mut checked = Ok(());
+        if !self.allow_malformed_uri {
+            checked = check_percent_encoding(req.path());
+        }
+        if self.reject_malformed_query {
+            checked = checked.and_then(|_| check_percent_encoding(req.query_string()));
+        }
+        if let Err(msg) = checked {
+            log::debug!("Malformed request uri {:?}: {}", req.uri(), msg);
+            let req = req.into_parts().0;
+            return Box::pin(async move {
+                Ok(WebResponse::new(Response::BadRequest().body(msg), req))
+            });
+        }
+
+        if let Some(location) = trailing_slash_redirect(self.trailing_slash, req.uri()) {
+            let req = req.into_parts().0;
+            return Box::pin(async move {
+                Ok(WebResponse::new(
+                    Response::PermanentRedirect()
+                        .header(header::LOCATION, location)
+                        .finish(),
+                    req,
+                ))
+            });
+        }
+
+        let res = self.router.recognize_validated(
+            &mut req,
+            |req, guards| {
+                if let Some(guards) = guards {
+                    for f in guards {
+                        if !f.check(req.head()) {
+                            return false;
+                        }
                     }
                 }
+                true
+            },
+            check_path,
+        );
+
+        if let Some(((srv, pattern), _info)) = res {
+            req.push_match_pattern(pattern);
+            if self.case_insensitive_capture {
+                req.match_info_mut().lowercase_segments();
             }
-            true
-        });
-
-        if let Some((srv, _info)) = res {
             srv.call(req)
         } else if let Some(ref default) = self.default {
-            default.call(req)
+            let methods = matched_methods(&self.router, &mut req);
+            req.set_matched_methods(methods);
+            match method_not_allowed(req) {
+                Ok(res) => Box::pin(async move { Ok(res) }),
+                Err(req) => default.call(req),
+            }
         } else {
             let req = req.into_parts().0;
             Box::pin(async { Ok(WebResponse::new(Response::NotFound().finish(), req)) })
@@ -301,6 +641,124 @@ impl<Err: ErrorRenderer> Service for AppRouting<Err> {
     }
 }
 
+/// Not found response renderer, set by `App::not_found()`
+///
+/// Renderer is used by app's default service.
+pub(super) struct NotFound(pub(super) Rc<dyn Fn(&HttpRequest) -> Response>);
+
+/// Method not allowed response renderer, set by `App::method_not_allowed()`
+///
+/// Renderer is used if resource matched request path but rejected
+/// request by method guards.
+pub(super) struct MethodNotAllowed(pub(super) Rc<dyn Fn(&HttpRequest) -> Response>);
+
+/// Render response with `MethodNotAllowed` renderer.
+///
+/// Request is returned back if request method is not rejected
+/// or renderer is not set.
+pub(super) fn method_not_allowed<Err>(
+    req: WebRequest<Err>,
+) -> Result<WebResponse, WebRequest<Err>> {
+    let f = match req.app_data::<MethodNotAllowed>() {
+        Some(f) if req.matched_methods().is_some() => f.0.clone(),
+        _ => return Err(req),
+    };
+    let (req, _) = req.into_parts();
+    let res = f(&req);
+    Ok(WebResponse::new(res, req))
+}
+
+/// Check matched path segments with resource guards
+pub(super) fn check_path<Err>(req: &WebRequest<Err>, guards: Option<&Guards>) -> bool {
+    guards
+        .map(|guards| guards.iter().all(|f| f.check_path(req.match_info())))
+        .unwrap_or(true)
+}
+
+/// Location of canonical path for redirect modes
+fn trailing_slash_redirect(mode: TrailingSlash, uri: &Uri) -> Option<String> {
+    let path = uri.path();
+    let path = match mode {
+        TrailingSlash::RedirectToNoSlash if path.len() > 1 && path.ends_with('/') => {
+            let trimmed = path.trim_end_matches('/');
+            if trimmed.is_empty() {
+                "/".to_string()
+            } else {
+                trimmed.to_string()
+            }
+        }
+        TrailingSlash::RedirectToSlash if !path.ends_with('/') => format!("{}/", path),
+        _ => return None,
+    };
+    // collapse leading slashes, `//host` and `/\host` are treated by
+    // browsers as network-path references to another host
+    let path = if path.starts_with("//") || path.starts_with("/\\") {
+        format!("/{}", path.trim_start_matches(&['/', '\\'][..]))
+    } else {
+        path
+    };
+    match uri.query() {
+        Some(query) => Some(format!("{}?{}", path, query)),
+        None => Some(path),
+    }
+}
+
+/// Check that percent-encoded sequences are valid and decode to utf-8
+fn check_percent_encoding(val: &str) -> Result<(), &'static str> {
+    let val = val.as_bytes();
+    if !val.contains(&b'%') {
+        return Ok(());
+    }
+
+    let mut decoded = Vec::with_capacity(val.len());
+    let mut idx = 0;
+    while idx < val.len() {
+        if val[idx] == b'%' {
+            let hex = |i: usize| val.get(i).and_then(|ch| (*ch as char).to_digit(16));
+            match (hex(idx + 1), hex(idx + 2)) {
+                (Some(d1), Some(d2)) => decoded.push((d1 << 4 | d2) as u8),
+                _ => return Err("Malformed percent-encoded sequence"),
+            }
+            idx += 3;
+        } else {
+            decoded.push(val[idx]);
+            idx += 1;
+        }
+    }
+    std::str::from_utf8(&decoded)
+        .map(|_| ())
+        .map_err(|_| "Percent-encoded sequence is not valid utf-8")
+}
+
+/// Collect methods of method guards of the resources that match request
+/// path and all non-method guards
+pub(super) fn matched_methods<T, Err>(
+    router: &Router<T, Guards>,
+    req: &mut WebRequest<Err>,
+) -> Vec<Method> {
+    let methods = RefCell::new(Vec::new());
+    router.recognize_checked(req, |req, guards| {
+        if let Some(guards) = guards {
+            let mut found = Vec::new();
+            for f in guards {
+                if let Some(m) = f.method() {
+                    found.push(m);
+                } else if !f.check(req.head()) {
+                    return false;
+                }
+            }
+            let mut methods = methods.borrow_mut();
+            for m in found {
+                if !methods.contains(m) {
+                    methods.push(m.clone());
+                }
+            }
+        }
+        false
+    });
+    methods.into_inner()
+}
+
 /// Web app service
 pub struct AppService<F, Err: ErrorRenderer> {
     filter: F,
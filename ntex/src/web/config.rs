@@ -1,10 +1,14 @@
 use std::{net::SocketAddr, rc::Rc};
 
 use crate::router::ResourceDef;
+use crate::service::{apply, boxed, Service, Transform};
 
+use super::request::WebRequest;
 use super::resource::Resource;
+use super::response::WebResponse;
 use super::route::Route;
-use super::service::{AppServiceFactory, ServiceFactoryWrapper, WebServiceFactory};
+use super::service::{AppServiceFactory, HttpService, ServiceConfigServices};
+use super::service::{ServiceFactoryWrapper, ServiceMiddleware, WebServiceFactory};
 use super::types::data::{Data, DataFactory};
 use super::{DefaultError, ErrorRenderer};
 
@@ -59,10 +63,11 @@ impl Default for AppConfig {
 /// Part of application configuration could be offloaded
 /// to set of external methods. This could help with
 /// modularization of big application configuration.
-pub struct ServiceConfig<Err = DefaultError> {
+pub struct ServiceConfig<Err: ErrorRenderer = DefaultError> {
     pub(super) services: Vec<Box<dyn AppServiceFactory<Err>>>,
     pub(super) data: Vec<Box<dyn DataFactory>>,
     pub(super) external: Vec<ResourceDef>,
+    pub(super) middleware: Vec<ServiceMiddleware<Err>>,
 }
 
 impl<Err: ErrorRenderer> ServiceConfig<Err> {
@@ -71,6 +76,21 @@ impl<Err: ErrorRenderer> ServiceConfig<Err> {
             services: Vec::new(),
             data: Vec::new(),
             external: Vec::new(),
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Take registered services, services are wrapped with
+    /// config's middlewares.
+    pub(super) fn take_services(&mut self) -> Vec<Box<dyn AppServiceFactory<Err>>> {
+        let services = std::mem::take(&mut self.services);
+        if self.middleware.is_empty() {
+            services
+        } else {
+            vec![Box::new(ServiceConfigServices {
+                services,
+                middleware: std::mem::take(&mut self.middleware),
+            })]
         }
     }
 
@@ -94,6 +114,23 @@ impl<Err: ErrorRenderer> ServiceConfig<Err> {
         )
     }
 
+    /// Configure named route for a specific path.
+    ///
+    /// This is same as `App::route_named()` method.
+    pub fn route_named(
+        &mut self,
+        name: &str,
+        path: &str,
+        mut route: Route<Err>,
+    ) -> &mut Self {
+        self.service(
+            Resource::new(path)
+                .name(name)
+                .add_guards(route.take_guards())
+                .route(route),
+        )
+    }
+
     /// Register http service.
     ///
     /// This is same as `App::service()` method.
@@ -106,6 +143,44 @@ impl<Err: ErrorRenderer> ServiceConfig<Err> {
         self
     }
 
+    /// Register a middleware for services of this config.
+    ///
+    /// Middleware is applied to each service registered by this config,
+    /// services registered by the app or by other configs are not
+    /// affected. Middleware applies to services regardless of the order
+    /// of `wrap()` and `service()` calls, the last registered middleware
+    /// is the outermost one.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, middleware, App, HttpResponse};
+    ///
+    /// fn config(cfg: &mut web::ServiceConfig) {
+    ///     cfg.wrap(middleware::DefaultHeaders::new().header("x-api", "v1"))
+    ///         .route("/api/users", web::get().to(|| async { HttpResponse::Ok() }));
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .configure(config)
+    ///         .route("/index.html", web::get().to(|| async { "Welcome!" }));
+    /// }
+    /// ```
+    pub fn wrap<M>(&mut self, mw: M) -> &mut Self
+    where
+        M: Transform<HttpService<Err>> + 'static,
+        M::Service: Service<
+                Request = WebRequest<Err>,
+                Response = WebResponse,
+                Error = Err::Container,
+            > + 'static,
+        <M::Service as Service>::Future: 'static,
+    {
+        let mw = Rc::new(mw);
+        self.middleware
+            .push(Box::new(move |f| boxed::factory(apply(mw.clone(), f))));
+        self
+    }
+
     /// Register an external resource.
     ///
     /// External resources are useful for URL generation purposes only
@@ -129,6 +204,7 @@ impl<Err: ErrorRenderer> ServiceConfig<Err> {
 mod tests {
     use super::*;
     use crate::http::{Method, StatusCode};
+    use crate::web::middleware::DefaultHeaders;
     use crate::web::test::{call_service, init_service, TestRequest};
     use crate::web::{self, App, HttpResponse};
     use crate::{Service};
@@ -206,4 +282,46 @@ mod tests {
         let resp = call_service(&srv, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[crate::rt_test]
+    async fn test_configure_wrap() {
+        let srv = init_service(
+            App::new()
+                .configure(|cfg| {
+                    cfg.route("/api", web::get().to(|| async { HttpResponse::Ok() }))
+                        .wrap(DefaultHeaders::new().header("x-api", "1"))
+                        .service(web::scope("/scope").route(
+                            "/test",
+                            web::get().to(|| async { HttpResponse::Ok() }),
+                        ));
+                })
+                .route(
+                    "/index.html",
+                    web::get().to(|| async { HttpResponse::Ok() }),
+                )
+                .service(web::scope("/other").configure(|cfg| {
+                    cfg.wrap(DefaultHeaders::new().header("x-other", "1"))
+                        .route("/test", web::get().to(|| async { HttpResponse::Ok() }));
+                })),
+        )
+        .await;
+
+        for path in &["/api", "/scope/test"] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(resp.headers().get("x-api").unwrap(), "1");
+        }
+
+        let req = TestRequest::with_uri("/index.html").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get("x-api").is_none());
+
+        let req = TestRequest::with_uri("/other/test").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("x-other").unwrap(), "1");
+        assert!(resp.headers().get("x-api").is_none());
+    }
 }
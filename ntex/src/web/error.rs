@@ -1,5 +1,5 @@
 //! Web error
-use std::{cell::RefCell, fmt, io::Write, marker::PhantomData};
+use std::{cell::RefCell, fmt, io, io::Write, marker::PhantomData};
 
 use derive_more::{Display, From};
 
@@ -11,7 +11,7 @@ pub use url_pkg::ParseError as UrlParseError;
 use super::{HttpRequest, HttpResponse};
 use crate::http::body::Body;
 use crate::http::helpers::Writer;
-use crate::http::{error, header, StatusCode};
+use crate::http::{error, header, HeaderMap, StatusCode};
 use crate::util::{BytesMut, Either};
 
 pub use super::error_default::{DefaultError, Error};
@@ -39,6 +39,12 @@ where
         StatusCode::INTERNAL_SERVER_ERROR
     }
 
+    /// Add error specific headers to the response, i.e. `Retry-After`
+    ///
+    /// Called by default `error_response()` implementation, response
+    /// does not get additional headers by default.
+    fn headers(&self, _: &mut HeaderMap) {}
+
     /// Generate response for error
     ///
     /// Internal server error is generated by default.
@@ -50,6 +56,7 @@ where
             header::CONTENT_TYPE,
             header::HeaderValue::from_static("text/plain; charset=utf-8"),
         );
+        self.headers(resp.headers_mut());
         resp.set_body(Body::from(buf))
     }
 }
@@ -69,6 +76,13 @@ where
         }
     }
 
+    fn headers(&self, headers: &mut HeaderMap) {
+        match self {
+            Either::Left(ref a) => a.headers(headers),
+            Either::Right(ref b) => b.headers(headers),
+        }
+    }
+
     fn error_response(&self, req: &HttpRequest) -> HttpResponse {
         match self {
             Either::Left(ref a) => a.error_response(req),
@@ -84,6 +98,13 @@ pub enum DataExtractorError {
     NotConfigured,
 }
 
+/// Errors which can occur when attempting to work with `ConnStats` extractor
+#[derive(Debug, PartialEq, Display)]
+pub enum ConnStatsError {
+    #[display(fmt = "Connection statistics are not available")]
+    NotAvailable,
+}
+
 /// Errors which can occur when attempting to generate resource uri.
 #[derive(Debug, PartialEq, Display, From)]
 pub enum UrlGenerationError {
@@ -143,12 +164,123 @@ pub enum JsonPayloadError {
     Payload(error::PayloadError),
 }
 
+/// A set of errors that can occur during parsing content typed payloads
+#[derive(Debug, Display, From)]
+pub enum ContentTypedError {
+    /// Json payload error
+    #[display(fmt = "{}", _0)]
+    Json(JsonPayloadError),
+    /// Urlencoded payload error
+    #[display(fmt = "{}", _0)]
+    Form(UrlencodedError),
+    #[cfg(feature = "msgpack")]
+    /// MessagePack payload error
+    #[display(fmt = "{}", _0)]
+    Payload(PayloadError),
+    #[cfg(feature = "msgpack")]
+    /// MessagePack deserialize error
+    #[display(fmt = "MessagePack deserialize error: {}", _0)]
+    MsgPack(rmp_serde::decode::Error),
+    /// Content type is not supported
+    #[display(fmt = "Unsupported content type")]
+    UnsupportedMediaType,
+}
+
+/// A set of errors that can occur during verifying payload digest
+#[derive(Debug, Display, From)]
+pub enum DigestError {
+    /// Payload error
+    #[display(fmt = "{}", _0)]
+    Payload(PayloadError),
+    /// Request does not contain supported digest
+    #[display(fmt = "Payload digest is missing")]
+    Missing,
+    /// Digest header is malformed
+    #[display(fmt = "Payload digest is malformed")]
+    Malformed,
+    /// Payload digest does not match
+    #[display(fmt = "Payload digest mismatch")]
+    Mismatch,
+}
+
+/// Errors which can occur when attempting to work with `IdempotencyKey` extractor
+#[derive(Debug, PartialEq, Display)]
+pub enum IdempotencyKeyError {
+    /// Request does not contain `Idempotency-Key` header
+    #[display(fmt = "Idempotency-Key header is missing")]
+    Missing,
+    /// Header value is empty, too long or contains invalid characters
+    #[display(fmt = "Idempotency-Key header is malformed")]
+    Malformed,
+}
+
+/// Errors which can occur when attempting to work with `Authorization` extractor
+#[derive(Debug, PartialEq, Display)]
+pub enum AuthorizationError {
+    /// Request does not contain `Authorization` header
+    #[display(fmt = "Authorization header is missing")]
+    Missing,
+    /// Header value does not start with a valid authentication scheme
+    #[display(fmt = "Authorization header is malformed")]
+    Malformed,
+}
+
+/// A set of errors that can occur during handling gRPC-web requests
+#[derive(Debug, Display, From)]
+pub enum GrpcWebError {
+    /// Payload error
+    #[display(fmt = "{}", _0)]
+    Payload(PayloadError),
+    /// Text mode body is not valid base64
+    #[display(fmt = "Cannot decode base64 body")]
+    Base64,
+    /// Content type is not supported
+    #[display(fmt = "Unsupported content type")]
+    UnsupportedMediaType,
+    /// Request method is not POST
+    #[display(fmt = "Method not allowed")]
+    MethodNotAllowed,
+}
+
+/// A set of errors that can occur during parsing multipart forms
+#[derive(Debug, Display, From)]
+pub enum MultipartError {
+    /// Content type is not `multipart/form-data` or boundary is missing
+    #[display(fmt = "Content type error")]
+    ContentType,
+    /// Payload error
+    #[display(fmt = "{}", _0)]
+    Payload(PayloadError),
+    /// Multipart body is malformed
+    #[display(fmt = "Malformed multipart body")]
+    Malformed,
+    /// Form does not contain required field
+    #[display(fmt = "Multipart field {:?} is missing", _0)]
+    #[from(ignore)]
+    MissingField(String),
+    /// Field value cannot be parsed
+    #[display(fmt = "Cannot parse multipart field {:?}", _0)]
+    #[from(ignore)]
+    Parse(String),
+    /// Uploaded file cannot be stored
+    #[display(fmt = "Cannot store uploaded file: {}", _0)]
+    Io(io::Error),
+}
+
 /// A set of errors that can occur during parsing request paths
 #[derive(Debug, Display, From)]
 pub enum PathError {
     /// Deserialize error
     #[display(fmt = "Path deserialize error: {}", _0)]
     Deserialize(serde::de::value::Error),
+    /// Path segment parse error
+    #[display(fmt = "Path segment parse error: {}", _0)]
+    #[from(ignore)]
+    Parse(String),
+    /// Path validation error, see `PathConfig::validator()`
+    #[display(fmt = "{}", _0)]
+    #[from(ignore)]
+    Invalid(String),
 }
 
 /// A set of errors that can occur during parsing query strings
@@ -157,6 +289,10 @@ pub enum QueryPayloadError {
     /// Deserialize error
     #[display(fmt = "Query deserialize error: {}", _0)]
     Deserialize(serde::de::value::Error),
+    /// Number of query parameters is bigger than allowed
+    #[display(fmt = "Query has more than {} parameters", limit)]
+    #[from(ignore)]
+    Overflow { limit: usize },
 }
 
 #[derive(Debug, Display, From)]
@@ -701,6 +837,44 @@ mod tests {
         )
     }
 
+    #[derive(Debug, Display)]
+    #[display(fmt = "Too many requests")]
+    struct RateLimited(u64);
+
+    impl WebResponseError<DefaultError> for RateLimited {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::TOO_MANY_REQUESTS
+        }
+
+        fn headers(&self, headers: &mut HeaderMap) {
+            headers.insert(header::RETRY_AFTER, self.0.into());
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_error_headers() {
+        use crate::web::test::{call_service, init_service, read_body};
+        use crate::web::{self, App};
+
+        let srv = init_service(App::new().route(
+            "/",
+            web::get().to(|| async { Err::<HttpResponse, _>(RateLimited(30)) }),
+        ))
+        .await;
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "30");
+        assert_eq!(
+            read_body(res).await,
+            crate::util::Bytes::from_static(b"Too many requests")
+        );
+
+        let e: Error = RateLimited(5).into();
+        let res = crate::http::ResponseError::error_response(&e);
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "5");
+    }
+
     #[test]
     fn test_other_errors() {
         let req = TestRequest::default().to_http_request();
@@ -833,6 +1007,26 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_content_typed_error() {
+        let req = TestRequest::default().to_http_request();
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &ContentTypedError::UnsupportedMediaType,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &ContentTypedError::Json(JsonPayloadError::Overflow),
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse = WebResponseError::<DefaultError>::error_response(
+            &ContentTypedError::Form(UrlencodedError::Parse),
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_query_payload_error() {
         let req = TestRequest::default().to_http_request();
@@ -12,6 +12,7 @@ use crate::http::{self, header, StatusCode};
 use crate::util::{timeout::TimeoutError, BytesMut};
 
 use super::error::{self, ErrorContainer, ErrorRenderer, WebResponseError};
+use super::types::AuthorizationConfig;
 use super::{HttpRequest, HttpResponse};
 
 /// Default error type
@@ -66,6 +67,7 @@ impl crate::http::error::ResponseError for Error {
             header::CONTENT_TYPE,
             header::HeaderValue::from_static("text/plain; charset=utf-8"),
         );
+        self.cause.headers(resp.headers_mut());
         resp.set_body(Body::from(buf))
     }
 }
@@ -92,11 +94,20 @@ impl<E: WebResponseError<DefaultError>> WebResponseError<DefaultError>
             TimeoutError::Timeout => StatusCode::GATEWAY_TIMEOUT,
         }
     }
+
+    fn headers(&self, headers: &mut http::HeaderMap) {
+        if let TimeoutError::Service(e) = self {
+            e.headers(headers)
+        }
+    }
 }
 
 /// `InternalServerError` for `DataExtractorError`
 impl WebResponseError<DefaultError> for error::DataExtractorError {}
 
+/// `InternalServerError` for `ConnStatsError`
+impl WebResponseError<DefaultError> for error::ConnStatsError {}
+
 /// `InternalServerError` for `JsonError`
 impl WebResponseError<DefaultError> for JsonError {}
 
@@ -176,10 +187,108 @@ impl WebResponseError<DefaultError> for error::JsonPayloadError {
     }
 }
 
-/// Error renderer for `PathError`
+/// Response renderer for `ContentTypedError`
+impl WebResponseError<DefaultError> for error::ContentTypedError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::ContentTypedError::Json(ref e) => e.status_code(),
+            error::ContentTypedError::Form(ref e) => e.status_code(),
+            #[cfg(feature = "msgpack")]
+            error::ContentTypedError::Payload(ref e) => e.status_code(),
+            #[cfg(feature = "msgpack")]
+            error::ContentTypedError::MsgPack(_) => StatusCode::BAD_REQUEST,
+            error::ContentTypedError::UnsupportedMediaType => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+        }
+    }
+}
+
+/// Response renderer for `DigestError`
+impl WebResponseError<DefaultError> for error::DigestError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::DigestError::Payload(ref e) => e.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Return `BadRequest` for `IdempotencyKeyError`
+impl WebResponseError<DefaultError> for error::IdempotencyKeyError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Response renderer for `AuthorizationError`
+///
+/// `Missing` returns `Unauthorized` with challenge from `AuthorizationConfig`,
+/// `Malformed` returns `BadRequest`
+impl WebResponseError<DefaultError> for error::AuthorizationError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::AuthorizationError::Missing => StatusCode::UNAUTHORIZED,
+            error::AuthorizationError::Malformed => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        let mut resp = HttpResponse::new(self.status_code());
+        let mut buf = BytesMut::new();
+        let _ = write!(Writer(&mut buf), "{}", self);
+        resp.headers_mut().insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        if *self == error::AuthorizationError::Missing {
+            if let Some(value) = req
+                .app_data::<AuthorizationConfig>()
+                .and_then(|cfg| cfg.challenge())
+            {
+                resp.headers_mut()
+                    .insert(header::WWW_AUTHENTICATE, value.clone());
+            }
+        }
+        resp.set_body(Body::from(buf))
+    }
+}
+
+/// Response renderer for `GrpcWebError`
+impl WebResponseError<DefaultError> for error::GrpcWebError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::GrpcWebError::Payload(ref e) => e.status_code(),
+            error::GrpcWebError::Base64 => StatusCode::BAD_REQUEST,
+            error::GrpcWebError::UnsupportedMediaType => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            error::GrpcWebError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+        }
+    }
+}
+
+/// Response renderer for `MultipartError`
+impl WebResponseError<DefaultError> for error::MultipartError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::MultipartError::Payload(ref e) => e.status_code(),
+            error::MultipartError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// `PathError` returns two possible results:
+///
+/// - `Invalid` returns *400 Bad Request*
+/// - Other errors returns *404 Not Found*
 impl WebResponseError<DefaultError> for error::PathError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::NOT_FOUND
+        match *self {
+            error::PathError::Invalid(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::NOT_FOUND,
+        }
     }
 }
 
@@ -190,9 +299,18 @@ impl WebResponseError<DefaultError> for error::QueryPayloadError {
     }
 }
 
+/// `PayloadError` returns two possible results:
+///
+/// - `Payload(Overflow)` returns `PayloadTooLarge`
+/// - Other errors returns `BadRequest`
 impl WebResponseError<DefaultError> for error::PayloadError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match *self {
+            error::PayloadError::Payload(http::error::PayloadError::Overflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
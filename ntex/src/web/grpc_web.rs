@@ -0,0 +1,563 @@
+//! gRPC-web support
+//!
+//! gRPC-web requests are translated to gRPC requests and passed to
+//! the inner service, responses are encoded with trailers in body.
+//! Both binary `application/grpc-web` and base64 encoded
+//! `application/grpc-web-text` content types are supported.
+//!
+//! ```rust
+//! use ntex::service::fn_service;
+//! use ntex::web::{self, grpc_web, App};
+//!
+//! fn main() {
+//!     let app = App::new().route_service(
+//!         "/helloworld.Greeter/SayHello",
+//!         grpc_web::GrpcWeb::new(fn_service(|req: grpc_web::GrpcRequest| async move {
+//!             Ok::<_, String>(grpc_web::GrpcResponse::new(req.body))
+//!         })),
+//!     );
+//! }
+//! ```
+use std::task::{Context, Poll};
+use std::{fmt, fmt::Write, future::Future, marker::PhantomData, pin::Pin, rc::Rc};
+
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::{Method, Response};
+use crate::service::{IntoServiceFactory, Service, ServiceFactory};
+use crate::util::{BufMut, Bytes, BytesMut};
+use crate::web::error::{ErrorRenderer, GrpcWebError};
+use crate::web::{FromRequest, WebRequest, WebResponse};
+
+const GRPC_WEB: &str = "application/grpc-web";
+const GRPC_WEB_TEXT: &str = "application/grpc-web-text";
+const GRPC_STATUS: &str = "grpc-status";
+const GRPC_MESSAGE: &str = "grpc-message";
+
+/// Trailers frame flag
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// gRPC request
+#[derive(Debug)]
+pub struct GrpcRequest {
+    /// Request path, `/{package.Service}/{Method}`
+    pub path: String,
+    /// Request metadata, content type is set to `application/grpc`
+    pub headers: HeaderMap,
+    /// Length-prefixed gRPC messages
+    pub body: Bytes,
+}
+
+/// gRPC response
+#[derive(Debug)]
+pub struct GrpcResponse {
+    /// Response metadata
+    pub headers: HeaderMap,
+    /// Length-prefixed gRPC messages
+    pub body: Bytes,
+    /// Response trailers, `grpc-status: 0` is used if status is not set
+    pub trailers: HeaderMap,
+}
+
+impl GrpcResponse {
+    /// Create successful response with length-prefixed messages
+    pub fn new(body: Bytes) -> Self {
+        GrpcResponse {
+            body,
+            headers: HeaderMap::new(),
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    /// Create response with error status
+    pub fn status(code: u32, message: &str) -> Self {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static(GRPC_STATUS),
+            HeaderValue::from(code),
+        );
+        if let Ok(msg) = HeaderValue::from_str(&encode_grpc_message(message)) {
+            trailers.insert(HeaderName::from_static(GRPC_MESSAGE), msg);
+        }
+        GrpcResponse {
+            trailers,
+            body: Bytes::new(),
+            headers: HeaderMap::new(),
+        }
+    }
+}
+
+/// Encode message as uncompressed length-prefixed gRPC frame
+pub fn encode_message(msg: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(msg.len() + 5);
+    encode_frame(0, msg, &mut buf);
+    buf.freeze()
+}
+
+/// Decode length-prefixed gRPC frames
+///
+/// Returns `None` if frames are malformed or compressed.
+pub fn decode_messages(body: &Bytes) -> Option<Vec<Bytes>> {
+    let mut messages = Vec::new();
+    let mut pos = 0;
+
+    while pos < body.len() {
+        if body.len() - pos < 5 || body[pos] != 0 {
+            return None;
+        }
+        let len = u32::from_be_bytes([
+            body[pos + 1],
+            body[pos + 2],
+            body[pos + 3],
+            body[pos + 4],
+        ]) as usize;
+        pos += 5;
+        if body.len() - pos < len {
+            return None;
+        }
+        messages.push(body.slice(pos..pos + len));
+        pos += len;
+    }
+    Some(messages)
+}
+
+/// gRPC-web adapter service factory
+///
+/// Inner service errors are reported with `grpc-status: 2` (UNKNOWN)
+/// trailer. Request's body size is limited by
+/// [**PayloadConfig**](../types/struct.PayloadConfig.html).
+pub struct GrpcWeb<F, Err> {
+    factory: F,
+    _t: PhantomData<Err>,
+}
+
+impl<F, Err> GrpcWeb<F, Err>
+where
+    F: ServiceFactory<
+        Config = (),
+        Request = GrpcRequest,
+        Response = GrpcResponse,
+        InitError = (),
+    >,
+    F::Error: fmt::Display,
+    Err: ErrorRenderer,
+    GrpcWebError: Into<Err::Container>,
+{
+    /// Create gRPC-web adapter for gRPC service
+    pub fn new<U: IntoServiceFactory<F>>(factory: U) -> Self {
+        GrpcWeb {
+            factory: factory.into_factory(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<F, Err> ServiceFactory for GrpcWeb<F, Err>
+where
+    F: ServiceFactory<
+        Config = (),
+        Request = GrpcRequest,
+        Response = GrpcResponse,
+        InitError = (),
+    >,
+    F::Future: 'static,
+    F::Service: 'static,
+    F::Error: fmt::Display,
+    Err: ErrorRenderer,
+    GrpcWebError: Into<Err::Container>,
+{
+    type Config = ();
+    type Request = WebRequest<Err>;
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type InitError = ();
+    type Service = GrpcWebService<F::Service, Err>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Service, Self::InitError>>>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let fut = self.factory.new_service(());
+        Box::pin(async move {
+            Ok(GrpcWebService {
+                service: Rc::new(fut.await?),
+                _t: PhantomData,
+            })
+        })
+    }
+}
+
+/// gRPC-web adapter service
+pub struct GrpcWebService<S, Err> {
+    service: Rc<S>,
+    _t: PhantomData<Err>,
+}
+
+impl<S, Err> Service for GrpcWebService<S, Err>
+where
+    S: Service<Request = GrpcRequest, Response = GrpcResponse> + 'static,
+    S::Error: fmt::Display,
+    Err: ErrorRenderer,
+    GrpcWebError: Into<Err::Container>,
+{
+    type Request = WebRequest<Err>;
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // inner service errors are reported in trailers
+        let _ = futures_core::ready!(self.service.poll_ready(cx));
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<Err>) -> Self::Future {
+        let srv = self.service.clone();
+
+        Box::pin(async move {
+            let (req, mut payload) = req.into_parts();
+
+            let mode = match check_request(&req) {
+                Ok(mode) => mode,
+                Err(e) => return Ok(WebResponse::from_err::<Err, _>(e, req)),
+            };
+
+            let body = match <Bytes as FromRequest<Err>>::from_request(
+                &req,
+                &mut payload,
+            )
+            .await
+            {
+                Ok(body) => mode.decode(body),
+                Err(e) => Err(GrpcWebError::Payload(e)),
+            };
+            let body = match body {
+                Ok(body) => body,
+                Err(e) => return Ok(WebResponse::from_err::<Err, _>(e, req)),
+            };
+
+            // translate to grpc request
+            let mut headers = req.headers().clone();
+            headers.remove(header::CONTENT_LENGTH);
+            headers.remove("x-grpc-web");
+            headers.insert(header::CONTENT_TYPE, mode.grpc_content_type());
+            let grpc_req = GrpcRequest {
+                body,
+                headers,
+                path: req.path().to_string(),
+            };
+
+            let grpc_res = match srv.call(grpc_req).await {
+                Ok(res) => res,
+                Err(e) => GrpcResponse::status(2, &e.to_string()),
+            };
+            Ok(WebResponse::new(mode.encode(grpc_res), req))
+        })
+    }
+}
+
+/// Request encoding mode
+struct Mode {
+    text: bool,
+    content_type: HeaderValue,
+}
+
+fn check_request(req: &crate::web::HttpRequest) -> Result<Mode, GrpcWebError> {
+    if req.method() != Method::POST {
+        return Err(GrpcWebError::MethodNotAllowed);
+    }
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .ok_or(GrpcWebError::UnsupportedMediaType)?;
+    let ct = content_type
+        .to_str()
+        .map_err(|_| GrpcWebError::UnsupportedMediaType)?
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    let text = if is_content_type(&ct, GRPC_WEB_TEXT) {
+        true
+    } else if is_content_type(&ct, GRPC_WEB) {
+        false
+    } else {
+        return Err(GrpcWebError::UnsupportedMediaType);
+    };
+    Ok(Mode {
+        text,
+        content_type: content_type.clone(),
+    })
+}
+
+/// Check content type, `+proto` style suffixes are allowed
+fn is_content_type(ct: &str, expected: &str) -> bool {
+    ct.strip_prefix(expected)
+        .map_or(false, |rest| rest.is_empty() || rest.starts_with('+'))
+}
+
+impl Mode {
+    fn grpc_content_type(&self) -> HeaderValue {
+        let ct = self.content_type.to_str().unwrap_or("");
+        let suffix = ct
+            .split(';')
+            .next()
+            .and_then(|ct| ct.find('+').map(|idx| ct[idx..].trim().to_string()))
+            .unwrap_or_default();
+        HeaderValue::from_str(&format!("application/grpc{}", suffix))
+            .unwrap_or_else(|_| HeaderValue::from_static("application/grpc"))
+    }
+
+    fn decode(&self, body: Bytes) -> Result<Bytes, GrpcWebError> {
+        if !self.text {
+            return Ok(body);
+        }
+
+        // text mode body could be a concatenation of padded base64 chunks
+        let data: Vec<u8> = body
+            .iter()
+            .copied()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        let mut buf = Vec::with_capacity(data.len() / 4 * 3);
+        let mut start = 0;
+        while start < data.len() {
+            let mut end = data[start..]
+                .iter()
+                .position(|b| *b == b'=')
+                .map_or(data.len(), |idx| start + idx);
+            while end < data.len() && data[end] == b'=' {
+                end += 1;
+            }
+            base64::decode_config_buf(&data[start..end], base64::STANDARD, &mut buf)
+                .map_err(|_| GrpcWebError::Base64)?;
+            start = end;
+        }
+        Ok(Bytes::from(buf))
+    }
+
+    fn encode(&self, res: GrpcResponse) -> Response {
+        let GrpcResponse {
+            headers,
+            body,
+            mut trailers,
+        } = res;
+
+        if !trailers.contains_key(GRPC_STATUS) {
+            trailers.insert(HeaderName::from_static(GRPC_STATUS), HeaderValue::from(0));
+        }
+        let mut block = Vec::new();
+        for (name, value) in trailers.iter() {
+            block.extend_from_slice(name.as_str().as_bytes());
+            block.push(b':');
+            block.extend_from_slice(value.as_bytes());
+            block.extend_from_slice(b"\r\n");
+        }
+
+        let mut buf = BytesMut::with_capacity(body.len() + block.len() + 5);
+        buf.extend_from_slice(&body);
+        encode_frame(TRAILERS_FLAG, &block, &mut buf);
+        let body = if self.text {
+            Bytes::from(base64::encode(&buf))
+        } else {
+            buf.freeze()
+        };
+
+        let mut response = Response::Ok();
+        for (name, value) in headers.iter() {
+            response.header(name.clone(), value.clone());
+        }
+        response
+            .set_header(header::CONTENT_TYPE, self.content_type.clone())
+            .body(body)
+    }
+}
+
+fn encode_frame(flags: u8, data: &[u8], buf: &mut BytesMut) {
+    buf.reserve(data.len() + 5);
+    buf.put_u8(flags);
+    buf.put_u32(data.len() as u32);
+    buf.extend_from_slice(data);
+}
+
+/// Percent-encode `grpc-message` value
+fn encode_grpc_message(msg: &str) -> String {
+    let mut out = String::with_capacity(msg.len());
+    for b in msg.bytes() {
+        if (0x20..0x7f).contains(&b) && b != b'%' {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{:02X}", b);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::fn_service;
+    use crate::web::test::{init_service, read_body, TestRequest};
+    use crate::web::App;
+
+    fn greeter() -> impl ServiceFactory<
+        Config = (),
+        Request = GrpcRequest,
+        Response = GrpcResponse,
+        Error = String,
+        InitError = (),
+    > {
+        fn_service(|req: GrpcRequest| async move {
+            assert_eq!(req.path, "/helloworld.Greeter/SayHello");
+            assert_eq!(
+                req.headers.get(header::CONTENT_TYPE).unwrap(),
+                "application/grpc+proto"
+            );
+            let msgs = decode_messages(&req.body).ok_or("bad frame".to_string())?;
+            if msgs[0] == "fail" {
+                return Err("failed: 100%".to_string());
+            }
+            let mut reply = b"hello ".to_vec();
+            reply.extend_from_slice(&msgs[0]);
+
+            let mut res = GrpcResponse::new(encode_message(&reply));
+            res.headers
+                .insert(HeaderName::from_static("x-meta"), HeaderValue::from(1));
+            Ok(res)
+        })
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut body = BytesMut::new();
+        body.extend_from_slice(&encode_message(b"one"));
+        body.extend_from_slice(&encode_message(b""));
+        let body = body.freeze();
+        assert_eq!(&body[..], b"\x00\x00\x00\x00\x03one\x00\x00\x00\x00\x00");
+        assert_eq!(
+            decode_messages(&body).unwrap(),
+            vec![Bytes::from_static(b"one"), Bytes::new()]
+        );
+        assert!(
+            decode_messages(&Bytes::from_static(b"\x00\x00\x00\x00\x03on")).is_none()
+        );
+        assert!(decode_messages(&Bytes::from_static(b"\x01\x00\x00\x00\x00")).is_none());
+
+        assert_eq!(encode_grpc_message("failed: 100%"), "failed: 100%25");
+        assert!(is_content_type("application/grpc-web+proto", GRPC_WEB));
+        assert!(!is_content_type("application/grpc-webx", GRPC_WEB));
+    }
+
+    #[crate::rt_test]
+    async fn test_grpc_web_unary() {
+        let srv = init_service(
+            App::new()
+                .route_service("/helloworld.Greeter/SayHello", GrpcWeb::new(greeter())),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+            .header("x-grpc-web", "1")
+            .set_payload(encode_message(b"world"))
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/grpc-web+proto"
+        );
+        assert_eq!(resp.headers().get("x-meta").unwrap(), "1");
+
+        let body = read_body(resp).await;
+        let mut expected = BytesMut::new();
+        expected.extend_from_slice(&encode_message(b"hello world"));
+        encode_frame(TRAILERS_FLAG, b"grpc-status:0\r\n", &mut expected);
+        assert_eq!(body, expected.freeze());
+    }
+
+    #[crate::rt_test]
+    async fn test_grpc_web_text() {
+        let srv = init_service(
+            App::new()
+                .route_service("/helloworld.Greeter/SayHello", GrpcWeb::new(greeter())),
+        )
+        .await;
+
+        // body is sent in two padded base64 chunks
+        let msg = encode_message(b"world");
+        let payload =
+            format!("{}{}", base64::encode(&msg[..4]), base64::encode(&msg[4..]));
+        let req = TestRequest::post()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(header::CONTENT_TYPE, "application/grpc-web-text+proto")
+            .set_payload(payload)
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/grpc-web-text+proto"
+        );
+
+        let body = base64::decode(&read_body(resp).await).unwrap();
+        let mut expected = BytesMut::new();
+        expected.extend_from_slice(&encode_message(b"hello world"));
+        encode_frame(TRAILERS_FLAG, b"grpc-status:0\r\n", &mut expected);
+        assert_eq!(&body[..], &expected[..]);
+
+        let req = TestRequest::post()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(header::CONTENT_TYPE, "application/grpc-web-text+proto")
+            .set_payload("not base64!")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[crate::rt_test]
+    async fn test_grpc_web_errors() {
+        let srv = init_service(
+            App::new()
+                .route_service("/helloworld.Greeter/SayHello", GrpcWeb::new(greeter())),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(header::CONTENT_TYPE, "application/grpc-web+proto")
+            .set_payload(encode_message(b"fail"))
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        let mut expected = BytesMut::new();
+        encode_frame(
+            TRAILERS_FLAG,
+            b"grpc-status:2\r\ngrpc-message:failed: 100%25\r\n",
+            &mut expected,
+        );
+        assert_eq!(body, expected.freeze());
+
+        let req = TestRequest::post()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(header::CONTENT_TYPE, "application/json")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let req = TestRequest::with_uri("/helloworld.Greeter/SayHello")
+            .header(header::CONTENT_TYPE, "application/grpc-web")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
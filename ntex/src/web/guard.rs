@@ -26,9 +26,10 @@
 //! }
 //! ```
 #![allow(non_snake_case)]
-use std::convert::TryFrom;
+use std::{convert::TryFrom, marker::PhantomData, str::FromStr};
 
 use crate::http::{header, RequestHead, Uri};
+use crate::router::Path;
 
 /// Trait defines resource guards. Guards are used for route selection.
 ///
@@ -38,6 +39,21 @@ use crate::http::{header, RequestHead, Uri};
 pub trait Guard {
     /// Check if request matches predicate
     fn check(&self, request: &RequestHead) -> bool;
+
+    /// Http method that guard matches, if any
+    ///
+    /// Used for reporting allowed methods of the matched resource.
+    fn method(&self) -> Option<&http::Method> {
+        None
+    }
+
+    /// Check if matched path segments satisfy predicate
+    ///
+    /// Called after resource's path is matched. If check fails, router
+    /// continues with next matching resource.
+    fn check_path(&self, _: &Path<Uri>) -> bool {
+        true
+    }
 }
 
 /// Create guard object for supplied function.
@@ -160,6 +176,10 @@ impl Guard for AllGuard {
         }
         true
     }
+
+    fn check_path(&self, path: &Path<Uri>) -> bool {
+        self.0.iter().all(|p| p.check_path(path))
+    }
 }
 
 /// Return guard that matches if supplied guard does not match.
@@ -184,6 +204,10 @@ impl Guard for MethodGuard {
     fn check(&self, request: &RequestHead) -> bool {
         request.method == self.0
     }
+
+    fn method(&self) -> Option<&http::Method> {
+        Some(&self.0)
+    }
 }
 
 /// Guard to match *GET* http method
@@ -257,6 +281,205 @@ impl Guard for HeaderGuard {
     }
 }
 
+/// Return predicate that matches if request's query contains specified
+/// parameter. If value is provided, parameter must have the same value.
+///
+/// ```rust
+/// use ntex::web::{self, guard, App, HttpResponse};
+///
+/// fn main() {
+///     App::new()
+///         .service(
+///             web::resource("/report")
+///                 .guard(guard::Query("format", Some("csv")))
+///                 .to(|| async { HttpResponse::Ok().body("csv") }),
+///         )
+///         .service(
+///             web::resource("/report").to(|| async { HttpResponse::Ok().body("json") }),
+///         );
+/// }
+/// ```
+pub fn Query(name: &'static str, value: Option<&'static str>) -> QueryGuard {
+    QueryGuard(name, value)
+}
+
+#[doc(hidden)]
+pub struct QueryGuard(&'static str, Option<&'static str>);
+
+impl Guard for QueryGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        let query = if let Some(query) = req.uri.query() {
+            query
+        } else {
+            return false;
+        };
+
+        serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+            .map(|params| {
+                params.iter().any(|(key, val)| {
+                    key == self.0 && self.1.map(|v| val == v).unwrap_or(true)
+                })
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Return predicate that matches if request's `Accept` header prefers
+/// specified media type.
+///
+/// Media type is preferred if its quality is the highest among all
+/// media ranges of the header. Media range with `*` subtype or `*/*`
+/// matches media type if there is no more specific range. Requests
+/// without `Accept` header accept any media type. Resources with the
+/// same quality are matched in registration order.
+///
+/// Note, guard does not know which media types are offered by other
+/// resources, quality is compared against all media ranges of the header.
+/// Request with `Accept: image/png, application/json;q=0.9` is not matched
+/// by `Accept(mime::APPLICATION_JSON)`, because `image/png` has higher
+/// quality. Register resource without `Accept` guard after guarded
+/// resources to handle such requests.
+///
+/// ```rust
+/// use ntex::web::{self, guard, App, HttpResponse};
+///
+/// fn main() {
+///     App::new()
+///         .service(
+///             web::resource("/item")
+///                 .guard(guard::Accept(mime::APPLICATION_JSON))
+///                 .route(web::get().to(|| async { HttpResponse::Ok().body("{}") })),
+///         )
+///         .service(
+///             web::resource("/item")
+///                 .guard(guard::Accept(mime::TEXT_HTML))
+///                 .route(web::get().to(|| async { HttpResponse::Ok().body("<html/>") })),
+///         );
+/// }
+/// ```
+pub fn Accept(mime: mime::Mime) -> AcceptGuard {
+    AcceptGuard(mime)
+}
+
+#[doc(hidden)]
+pub struct AcceptGuard(mime::Mime);
+
+impl Guard for AcceptGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        accept_quality(&req.headers, &self.0)
+            .map(|(q, max)| q > 0.0 && q >= max)
+            .unwrap_or(true)
+    }
+}
+
+/// Quality of the most specific `Accept` media range that matches media
+/// type, zero if no range matches, and max quality of all media ranges.
+///
+/// Returns `None` if headers do not contain `Accept` header.
+pub(super) fn accept_quality(
+    headers: &header::HeaderMap,
+    mime: &mime::Mime,
+) -> Option<(f32, f32)> {
+    let mut ranges = headers
+        .get_all(header::ACCEPT)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|range| range.trim().parse::<mime::Mime>().ok())
+        .peekable();
+    ranges.peek()?;
+
+    // (specificity, quality) of the best matching range and max quality
+    let mut matched: Option<(u8, f32)> = None;
+    let mut max = 0.0;
+    for range in ranges {
+        let q = range
+            .get_param("q")
+            .and_then(|q| q.as_str().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if q > max {
+            max = q;
+        }
+
+        let specificity = if range.type_() == mime::STAR {
+            0
+        } else if range.type_() != mime.type_() {
+            continue;
+        } else if range.subtype() == mime::STAR {
+            1
+        } else if range.subtype() == mime.subtype() {
+            2
+        } else {
+            continue;
+        };
+        if matched.map(|(s, _)| specificity > s).unwrap_or(true) {
+            matched = Some((specificity, q));
+        }
+    }
+    Some((matched.map(|(_, q)| q).unwrap_or(0.0), max))
+}
+
+/// Return guard that matches if path segment could be parsed to `T`.
+///
+/// If segment value could not be parsed with `FromStr`, resource does not
+/// match and router continues with next resource, so requests fall through
+/// to other resources instead of failing in extractor. Parsed value could
+/// be extracted with `types::PathParam<T>`, or with `PathParam::named()`
+/// if resource has several dynamic segments. Guard does not modify request.
+///
+/// ```rust
+/// use std::str::FromStr;
+/// use ntex::web::{self, guard, types::PathParam, App, HttpResponse};
+///
+/// enum Kind { Books, Music }
+///
+/// impl FromStr for Kind {
+///     type Err = ();
+///
+///     fn from_str(s: &str) -> Result<Self, ()> {
+///         match s {
+///             "books" => Ok(Kind::Books),
+///             "music" => Ok(Kind::Music),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// async fn index(kind: PathParam<Kind>) -> &'static str {
+///     match *kind {
+///         Kind::Books => "books",
+///         Kind::Music => "music",
+///     }
+/// }
+///
+/// fn main() {
+///     App::new()
+///         .service(
+///             web::resource("/{kind}")
+///                 .guard(guard::PathParam::<Kind>("kind"))
+///                 .to(index),
+///         )
+///         .service(web::resource("/{page}").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+pub fn PathParam<T: FromStr + 'static>(name: &'static str) -> PathParamGuard<T> {
+    PathParamGuard(name, PhantomData)
+}
+
+#[doc(hidden)]
+pub struct PathParamGuard<T>(&'static str, PhantomData<fn() -> T>);
+
+impl<T: FromStr> Guard for PathParamGuard<T> {
+    fn check(&self, _: &RequestHead) -> bool {
+        true
+    }
+
+    fn check_path(&self, path: &Path<Uri>) -> bool {
+        path.get(self.0)
+            .map(|val| val.parse::<T>().is_ok())
+            .unwrap_or(false)
+    }
+}
+
 /// Return predicate that matches if request contains specified Host name.
 ///
 /// ```rust
@@ -325,7 +548,9 @@ impl Guard for HostGuard {
 mod tests {
     use super::*;
     use crate::http::{header, Method};
-    use crate::web::test::TestRequest;
+    use crate::web::test::{init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpRequest};
+    use crate::{util::Bytes, Service};
 
     #[test]
     fn test_header() {
@@ -342,6 +567,212 @@ mod tests {
         assert!(!pred.check(req.head()));
     }
 
+    #[test]
+    fn test_query() {
+        let req = TestRequest::with_uri("/?format=csv&flag").to_http_request();
+
+        assert!(Query("format", None).check(req.head()));
+        assert!(Query("format", Some("csv")).check(req.head()));
+        assert!(!Query("format", Some("json")).check(req.head()));
+        assert!(Query("flag", None).check(req.head()));
+        assert!(!Query("other", None).check(req.head()));
+
+        let req = TestRequest::with_uri("/").to_http_request();
+        assert!(!Query("format", None).check(req.head()));
+    }
+
+    #[crate::rt_test]
+    async fn test_query_routing() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/report")
+                        .guard(Query("format", Some("csv")))
+                        .to(|| async { "csv" }),
+                )
+                .service(web::resource("/report").to(|| async { "json" })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/report?format=csv").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"csv"));
+
+        let req = TestRequest::with_uri("/report?format=json").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"json"));
+
+        let req = TestRequest::with_uri("/report").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"json"));
+    }
+
+    #[test]
+    fn test_accept() {
+        let json = Accept(mime::APPLICATION_JSON);
+        let html = Accept(mime::TEXT_HTML);
+
+        let req = TestRequest::default().to_http_request();
+        assert!(json.check(req.head()));
+        assert!(html.check(req.head()));
+
+        let req = TestRequest::with_header(header::ACCEPT, "application/json")
+            .to_http_request();
+        assert!(json.check(req.head()));
+        assert!(!html.check(req.head()));
+
+        let req = TestRequest::with_header(
+            header::ACCEPT,
+            "text/html, application/xhtml+xml, */*;q=0.8",
+        )
+        .to_http_request();
+        assert!(!json.check(req.head()));
+        assert!(html.check(req.head()));
+
+        let req = TestRequest::with_header(header::ACCEPT, "text/*, text/html;q=0")
+            .to_http_request();
+        assert!(!html.check(req.head()));
+        assert!(Accept(mime::TEXT_PLAIN).check(req.head()));
+
+        let req = TestRequest::with_header(header::ACCEPT, "*/*").to_http_request();
+        assert!(json.check(req.head()));
+        assert!(html.check(req.head()));
+
+        // quality is compared against all media ranges
+        let req = TestRequest::with_header(
+            header::ACCEPT,
+            "image/png, application/json;q=0.9",
+        )
+        .to_http_request();
+        assert!(!json.check(req.head()));
+    }
+
+    #[crate::rt_test]
+    async fn test_accept_routing() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/item")
+                        .guard(Accept(mime::APPLICATION_JSON))
+                        .route(web::get().to(|| async { "json" })),
+                )
+                .service(
+                    web::resource("/item")
+                        .guard(Accept(mime::TEXT_HTML))
+                        .route(web::get().to(|| async { "html" })),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/item")
+            .header(header::ACCEPT, "text/html,application/json;q=0.9")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"html"));
+
+        let req = TestRequest::with_uri("/item")
+            .header(header::ACCEPT, "application/json")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"json"));
+
+        let req = TestRequest::with_uri("/item")
+            .method(Method::POST)
+            .header(header::ACCEPT, "text/html")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), crate::http::StatusCode::METHOD_NOT_ALLOWED);
+
+        let req = TestRequest::with_uri("/item")
+            .header(header::ACCEPT, "image/png")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), crate::http::StatusCode::NOT_FOUND);
+    }
+
+    #[crate::rt_test]
+    async fn test_path_param_routing() {
+        #[derive(Debug, PartialEq)]
+        enum Kind {
+            Books,
+            Music,
+        }
+
+        impl FromStr for Kind {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, ()> {
+                match s {
+                    "books" => Ok(Kind::Books),
+                    "music" => Ok(Kind::Music),
+                    _ => Err(()),
+                }
+            }
+        }
+
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/{kind}")
+                        .guard(PathParam::<Kind>("kind"))
+                        .to(|kind: web::types::PathParam<Kind>| async move {
+                            format!("{:?}", *kind)
+                        }),
+                )
+                .service(
+                    web::scope("/shop").service(
+                        web::resource("/{kind}/{id}")
+                            .guard(All(Get()).and(PathParam::<Kind>("kind")))
+                            .to(|| async { "item" }),
+                    ),
+                )
+                .service(
+                    web::resource("/{user}/{kind}")
+                        .guard(PathParam::<Kind>("kind"))
+                        .to(|req: HttpRequest| async move {
+                            let kind =
+                                web::types::PathParam::<Kind>::named(&req, "kind")
+                                    .unwrap();
+                            format!("user {:?}", *kind)
+                        }),
+                )
+                .service(web::resource("/{page}").to(
+                    |page: web::types::Path<String>| async move { page.into_inner() },
+                ))
+                .default_service(web::to(|| async { "default" })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/books").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"Books"));
+
+        let req = TestRequest::with_uri("/music").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"Music"));
+
+        let req = TestRequest::with_uri("/music/books").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"user Books"));
+
+        let req = TestRequest::with_uri("/books/about").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"default"));
+
+        // invalid kind falls through to catch-all resource
+        let req = TestRequest::with_uri("/about").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"about"));
+
+        let req = TestRequest::with_uri("/shop/books/1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"item"));
+
+        let req = TestRequest::with_uri("/shop/games/1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"default"));
+    }
+
     #[test]
     fn test_host() {
         let req = TestRequest::default()
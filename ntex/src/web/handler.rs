@@ -1,5 +1,8 @@
 use std::{future::Future, marker::PhantomData, pin::Pin, task::Context, task::Poll};
 
+use crate::time::{sleep, Millis};
+use crate::util::{select, Either};
+
 use super::error::ErrorRenderer;
 use super::extract::FromRequest;
 use super::httprequest::HttpRequest;
@@ -39,6 +42,15 @@ pub(super) trait HandlerFn<Err: ErrorRenderer> {
         _: WebRequest<Err>,
     ) -> Pin<Box<dyn Future<Output = Result<WebResponse, Err::Container>>>>;
 
+    /// Call handler, if handler does not complete within `timeout`
+    /// handler's future is dropped and `err` is rendered as a response.
+    fn call_timeout(
+        &self,
+        _: WebRequest<Err>,
+        timeout: Millis,
+        err: fn() -> Err::Container,
+    ) -> Pin<Box<dyn Future<Output = Result<WebResponse, Err::Container>>>>;
+
     fn clone_handler(&self) -> Box<dyn HandlerFn<Err>>;
 }
 
@@ -93,6 +105,29 @@ where
         })
     }
 
+    fn call_timeout(
+        &self,
+        req: WebRequest<Err>,
+        timeout: Millis,
+        err: fn() -> Err::Container,
+    ) -> Pin<Box<dyn Future<Output = Result<WebResponse, Err::Container>>>> {
+        let (req, mut payload) = req.into_parts();
+        let fut = HandlerWrapperResponse {
+            hnd: self.hnd.clone(),
+            from_request: Some(T::from_request(&req, &mut payload)),
+            handler: None,
+            responder: None,
+            req: Some(req.clone()),
+        };
+
+        Box::pin(async move {
+            match select(fut, sleep(timeout)).await {
+                Either::Left(res) => res,
+                Either::Right(_) => Ok(WebResponse::from_err::<Err, _>(err(), req)),
+            }
+        })
+    }
+
     fn clone_handler(&self) -> Box<dyn HandlerFn<Err>> {
         Box::new(HandlerWrapper {
             hnd: self.hnd.clone(),
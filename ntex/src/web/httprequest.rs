@@ -1,5 +1,6 @@
-use std::{cell::Ref, cell::RefCell, cell::RefMut, fmt, net, rc::Rc};
+use std::{cell::Ref, cell::RefCell, cell::RefMut, fmt, net, rc::Rc, time::Instant};
 
+use crate::http::h1::{ConnExtensions, ConnInfo, ConnStats, SocketOptions};
 use crate::http::{
     HeaderMap, HttpMessage, Message, Method, Payload, RequestHead, Uri, Version,
 };
@@ -21,6 +22,10 @@ pub(crate) struct HttpRequestInner {
     pub(crate) path: Path<Uri>,
     pub(crate) payload: Payload,
     pub(crate) app_data: Rc<Extensions>,
+    pub(crate) match_pattern: String,
+    pub(crate) matched_methods: Option<Vec<Method>>,
+    pub(crate) received_at: Instant,
+    pub(crate) conn: Option<ConnInfo>,
     rmap: Rc<ResourceMap>,
     config: AppConfig,
     pool: &'static HttpRequestPool,
@@ -45,10 +50,69 @@ impl HttpRequest {
             rmap,
             config,
             pool,
+            match_pattern: String::new(),
+            matched_methods: None,
+            received_at: Instant::now(),
+            conn: None,
         }))
     }
 }
 
+/// Copy of the request's head
+///
+/// Used for rendering responses after request is consumed by service.
+pub(crate) struct RequestCopy {
+    head: (Method, Uri, Version, HeaderMap, Option<net::SocketAddr>),
+    rmap: Rc<ResourceMap>,
+    config: AppConfig,
+    data: Rc<Extensions>,
+    pool: &'static HttpRequestPool,
+}
+
+impl RequestCopy {
+    pub(crate) fn new(req: &HttpRequest) -> Self {
+        let head = req.head();
+        RequestCopy {
+            head: (
+                head.method.clone(),
+                head.uri.clone(),
+                head.version,
+                head.headers.clone(),
+                head.peer_addr,
+            ),
+            rmap: req.0.rmap.clone(),
+            config: req.0.config.clone(),
+            data: req.0.app_data.clone(),
+            pool: req.0.pool,
+        }
+    }
+
+    /// Request's path
+    pub(crate) fn path(&self) -> &str {
+        self.head.1.path()
+    }
+
+    /// Create new request from the copy of the head
+    pub(crate) fn request(&self) -> HttpRequest {
+        let mut head = Message::<RequestHead>::new();
+        head.method = self.head.0.clone();
+        head.uri = self.head.1.clone();
+        head.version = self.head.2;
+        head.headers = self.head.3.clone();
+        head.peer_addr = self.head.4;
+
+        HttpRequest::new(
+            Path::new(head.uri.clone()),
+            head,
+            Payload::None,
+            self.rmap.clone(),
+            self.config.clone(),
+            self.data.clone(),
+            self.pool,
+        )
+    }
+}
+
 impl HttpRequest {
     /// This method returns reference to the request head
     #[inline]
@@ -133,6 +197,24 @@ impl HttpRequest {
         self.head().extensions_mut()
     }
 
+    /// Socket options of the http/1 connection
+    #[inline]
+    pub fn socket_options(&self) -> Option<&SocketOptions> {
+        self.0.conn.as_ref().map(|conn| &conn.options)
+    }
+
+    /// Statistics of the http/1 connection
+    #[inline]
+    pub fn conn_stats(&self) -> Option<&ConnStats> {
+        self.0.conn.as_ref().map(|conn| &conn.stats)
+    }
+
+    /// Connection-level extensions of the http/1 connection
+    #[inline]
+    pub fn conn_extensions(&self) -> Option<&ConnExtensions> {
+        self.0.conn.as_ref().map(|conn| &conn.extensions)
+    }
+
     #[cfg(feature = "url")]
     /// Generate url for named resource
     ///
@@ -177,6 +259,41 @@ impl HttpRequest {
         self.url_for(name, &NO_PARAMS)
     }
 
+    /// Get a pattern of the resource that matched the request path,
+    /// e.g. `/user/{id}`.
+    ///
+    /// Patterns of nested scopes are joined together. Returns `None`
+    /// if request path does not match any resource.
+    #[inline]
+    pub fn match_pattern(&self) -> Option<&str> {
+        if self.0.match_pattern.is_empty() {
+            None
+        } else {
+            Some(&self.0.match_pattern)
+        }
+    }
+
+    /// Get http methods of the resource that matched the request path
+    /// but rejected the request method.
+    ///
+    /// Methods are available to the default service, so `Allow` header
+    /// could be constructed for *405 Method Not Allowed* response. Returns
+    /// `None` if request path does not match any resource or resource
+    /// is not restricted by methods.
+    #[inline]
+    pub fn matched_methods(&self) -> Option<&[Method]> {
+        self.0.matched_methods.as_deref()
+    }
+
+    /// Time when request processing started.
+    ///
+    /// Timestamp is taken when application receives the request, so
+    /// handlers and middlewares could measure request latency.
+    #[inline]
+    pub fn received_at(&self) -> Instant {
+        self.0.received_at
+    }
+
     #[inline]
     /// Get a reference to a `ResourceMap` of current application.
     pub fn resource_map(&self) -> &ResourceMap {
@@ -248,6 +365,9 @@ impl Drop for HttpRequest {
             let v = &mut self.0.pool.0.borrow_mut();
             if v.len() < 128 {
                 self.extensions_mut().clear();
+                if let Some(inner) = Rc::get_mut(&mut self.0) {
+                    inner.conn = None;
+                }
                 v.push(self.0.clone());
             }
         }
@@ -364,10 +484,10 @@ mod tests {
         {
             let cookies = req.cookies().unwrap();
             assert_eq!(cookies.len(), 2);
-            assert_eq!(cookies[0].name(), "cookie2");
-            assert_eq!(cookies[0].value(), "value2");
-            assert_eq!(cookies[1].name(), "cookie1");
-            assert_eq!(cookies[1].value(), "value1");
+            assert_eq!(cookies[0].name(), "cookie1");
+            assert_eq!(cookies[0].value(), "value1");
+            assert_eq!(cookies[1].name(), "cookie2");
+            assert_eq!(cookies[1].value(), "value2");
         }
 
         let cookie = req.cookie("cookie1");
@@ -457,6 +577,34 @@ mod tests {
         );
     }
 
+    #[crate::rt_test]
+    async fn test_received_at() {
+        let times = Rc::new(RefCell::new(Vec::new()));
+        let times2 = times.clone();
+        let srv = init_service(App::new().route(
+            "/",
+            web::get().to(move |req: HttpRequest| {
+                let received = req.received_at();
+                assert!(received <= Instant::now());
+                times2.borrow_mut().push(received);
+                async { HttpResponse::Ok() }
+            }),
+        ))
+        .await;
+
+        // request objects are reused, each request gets new timestamp
+        for _ in 0..2 {
+            let req = TestRequest::default().to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            drop(resp);
+            crate::time::sleep(crate::time::Millis(5)).await;
+        }
+        let times = times.borrow();
+        assert_eq!(times.len(), 2);
+        assert!(times[0] < times[1]);
+    }
+
     #[crate::rt_test]
     async fn test_data() {
         let srv = init_service(App::new().app_data(10usize).service(
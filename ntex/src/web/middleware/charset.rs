@@ -0,0 +1,187 @@
+//! Middleware for labeling text responses with charset
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, pin::Pin};
+
+use crate::http::header::{HeaderValue, CONTENT_TYPE};
+use crate::service::{Service, Transform};
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for appending `charset=utf-8` parameter to response content type.
+///
+/// Parameter is added to `text/*` and `application/json` content types
+/// if response's `Content-Type` header has no `charset` parameter.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::DefaultCharset::new())
+///         .route("/index.html", web::get().to(|| async {
+///             // Content-Type: text/html; charset=utf-8
+///             HttpResponse::Ok().content_type("text/html").body("<html></html>")
+///         }));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct DefaultCharset;
+
+impl DefaultCharset {
+    /// Construct `DefaultCharset` middleware.
+    pub fn new() -> DefaultCharset {
+        DefaultCharset
+    }
+}
+
+impl<S, E> Transform<S> for DefaultCharset
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Service = DefaultCharsetMiddleware<S, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        DefaultCharsetMiddleware {
+            service,
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct DefaultCharsetMiddleware<S, E> {
+    service: S,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> Service for DefaultCharsetMiddleware<S, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            let value = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|val| val.to_str().ok())
+                .filter(|ct| needs_charset(ct))
+                .and_then(|ct| {
+                    HeaderValue::try_from(format!("{}; charset=utf-8", ct.trim_end()))
+                        .ok()
+                });
+            if let Some(value) = value {
+                res.headers_mut().insert(CONTENT_TYPE, value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Check if content type is textual and has no charset parameter
+fn needs_charset(ct: &str) -> bool {
+    let mut parts = ct.split(';');
+    let essence = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+    if !(essence.starts_with("text/") || essence == "application/json") {
+        return false;
+    }
+    !parts.any(|param| {
+        param
+            .split('=')
+            .next()
+            .map(|name| name.trim().eq_ignore_ascii_case("charset"))
+            .unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_default_charset() {
+        let srv = init_service(
+            App::new()
+                .wrap(DefaultCharset::new())
+                .route(
+                    "/text",
+                    web::get().to(|| async {
+                        HttpResponse::Ok().content_type("text/plain").body("text")
+                    }),
+                )
+                .route(
+                    "/json",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body("{}")
+                    }),
+                )
+                .route(
+                    "/charset",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("text/html; Charset=iso-8859-1")
+                            .body("html")
+                    }),
+                )
+                .route(
+                    "/binary",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/octet-stream")
+                            .body("data")
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/text").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "text/plain; charset=utf-8"
+        );
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json; charset=utf-8"
+        );
+
+        let req = TestRequest::with_uri("/charset").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "text/html; Charset=iso-8859-1"
+        );
+
+        let req = TestRequest::with_uri("/binary").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Middleware for rejecting requests while server is draining
+use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
+use std::task::{Context, Poll};
+use std::{fmt, future::Future, marker::PhantomData, pin::Pin};
+
+use crate::http::header::{HeaderValue, RETRY_AFTER};
+use crate::service::{Service, Transform};
+use crate::time::Seconds;
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// `Middleware` for rejecting requests while server is draining.
+///
+/// In draining mode all new requests get `503 Service Unavailable`
+/// response with `Retry-After` header, connection is closed after response.
+/// Requests that are already in process are completed as usual.
+///
+/// `Drain` is a handle to shared state, clones of the middleware could
+/// be used for switching draining mode. `ServerBuilder::on_drain()`
+/// registers a callback for `SIGUSR1` signal.
+///
+/// ```rust,no_run
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// #[ntex::main]
+/// async fn main() -> std::io::Result<()> {
+///     let drain = middleware::Drain::new();
+///     let handle = drain.clone();
+///
+///     web::server(move || {
+///         App::new()
+///             .wrap(drain.clone())
+///             .service(web::resource("/").to(|| async { HttpResponse::Ok() }))
+///     })
+///     .on_drain(move || handle.start())
+///     .bind("127.0.0.1:59090")?
+///     .run()
+///     .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Drain {
+    draining: Arc<AtomicBool>,
+    retry_after: Seconds,
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Drain {
+            draining: Arc::new(AtomicBool::new(false)),
+            retry_after: Seconds(30),
+        }
+    }
+}
+
+impl Drain {
+    /// Construct `Drain` middleware.
+    pub fn new() -> Drain {
+        Drain::default()
+    }
+
+    /// Set `Retry-After` header value.
+    ///
+    /// By default retry after is set to 30 seconds.
+    pub fn retry_after(mut self, secs: Seconds) -> Self {
+        self.retry_after = secs;
+        self
+    }
+
+    /// Switch to draining mode.
+    pub fn start(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    /// Leave draining mode.
+    pub fn stop(&self) {
+        self.draining.store(false, Ordering::Relaxed);
+    }
+
+    /// Check if draining mode is enabled.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for Drain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Drain")
+            .field("draining", &self.is_draining())
+            .field("retry_after", &self.retry_after)
+            .finish()
+    }
+}
+
+impl<S, E> Transform<S> for Drain
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Service = DrainMiddleware<S, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        DrainMiddleware {
+            service,
+            drain: self.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct DrainMiddleware<S, E> {
+    service: S,
+    drain: Drain,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> Service for DrainMiddleware<S, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        if self.drain.is_draining() {
+            let res = req.into_response(
+                HttpResponse::ServiceUnavailable()
+                    .header(RETRY_AFTER, HeaderValue::from(self.drain.retry_after.0))
+                    .force_close()
+                    .finish(),
+            );
+            Box::pin(async move { Ok(res) })
+        } else {
+            Box::pin(self.service.call(req))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::util::lazy;
+    use crate::web::test::{ok_service, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_drain() {
+        let drain = Drain::new().retry_after(Seconds(10));
+        let mw = drain.new_transform(ok_service());
+
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::default().to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(RETRY_AFTER));
+
+        drain.start();
+        assert!(drain.is_draining());
+        let req = TestRequest::default().to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "10");
+
+        drain.stop();
+        let req = TestRequest::default().to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,240 @@
+//! Middleware for exempting paths from other middleware
+use std::task::{Context, Poll};
+use std::{marker::PhantomData, rc::Rc};
+
+use crate::service::{Service, Transform};
+use crate::util::Either;
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` that bypasses inner middleware for specified path prefixes.
+///
+/// Requests with matching path are passed directly to the service, inner
+/// middleware is not called. This is useful for global redirect or auth
+/// middlewares that must not be applied to some resources, for example
+/// to `/.well-known/*` uris.
+///
+/// Middleware must be registered with `App::wrap()`, paths are matched
+/// before routing.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::Exempt::new(middleware::DefaultHeaders::new()).well_known())
+///         .service(web::well_known("/var/www/.well-known"));
+/// }
+/// ```
+pub struct Exempt<M> {
+    mw: M,
+    prefixes: Rc<Vec<String>>,
+}
+
+impl<M> Exempt<M> {
+    /// Construct `Exempt` middleware for inner middleware.
+    pub fn new(mw: M) -> Self {
+        Exempt {
+            mw,
+            prefixes: Rc::new(Vec::new()),
+        }
+    }
+
+    /// Add path prefix that bypasses inner middleware.
+    ///
+    /// Prefix matches whole path segments, i.e. `/static` matches
+    /// `/static` and `/static/app.js` but not `/statics`.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        let prefix = if prefix.starts_with('/') {
+            prefix.to_string()
+        } else {
+            format!("/{}", prefix)
+        };
+        Rc::get_mut(&mut self.prefixes)
+            .expect("Multiple copies exist")
+            .push(prefix);
+        self
+    }
+
+    /// Bypass inner middleware for `/.well-known/*` uris.
+    pub fn well_known(self) -> Self {
+        self.prefix("/.well-known")
+    }
+}
+
+impl<S, M, E> Transform<S> for Exempt<M>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    M: Transform<Rc<S>>,
+    M::Service:
+        Service<Request = WebRequest<E>, Response = WebResponse, Error = S::Error>,
+{
+    type Service = ExemptMiddleware<S, M::Service, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        let service = Rc::new(service);
+        ExemptMiddleware {
+            middleware: self.mw.new_transform(service.clone()),
+            service,
+            prefixes: self.prefixes.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct ExemptMiddleware<S, T, E> {
+    service: Rc<S>,
+    middleware: T,
+    prefixes: Rc<Vec<String>>,
+    _t: PhantomData<E>,
+}
+
+impl<S, T, E> ExemptMiddleware<S, T, E> {
+    fn is_exempt(&self, path: &str) -> bool {
+        self.prefixes.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .map(|rest| rest.is_empty() || rest.starts_with('/'))
+                .unwrap_or(false)
+        })
+    }
+}
+
+impl<S, T, E> Service for ExemptMiddleware<S, T, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    T: Service<Request = WebRequest<E>, Response = WebResponse, Error = S::Error>,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Either<S::Future, T::Future>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let ready1 = self.service.poll_ready(cx)?.is_ready();
+        let ready2 = self.middleware.poll_ready(cx)?.is_ready();
+        if ready1 && ready2 {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        let ready1 = self.service.poll_shutdown(cx, is_error).is_ready();
+        let ready2 = self.middleware.poll_shutdown(cx, is_error).is_ready();
+        if ready1 && ready2 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    #[inline]
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        if self.is_exempt(req.path()) {
+            Either::Left(self.service.call(req))
+        } else {
+            Either::Right(self.middleware.call(req))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, future::Future, pin::Pin};
+
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::util::Bytes;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    /// Redirects all plain http requests to https
+    struct RedirectHttps;
+
+    impl<S> Transform<S> for RedirectHttps {
+        type Service = RedirectHttpsMiddleware<S>;
+
+        fn new_transform(&self, service: S) -> Self::Service {
+            RedirectHttpsMiddleware(service)
+        }
+    }
+
+    struct RedirectHttpsMiddleware<S>(S);
+
+    impl<S, E> Service for RedirectHttpsMiddleware<S>
+    where
+        S: Service<Request = WebRequest<E>, Response = WebResponse>,
+        S::Future: 'static,
+        E: 'static,
+    {
+        type Request = WebRequest<E>;
+        type Response = WebResponse;
+        type Error = S::Error;
+        type Future = Either<
+            S::Future,
+            Pin<Box<dyn Future<Output = Result<WebResponse, S::Error>>>>,
+        >;
+
+        fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.0.poll_ready(cx)
+        }
+
+        fn call(&self, req: WebRequest<E>) -> Self::Future {
+            if req.connection_info().scheme() == "https" {
+                Either::Left(self.0.call(req))
+            } else {
+                let url =
+                    format!("https://{}{}", req.connection_info().host(), req.uri());
+                let res = HttpResponse::MovedPermanently()
+                    .header(header::LOCATION, url)
+                    .finish();
+                Either::Right(Box::pin(async move { Ok(req.into_response(res)) }))
+            }
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_exempt_well_known() {
+        let dir =
+            std::env::temp_dir().join(format!("ntex-exempt-{}", std::process::id()));
+        fs::create_dir_all(dir.join("acme-challenge")).unwrap();
+        fs::write(dir.join("acme-challenge").join("token"), "token.key").unwrap();
+
+        let srv = init_service(
+            App::new()
+                .wrap(Exempt::new(RedirectHttps).well_known())
+                .service(web::well_known(&dir))
+                .route("/index.html", web::get().to(|| async { "index" })),
+        )
+        .await;
+
+        let req =
+            TestRequest::with_uri("/.well-known/acme-challenge/token").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"token.key"));
+
+        let req =
+            TestRequest::with_uri("/.well-known/acme-challenge/unknown").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // directories are not served
+        let req = TestRequest::with_uri("/.well-known/acme-challenge").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/index.html").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+
+        let req = TestRequest::with_uri("/.well-knownx").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,487 @@
+//! Middleware for replaying responses of repeated requests
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use std::{cell::RefCell, collections::HashMap, future::Future, pin::Pin, rc::Rc};
+use std::{sync::Arc, sync::Mutex};
+
+use crate::channel::oneshot;
+use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::http::{Method, Response, StatusCode};
+use crate::service::{Service, Transform};
+use crate::time::{self, Millis};
+use crate::util::{next, Bytes, BytesMut, Either};
+use crate::web::{error::IdempotencyKeyError, types::IdempotencyKey};
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// `Idempotent-Replayed` header name
+const REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Response stored for idempotency key.
+#[derive(Clone, Debug)]
+pub struct StoredResponse {
+    /// Response status
+    pub status: StatusCode,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body
+    pub body: Bytes,
+}
+
+impl StoredResponse {
+    fn to_response(&self) -> Response {
+        let mut res = Response::new(self.status);
+        *res.headers_mut() = self.headers.clone();
+        res.headers_mut()
+            .insert(REPLAYED, HeaderValue::from_static("true"));
+        res.set_body(self.body.clone().into())
+    }
+}
+
+/// Storage for responses of `Idempotency` middleware.
+pub trait IdempotencyStore {
+    /// Load response stored for the key.
+    ///
+    /// Key is the `Idempotency-Key` value prefixed with request method and path.
+    fn get(&self, key: &str) -> BoxFuture<Option<StoredResponse>>;
+
+    /// Store response for the key, response must be available for `ttl`.
+    fn set(&self, key: &str, res: StoredResponse, ttl: Millis) -> BoxFuture<()>;
+}
+
+/// In-memory store for `Idempotency` middleware.
+///
+/// Clones of the store share the same storage, so single store could
+/// be used by all workers. Expired responses are removed on insert.
+#[derive(Clone, Default)]
+pub struct MemoryIdempotencyStore(
+    Arc<Mutex<HashMap<String, (Instant, StoredResponse)>>>,
+);
+
+impl MemoryIdempotencyStore {
+    /// Create new in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn get(&self, key: &str) -> BoxFuture<Option<StoredResponse>> {
+        let now = time::now();
+        let res = self
+            .0
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(expire, _)| *expire > now)
+            .map(|(_, res)| res.clone());
+        Box::pin(async move { res })
+    }
+
+    fn set(&self, key: &str, res: StoredResponse, ttl: Millis) -> BoxFuture<()> {
+        let now = time::now();
+        let mut items = self.0.lock().unwrap();
+        items.retain(|_, (expire, _)| *expire > now);
+        items.insert(key.to_string(), (now + Duration::from(ttl), res));
+        Box::pin(async {})
+    }
+}
+
+/// `Middleware` for replaying responses of repeated requests.
+///
+/// Middleware handles `POST` and `PATCH` requests with `Idempotency-Key`
+/// header. First response for the key is stored and replayed for
+/// repeated requests with the same key within configured ttl, replayed
+/// responses contain `Idempotent-Replayed: true` header. Server error
+/// responses are not stored, so request could be retried. Requests
+/// with malformed key get *Bad Request* response.
+///
+/// Concurrent requests with the same key are serialized within a worker,
+/// request waits until first request completes and then gets replayed
+/// response. Response body is buffered in memory.
+///
+/// Responses are stored per request method and path, so the same key
+/// used for different resources does not replay another resource's response.
+/// By default responses are stored in `MemoryIdempotencyStore` for 24 hours.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let store = middleware::MemoryIdempotencyStore::new();
+///
+///     let app = App::new()
+///         .wrap(middleware::Idempotency::with_store(store.clone()))
+///         .route("/payment", web::post().to(|| async { HttpResponse::Created() }));
+/// }
+/// ```
+pub struct Idempotency<St = MemoryIdempotencyStore> {
+    store: Rc<St>,
+    ttl: Millis,
+}
+
+impl Default for Idempotency {
+    fn default() -> Self {
+        Idempotency::with_store(MemoryIdempotencyStore::new())
+    }
+}
+
+impl Idempotency {
+    /// Construct `Idempotency` middleware with in-memory store.
+    pub fn new() -> Self {
+        Idempotency::default()
+    }
+}
+
+impl<St: IdempotencyStore> Idempotency<St> {
+    /// Construct `Idempotency` middleware with custom store.
+    pub fn with_store(store: St) -> Self {
+        Idempotency {
+            store: Rc::new(store),
+            ttl: Millis(24 * 60 * 60 * 1000),
+        }
+    }
+
+    /// Set time for which responses are replayed.
+    ///
+    /// By default ttl is 24 hours.
+    pub fn ttl(mut self, ttl: Millis) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+impl<S, St, E> Transform<S> for Idempotency<St>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse> + 'static,
+    St: IdempotencyStore + 'static,
+    E: 'static,
+{
+    type Service = IdempotencyMiddleware<S, St>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        IdempotencyMiddleware {
+            service: Rc::new(service),
+            store: self.store.clone(),
+            ttl: self.ttl,
+            inflight: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+}
+
+type InFlight = Rc<RefCell<HashMap<String, Vec<oneshot::Sender<()>>>>>;
+
+pub struct IdempotencyMiddleware<S, St> {
+    service: Rc<S>,
+    store: Rc<St>,
+    ttl: Millis,
+    inflight: InFlight,
+}
+
+impl<S, St, E> Service for IdempotencyMiddleware<S, St>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse> + 'static,
+    St: IdempotencyStore + 'static,
+    E: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Either<S::Future, BoxFuture<Result<WebResponse, S::Error>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let method = &req.head().method;
+        if method != Method::POST && method != Method::PATCH {
+            return Either::Left(self.service.call(req));
+        }
+
+        let key = match IdempotencyKey::from_headers(req.headers()) {
+            Ok(key) => format!("{} {} {}", method, req.path(), key.into_inner()),
+            Err(IdempotencyKeyError::Missing) => {
+                return Either::Left(self.service.call(req))
+            }
+            Err(e) => {
+                let res = HttpResponse::BadRequest().body(e.to_string());
+                return Either::Right(Box::pin(
+                    async move { Ok(req.into_response(res)) },
+                ));
+            }
+        };
+
+        let srv = self.service.clone();
+        let store = self.store.clone();
+        let ttl = self.ttl;
+        let inflight = self.inflight.clone();
+
+        Either::Right(Box::pin(async move {
+            // wait for in-flight request with the same key
+            loop {
+                let rx = match inflight.borrow_mut().get_mut(&key) {
+                    Some(waiters) => {
+                        let (tx, rx) = oneshot::channel();
+                        waiters.push(tx);
+                        rx
+                    }
+                    None => break,
+                };
+                let _ = rx.await;
+            }
+            let _guard = InFlightGuard::new(inflight, key.clone());
+
+            if let Some(stored) = store.get(&key).await {
+                log::trace!("Replay response for idempotency key {:?}", key);
+                return Ok(req.into_response(stored.to_response()));
+            }
+
+            let mut res = srv.call(req).await?;
+            if res.status().is_server_error() {
+                return Ok(res);
+            }
+
+            // buffer response body
+            let mut body = res.take_body();
+            let mut buf = BytesMut::new();
+            while let Some(item) = next(&mut body).await {
+                match item {
+                    Ok(chunk) => buf.extend_from_slice(&chunk),
+                    Err(e) => {
+                        log::error!("Cannot read response body: {:?}", e);
+                        return Ok(res.into_response(
+                            HttpResponse::InternalServerError().finish(),
+                        ));
+                    }
+                }
+            }
+            let stored = StoredResponse {
+                status: res.status(),
+                headers: res.headers().clone(),
+                body: buf.freeze(),
+            };
+            store.set(&key, stored.clone(), ttl).await;
+
+            let mut response = Response::new(stored.status);
+            *response.headers_mut() = stored.headers;
+            Ok(res.into_response(response.set_body(stored.body.into())))
+        }))
+    }
+}
+
+/// Removes in-flight key and wakes up waiting requests
+struct InFlightGuard {
+    inflight: InFlight,
+    key: String,
+}
+
+impl InFlightGuard {
+    fn new(inflight: InFlight, key: String) -> Self {
+        inflight.borrow_mut().insert(key.clone(), Vec::new());
+        InFlightGuard { inflight, key }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        // dropping senders wakes up waiters
+        self.inflight.borrow_mut().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::time::sleep;
+    use crate::util::join;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App};
+
+    #[crate::rt_test]
+    async fn test_idempotency_replay() {
+        let counter = Rc::new(Cell::new(0));
+        let counter2 = counter.clone();
+        let srv = init_service(App::new().wrap(Idempotency::new()).route(
+            "/",
+            web::to(move || {
+                counter2.set(counter2.get() + 1);
+                let val = counter2.get();
+                async move {
+                    HttpResponse::Created()
+                        .header("x-counter", val.to_string())
+                        .body(val.to_string())
+                }
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .header("idempotency-key", "key1")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert!(!res.headers().contains_key(REPLAYED));
+        assert_eq!(read_body(res).await, Bytes::from_static(b"1"));
+
+        // repeated request
+        let req = TestRequest::post()
+            .header("idempotency-key", "key1")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers().get(REPLAYED).unwrap(), "true");
+        assert_eq!(res.headers().get("x-counter").unwrap(), "1");
+        assert_eq!(read_body(res).await, Bytes::from_static(b"1"));
+
+        // new key
+        let req = TestRequest::post()
+            .header("idempotency-key", "key2")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"2"));
+
+        // requests without key and non POST/PATCH requests are not stored
+        let req = TestRequest::post().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"3"));
+        let req = TestRequest::get()
+            .header("idempotency-key", "key1")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"4"));
+
+        // malformed key
+        let req = TestRequest::post()
+            .header("idempotency-key", "")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(counter.get(), 4);
+    }
+
+    #[test]
+    fn test_idempotency_default_ttl() {
+        assert_eq!(Idempotency::new().ttl, Millis(24 * 60 * 60 * 1000));
+        assert_eq!(Idempotency::new().ttl(Millis(5)).ttl, Millis(5));
+    }
+
+    #[crate::rt_test]
+    async fn test_idempotency_key_scope() {
+        let srv = init_service(
+            App::new()
+                .wrap(Idempotency::new())
+                .route("/a", web::post().to(|| async { "a" }))
+                .route("/b", web::post().to(|| async { "b" }))
+                .route("/b", web::patch().to(|| async { "patch b" })),
+        )
+        .await;
+
+        for (method, path, body) in [
+            (Method::POST, "/a", "a"),
+            (Method::POST, "/b", "b"),
+            (Method::PATCH, "/b", "patch b"),
+            (Method::POST, "/a", "a"),
+        ] {
+            let req = TestRequest::with_uri(path)
+                .method(method)
+                .header("idempotency-key", "key")
+                .to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(read_body(res).await, Bytes::from(body), "{}", path);
+        }
+
+        // repeated request is replayed for the same resource only
+        let req = TestRequest::with_uri("/b")
+            .method(Method::POST)
+            .header("idempotency-key", "key")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(REPLAYED).unwrap(), "true");
+        assert_eq!(read_body(res).await, Bytes::from_static(b"b"));
+    }
+
+    #[crate::rt_test]
+    async fn test_idempotency_ttl_and_errors() {
+        let counter = Rc::new(Cell::new(0));
+        let counter2 = counter.clone();
+        let srv =
+            init_service(App::new().wrap(Idempotency::new().ttl(Millis(1000))).route(
+                "/",
+                web::to(move || {
+                    counter2.set(counter2.get() + 1);
+                    let val = counter2.get();
+                    async move {
+                        if val == 1 {
+                            HttpResponse::ServiceUnavailable().finish()
+                        } else {
+                            HttpResponse::Ok().body(val.to_string())
+                        }
+                    }
+                }),
+            ))
+            .await;
+
+        // server errors are not stored
+        let req = TestRequest::post()
+            .header("idempotency-key", "k")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let req = TestRequest::post()
+            .header("idempotency-key", "k")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"2"));
+
+        // expired response is not replayed
+        sleep(Millis(1100)).await;
+        let req = TestRequest::post()
+            .header("idempotency-key", "k")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(REPLAYED));
+        assert_eq!(read_body(res).await, Bytes::from_static(b"3"));
+    }
+
+    #[crate::rt_test]
+    async fn test_idempotency_concurrent() {
+        let counter = Rc::new(Cell::new(0));
+        let counter2 = counter.clone();
+        let srv = init_service(App::new().wrap(Idempotency::new()).route(
+            "/",
+            web::to(move || {
+                counter2.set(counter2.get() + 1);
+                let val = counter2.get();
+                async move {
+                    sleep(Millis(50)).await;
+                    val.to_string()
+                }
+            }),
+        ))
+        .await;
+
+        let req1 = TestRequest::patch()
+            .header("idempotency-key", "key")
+            .to_request();
+        let req2 = TestRequest::patch()
+            .header("idempotency-key", "key")
+            .to_request();
+        let (res1, res2) = join(srv.call(req1), srv.call(req2)).await;
+        let (res1, res2) = (res1.unwrap(), res2.unwrap());
+        assert!(!res1.headers().contains_key(REPLAYED));
+        assert_eq!(res2.headers().get(REPLAYED).unwrap(), "true");
+        assert_eq!(read_body(res1).await, Bytes::from_static(b"1"));
+        assert_eq!(read_body(res2).await, Bytes::from_static(b"1"));
+        assert_eq!(counter.get(), 1);
+    }
+}
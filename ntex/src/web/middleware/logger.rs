@@ -6,7 +6,7 @@ use std::{convert::TryFrom, env, error::Error, future::Future, pin::Pin, rc::Rc,
 use regex::Regex;
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
-use crate::http::header::HeaderName;
+use crate::http::header::{HeaderMap, HeaderName};
 use crate::service::{Service, Transform};
 use crate::util::{Bytes, Either, HashSet};
 use crate::web::{HttpResponse, WebRequest, WebResponse};
@@ -157,14 +157,13 @@ where
         if self.inner.exclude.contains(req.path()) {
             Either::Right(self.service.call(req))
         } else {
-            let time = time::SystemTime::now();
             let mut format = self.inner.format.clone();
 
             for unit in &mut format.0 {
-                unit.render_request(time, &req);
+                unit.render_request(time::SystemTime::now(), &req);
             }
             Either::Left(LoggerResponse {
-                time,
+                time: req.received_at(),
                 format: Some(format),
                 fut: self.service.call(req),
             })
@@ -178,7 +177,7 @@ pin_project_lite::pin_project! {
     {
         #[pin]
         fut: S::Future,
-        time: time::SystemTime,
+        time: time::Instant,
         format: Option<Format>,
     }
 }
@@ -222,7 +221,7 @@ struct StreamLog {
     body: ResponseBody<Body>,
     format: Option<Format>,
     size: usize,
-    time: time::SystemTime,
+    time: time::Instant,
 }
 
 impl Drop for StreamLog {
@@ -256,6 +255,10 @@ impl MessageBody for StreamLog {
             val => val,
         }
     }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.body.trailers()
+    }
 }
 
 /// A formatting style for the `Logger`, consisting of multiple
@@ -349,20 +352,18 @@ impl FormatText {
         &self,
         fmt: &mut fmt::Formatter<'_>,
         size: usize,
-        entry_time: time::SystemTime,
+        entry_time: time::Instant,
     ) -> Result<(), fmt::Error> {
         match *self {
             FormatText::Str(ref string) => fmt.write_str(string),
             FormatText::Percent => "%".fmt(fmt),
             FormatText::ResponseSize => size.fmt(fmt),
             FormatText::Time => {
-                let rt = entry_time.elapsed().unwrap();
-                let rt = rt.as_secs_f64();
+                let rt = entry_time.elapsed().as_secs_f64();
                 fmt.write_fmt(format_args!("{:.6}", rt))
             }
             FormatText::TimeMillis => {
-                let rt = entry_time.elapsed().unwrap();
-                let rt = (rt.as_nanos() as f64) / 1_000_000.0;
+                let rt = (entry_time.elapsed().as_nanos() as f64) / 1_000_000.0;
                 fmt.write_fmt(format_args!("{:.6}", rt))
             }
             FormatText::EnvironHeader(ref name) => {
@@ -461,7 +462,7 @@ mod tests {
     use super::*;
     use crate::http::{header, StatusCode};
     use crate::service::{IntoService, Transform};
-    use crate::util::lazy;
+    use crate::util::{lazy, poll_fn};
     use crate::web::test::{self, TestRequest};
     use crate::web::{DefaultError, Error};
 
@@ -501,6 +502,52 @@ mod tests {
         assert_eq!(body, Bytes::from_static(b"TEST"));
     }
 
+    #[crate::rt_test]
+    async fn test_logger_trailers() {
+        struct TrailersBody(Option<Bytes>);
+
+        impl MessageBody for TrailersBody {
+            fn size(&self) -> BodySize {
+                BodySize::Stream
+            }
+
+            fn poll_next_chunk(
+                &mut self,
+                _: &mut Context<'_>,
+            ) -> Poll<Option<Result<Bytes, Box<dyn std::error::Error>>>> {
+                Poll::Ready(self.0.take().map(Ok))
+            }
+
+            fn trailers(&mut self) -> Option<HeaderMap> {
+                let mut trailers = HeaderMap::new();
+                trailers.insert(
+                    HeaderName::from_static("digest"),
+                    header::HeaderValue::from_static("sha-256=test"),
+                );
+                Some(trailers)
+            }
+        }
+
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().body(
+                Body::from_message(TrailersBody(Some(Bytes::from_static(b"TEST")))),
+            )))
+        };
+        let logger = Logger::default();
+        let srv = Transform::new_transform(&logger, srv.into_service());
+
+        let req = TestRequest::default().to_srv_request();
+        let mut res = srv.call(req).await.unwrap();
+        let mut body = res.take_body();
+        assert_eq!(
+            poll_fn(|cx| body.poll_next_chunk(cx)).await.unwrap().ok(),
+            Some(Bytes::from_static(b"TEST"))
+        );
+        assert!(poll_fn(|cx| body.poll_next_chunk(cx)).await.is_none());
+        let trailers = body.trailers().unwrap();
+        assert!(trailers.contains_key("digest"));
+    }
+
     #[crate::rt_test]
     async fn test_url_path() {
         let mut format = Format::new("%T %U");
@@ -523,7 +570,7 @@ mod tests {
 
         let render = |fmt: &mut fmt::Formatter<'_>| {
             for unit in &format.0 {
-                unit.render(fmt, 1024, now)?;
+                unit.render(fmt, 1024, req.received_at())?;
             }
             Ok(())
         };
@@ -551,10 +598,9 @@ mod tests {
             unit.render_response(&resp);
         }
 
-        let entry_time = time::SystemTime::now();
         let render = |fmt: &mut fmt::Formatter<'_>| {
             for unit in &format.0 {
-                unit.render(fmt, 1024, entry_time)?;
+                unit.render(fmt, 1024, req.received_at())?;
             }
             Ok(())
         };
@@ -581,7 +627,7 @@ mod tests {
 
         let render = |fmt: &mut fmt::Formatter<'_>| {
             for unit in &format.0 {
-                unit.render(fmt, 1024, now)?;
+                unit.render(fmt, 1024, req.received_at())?;
             }
             Ok(())
         };
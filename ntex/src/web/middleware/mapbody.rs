@@ -0,0 +1,338 @@
+//! Middleware for transforming response bodies
+use std::task::{Context, Poll};
+use std::{error::Error, future::Future, pin::Pin, rc::Rc};
+
+use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
+use crate::http::header::{
+    HeaderMap, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE,
+};
+use crate::service::{Service, Transform};
+use crate::util::{next, Bytes, BytesMut};
+use crate::web::{WebRequest, WebResponse};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// `Middleware` for transforming response bodies.
+///
+/// Response body is transformed if predicate returns `true` for response's
+/// `Content-Type` header value. Body is loaded to memory, transformed and
+/// `Content-Length` header is updated. Responses without `Content-Type`
+/// header, encoded responses and responses larger than `max_size` are
+/// passed as is. Streaming responses are buffered until `max_size` is
+/// reached, larger streams are passed without transformation. Trailers
+/// of transformed bodies are dropped.
+///
+/// Default `max_size` is 1Mb.
+///
+/// ```rust
+/// use ntex::util::Bytes;
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::MapBody::new(
+///             |ct| ct.starts_with("text/html"),
+///             |body: Bytes| {
+///                 let body = String::from_utf8_lossy(&body);
+///                 let lines: Vec<_> = body.lines().map(|l| l.trim()).collect();
+///                 Bytes::from(lines.concat())
+///             },
+///         ))
+///         .service(web::resource("/index.html").to(|| async {
+///             HttpResponse::Ok()
+///                 .content_type("text/html")
+///                 .body("<html>\n  <body>index</body>\n</html>")
+///         }));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MapBody {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    predicate: Box<dyn Fn(&str) -> bool>,
+    f: Box<dyn Fn(Bytes) -> Bytes>,
+    max_size: usize,
+}
+
+impl MapBody {
+    /// Construct `MapBody` middleware with content type predicate and
+    /// transform function.
+    pub fn new<P, F>(predicate: P, f: F) -> Self
+    where
+        P: Fn(&str) -> bool + 'static,
+        F: Fn(Bytes) -> Bytes + 'static,
+    {
+        MapBody {
+            inner: Rc::new(Inner {
+                predicate: Box::new(predicate),
+                f: Box::new(f),
+                max_size: 1_048_576,
+            }),
+        }
+    }
+
+    /// Set max size of the transformed body.
+    ///
+    /// Larger responses are passed without transformation.
+    pub fn max_size(mut self, size: usize) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .max_size = size;
+        self
+    }
+}
+
+impl<S> Transform<S> for MapBody {
+    type Service = MapBodyMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        MapBodyMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct MapBodyMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, E> Service for MapBodyMiddleware<S>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<WebResponse, S::Error>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let fut = self.service.call(req);
+        let inner = self.inner.clone();
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            let matched = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|val| val.to_str().ok())
+                .map(|ct| (inner.predicate)(ct))
+                .unwrap_or(false);
+            if !matched || res.headers().contains_key(CONTENT_ENCODING) {
+                return Ok(res);
+            }
+            match res.response().body().size() {
+                BodySize::None | BodySize::Empty => return Ok(res),
+                BodySize::Sized(size) if size > inner.max_size as u64 => return Ok(res),
+                _ => (),
+            }
+
+            let mut body = res.take_body();
+            let mut buf = BytesMut::new();
+            while let Some(item) = next(&mut body).await {
+                match item {
+                    Ok(chunk) => buf.extend_from_slice(&chunk),
+                    Err(err) => {
+                        return Ok(res.map_body(|_, _| {
+                            ResponseBody::Other(Body::from_message(Prefixed {
+                                prefix: Some(buf.freeze()),
+                                error: Some(err),
+                                body: None,
+                            }))
+                        }));
+                    }
+                }
+                if buf.len() > inner.max_size {
+                    return Ok(res.map_body(|_, _| {
+                        ResponseBody::Other(Body::from_message(Prefixed {
+                            prefix: Some(buf.freeze()),
+                            error: None,
+                            body: Some(body),
+                        }))
+                    }));
+                }
+            }
+
+            let body = (inner.f)(buf.freeze());
+            res.headers_mut()
+                .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+            Ok(res.map_body(|_, _| ResponseBody::Other(Body::Bytes(body))))
+        })
+    }
+}
+
+/// Already loaded part of the body followed by the rest of the stream
+struct Prefixed {
+    prefix: Option<Bytes>,
+    error: Option<Box<dyn Error>>,
+    body: Option<ResponseBody<Body>>,
+}
+
+impl MessageBody for Prefixed {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        if let Some(prefix) = self.prefix.take() {
+            if !prefix.is_empty() {
+                return Poll::Ready(Some(Ok(prefix)));
+            }
+        }
+        if let Some(err) = self.error.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+        match self.body {
+            Some(ref mut body) => body.poll_next_chunk(cx),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn trailers(&mut self) -> Option<HeaderMap> {
+        self.body.as_mut().and_then(|body| body.trailers())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    /// Remove whitespace outside of json strings
+    fn minify_json(body: Bytes) -> Bytes {
+        let mut buf = Vec::with_capacity(body.len());
+        let mut in_str = false;
+        let mut escaped = false;
+        for &b in body.iter() {
+            if in_str {
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_str = false;
+                }
+            } else if b == b'"' {
+                in_str = true;
+            } else if b.is_ascii_whitespace() {
+                continue;
+            }
+            buf.push(b);
+        }
+        Bytes::from(buf)
+    }
+
+    #[crate::rt_test]
+    async fn test_map_body_json() {
+        let srv = init_service(
+            App::new()
+                .wrap(
+                    MapBody::new(|ct| ct.starts_with("application/json"), minify_json)
+                        .max_size(64),
+                )
+                .route(
+                    "/json",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body("{\n  \"name\": \"a b\",\n  \"id\": 1\n}")
+                    }),
+                )
+                .route(
+                    "/stream",
+                    web::get().to(|| async {
+                        let chunks = vec![
+                            Ok::<_, std::io::Error>(Bytes::from_static(b"{ \"id\": ")),
+                            Ok(Bytes::from_static(b"1 }")),
+                        ];
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .streaming(futures::stream::iter(chunks))
+                    }),
+                )
+                .route(
+                    "/large",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body(format!("[{}]", " 1,".repeat(30)))
+                    }),
+                )
+                .route(
+                    "/text",
+                    web::get().to(|| async {
+                        HttpResponse::Ok().content_type("text/plain").body("a b")
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_LENGTH).unwrap(), "21");
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"{\"name\":\"a b\",\"id\":1}")
+        );
+
+        let req = TestRequest::with_uri("/stream").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"{\"id\":1}"));
+
+        // larger than max size
+        let req = TestRequest::with_uri("/large").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await.len(), 92);
+
+        let req = TestRequest::with_uri("/text").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"a b"));
+    }
+
+    #[crate::rt_test]
+    async fn test_map_body_large_stream() {
+        let srv = init_service(
+            App::new()
+                .wrap(MapBody::new(|_| true, |_| Bytes::new()).max_size(4))
+                .route(
+                    "/",
+                    web::get().to(|| async {
+                        let chunks = vec![
+                            Ok::<_, std::io::Error>(Bytes::from_static(b"123")),
+                            Ok(Bytes::from_static(b"456")),
+                            Ok(Bytes::from_static(b"789")),
+                        ];
+                        HttpResponse::Ok()
+                            .content_type("text/plain")
+                            .streaming(futures::stream::iter(chunks))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"123456789"));
+    }
+}
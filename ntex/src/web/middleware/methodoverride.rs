@@ -0,0 +1,342 @@
+//! Middleware for overriding request method
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, pin::Pin, rc::Rc};
+
+use crate::http::header::{HeaderName, CONTENT_TYPE};
+use crate::http::{h1, Method};
+use crate::service::{Service, Transform};
+use crate::util::{next, BytesMut};
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+const METHOD_OVERRIDE: HeaderName = HeaderName::from_static("x-http-method-override");
+const FORM_FIELD: &str = "_method";
+
+/// `Middleware` for overriding request method.
+///
+/// HTML forms could send only `GET` and `POST` requests. This middleware
+/// rewrites method of `POST` requests to the method specified in
+/// `X-HTTP-Method-Override` header or in `_method` field of url encoded
+/// form. Only `PUT`, `PATCH` and `DELETE` methods could be used as override,
+/// requests with any other method are passed through unchanged.
+///
+/// Middleware must be registered with `App::wrap()`, so method is updated
+/// before routing.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::MethodOverride::new())
+///         .service(
+///             web::resource("/item")
+///                 .route(web::delete().to(|| async { HttpResponse::Ok() }))
+///         );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MethodOverride {
+    form: bool,
+    limit: usize,
+}
+
+impl Default for MethodOverride {
+    fn default() -> Self {
+        MethodOverride {
+            form: true,
+            limit: 16_384,
+        }
+    }
+}
+
+impl MethodOverride {
+    /// Construct `MethodOverride` middleware.
+    pub fn new() -> MethodOverride {
+        MethodOverride::default()
+    }
+
+    /// Enable or disable `_method` form field lookup.
+    ///
+    /// Middleware has to read request's body to check form field.
+    /// Enabled by default.
+    pub fn form(mut self, enabled: bool) -> Self {
+        self.form = enabled;
+        self
+    }
+
+    /// Change max size of form body that could be checked for `_method` field.
+    ///
+    /// Requests with larger bodies get `413 Payload Too Large` response.
+    /// By default limit is 16Kb.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<S, E> Transform<S> for MethodOverride
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse> + 'static,
+    S::Future: 'static,
+    E: 'static,
+{
+    type Service = MethodOverrideMiddleware<S, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        MethodOverrideMiddleware {
+            service: Rc::new(service),
+            cfg: self.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct MethodOverrideMiddleware<S, E> {
+    service: Rc<S>,
+    cfg: MethodOverride,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> Service for MethodOverrideMiddleware<S, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse> + 'static,
+    S::Future: 'static,
+    E: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, mut req: WebRequest<E>) -> Self::Future {
+        if req.head().method != Method::POST {
+            return Box::pin(self.service.call(req));
+        }
+
+        if let Some(method) = req
+            .headers()
+            .get(&METHOD_OVERRIDE)
+            .and_then(|val| val.to_str().ok())
+            .and_then(override_method)
+        {
+            log::trace!("Override request method with {:?}", method);
+            req.head_mut().method = method;
+            return Box::pin(self.service.call(req));
+        }
+
+        if !self.cfg.form || !is_form(&req) {
+            return Box::pin(self.service.call(req));
+        }
+
+        let srv = self.service.clone();
+        let limit = self.cfg.limit;
+        Box::pin(async move {
+            let mut payload = req.take_payload();
+            let mut body = BytesMut::new();
+            while let Some(item) = next(&mut payload).await {
+                match item {
+                    Ok(chunk) => {
+                        if body.len() + chunk.len() > limit {
+                            return Ok(req.into_response(
+                                HttpResponse::PayloadTooLarge().finish(),
+                            ));
+                        }
+                        body.extend_from_slice(&chunk);
+                    }
+                    Err(e) => {
+                        log::trace!("Cannot read form body: {:?}", e);
+                        return Ok(
+                            req.into_response(HttpResponse::BadRequest().finish())
+                        );
+                    }
+                }
+            }
+            let body = body.freeze();
+
+            if let Some(method) =
+                serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body)
+                    .ok()
+                    .and_then(|fields| {
+                        fields
+                            .into_iter()
+                            .find(|(name, _)| name == FORM_FIELD)
+                            .and_then(|(_, value)| override_method(&value))
+                    })
+            {
+                log::trace!("Override request method with {:?}", method);
+                req.head_mut().method = method;
+            }
+
+            // restore request's body
+            let mut pl = h1::Payload::empty();
+            pl.unread_data(body);
+            req.set_payload(pl.into());
+
+            srv.call(req).await
+        })
+    }
+}
+
+fn override_method(value: &str) -> Option<Method> {
+    match Method::from_bytes(value.trim().to_ascii_uppercase().as_bytes()) {
+        Ok(method)
+            if method == Method::PUT
+                || method == Method::PATCH
+                || method == Method::DELETE =>
+        {
+            Some(method)
+        }
+        _ => None,
+    }
+}
+
+fn is_form<E>(req: &WebRequest<E>) -> bool {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .map(|ct| {
+            ct.split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::util::{lazy, Bytes};
+    use crate::web::test::{init_service, read_body, TestRequest};
+    use crate::web::{self, App};
+
+    #[crate::rt_test]
+    async fn test_method_override_header() {
+        let srv = init_service(
+            App::new().wrap(MethodOverride::new()).service(
+                web::resource("/")
+                    .route(web::delete().to(|| async { "delete" }))
+                    .route(web::post().to(|| async { "post" }))
+                    .route(web::get().to(|| async { "get" })),
+            ),
+        )
+        .await;
+        assert!(lazy(|cx| srv.poll_ready(cx).is_ready()).await);
+
+        let req = TestRequest::post()
+            .header("x-http-method-override", "DELETE")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"delete"));
+
+        let req = TestRequest::post()
+            .header("x-http-method-override", "delete")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"delete"));
+
+        // only PUT, PATCH and DELETE are allowed
+        let req = TestRequest::post()
+            .header("x-http-method-override", "GET")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"post"));
+
+        // GET requests cannot be overridden
+        let req = TestRequest::get()
+            .header("x-http-method-override", "DELETE")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"get"));
+    }
+
+    #[crate::rt_test]
+    async fn test_method_override_form() {
+        let srv = init_service(
+            App::new().wrap(MethodOverride::new().limit(64)).service(
+                web::resource("/")
+                    .route(web::put().to(|body: Bytes| async move { body }))
+                    .route(web::post().to(|| async { "post" }))
+                    .route(web::get().to(|| async { "get" })),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload("name=test&_method=put")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"name=test&_method=put")
+        );
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload("name=test")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"post"));
+
+        // not a form
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "text/plain")
+            .set_payload("_method=put")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"post"));
+
+        // GET requests cannot be overridden
+        let req = TestRequest::get()
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload("_method=put")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"get"));
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload(format!("_method=put&data={}", "x".repeat(64)))
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[crate::rt_test]
+    async fn test_method_override_form_disabled() {
+        let srv = init_service(
+            App::new().wrap(MethodOverride::new().form(false)).service(
+                web::resource("/")
+                    .route(web::put().to(|| async { "put" }))
+                    .route(web::post().to(|| async { "post" })),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload("_method=put")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"post"));
+
+        let req = TestRequest::post()
+            .header("x-http-method-override", "PUT")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"put"));
+    }
+}
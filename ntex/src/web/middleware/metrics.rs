@@ -0,0 +1,349 @@
+//! Middleware for collecting per-route latency histograms
+use std::task::{Context, Poll};
+use std::{fmt, fmt::Write, future::Future, marker::PhantomData, pin::Pin};
+use std::{sync::Arc, sync::Mutex, time::Duration, time::Instant};
+
+use crate::service::{Service, Transform};
+use crate::util::HashMap;
+use crate::web::{WebRequest, WebResponse};
+
+/// Number of buckets per power of two, defines histogram precision
+const SUB_BUCKETS: u64 = 64;
+
+/// `Middleware` for collecting request latency per route.
+///
+/// Handler durations are recorded into histograms keyed by the matched
+/// resource pattern (see `HttpRequest::match_pattern()`). Requests that do
+/// not match any resource are not recorded. Number of tracked routes is
+/// limited, durations for routes beyond the limit are dropped.
+///
+/// `Metrics` is a handle to shared histograms, a clone of the middleware
+/// could be used for rendering percentiles.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let metrics = middleware::Metrics::new();
+///     let handle = metrics.clone();
+///
+///     let app = App::new()
+///         .wrap(metrics)
+///         .service(web::resource("/user/{id}").to(|| async { HttpResponse::Ok() }))
+///         .service(web::resource("/metrics").to(move || {
+///             let body = handle.render();
+///             async move { HttpResponse::Ok().body(body) }
+///         }));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    max_routes: usize,
+    routes: HashMap<String, Histogram>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            inner: Arc::new(Mutex::new(Inner {
+                max_routes: 128,
+                routes: HashMap::default(),
+            })),
+        }
+    }
+}
+
+impl Metrics {
+    /// Construct `Metrics` middleware.
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Set max number of tracked routes.
+    ///
+    /// By default 128 routes are tracked.
+    pub fn max_routes(self, max: usize) -> Self {
+        self.inner.lock().unwrap().max_routes = max;
+        self
+    }
+
+    /// Record handler duration for the route.
+    pub fn record(&self, route: &str, duration: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(hist) = inner.routes.get_mut(route) {
+            hist.record(duration);
+        } else if inner.routes.len() < inner.max_routes {
+            let mut hist = Histogram::default();
+            hist.record(duration);
+            inner.routes.insert(route.to_string(), hist);
+        }
+    }
+
+    /// Get list of tracked routes.
+    pub fn routes(&self) -> Vec<String> {
+        let mut routes: Vec<_> =
+            self.inner.lock().unwrap().routes.keys().cloned().collect();
+        routes.sort();
+        routes
+    }
+
+    /// Get number of recorded durations for the route.
+    pub fn count(&self, route: &str) -> u64 {
+        self.inner
+            .lock()
+            .unwrap()
+            .routes
+            .get(route)
+            .map(|hist| hist.count)
+            .unwrap_or(0)
+    }
+
+    /// Get duration percentile for the route.
+    ///
+    /// `quantile` must be in range of `0.0..=1.0`, i.e. `0.95` for p95.
+    pub fn percentile(&self, route: &str, quantile: f64) -> Option<Duration> {
+        self.inner
+            .lock()
+            .unwrap()
+            .routes
+            .get(route)
+            .map(|hist| hist.percentile(quantile))
+    }
+
+    /// Render p50/p95/p99 percentiles for all tracked routes.
+    ///
+    /// Output uses prometheus text format.
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut routes: Vec<_> = inner.routes.iter().collect();
+        routes.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE http_request_duration_seconds summary");
+        for (route, hist) in routes {
+            let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+            for q in &[0.5, 0.95, 0.99] {
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds{{route=\"{}\",quantile=\"{}\"}} {}",
+                    route,
+                    q,
+                    hist.percentile(*q).as_secs_f64()
+                );
+            }
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_count{{route=\"{}\"}} {}",
+                route, hist.count
+            );
+        }
+        out
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("routes", &self.routes())
+            .finish()
+    }
+}
+
+impl<S, E> Transform<S> for Metrics
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Service = MetricsMiddleware<S, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        MetricsMiddleware {
+            service,
+            metrics: self.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct MetricsMiddleware<S, E> {
+    service: S,
+    metrics: Metrics,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> Service for MetricsMiddleware<S, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            if let Some(pattern) = res.request().match_pattern() {
+                metrics.record(pattern, start.elapsed());
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Log-linear histogram of durations in microseconds.
+///
+/// Values are stored with relative error less than 1/64.
+#[derive(Default)]
+struct Histogram {
+    count: u64,
+    buckets: Vec<u64>,
+}
+
+impl Histogram {
+    fn record(&mut self, duration: Duration) {
+        let idx = bucket_index(duration.as_micros() as u64);
+        if self.buckets.len() <= idx {
+            self.buckets.resize(idx + 1, 0);
+        }
+        self.buckets[idx] += 1;
+        self.count += 1;
+    }
+
+    fn percentile(&self, quantile: f64) -> Duration {
+        let quantile = quantile.clamp(0.0, 1.0);
+        let rank = ((quantile * self.count as f64).ceil() as u64).max(1);
+
+        let mut total = 0;
+        for (idx, cnt) in self.buckets.iter().enumerate() {
+            total += cnt;
+            if total >= rank {
+                return Duration::from_micros(bucket_value(idx));
+            }
+        }
+        Duration::from_micros(0)
+    }
+}
+
+/// Bucket index for the value
+fn bucket_index(val: u64) -> usize {
+    if val < SUB_BUCKETS * 2 {
+        val as usize
+    } else {
+        let shift = 63 - val.leading_zeros() as u64 - 6;
+        (SUB_BUCKETS * shift + (val >> shift)) as usize
+    }
+}
+
+/// Lowest value of the bucket
+fn bucket_value(idx: usize) -> u64 {
+    let idx = idx as u64;
+    if idx < SUB_BUCKETS * 2 {
+        idx
+    } else {
+        let shift = idx / SUB_BUCKETS - 1;
+        (idx - SUB_BUCKETS * shift) << shift
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{self, TestRequest};
+    use crate::web::{self, App, DefaultError, Error, HttpResponse};
+
+    #[test]
+    fn test_histogram() {
+        for val in &[0, 1, 127, 128, 129, 1000, 65_535, 1_000_000, u64::MAX / 2] {
+            let low = bucket_value(bucket_index(*val));
+            assert!(low <= *val && *val - low <= *val / SUB_BUCKETS);
+        }
+
+        let metrics = Metrics::new();
+        for ms in 1..=100 {
+            metrics.record("/test", Duration::from_millis(ms));
+        }
+        assert_eq!(metrics.count("/test"), 100);
+
+        let p50 = metrics.percentile("/test", 0.5).unwrap();
+        assert!(p50 <= Duration::from_millis(50) && p50 > Duration::from_millis(49));
+        let p95 = metrics.percentile("/test", 0.95).unwrap();
+        assert!(p95 <= Duration::from_millis(95) && p95 > Duration::from_millis(93));
+        let p100 = metrics.percentile("/test", 1.0).unwrap();
+        assert!(p100 <= Duration::from_millis(100) && p100 > Duration::from_millis(98));
+        assert!(metrics.percentile("/unknown", 0.5).is_none());
+    }
+
+    #[test]
+    fn test_max_routes() {
+        let metrics = Metrics::new().max_routes(2);
+        metrics.record("/a", Duration::from_millis(1));
+        metrics.record("/b", Duration::from_millis(1));
+        metrics.record("/c", Duration::from_millis(1));
+        metrics.record("/a", Duration::from_millis(1));
+        assert_eq!(metrics.routes(), vec!["/a".to_string(), "/b".to_string()]);
+        assert_eq!(metrics.count("/a"), 2);
+        assert_eq!(metrics.count("/c"), 0);
+
+        let out = metrics.render();
+        assert!(out.contains(
+            "http_request_duration_seconds{route=\"/a\",quantile=\"0.95\"} 0.001\n"
+        ));
+        assert!(out.contains("http_request_duration_seconds_count{route=\"/a\"} 2\n"));
+    }
+
+    #[crate::rt_test]
+    async fn test_metrics_middleware() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let metrics = Metrics::new();
+        let mw = metrics.new_transform(srv.into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        // not matched requests are not recorded
+        let req = TestRequest::default().to_srv_request();
+        let _ = mw.call(req).await.unwrap();
+        assert!(metrics.routes().is_empty());
+
+        let metrics = Metrics::new();
+        let srv = test::init_service(App::new().wrap(metrics.clone()).service(
+            web::scope("/api").service(
+                web::resource("/user/{id}").to(|| async { HttpResponse::Ok() }),
+            ),
+        ))
+        .await;
+
+        for id in 0..3 {
+            let req = TestRequest::with_uri(&format!("/api/user/{}", id)).to_request();
+            let _ = srv.call(req).await.unwrap();
+        }
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let _ = srv.call(req).await.unwrap();
+
+        assert_eq!(metrics.routes(), vec!["/api/user/{id}".to_string()]);
+        assert_eq!(metrics.count("/api/user/{id}"), 3);
+    }
+}
@@ -5,8 +5,43 @@ mod compress;
 #[cfg(feature = "compress")]
 pub use self::compress::Compress;
 
+mod charset;
+pub use self::charset::DefaultCharset;
+
 mod logger;
 pub use self::logger::Logger;
 
+mod drain;
+pub use self::drain::Drain;
+
+mod exempt;
+pub use self::exempt::Exempt;
+
 mod defaultheaders;
 pub use self::defaultheaders::DefaultHeaders;
+
+mod idempotency;
+pub use self::idempotency::{
+    Idempotency, IdempotencyStore, MemoryIdempotencyStore, StoredResponse,
+};
+
+mod mapbody;
+pub use self::mapbody::MapBody;
+
+mod methodoverride;
+pub use self::methodoverride::MethodOverride;
+
+mod metrics;
+pub use self::metrics::Metrics;
+
+mod oncontenttype;
+pub use self::oncontenttype::OnContentType;
+
+mod rejectbody;
+pub use self::rejectbody::RejectBody;
+
+mod trace;
+pub use self::trace::TracePropagation;
+
+mod wrapfn;
+pub use self::wrapfn::{Next, WrapFn};
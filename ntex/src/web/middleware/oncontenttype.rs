@@ -0,0 +1,286 @@
+//! Middleware for applying other middleware to responses with specific content type
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, pin::Pin, rc::Rc};
+
+use mime::Mime;
+
+use crate::http::{header::CONTENT_TYPE, Response};
+use crate::service::{Service, Transform};
+use crate::util::Ready;
+use crate::web::httprequest::RequestCopy;
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// `Middleware` that applies inner middleware only to responses with
+/// matching content type.
+///
+/// Request is passed to the service first, inner middleware is called
+/// after response is ready and only if response's `Content-Type` matches
+/// `mime`, i.e. `text/html` matches `text/html; charset=utf-8` and
+/// `text/*` matches any text content type. Other responses are passed
+/// as is, without buffering. This is intended for post-processing
+/// middlewares, for example for body transformation. Inner middleware
+/// receives response produced by the service, request payload is already
+/// consumed at this point. If request is still referenced, for example
+/// handler keeps a clone of `HttpRequest`, inner middleware receives a copy
+/// of the request's head, without extensions and match info.
+///
+/// ```rust
+/// use ntex::util::Bytes;
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::OnContentType::new(
+///             mime::TEXT_HTML,
+///             middleware::MapBody::new(|_| true, |body: Bytes| {
+///                 let body = String::from_utf8_lossy(&body)
+///                     .replace("</body>", "<script src=\"/app.js\"></script></body>");
+///                 Bytes::from(body)
+///             }),
+///         ))
+///         .service(web::resource("/index.html").to(|| async {
+///             HttpResponse::Ok()
+///                 .content_type("text/html")
+///                 .body("<html><body>index</body></html>")
+///         }));
+/// }
+/// ```
+pub struct OnContentType<M> {
+    mw: M,
+    mime: Rc<Mime>,
+}
+
+impl<M> OnContentType<M> {
+    /// Construct `OnContentType` middleware for content type and inner middleware.
+    pub fn new(mime: Mime, mw: M) -> Self {
+        OnContentType {
+            mw,
+            mime: Rc::new(mime),
+        }
+    }
+}
+
+impl<S, M, E> Transform<S> for OnContentType<M>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+    M: Transform<Replay<E, S::Error>>,
+    M::Service: Service<Request = WebRequest<E>, Response = WebResponse, Error = S::Error>
+        + 'static,
+    E: 'static,
+{
+    type Service = OnContentTypeMiddleware<S, M::Service>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        OnContentTypeMiddleware {
+            service,
+            middleware: Rc::new(self.mw.new_transform(Replay(PhantomData))),
+            mime: self.mime.clone(),
+        }
+    }
+}
+
+pub struct OnContentTypeMiddleware<S, T> {
+    service: S,
+    middleware: Rc<T>,
+    mime: Rc<Mime>,
+}
+
+impl<S, T, E> Service for OnContentTypeMiddleware<S, T>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+    T: Service<Request = WebRequest<E>, Response = WebResponse, Error = S::Error>
+        + 'static,
+    E: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let ready1 = self.service.poll_ready(cx)?.is_ready();
+        let ready2 = self.middleware.poll_ready(cx)?.is_ready();
+        if ready1 && ready2 {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        let ready1 = self.service.poll_shutdown(cx, is_error).is_ready();
+        let ready2 = self.middleware.poll_shutdown(cx, is_error).is_ready();
+        if ready1 && ready2 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let fut = self.service.call(req);
+        let middleware = self.middleware.clone();
+        let mime = self.mime.clone();
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let matched = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|val| val.to_str().ok())
+                .and_then(|ct| ct.parse::<Mime>().ok())
+                .map(|ct| {
+                    ct.type_() == mime.type_()
+                        && (mime.subtype() == mime::STAR
+                            || ct.subtype() == mime.subtype())
+                })
+                .unwrap_or(false);
+            if !matched {
+                return Ok(res);
+            }
+
+            let req = res.request().clone();
+            let response = Response::from(res);
+            let req = match WebRequest::from_request(req) {
+                Ok(req) => req,
+                Err(req) => {
+                    log::debug!("Request is shared, use copy of the request");
+                    WebRequest::new(RequestCopy::new(&req).request())
+                }
+            };
+            req.extensions_mut().insert(Replayed(response));
+            middleware.call(req).await
+        })
+    }
+}
+
+/// Response produced by the service
+struct Replayed(Response);
+
+#[doc(hidden)]
+/// Service that returns already produced response to inner middleware
+pub struct Replay<E, Er>(PhantomData<(E, Er)>);
+
+impl<E, Er> Service for Replay<E, Er> {
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = Er;
+    type Future = Ready<WebResponse, Er>;
+
+    #[inline]
+    fn poll_ready(&self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let res = req.extensions_mut().remove::<Replayed>();
+        match res {
+            Some(Replayed(res)) => Ready::Ok(req.into_response(res)),
+            None => Ready::Ok(req.into_response(HttpResponse::InternalServerError())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::http::body::{BodySize, MessageBody};
+    use crate::util::Bytes;
+    use crate::web::middleware::MapBody;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpRequest};
+
+    #[crate::rt_test]
+    async fn test_on_content_type() {
+        let shared = Rc::new(RefCell::new(None));
+        let shared2 = shared.clone();
+
+        let srv = init_service(
+            App::new()
+                .wrap_on_content_type(
+                    mime::TEXT_HTML,
+                    MapBody::new(
+                        |_| true,
+                        |body: Bytes| {
+                            let body = String::from_utf8_lossy(&body)
+                                .replace("</body>", "<script></script></body>");
+                            Bytes::from(body)
+                        },
+                    ),
+                )
+                .route(
+                    "/html",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("text/html; charset=utf-8")
+                            .body("<body></body>")
+                    }),
+                )
+                .route(
+                    "/shared",
+                    web::get().to(move |req: HttpRequest| {
+                        *shared2.borrow_mut() = Some(req);
+                        async {
+                            HttpResponse::Ok()
+                                .content_type("text/html")
+                                .body("<body></body>")
+                        }
+                    }),
+                )
+                .route(
+                    "/text",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("text/plain")
+                            .body("</body>")
+                    }),
+                )
+                .route(
+                    "/stream",
+                    web::get().to(|| async {
+                        let chunks = vec![Ok::<_, std::io::Error>(Bytes::from_static(
+                            b"</body>",
+                        ))];
+                        HttpResponse::Ok()
+                            .content_type("application/octet-stream")
+                            .streaming(futures::stream::iter(chunks))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/html").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"<body><script></script></body>")
+        );
+
+        // request is kept by handler
+        let req = TestRequest::with_uri("/shared").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"<body><script></script></body>")
+        );
+        assert!(shared.borrow().is_some());
+
+        let req = TestRequest::with_uri("/text").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"</body>"));
+
+        // not buffered
+        let req = TestRequest::with_uri("/stream").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.response().body().size(), BodySize::Stream);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"</body>"));
+    }
+}
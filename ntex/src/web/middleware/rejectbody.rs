@@ -0,0 +1,188 @@
+//! Middleware for rejecting requests with unexpected bodies
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, pin::Pin, rc::Rc};
+
+use crate::http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+use crate::http::{Method, RequestHead};
+use crate::service::{Service, Transform};
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// `Middleware` for rejecting requests that carry a body.
+///
+/// Requests with specified methods are rejected with `400 Bad Request`
+/// response if request contains non-empty body, i.e. `Content-Length` is
+/// not zero or `Transfer-Encoding` header is set.
+///
+/// By default `GET`, `HEAD` and `DELETE` requests are checked.
+///
+/// ```rust
+/// use ntex::http::Method;
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::RejectBody::on(vec![Method::GET, Method::DELETE]))
+///         .service(
+///             web::resource("/test")
+///                 .route(web::get().to(|| async { HttpResponse::Ok() }))
+///         );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RejectBody {
+    methods: Rc<Vec<Method>>,
+}
+
+impl Default for RejectBody {
+    fn default() -> Self {
+        RejectBody::on(vec![Method::GET, Method::HEAD, Method::DELETE])
+    }
+}
+
+impl RejectBody {
+    /// Construct `RejectBody` middleware with default methods.
+    pub fn new() -> RejectBody {
+        RejectBody::default()
+    }
+
+    /// Construct `RejectBody` middleware for specified methods.
+    pub fn on<I>(methods: I) -> RejectBody
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        RejectBody {
+            methods: Rc::new(methods.into_iter().collect()),
+        }
+    }
+}
+
+impl<S, E> Transform<S> for RejectBody
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Service = RejectBodyMiddleware<S, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        RejectBodyMiddleware {
+            service,
+            methods: self.methods.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct RejectBodyMiddleware<S, E> {
+    service: S,
+    methods: Rc<Vec<Method>>,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> Service for RejectBodyMiddleware<S, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        if self.methods.contains(&req.head().method) && has_body(req.head()) {
+            log::trace!("Unexpected request body for {:?}", req.head().method);
+            let res = req.into_response(HttpResponse::BadRequest().finish());
+            Box::pin(async move { Ok(res) })
+        } else {
+            Box::pin(self.service.call(req))
+        }
+    }
+}
+
+fn has_body(head: &RequestHead) -> bool {
+    if head.headers.contains_key(TRANSFER_ENCODING) {
+        return true;
+    }
+    head.headers
+        .get(CONTENT_LENGTH)
+        .map(|len| len.to_str().ok().and_then(|s| s.trim().parse::<u64>().ok()))
+        .map(|len| len != Some(0))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::util::lazy;
+    use crate::web::test::{ok_service, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_reject_body() {
+        let mw = RejectBody::new().new_transform(ok_service());
+
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::default().to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .header(header::CONTENT_LENGTH, "0")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .header(header::CONTENT_LENGTH, "4")
+            .set_payload("test")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::default()
+            .header(header::TRANSFER_ENCODING, "chunked")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::default()
+            .method(Method::POST)
+            .header(header::CONTENT_LENGTH, "4")
+            .set_payload("test")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_reject_body_methods() {
+        let mw = RejectBody::on(vec![Method::DELETE]).new_transform(ok_service());
+
+        let req = TestRequest::default()
+            .header(header::CONTENT_LENGTH, "4")
+            .set_payload("test")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::default()
+            .method(Method::DELETE)
+            .header(header::CONTENT_LENGTH, "4")
+            .set_payload("test")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
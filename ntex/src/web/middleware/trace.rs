@@ -0,0 +1,185 @@
+//! Middleware for W3C trace context propagation
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, pin::Pin};
+
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::service::{Service, Transform};
+use crate::web::types::trace::{TRACEPARENT, TRACESTATE};
+use crate::web::types::TraceContext;
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for W3C trace context propagation.
+///
+/// Middleware parses `traceparent` and `tracestate` request headers,
+/// or generates new trace context if headers are absent or invalid.
+/// Trace context is stored in request extensions and is available to
+/// handlers with `TraceContext` extractor. Response gets `traceparent`
+/// and `tracestate` headers of the current span, if response does not
+/// contain them yet.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::TracePropagation::new())
+///         .service(
+///             web::resource("/index.html")
+///                 .route(web::get().to(|| async { HttpResponse::Ok() }))
+///         );
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TracePropagation;
+
+impl TracePropagation {
+    /// Construct `TracePropagation` middleware.
+    pub fn new() -> Self {
+        TracePropagation
+    }
+}
+
+impl<S, E> Transform<S> for TracePropagation
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Service = TracePropagationMiddleware<S, E>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        TracePropagationMiddleware {
+            service,
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct TracePropagationMiddleware<S, E> {
+    service: S,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> Service for TracePropagationMiddleware<S, E>
+where
+    S: Service<Request = WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Request = WebRequest<E>;
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let ctx = TraceContext::from_headers(req.headers()).unwrap_or_default();
+        req.extensions_mut().insert(ctx.clone());
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            let name = HeaderName::from_static(TRACEPARENT);
+            if !res.headers().contains_key(&name) {
+                if let Ok(val) = HeaderValue::from_str(&ctx.traceparent()) {
+                    res.headers_mut().insert(name, val);
+                }
+            }
+            if let Some(state) = ctx.state() {
+                let name = HeaderName::from_static(TRACESTATE);
+                if !res.headers().contains_key(&name) {
+                    if let Ok(val) = HeaderValue::from_str(state) {
+                        res.headers_mut().insert(name, val);
+                    }
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Bytes;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App};
+
+    #[crate::rt_test]
+    async fn test_trace_pass_through() {
+        let srv = init_service(App::new().wrap(TracePropagation::new()).route(
+            "/",
+            web::get().to(|ctx: TraceContext| async move {
+                format!("{} {}", ctx.trace_id(), ctx.parent_id().unwrap_or_default())
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default()
+            .header(
+                TRACEPARENT,
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .header(TRACESTATE, "congo=t61rcWkgMzE")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        let traceparent = res
+            .headers()
+            .get(TRACEPARENT)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(traceparent.ends_with("-01"));
+        assert!(!traceparent.contains("00f067aa0ba902b7"));
+        assert_eq!(res.headers().get(TRACESTATE).unwrap(), "congo=t61rcWkgMzE");
+        assert_eq!(
+            read_body(res).await,
+            Bytes::from_static(b"4bf92f3577b34da6a3ce929d0e0e4736 00f067aa0ba902b7")
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_trace_generate() {
+        let srv = init_service(App::new().wrap(TracePropagation::new()).route(
+            "/",
+            web::get().to(|ctx: TraceContext| async move { ctx.traceparent() }),
+        ))
+        .await;
+
+        for req in [
+            TestRequest::default().to_request(),
+            TestRequest::default()
+                .header(TRACEPARENT, "invalid")
+                .header(TRACESTATE, "congo=t61rcWkgMzE")
+                .to_request(),
+        ] {
+            let res = call_service(&srv, req).await;
+            assert!(!res.headers().contains_key(TRACESTATE));
+            let traceparent = res
+                .headers()
+                .get(TRACEPARENT)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+
+            // generated value is valid
+            let ctx = TraceContext::parse(&traceparent).unwrap();
+            assert!(ctx.sampled());
+            assert_eq!(traceparent.len(), 55);
+
+            // handler and response share the same context
+            assert_eq!(read_body(res).await, Bytes::from(traceparent));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Middleware defined by a function
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, rc::Rc};
+
+use crate::service::boxed::{self, BoxFuture, RcService};
+use crate::service::{Service, Transform};
+use crate::web::{ErrorRenderer, WebRequest, WebResponse};
+
+/// Inner service of the function middleware.
+///
+/// Future returned by `Next::call()` does not borrow `Next`, so it
+/// could be awaited in a returned `async move` block.
+pub struct Next<Err: ErrorRenderer>(
+    RcService<WebRequest<Err>, WebResponse, Err::Container>,
+);
+
+impl<Err: ErrorRenderer> Next<Err> {
+    /// Call inner service.
+    pub fn call(&self, req: WebRequest<Err>) -> BoxFuture<WebResponse, Err::Container> {
+        self.0.call(req)
+    }
+}
+
+impl<Err: ErrorRenderer> Clone for Next<Err> {
+    fn clone(&self) -> Self {
+        Next(self.0.clone())
+    }
+}
+
+/// `Middleware` created from a function, see `App::wrap_fn()`.
+pub struct WrapFn<F, Err> {
+    f: Rc<F>,
+    _t: PhantomData<Err>,
+}
+
+impl<F, Err> WrapFn<F, Err> {
+    pub(crate) fn new(f: F) -> Self {
+        WrapFn {
+            f: Rc::new(f),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<S, F, R, Err> Transform<S> for WrapFn<F, Err>
+where
+    S: Service<
+            Request = WebRequest<Err>,
+            Response = WebResponse,
+            Error = Err::Container,
+        > + 'static,
+    S::Future: 'static,
+    F: Fn(WebRequest<Err>, &Next<Err>) -> R,
+    R: Future<Output = Result<WebResponse, Err::Container>>,
+    Err: ErrorRenderer,
+{
+    type Service = WrapFnMiddleware<F, Err>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        WrapFnMiddleware {
+            f: self.f.clone(),
+            next: Next(boxed::rcservice(service)),
+        }
+    }
+}
+
+pub struct WrapFnMiddleware<F, Err: ErrorRenderer> {
+    f: Rc<F>,
+    next: Next<Err>,
+}
+
+impl<F, R, Err> Service for WrapFnMiddleware<F, Err>
+where
+    F: Fn(WebRequest<Err>, &Next<Err>) -> R,
+    R: Future<Output = Result<WebResponse, Err::Container>>,
+    Err: ErrorRenderer,
+{
+    type Request = WebRequest<Err>;
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Future = R;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.next.0.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.next.0.poll_shutdown(cx, is_error)
+    }
+
+    #[inline]
+    fn call(&self, req: WebRequest<Err>) -> Self::Future {
+        (self.f)(req, &self.next)
+    }
+}
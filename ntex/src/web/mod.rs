@@ -68,6 +68,7 @@ mod config;
 pub mod error;
 mod error_default;
 mod extract;
+pub mod grpc_web;
 pub mod guard;
 mod handler;
 mod httprequest;
@@ -83,6 +84,8 @@ mod scope;
 mod server;
 mod service;
 pub mod test;
+#[cfg(feature = "tower")]
+pub mod tower;
 pub mod types;
 mod util;
 pub mod ws;
@@ -101,7 +104,7 @@ pub use ntex_macros::web_trace as trace;
 pub use crate::http::Response as HttpResponse;
 pub use crate::http::ResponseBuilder as HttpResponseBuilder;
 
-pub use self::app::App;
+pub use self::app::{App, TrailingSlash};
 pub use self::config::ServiceConfig;
 pub use self::error::{
     DefaultError, Error, ErrorContainer, ErrorRenderer, WebResponseError,
@@ -111,7 +114,9 @@ pub use self::handler::Handler;
 pub use self::httprequest::HttpRequest;
 pub use self::request::WebRequest;
 pub use self::resource::Resource;
-pub use self::responder::Responder;
+pub use self::responder::{
+    Binary, Multipart, Negotiate, Ranged, Redirect, Responder, WithHeader, WithStatus,
+};
 pub use self::response::WebResponse;
 pub use self::route::Route;
 pub use self::scope::Scope;
@@ -131,7 +136,7 @@ pub mod dev {
     pub use crate::web::rmap::ResourceMap;
     pub use crate::web::route::IntoRoutes;
     pub use crate::web::service::{
-        WebServiceAdapter, WebServiceConfig, WebServiceFactory,
+        WebServiceAdapter, WebServiceConfig, WebServiceFactory, WithRenderer,
     };
 
     pub(crate) fn insert_slesh(mut patterns: Vec<String>) -> Vec<String> {
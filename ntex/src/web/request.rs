@@ -1,8 +1,9 @@
 use std::cell::{Ref, RefMut};
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::{fmt, net};
+use std::{fmt, net, time::Instant};
 
+use crate::http::h1::{ConnExtensions, ConnStats, SocketOptions};
 use crate::http::{
     header, HeaderMap, HttpMessage, Method, Payload, RequestHead, Response, Uri, Version,
 };
@@ -11,9 +12,9 @@ use crate::util::Extensions;
 
 use super::config::AppConfig;
 use super::error::{ErrorRenderer, WebResponseError};
-use super::httprequest::HttpRequest;
+use super::httprequest::{HttpRequest, RequestCopy};
 use super::info::ConnectionInfo;
-use super::response::WebResponse;
+use super::response::{render_error, WebResponse};
 use super::rmap::ResourceMap;
 
 /// An service http request
@@ -28,7 +29,8 @@ impl<Err: ErrorRenderer> WebRequest<Err> {
     /// Create web response for error
     #[inline]
     pub fn render_error<E: WebResponseError<Err>>(self, err: E) -> WebResponse {
-        WebResponse::new(err.error_response(&self.req), self.req)
+        let res = render_error(&self.req, &err, err.error_response(&self.req));
+        WebResponse::new(res, self.req)
     }
 
     /// Create web response for error
@@ -47,6 +49,16 @@ impl<Err> WebRequest<Err> {
         }
     }
 
+    /// Change error renderer type of the request
+    pub(super) fn cast<Err2>(self) -> WebRequest<Err2> {
+        WebRequest::new(self.req)
+    }
+
+    /// Copy of the request's head
+    pub(super) fn copy(&self) -> RequestCopy {
+        RequestCopy::new(&self.req)
+    }
+
     /// Deconstruct request into parts
     pub fn into_parts(mut self) -> (HttpRequest, Payload) {
         let pl = Rc::get_mut(&mut (self.req).0).unwrap().payload.take();
@@ -181,6 +193,44 @@ impl<Err> WebRequest<Err> {
         self.req.match_info_mut()
     }
 
+    #[inline]
+    /// Get a pattern of the resource that matched the request path.
+    pub fn match_pattern(&self) -> Option<&str> {
+        self.req.match_pattern()
+    }
+
+    #[inline]
+    /// Time when request processing started.
+    pub fn received_at(&self) -> Instant {
+        self.req.received_at()
+    }
+
+    #[inline]
+    /// Get http methods of the resource that matched the request path
+    /// but rejected the request method.
+    pub fn matched_methods(&self) -> Option<&[Method]> {
+        self.req.matched_methods()
+    }
+
+    /// Set http methods of the matched resource
+    pub(crate) fn set_matched_methods(&mut self, methods: Vec<Method>) {
+        Rc::get_mut(&mut (self.req).0).unwrap().matched_methods = if methods.is_empty() {
+            None
+        } else {
+            Some(methods)
+        };
+    }
+
+    /// Append matched resource pattern
+    pub(crate) fn push_match_pattern(&mut self, pattern: &str) {
+        let mp = &mut Rc::get_mut(&mut (self.req).0).unwrap().match_pattern;
+        if mp.ends_with('/') && pattern.starts_with('/') {
+            mp.push_str(&pattern[1..]);
+        } else {
+            mp.push_str(pattern);
+        }
+    }
+
     #[inline]
     /// Get a reference to a `ResourceMap` of current application.
     pub fn resource_map(&self) -> &ResourceMap {
@@ -231,6 +281,41 @@ impl<Err> WebRequest<Err> {
     pub fn extensions_mut(&self) -> RefMut<'_, Extensions> {
         self.req.extensions_mut()
     }
+
+    /// Socket options of the http/1 connection
+    #[inline]
+    pub fn socket_options(&self) -> Option<&SocketOptions> {
+        self.req.socket_options()
+    }
+
+    /// Statistics of the http/1 connection
+    #[inline]
+    pub fn conn_stats(&self) -> Option<&ConnStats> {
+        self.req.conn_stats()
+    }
+
+    /// Connection-level extensions of the http/1 connection
+    #[inline]
+    pub fn conn_extensions(&self) -> Option<&ConnExtensions> {
+        self.req.conn_extensions()
+    }
+
+    /// Insert a value into request extensions.
+    ///
+    /// Request extensions are shared with `HttpRequest`, value inserted
+    /// by filter or middleware is available to the handler via
+    /// `HttpRequest::extensions()`. If a value of this type already
+    /// exists, it is replaced.
+    #[inline]
+    pub fn insert_extension<T: 'static>(&mut self, val: T) {
+        self.req.extensions_mut().insert(val)
+    }
+
+    /// Get a clone of the request extension of specified type.
+    #[inline]
+    pub fn get_extension<T: Clone + 'static>(&self) -> Option<T> {
+        self.req.extensions().get::<T>().cloned()
+    }
 }
 
 impl<Err> Resource<Uri> for WebRequest<Err> {
@@ -2,13 +2,14 @@ use std::{
     cell::RefCell, fmt, future::Future, pin::Pin, rc::Rc, task::Context, task::Poll,
 };
 
-use crate::http::Response;
+use crate::http::{header::ALLOW, Method, Response, StatusCode};
 use crate::router::{IntoPattern, ResourceDef};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{pipeline_factory, PipelineFactory};
 use crate::service::{Identity, IntoServiceFactory, Service, ServiceFactory, Transform};
 use crate::util::{Either, Extensions, Ready};
 
+use super::app_service::method_not_allowed;
 use super::dev::{insert_slesh, WebServiceConfig, WebServiceFactory};
 use super::error::ErrorRenderer;
 use super::extract::FromRequest;
@@ -56,6 +57,7 @@ pub struct Resource<Err: ErrorRenderer, M = Identity, T = Filter<Err>> {
     data: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
     default: Rc<RefCell<Option<Rc<HttpNewService<Err>>>>>,
+    auto_options: bool,
 }
 
 impl<Err: ErrorRenderer> Resource<Err> {
@@ -69,6 +71,7 @@ impl<Err: ErrorRenderer> Resource<Err> {
             guards: Vec::new(),
             data: None,
             default: Rc::new(RefCell::new(None)),
+            auto_options: false,
         }
     }
 }
@@ -174,7 +177,8 @@ where
     /// Provide resource specific data. This method allows to add extractor
     /// configuration or specific state available via `Data<T>` extractor.
     /// Provided data is available for all routes registered for the current resource.
-    /// Resource data overrides data registered by `App::data()` method.
+    /// Resource data overrides data registered by `App::data()` method,
+    /// other resources get application's data.
     ///
     /// ```rust
     /// use ntex::web::{self, App, FromRequest};
@@ -277,6 +281,7 @@ where
             routes: self.routes,
             default: self.default,
             data: self.data,
+            auto_options: self.auto_options,
         }
     }
 
@@ -297,9 +302,33 @@ where
             routes: self.routes,
             default: self.default,
             data: self.data,
+            auto_options: self.auto_options,
         }
     }
 
+    /// Respond to `OPTIONS` requests automatically.
+    ///
+    /// If resource has no route for `OPTIONS` method, *204* response
+    /// with `Allow` header is returned. Header lists methods of resource's
+    /// routes.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/index.html")
+    ///             .auto_options()
+    ///             .route(web::get().to(|| async { HttpResponse::Ok() }))
+    ///             .route(web::post().to(|| async { HttpResponse::Ok() }))
+    ///     );
+    /// }
+    /// ```
+    pub fn auto_options(mut self) -> Self {
+        self.auto_options = true;
+        self
+    }
+
     /// Default service to be used if no matching route could be found.
     /// By default *405* response get returned. Resource does not use
     /// default handler from `App` or `Scope`.
@@ -349,9 +378,11 @@ where
             Some(std::mem::take(&mut self.guards))
         };
         let mut rdef = if config.is_root() || !self.rdef.is_empty() {
-            ResourceDef::new(insert_slesh(self.rdef.clone()))
+            ResourceDef::new(
+                config.merge_trailing_slash(insert_slesh(self.rdef.clone())),
+            )
         } else {
-            ResourceDef::new(self.rdef.clone())
+            ResourceDef::new(config.merge_trailing_slash(self.rdef.clone()))
         };
         if let Some(ref name) = self.name {
             *rdef.name_mut() = name.clone();
@@ -365,6 +396,13 @@ where
             routes: self.routes,
             data: self.data.map(Rc::new),
             default: self.default,
+            auto_options: if self.auto_options {
+                Some(StatusCode::NO_CONTENT)
+            } else if config.automatic_options() {
+                Some(StatusCode::OK)
+            } else {
+                None
+            },
         };
 
         config.register_service(
@@ -378,6 +416,27 @@ where
             None,
         )
     }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        let mut methods = Vec::new();
+        for route in &self.routes {
+            let mut route_methods = route.methods().peekable();
+            if route_methods.peek().is_none() {
+                // route matches any method
+                methods.clear();
+                break;
+            }
+            for m in route_methods {
+                if !methods.contains(m) {
+                    methods.push(m.clone());
+                }
+            }
+        }
+        insert_slesh(self.rdef.clone())
+            .into_iter()
+            .map(|path| (path, methods.clone()))
+            .collect()
+    }
 }
 
 impl<Err, M, T> IntoServiceFactory<ResourceServiceFactory<Err, M, PipelineFactory<T>>>
@@ -403,6 +462,11 @@ where
             routes: self.routes,
             data: self.data.map(Rc::new),
             default: self.default,
+            auto_options: if self.auto_options {
+                Some(StatusCode::NO_CONTENT)
+            } else {
+                None
+            },
         };
 
         ResourceServiceFactory {
@@ -538,6 +602,7 @@ struct ResourceRouterFactory<Err: ErrorRenderer> {
     routes: Vec<Route<Err>>,
     data: Option<Rc<Extensions>>,
     default: Rc<RefCell<Option<Rc<HttpNewService<Err>>>>>,
+    auto_options: Option<StatusCode>,
 }
 
 impl<Err: ErrorRenderer> ServiceFactory for ResourceRouterFactory<Err> {
@@ -553,6 +618,7 @@ impl<Err: ErrorRenderer> ServiceFactory for ResourceRouterFactory<Err> {
         let data = self.data.clone();
         let routes = self.routes.iter().map(|route| route.service()).collect();
         let default_fut = self.default.borrow().as_ref().map(|f| f.new_service(()));
+        let auto_options = self.auto_options;
 
         Box::pin(async move {
             let default = if let Some(fut) = default_fut {
@@ -565,6 +631,7 @@ impl<Err: ErrorRenderer> ServiceFactory for ResourceRouterFactory<Err> {
                 routes,
                 data,
                 default,
+                auto_options,
             })
         })
     }
@@ -574,6 +641,7 @@ struct ResourceRouter<Err: ErrorRenderer> {
     routes: Vec<RouteService<Err>>,
     data: Option<Rc<Extensions>>,
     default: Option<HttpService<Err>>,
+    auto_options: Option<StatusCode>,
 }
 
 impl<Err: ErrorRenderer> Service for ResourceRouter<Err> {
@@ -599,13 +667,39 @@ impl<Err: ErrorRenderer> Service for ResourceRouter<Err> {
                 return Either::Right(route.call(req));
             }
         }
+
+        let mut methods = Vec::new();
+        for m in self.routes.iter().flat_map(|route| route.methods()) {
+            if !methods.contains(m) {
+                methods.push(m.clone());
+            }
+        }
+
+        if let Some(status) = self.auto_options {
+            if req.head().method == Method::OPTIONS && !methods.is_empty() {
+                let mut allow = methods
+                    .iter()
+                    .map(|m| m.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                allow.push_str(", OPTIONS");
+                return Either::Left(Ready::Ok(WebResponse::new(
+                    Response::build(status).header(ALLOW, allow).finish(),
+                    req.into_parts().0,
+                )));
+            }
+        }
+        req.set_matched_methods(methods);
+
         if let Some(ref default) = self.default {
             Either::Right(default.call(req))
         } else {
-            Either::Left(Ready::Ok(WebResponse::new(
-                Response::MethodNotAllowed().finish(),
-                req.into_parts().0,
-            )))
+            Either::Left(Ready::Ok(method_not_allowed(req).unwrap_or_else(|req| {
+                WebResponse::new(
+                    Response::MethodNotAllowed().finish(),
+                    req.into_parts().0,
+                )
+            })))
         }
     }
 }
@@ -616,11 +710,11 @@ mod tests {
     use crate::http::{Method, StatusCode};
     use crate::time::{sleep, Millis};
     use crate::web::middleware::DefaultHeaders;
-    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
     use crate::web::{
-        self, guard, request::WebRequest, App, DefaultError, HttpResponse,
+        self, guard, request::WebRequest, App, DefaultError, HttpResponse, WebResponse,
     };
-    use crate::{service::fn_service, util::Ready};
+    use crate::{service::fn_service, util::Bytes, util::Ready};
 
     #[crate::rt_test]
     async fn test_filter() {
@@ -739,6 +833,128 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[crate::rt_test]
+    async fn test_auto_options() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .auto_options()
+                        .route(web::get().to(|| async { HttpResponse::Ok() }))
+                        .route(web::post().to(|| async { HttpResponse::Ok() }))
+                        .route(web::get().to(|| async { HttpResponse::Ok() })),
+                )
+                .service(
+                    web::resource("/custom")
+                        .auto_options()
+                        .route(web::get().to(|| async { HttpResponse::Ok() }))
+                        .route(web::method(Method::OPTIONS).to(|| async {
+                            HttpResponse::Ok().header(header::ALLOW, "GET").finish()
+                        })),
+                )
+                .service(
+                    web::resource("/disabled")
+                        .route(web::get().to(|| async { HttpResponse::Ok() })),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            HeaderValue::from_static("GET, POST, OPTIONS")
+        );
+
+        // other methods are not affected
+        let req = TestRequest::with_uri("/test")
+            .method(Method::DELETE)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        // custom handler
+        let req = TestRequest::with_uri("/custom")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            HeaderValue::from_static("GET")
+        );
+
+        let req = TestRequest::with_uri("/disabled")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[crate::rt_test]
+    async fn test_matched_methods() {
+        async fn allow(
+            r: WebRequest<DefaultError>,
+        ) -> Result<WebResponse, crate::web::Error> {
+            let allow = r
+                .matched_methods()
+                .map(|methods| {
+                    methods
+                        .iter()
+                        .map(|m| m.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            Ok(r.into_response(
+                HttpResponse::MethodNotAllowed()
+                    .header(header::ALLOW, allow)
+                    .finish(),
+            ))
+        }
+
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(|| async { HttpResponse::Ok() }))
+                        .route(web::head().to(|| async { HttpResponse::Ok() }))
+                        .default_service(allow),
+                )
+                .route("/app", web::get().to(|| async { HttpResponse::Ok() }))
+                .route("/app", web::head().to(|| async { HttpResponse::Ok() }))
+                .default_service(allow),
+        )
+        .await;
+
+        for path in &["/test", "/app"] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+
+            let req = TestRequest::with_uri(path)
+                .method(Method::POST)
+                .to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(
+                resp.headers().get(header::ALLOW).unwrap(),
+                HeaderValue::from_static("GET, HEAD")
+            );
+        }
+
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            HeaderValue::from_static("")
+        );
+    }
+
     #[crate::rt_test]
     async fn test_resource_guards() {
         let srv = init_service(
@@ -810,4 +1026,31 @@ mod tests {
         let resp = call_service(&srv, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[crate::rt_test]
+    async fn test_data_shadows_app_data() {
+        let srv =
+            init_service(
+                App::new()
+                    .data(1u32)
+                    .service(web::resource("/local").data(2u32).to(
+                        |data: web::types::Data<u32>| async move { data.to_string() },
+                    ))
+                    .service(web::resource("/app").to(
+                        |data: web::types::Data<u32>| async move { data.to_string() },
+                    )),
+            )
+            .await;
+
+        // request pool reuses requests, local data must not leak
+        for _ in 0..2 {
+            let req = TestRequest::with_uri("/local").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(read_body(resp).await, Bytes::from_static(b"2"));
+
+            let req = TestRequest::with_uri("/app").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+        }
+    }
 }
@@ -1,15 +1,26 @@
 use std::task::{Context, Poll};
-use std::{convert::TryFrom, future::Future, marker::PhantomData, pin::Pin};
+use std::time::SystemTime;
+use std::{collections::VecDeque, convert::TryFrom, error::Error, fmt, future::Future};
+use std::{marker::PhantomData, ops, pin::Pin};
 
+use nanorand::{Rng, WyRand};
+
+use crate::http::body::{Body, BodySize, MessageBody};
 use crate::http::error::HttpError;
-use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
-use crate::http::{Response, ResponseBuilder, StatusCode};
+use crate::http::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE,
+    CONTENT_TYPE, ETAG, IF_RANGE, LAST_MODIFIED, LOCATION, RANGE, VARY,
+};
+use crate::http::{Method, Response, ResponseBuilder, StatusCode};
 use crate::util::{Bytes, BytesMut, Either};
 
 use super::error::{
     DefaultError, ErrorContainer, ErrorRenderer, InternalError, WebResponseError,
 };
+use super::guard::accept_quality;
 use super::httprequest::HttpRequest;
+use super::response::{log_error, render_error};
+use super::types::Json;
 
 pub struct Ready<T>(Option<T>);
 
@@ -142,7 +153,76 @@ where
     fn respond_to(self, req: &HttpRequest) -> Self::Future {
         match self {
             Ok(val) => Either::Left(val.respond_to(req)),
-            Err(e) => Either::Right(Ready(Some(e.into().error_response(req)))),
+            Err(e) => {
+                let e = e.into();
+                let res = render_error(req, &e, e.error_response(req));
+                log_error(req, res.status(), &e);
+                Either::Right(Ready(Some(res)))
+            }
+        }
+    }
+}
+
+// `(StatusCode, T)` responders for common types
+macro_rules! status_responder {
+    ($($t:ty),*) => {
+        $(
+            impl<Err: ErrorRenderer> Responder<Err> for (StatusCode, $t) {
+                type Error = <$t as Responder<Err>>::Error;
+                type Future = CustomResponderFut<$t, Err>;
+
+                fn respond_to(self, req: &HttpRequest) -> Self::Future {
+                    CustomResponderFut {
+                        fut: Responder::<Err>::respond_to(self.1, req),
+                        status: Some(self.0),
+                        headers: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+status_responder!(
+    &'static str,
+    &'static [u8],
+    String,
+    Bytes,
+    BytesMut,
+    Response
+);
+
+/// Status code overrides status of the inner responder's response.
+///
+/// `(StatusCode, T)` tuple is supported for common responders: strings,
+/// bytes, `Response` and `Json<T>`. Generic implementation conflicts with
+/// `(T, StatusCode)` implementation.
+///
+/// ```rust
+/// use ntex::http::StatusCode;
+/// use ntex::web::{self, types::Json, App};
+///
+/// async fn create() -> (StatusCode, Json<Vec<&'static str>>) {
+///     (StatusCode::CREATED, Json(vec!["item"]))
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/items", web::post().to(create));
+/// }
+/// ```
+impl<T, Err> Responder<Err> for (StatusCode, Json<T>)
+where
+    Json<T>: Responder<Err>,
+    Err: ErrorRenderer,
+{
+    type Error = <Json<T> as Responder<Err>>::Error;
+    type Future = CustomResponderFut<Json<T>, Err>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        CustomResponderFut {
+            fut: Responder::<Err>::respond_to(self.1, req),
+            status: Some(self.0),
+            headers: None,
         }
     }
 }
@@ -242,6 +322,42 @@ impl<Err: ErrorRenderer> Responder<Err> for BytesMut {
     }
 }
 
+/// Responder for binary content with explicit content type.
+///
+/// Responds with `200 OK`, `Content-Type` and `Content-Length` headers.
+///
+/// ```rust
+/// use ntex::web::{Binary, Responder};
+///
+/// async fn logo() -> impl Responder {
+///     Binary::new(&b"\x89PNG\r\n\x1a\n"[..], mime::IMAGE_PNG)
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct Binary(pub Bytes, pub mime::Mime);
+
+impl Binary {
+    /// Create binary responder for the content and content type.
+    pub fn new<B: Into<Bytes>>(body: B, content_type: mime::Mime) -> Self {
+        Binary(body.into(), content_type)
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for Binary {
+    type Error = Err::Container;
+    type Future = Ready<Response>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        Ready(Some(
+            Response::build(StatusCode::OK)
+                .content_type(self.1.as_ref())
+                .header(CONTENT_LENGTH, self.0.len())
+                .body(self.0),
+        ))
+    }
+}
+
 /// Allows to override status code and headers for a responder.
 pub struct CustomResponder<T: Responder<Err>, Err> {
     responder: T,
@@ -367,6 +483,77 @@ impl<T: Responder<Err>, Err: ErrorRenderer> Future for CustomResponderFut<T, Err
     }
 }
 
+/// Responder wrapper that overrides status code of the inner responder.
+///
+/// ```rust
+/// use ntex::http::StatusCode;
+/// use ntex::web::{types::Json, Responder, WithStatus};
+///
+/// async fn create() -> impl Responder {
+///     WithStatus(Json(vec!["item"]), StatusCode::CREATED)
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug)]
+pub struct WithStatus<R>(pub R, pub StatusCode);
+
+impl<R, Err> Responder<Err> for WithStatus<R>
+where
+    R: Responder<Err>,
+    Err: ErrorRenderer,
+{
+    type Error = R::Error;
+    type Future = CustomResponderFut<R, Err>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        CustomResponderFut {
+            fut: self.0.respond_to(req),
+            status: Some(self.1),
+            headers: None,
+        }
+    }
+}
+
+/// Responder wrapper that sets a header on the inner responder's response.
+///
+/// Header replaces headers with the same name set by the inner responder.
+///
+/// ```rust
+/// use ntex::http::header::{HeaderName, HeaderValue};
+/// use ntex::web::{Responder, WithHeader};
+///
+/// async fn index() -> impl Responder {
+///     WithHeader(
+///         "Welcome!",
+///         HeaderName::from_static("x-version"),
+///         HeaderValue::from_static("1.2.3"),
+///     )
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug)]
+pub struct WithHeader<R>(pub R, pub HeaderName, pub HeaderValue);
+
+impl<R, Err> Responder<Err> for WithHeader<R>
+where
+    R: Responder<Err>,
+    Err: ErrorRenderer,
+{
+    type Error = R::Error;
+    type Future = CustomResponderFut<R, Err>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let mut headers = HeaderMap::new();
+        headers.insert(self.1, self.2);
+
+        CustomResponderFut {
+            fut: self.0.respond_to(req),
+            status: None,
+            headers: Some(headers),
+        }
+    }
+}
+
 /// Combines two different responder types into a single type
 ///
 /// ```rust
@@ -401,6 +588,110 @@ where
     }
 }
 
+/// Responder that selects one of two responders by request's `Accept` header.
+///
+/// Each responder is bound to a media type, responder with higher quality
+/// of its media type in `Accept` header is used. First responder is used
+/// if request has no `Accept` header, if qualities are equal, i.e. for
+/// `Accept: */*`, and if none of media types is acceptable. Response gets
+/// `Vary: Accept` header.
+///
+/// ```rust
+/// use ntex::web::{types::Json, Negotiate, Responder};
+///
+/// async fn index() -> impl Responder {
+///     let items = vec!["a", "b"];
+///     let text = items.join("\n");
+///     Negotiate::new(
+///         (mime::APPLICATION_JSON, Json(items)),
+///         (mime::TEXT_PLAIN, text),
+///     )
+/// }
+/// # fn main() {}
+/// ```
+pub struct Negotiate<A, B> {
+    first: (mime::Mime, A),
+    second: (mime::Mime, B),
+}
+
+impl<A, B> Negotiate<A, B> {
+    /// Create responder from two pairs of media type and responder.
+    pub fn new(first: (mime::Mime, A), second: (mime::Mime, B)) -> Self {
+        Negotiate { first, second }
+    }
+}
+
+impl<A, B> fmt::Debug for Negotiate<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Negotiate")
+            .field("first", &self.first.0)
+            .field("second", &self.second.0)
+            .finish()
+    }
+}
+
+impl<A, B, Err> Responder<Err> for Negotiate<A, B>
+where
+    A: Responder<Err>,
+    B: Responder<Err>,
+    Err: ErrorRenderer,
+{
+    type Error = Err::Container;
+    type Future = Either<NegotiateFut<A, Err>, NegotiateFut<B, Err>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        let headers = req.headers();
+        let first = accept_quality(headers, &self.first.0).map(|(q, _)| q);
+        let second = accept_quality(headers, &self.second.0).map(|(q, _)| q);
+
+        match (first, second) {
+            (Some(first), Some(second)) if second > first => {
+                Either::Right(NegotiateFut {
+                    fut: self.second.1.respond_to(req),
+                })
+            }
+            _ => Either::Left(NegotiateFut {
+                fut: self.first.1.respond_to(req),
+            }),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    pub struct NegotiateFut<T: Responder<Err>, Err: ErrorRenderer> {
+        #[pin]
+        fut: T::Future,
+    }
+}
+
+impl<T: Responder<Err>, Err: ErrorRenderer> Future for NegotiateFut<T, Err> {
+    type Output = Response;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut res = if let Poll::Ready(res) = self.project().fut.poll(cx) {
+            res
+        } else {
+            return Poll::Pending;
+        };
+
+        // keep vary headers set by inner responder
+        let varies = res
+            .headers()
+            .get_all(VARY)
+            .filter_map(|val| val.to_str().ok())
+            .flat_map(|val| val.split(','))
+            .any(|val| {
+                let val = val.trim();
+                val == "*" || val.eq_ignore_ascii_case("accept")
+            });
+        if !varies {
+            res.headers_mut()
+                .append(VARY, HeaderValue::from_static("accept"));
+        }
+        Poll::Ready(res)
+    }
+}
+
 impl<T, Err> Responder<Err> for InternalError<T, Err>
 where
     T: std::fmt::Debug + std::fmt::Display + 'static,
@@ -414,11 +705,541 @@ where
     }
 }
 
+impl Response {
+    /// Create redirect responder that preserves current request's query.
+    ///
+    /// See [`Redirect`] for details.
+    pub fn redirect_preserving_query<S: Into<String>>(location: S) -> Redirect {
+        Redirect::new(location).preserve_query()
+    }
+}
+
+/// Redirect responder.
+///
+/// Responds with `302 Found` and `Location` header. Optionally, current
+/// request's query string is merged into location's query, parameters of
+/// the location take precedence over parameters of the request.
+///
+/// ```rust
+/// use ntex::web::{HttpResponse, Responder};
+///
+/// async fn index() -> impl Responder {
+///     // `/index.html?page=2` redirects to `/new/index.html?lang=en&page=2`
+///     HttpResponse::redirect_preserving_query("/new/index.html").param("lang", "en")
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    location: String,
+    preserve_query: bool,
+    params: Vec<(String, String)>,
+}
+
+impl Redirect {
+    /// Create redirect responder for the location.
+    pub fn new<S: Into<String>>(location: S) -> Self {
+        Redirect {
+            location: location.into(),
+            preserve_query: false,
+            params: Vec::new(),
+        }
+    }
+
+    /// Append current request's query to the location.
+    pub fn preserve_query(mut self) -> Self {
+        self.preserve_query = true;
+        self
+    }
+
+    /// Add query parameter to the location.
+    ///
+    /// Parameter replaces parameters with the same name from
+    /// the location and from current request.
+    pub fn param<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.params.push((key.into(), value.into()));
+        self
+    }
+
+    fn location(&self, req: &HttpRequest) -> String {
+        if !self.preserve_query && self.params.is_empty() {
+            return self.location.clone();
+        }
+
+        let (location, fragment) = match self.location.find('#') {
+            Some(idx) => self.location.split_at(idx),
+            None => (self.location.as_str(), ""),
+        };
+        let (path, query) = match location.find('?') {
+            Some(idx) => (&location[..idx], &location[idx + 1..]),
+            None => (location, ""),
+        };
+
+        let mut pairs: Vec<(String, String)> =
+            serde_urlencoded::from_str(query).unwrap_or_default();
+        if self.preserve_query {
+            let req_pairs: Vec<(String, String)> =
+                serde_urlencoded::from_str(req.query_string()).unwrap_or_default();
+            let keys: Vec<_> = pairs.iter().map(|(k, _)| k.clone()).collect();
+            pairs.extend(req_pairs.into_iter().filter(|(k, _)| !keys.contains(k)));
+        }
+        for (key, value) in &self.params {
+            pairs.retain(|(k, _)| k != key);
+            pairs.push((key.clone(), value.clone()));
+        }
+
+        let query = serde_urlencoded::to_string(&pairs).unwrap_or_default();
+        if query.is_empty() {
+            format!("{}{}", path, fragment)
+        } else {
+            format!("{}?{}{}", path, query, fragment)
+        }
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for Redirect {
+    type Error = Err::Container;
+    type Future = Ready<Response>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        Ready(Some(
+            Response::Found()
+                .header(LOCATION, self.location(req))
+                .finish(),
+        ))
+    }
+}
+
+/// Responder for dynamic content that honours `Range` request header.
+///
+/// Body is produced on demand by a function that receives requested byte
+/// range of the content. Responds with `206 Partial Content` and
+/// `Content-Range` header for satisfiable ranges, multiple ranges are
+/// sent as `multipart/byteranges` body. Responds with
+/// `416 Range Not Satisfiable` if none of requested ranges could be
+/// satisfied. Requests without `Range` header get `200 OK` with full content.
+///
+/// If request contains `If-Range` header, range is served only if header
+/// matches content's validator, configured with `etag()` or
+/// `last_modified()` methods, otherwise full content is sent.
+///
+/// Overlapping and adjacent ranges are merged. Requests with more than
+/// 32 ranges get full content.
+///
+/// ```rust
+/// use ntex::web::{Ranged, Responder};
+/// use ntex::util::Bytes;
+///
+/// async fn index() -> impl Responder {
+///     Ranged::new(1024, |range| {
+///         Bytes::from(range.map(|i| (i % 256) as u8).collect::<Vec<_>>())
+///     })
+///     .content_type("application/octet-stream")
+/// }
+/// # fn main() {}
+/// ```
+pub struct Ranged<F> {
+    len: u64,
+    content_type: Option<HeaderValue>,
+    etag: Option<HeaderValue>,
+    last_modified: Option<httpdate::HttpDate>,
+    producer: F,
+}
+
+impl<F> Ranged<F>
+where
+    F: Fn(ops::Range<u64>) -> Bytes,
+{
+    /// Create ranged responder for content of `len` bytes.
+    pub fn new(len: u64, producer: F) -> Self {
+        Ranged {
+            len,
+            producer,
+            content_type: None,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Set content type of the content.
+    pub fn content_type<V>(mut self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+    {
+        self.content_type = HeaderValue::try_from(value).ok();
+        self
+    }
+
+    /// Set entity tag of the content, i.e. `"v1"`.
+    ///
+    /// Entity tag is sent in `ETag` header and is used for `If-Range`
+    /// evaluation. Weak entity tags never match `If-Range` header.
+    pub fn etag<V>(mut self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+    {
+        self.etag = HeaderValue::try_from(value).ok();
+        self
+    }
+
+    /// Set last modification time of the content.
+    ///
+    /// Time is sent in `Last-Modified` header and is used for `If-Range`
+    /// evaluation.
+    pub fn last_modified(mut self, time: SystemTime) -> Self {
+        self.last_modified = Some(time.into());
+        self
+    }
+
+    /// Check if `If-Range` header is absent or matches content's validator
+    fn if_range(&self, req: &HttpRequest) -> bool {
+        let val = match req.headers().get(IF_RANGE) {
+            Some(val) => match val.to_str() {
+                Ok(val) => val.trim(),
+                Err(_) => return false,
+            },
+            None => return true,
+        };
+
+        if val.starts_with('"') || val.starts_with("W/") {
+            // strong comparison, weak entity tags never match
+            match self.etag {
+                Some(ref etag) => {
+                    !val.starts_with("W/") && etag.as_bytes() == val.as_bytes()
+                }
+                None => false,
+            }
+        } else {
+            match (self.last_modified, val.parse::<httpdate::HttpDate>()) {
+                (Some(modified), Ok(date)) => modified == date,
+                _ => false,
+            }
+        }
+    }
+
+    fn response(self, req: &HttpRequest) -> Response {
+        let ranges = if req.method() == Method::GET && self.if_range(req) {
+            req.headers()
+                .get(RANGE)
+                .and_then(|val| val.to_str().ok())
+                .and_then(|val| parse_ranges(val, self.len))
+        } else {
+            None
+        };
+
+        let mut res = match ranges {
+            None => {
+                let mut res = Response::Ok();
+                if let Some(ref ct) = self.content_type {
+                    res.header(CONTENT_TYPE, ct.clone());
+                }
+                res.body((self.producer)(0..self.len))
+            }
+            Some(ranges) if ranges.is_empty() => Response::RangeNotSatisfiable()
+                .header(CONTENT_RANGE, format!("bytes */{}", self.len))
+                .finish(),
+            Some(ranges) if ranges.len() == 1 => {
+                let range = ranges[0].clone();
+                let mut res = Response::PartialContent();
+                if let Some(ref ct) = self.content_type {
+                    res.header(CONTENT_TYPE, ct.clone());
+                }
+                res.header(
+                    CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", range.start, range.end - 1, self.len),
+                )
+                .body((self.producer)(range))
+            }
+            Some(ranges) => {
+                let boundary = format!("{:016x}", WyRand::new().generate::<u64>());
+                let mut body = BytesMut::new();
+                for range in ranges {
+                    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+                    if let Some(ref ct) = self.content_type {
+                        body.extend_from_slice(b"Content-Type: ");
+                        body.extend_from_slice(ct.as_bytes());
+                        body.extend_from_slice(b"\r\n");
+                    }
+                    body.extend_from_slice(
+                        format!(
+                            "Content-Range: bytes {}-{}/{}\r\n\r\n",
+                            range.start,
+                            range.end - 1,
+                            self.len
+                        )
+                        .as_bytes(),
+                    );
+                    body.extend_from_slice(&(self.producer)(range));
+                    body.extend_from_slice(b"\r\n");
+                }
+                body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+                Response::PartialContent()
+                    .content_type(format!("multipart/byteranges; boundary={}", boundary))
+                    .body(body.freeze())
+            }
+        };
+        res.headers_mut()
+            .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if let Some(etag) = self.etag {
+            res.headers_mut().insert(ETAG, etag);
+        }
+        if let Some(modified) = self.last_modified {
+            if let Ok(val) = HeaderValue::try_from(modified.to_string()) {
+                res.headers_mut().insert(LAST_MODIFIED, val);
+            }
+        }
+        res
+    }
+}
+
+impl<F> fmt::Debug for Ranged<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ranged")
+            .field("len", &self.len)
+            .field("content_type", &self.content_type)
+            .field("etag", &self.etag)
+            .field("last_modified", &self.last_modified)
+            .finish()
+    }
+}
+
+impl<F, Err: ErrorRenderer> Responder<Err> for Ranged<F>
+where
+    F: Fn(ops::Range<u64>) -> Bytes,
+{
+    type Error = Err::Container;
+    type Future = Ready<Response>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        Ready(Some(self.response(req)))
+    }
+}
+
+/// Responder for `multipart/mixed` content.
+///
+/// Each part has its own headers and body, part bodies are streamed
+/// one after another. Boundary is generated randomly, response gets
+/// `Content-Length` header only if sizes of all parts are known.
+///
+/// ```rust
+/// use ntex::web::{Multipart, Responder};
+///
+/// async fn index() -> impl Responder {
+///     Multipart::mixed()
+///         .part("text/plain", "plain text")
+///         .part("application/json", r#"{"name": "test"}"#)
+/// }
+/// # fn main() {}
+/// ```
+pub struct Multipart {
+    boundary: String,
+    parts: VecDeque<(Bytes, Body)>,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        let mut rng = WyRand::new();
+        Multipart {
+            boundary: format!(
+                "{:016x}{:016x}",
+                rng.generate::<u64>(),
+                rng.generate::<u64>()
+            ),
+            parts: VecDeque::new(),
+        }
+    }
+}
+
+impl Multipart {
+    /// Create `multipart/mixed` responder.
+    pub fn mixed() -> Self {
+        Multipart::default()
+    }
+
+    /// Add part with specified content type.
+    pub fn part<V, B>(self, content_type: V, body: B) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+        B: Into<Body>,
+    {
+        let mut headers = HeaderMap::new();
+        if let Ok(ct) = HeaderValue::try_from(content_type) {
+            headers.insert(CONTENT_TYPE, ct);
+        }
+        self.part_with_headers(headers, body)
+    }
+
+    /// Add part with specified headers.
+    pub fn part_with_headers<B: Into<Body>>(
+        mut self,
+        headers: HeaderMap,
+        body: B,
+    ) -> Self {
+        let mut head = BytesMut::new();
+        head.extend_from_slice(b"--");
+        head.extend_from_slice(self.boundary.as_bytes());
+        head.extend_from_slice(b"\r\n");
+        for (name, value) in headers.iter() {
+            head.extend_from_slice(name.as_str().as_bytes());
+            head.extend_from_slice(b": ");
+            head.extend_from_slice(value.as_bytes());
+            head.extend_from_slice(b"\r\n");
+        }
+        head.extend_from_slice(b"\r\n");
+        self.parts.push_back((head.freeze(), body.into()));
+        self
+    }
+
+    /// Multipart boundary.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("boundary", &self.boundary)
+            .field("parts", &self.parts.len())
+            .finish()
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for Multipart {
+    type Error = Err::Container;
+    type Future = Ready<Response>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let ct = format!("multipart/mixed; boundary={}", self.boundary);
+        let body = MultipartBody {
+            tail: Some(Bytes::from(format!("--{}--\r\n", self.boundary))),
+            parts: self.parts,
+            current: None,
+        };
+        Ready(Some(
+            Response::Ok()
+                .content_type(ct)
+                .body(Body::from_message(body)),
+        ))
+    }
+}
+
+struct MultipartBody {
+    parts: VecDeque<(Bytes, Body)>,
+    current: Option<Body>,
+    tail: Option<Bytes>,
+}
+
+impl MessageBody for MultipartBody {
+    fn size(&self) -> BodySize {
+        let mut size = self.tail.as_ref().map(|t| t.len() as u64).unwrap_or(0);
+        for (head, body) in &self.parts {
+            size += head.len() as u64 + 2;
+            match body.size() {
+                BodySize::None | BodySize::Empty => (),
+                BodySize::Sized(len) => size += len,
+                BodySize::Stream => return BodySize::Stream,
+            }
+        }
+        if self.current.is_some() {
+            BodySize::Stream
+        } else {
+            BodySize::Sized(size)
+        }
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        if let Some(ref mut body) = self.current {
+            match body.poll_next_chunk(cx) {
+                Poll::Ready(None) => {
+                    // part body is completed
+                    self.current = None;
+                    Poll::Ready(Some(Ok(Bytes::from_static(b"\r\n"))))
+                }
+                res => res,
+            }
+        } else if let Some((head, body)) = self.parts.pop_front() {
+            self.current = Some(body);
+            Poll::Ready(Some(Ok(head)))
+        } else {
+            Poll::Ready(self.tail.take().map(Ok))
+        }
+    }
+}
+
+/// Max number of ranges in `Range` header
+const MAX_RANGES: usize = 32;
+
+/// Parse `Range` header value.
+///
+/// Returns `None` if header is malformed or contains too many ranges and
+/// must be ignored, and empty list if none of the ranges is satisfiable.
+/// Overlapping and adjacent ranges are merged.
+fn parse_ranges(header: &str, len: u64) -> Option<Vec<ops::Range<u64>>> {
+    let header = header.trim();
+    if header.len() < 6 || !header[..6].eq_ignore_ascii_case("bytes=") {
+        return None;
+    }
+
+    let mut ranges = Vec::new();
+    for spec in header[6..]
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+    {
+        if ranges.len() == MAX_RANGES {
+            return None;
+        }
+        let (start, end) = spec.split_at(spec.find('-')?);
+        let (start, end) = (start.trim(), end[1..].trim());
+
+        if start.is_empty() {
+            // suffix range, last N bytes
+            let suffix: u64 = end.parse().ok()?;
+            if suffix > 0 && len > 0 {
+                ranges.push(len - suffix.min(len)..len);
+            }
+        } else {
+            let start: u64 = start.parse().ok()?;
+            let end = if end.is_empty() {
+                len
+            } else {
+                let end: u64 = end.parse().ok()?;
+                if end < start {
+                    return None;
+                }
+                end.saturating_add(1).min(len)
+            };
+            if start < len {
+                ranges.push(start..end);
+            }
+        }
+    }
+
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<ops::Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    Some(merged)
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use crate::http::body::{Body, ResponseBody};
-    use crate::http::header::{HeaderValue, CONTENT_TYPE};
+    use crate::http::body::{Body, BodyStream, ResponseBody};
+    use crate::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
     use crate::http::{Response as HttpResponse, StatusCode};
     use crate::web;
     use crate::web::test::{init_service, TestRequest};
@@ -543,6 +1364,120 @@ pub(crate) mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[crate::rt_test]
+    async fn test_negotiate_responder() {
+        let srv = init_service(web::App::new().route(
+            "/items",
+            web::get().to(|| async {
+                Negotiate::new(
+                    (mime::APPLICATION_JSON, web::types::Json(vec!["a", "b"])),
+                    (mime::TEXT_PLAIN, "a\nb"),
+                )
+            }),
+        ))
+        .await;
+
+        let cases: &[(Option<&str>, &str, &[u8])] = &[
+            (None, "application/json", b"[\"a\",\"b\"]"),
+            (
+                Some("application/json"),
+                "application/json",
+                b"[\"a\",\"b\"]",
+            ),
+            (Some("text/plain"), "text/plain; charset=utf-8", b"a\nb"),
+            (Some("*/*"), "application/json", b"[\"a\",\"b\"]"),
+            (
+                Some("application/json;q=0.5, text/*"),
+                "text/plain; charset=utf-8",
+                b"a\nb",
+            ),
+            (Some("image/png"), "application/json", b"[\"a\",\"b\"]"),
+        ];
+        for (accept, ct, body) in cases {
+            let mut req = TestRequest::with_uri("/items");
+            if let Some(accept) = accept {
+                req = req.header(crate::http::header::ACCEPT, *accept);
+            }
+            let resp = srv.call(req.to_request()).await.unwrap();
+            assert_eq!(
+                resp.headers().get(CONTENT_TYPE).unwrap(),
+                ct,
+                "{:?}",
+                accept
+            );
+            assert_eq!(resp.headers().get(VARY).unwrap(), "accept");
+            assert_eq!(crate::web::test::read_body(resp).await, Bytes::from(*body));
+        }
+
+        // vary header of inner responder is kept
+        let req = TestRequest::default()
+            .header(crate::http::header::ACCEPT, "text/plain")
+            .to_http_request();
+        let resp: HttpResponse = responder(Negotiate::new(
+            (mime::APPLICATION_JSON, "[]"),
+            (
+                mime::TEXT_PLAIN,
+                HttpResponse::Ok()
+                    .header(VARY, "accept-encoding")
+                    .body("text"),
+            ),
+        ))
+        .respond_to(&req)
+        .await;
+        let vary: Vec<_> = resp.headers().get_all(VARY).collect();
+        assert_eq!(vary, vec!["accept-encoding", "accept"]);
+    }
+
+    #[crate::rt_test]
+    async fn test_binary_responder() {
+        let req = TestRequest::default().to_http_request();
+        let png = Bytes::from_static(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR");
+
+        let resp: HttpResponse = responder(Binary::new(png.clone(), mime::IMAGE_PNG))
+            .respond_to(&req)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "image/png");
+        assert_eq!(resp.headers().get(CONTENT_LENGTH).unwrap(), "16");
+        assert_eq!(resp.body().get_ref(), &png[..]);
+    }
+
+    #[crate::rt_test]
+    async fn test_redirect_responder() {
+        let req = TestRequest::with_uri("/index.html?page=2&lang=de").to_http_request();
+
+        let resp: HttpResponse =
+            responder(Redirect::new("/new?a=1")).respond_to(&req).await;
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        assert_eq!(resp.headers().get(LOCATION).unwrap(), "/new?a=1");
+
+        let resp: HttpResponse =
+            responder(HttpResponse::redirect_preserving_query("/new"))
+                .respond_to(&req)
+                .await;
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        assert_eq!(resp.headers().get(LOCATION).unwrap(), "/new?page=2&lang=de");
+
+        let resp: HttpResponse = responder(
+            HttpResponse::redirect_preserving_query("/new?lang=en#top")
+                .param("page", "3")
+                .param("q", "a b"),
+        )
+        .respond_to(&req)
+        .await;
+        assert_eq!(
+            resp.headers().get(LOCATION).unwrap(),
+            "/new?lang=en&page=3&q=a+b#top"
+        );
+
+        let req = TestRequest::with_uri("/index.html").to_http_request();
+        let resp: HttpResponse =
+            responder(HttpResponse::redirect_preserving_query("/new"))
+                .respond_to(&req)
+                .await;
+        assert_eq!(resp.headers().get(LOCATION).unwrap(), "/new");
+    }
+
     #[crate::rt_test]
     async fn test_result_responder() {
         let req = TestRequest::default().to_http_request();
@@ -618,4 +1553,325 @@ pub(crate) mod tests {
             HeaderValue::from_static("json")
         );
     }
+
+    #[crate::rt_test]
+    async fn test_multipart_responder() {
+        let srv =
+            init_service(web::App::new().service(web::resource("/").to(|| async {
+                let mut headers = HeaderMap::new();
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+                headers.insert(
+                    HeaderName::from_static("content-id"),
+                    HeaderValue::from_static("<part2>"),
+                );
+                let stream = futures::stream::iter(vec![
+                    Ok::<_, std::io::Error>(Bytes::from_static(b"chunk1,")),
+                    Ok(Bytes::from_static(b"chunk2")),
+                ]);
+                Multipart::mixed()
+                    .part("application/json", r#"{"name":"test"}"#)
+                    .part_with_headers(headers, "second part")
+                    .part(
+                        "application/octet-stream",
+                        Body::from_message(BodyStream::new(stream)),
+                    )
+            })))
+            .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let ct = resp.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap();
+        assert!(ct.starts_with("multipart/mixed; boundary="));
+        let boundary = ct[26..].to_string();
+        assert_eq!(boundary.len(), 32);
+
+        // parse parts back
+        let body = web::test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        let (body, tail) =
+            body.split_at(body.find(&format!("--{}--", boundary)).unwrap());
+        assert_eq!(tail, format!("--{}--\r\n", boundary));
+
+        let parts: Vec<(Vec<&str>, &str)> = body
+            .split(&format!("--{}\r\n", boundary))
+            .skip(1)
+            .map(|part| {
+                let (head, body) = part.split_at(part.find("\r\n\r\n").unwrap());
+                let body = body[4..].strip_suffix("\r\n").unwrap();
+                (head.split("\r\n").collect(), body)
+            })
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                (vec!["content-type: application/json"], r#"{"name":"test"}"#),
+                (
+                    vec!["content-type: text/plain", "content-id: <part2>"],
+                    "second part"
+                ),
+                (
+                    vec!["content-type: application/octet-stream"],
+                    "chunk1,chunk2"
+                ),
+            ]
+        );
+
+        // size is known if all parts are sized
+        let req = TestRequest::default().to_http_request();
+        let resp = Responder::<DefaultError>::respond_to(
+            Multipart::mixed()
+                .part("text/plain", "1")
+                .part("text/plain", ""),
+            &req,
+        )
+        .await;
+        let mut expected = 0;
+        for part in &["1", ""] {
+            expected += format!(
+                "--{}\r\ncontent-type: text/plain\r\n\r\n{}\r\n",
+                "x".repeat(32),
+                part
+            )
+            .len();
+        }
+        expected += 32 + 6;
+        assert_eq!(resp.body().size(), BodySize::Sized(expected as u64));
+    }
+
+    #[crate::rt_test]
+    async fn test_ranged_responder() {
+        let srv =
+            init_service(web::App::new().service(web::resource("/").to(|| async {
+                Ranged::new(10, |range| {
+                    Bytes::from(range.map(|i| b'0' + i as u8).collect::<Vec<_>>())
+                })
+                .content_type("text/plain")
+            })))
+            .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(
+            web::test::read_body(resp).await,
+            Bytes::from_static(b"0123456789")
+        );
+
+        for (range, content_range, body) in &[
+            ("bytes=2-4", "bytes 2-4/10", &b"234"[..]),
+            ("bytes=7-", "bytes 7-9/10", b"789"),
+            ("bytes=-3", "bytes 7-9/10", b"789"),
+            ("bytes=5-100", "bytes 5-9/10", b"56789"),
+        ] {
+            let req = TestRequest::default().header(RANGE, *range).to_request();
+            let resp = srv.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), content_range);
+            assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+            assert_eq!(web::test::read_body(resp).await, Bytes::from(*body));
+        }
+
+        // multiple ranges
+        let req = TestRequest::default()
+            .header(RANGE, "bytes=0-1, 8-")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        let ct = resp.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap();
+        assert!(ct.starts_with("multipart/byteranges; boundary="));
+        let boundary = ct[31..].to_string();
+        assert_eq!(
+            web::test::read_body(resp).await,
+            Bytes::from(format!(
+                "--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+                 --{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\r\n\
+                 --{0}--\r\n",
+                boundary
+            ))
+        );
+
+        // overlapping and adjacent ranges are merged
+        for range in &["bytes=2-4, 3-6", "bytes=5-6,2-4", "bytes=2-3,4-4,5-6"] {
+            let req = TestRequest::default().header(RANGE, *range).to_request();
+            let resp = srv.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "bytes 2-6/10");
+            assert_eq!(
+                web::test::read_body(resp).await,
+                Bytes::from_static(b"23456")
+            );
+        }
+
+        // too many ranges, full content
+        let range = format!(
+            "bytes={}",
+            (0..33).map(|_| "0-0").collect::<Vec<_>>().join(",")
+        );
+        let req = TestRequest::default().header(RANGE, range).to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            web::test::read_body(resp).await,
+            Bytes::from_static(b"0123456789")
+        );
+
+        // unsatisfiable
+        let req = TestRequest::default()
+            .header(RANGE, "bytes=10-")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "bytes */10");
+
+        // malformed header is ignored
+        let req = TestRequest::default()
+            .header(RANGE, "bytes=4-2")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let req = TestRequest::default()
+            .header(RANGE, "items=1-2")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // only GET requests are ranged
+        let req = TestRequest::post().header(RANGE, "bytes=2-4").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_ranged_if_range() {
+        let modified =
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        let srv = init_service(web::App::new().service(web::resource("/").to(
+            move || async move {
+                Ranged::new(10, |range| {
+                    Bytes::from(range.map(|i| b'0' + i as u8).collect::<Vec<_>>())
+                })
+                .etag("\"v1\"")
+                .last_modified(modified)
+            },
+        )))
+        .await;
+        let date = httpdate::HttpDate::from(modified).to_string();
+        let other =
+            httpdate::HttpDate::from(modified + std::time::Duration::from_secs(1))
+                .to_string();
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.headers().get(ETAG).unwrap(), "\"v1\"");
+        assert_eq!(resp.headers().get(LAST_MODIFIED).unwrap(), date.as_str());
+
+        // matching validators
+        for if_range in &["\"v1\"", date.as_str()] {
+            let req = TestRequest::default()
+                .header(RANGE, "bytes=2-4")
+                .header(IF_RANGE, *if_range)
+                .to_request();
+            let resp = srv.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "bytes 2-4/10");
+            assert_eq!(web::test::read_body(resp).await, Bytes::from_static(b"234"));
+        }
+
+        // non-matching validators
+        for if_range in &["\"v2\"", "W/\"v1\"", other.as_str(), "invalid"] {
+            let req = TestRequest::default()
+                .header(RANGE, "bytes=2-4")
+                .header(IF_RANGE, *if_range)
+                .to_request();
+            let resp = srv.call(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert!(resp.headers().get(CONTENT_RANGE).is_none());
+            assert_eq!(
+                web::test::read_body(resp).await,
+                Bytes::from_static(b"0123456789")
+            );
+        }
+
+        // validators are not configured
+        let srv =
+            init_service(web::App::new().service(web::resource("/").to(|| async {
+                Ranged::new(10, |range| {
+                    Bytes::from(range.map(|i| b'0' + i as u8).collect::<Vec<_>>())
+                })
+            })))
+            .await;
+        let req = TestRequest::default()
+            .header(RANGE, "bytes=2-4")
+            .header(IF_RANGE, "\"v1\"")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_status_code_tuple_responder() {
+        let req = TestRequest::default().to_http_request();
+        let res = responder((StatusCode::ACCEPTED, "ok"))
+            .respond_to(&req)
+            .await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        assert_eq!(res.body().get_ref(), b"ok");
+
+        let res = responder((StatusCode::CREATED, web::types::Json(vec!["item"])))
+            .respond_to(&req)
+            .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.body().get_ref(), b"[\"item\"]");
+
+        // tuple status overrides inner responder status
+        let res = responder((StatusCode::CREATED, Response::BadRequest().body("error")))
+            .respond_to(&req)
+            .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.body().get_ref(), b"error");
+    }
+
+    #[crate::rt_test]
+    async fn test_with_status_and_header() {
+        let req = TestRequest::default().to_http_request();
+        let res = responder(WithStatus("test", StatusCode::ACCEPTED))
+            .respond_to(&req)
+            .await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        assert_eq!(res.body().get_ref(), b"test");
+
+        let res = responder(WithStatus(
+            WithHeader(
+                web::types::Json(vec!["item"]),
+                HeaderName::from_static("x-version"),
+                HeaderValue::from_static("1"),
+            ),
+            StatusCode::CREATED,
+        ))
+        .respond_to(&req)
+        .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers().get("x-version").unwrap(), "1");
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+        assert_eq!(res.body().get_ref(), b"[\"item\"]");
+
+        // header overrides inner responder's header
+        let res = responder(WithHeader(
+            web::types::Json(vec!["item"]),
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/vnd.api+json"),
+        ))
+        .respond_to(&req)
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/vnd.api+json")
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, rc::Rc};
 
 use crate::http::body::{Body, MessageBody, ResponseBody};
 use crate::http::{HeaderMap, Response, ResponseHead, StatusCode};
@@ -6,6 +6,61 @@ use crate::http::{HeaderMap, Response, ResponseHead, StatusCode};
 use super::error::{ErrorContainer, ErrorRenderer};
 use super::httprequest::HttpRequest;
 
+/// Error log level selector, configured with `App::error_log_level()`
+#[derive(Clone)]
+pub(super) struct ErrorLogLevel(pub(super) Rc<dyn Fn(StatusCode) -> Option<log::Level>>);
+
+/// Default error log level.
+///
+/// Internal server errors are logged at `error` level, all other
+/// errors are logged at `debug` level.
+pub(super) fn default_error_log_level(status: StatusCode) -> Option<log::Level> {
+    if status == StatusCode::INTERNAL_SERVER_ERROR {
+        Some(log::Level::Error)
+    } else {
+        Some(log::Level::Debug)
+    }
+}
+
+/// Log error with level selected by application's error log level selector
+pub(super) fn log_error<E: fmt::Debug>(req: &HttpRequest, status: StatusCode, err: &E) {
+    let level = if let Some(f) = req.app_data::<ErrorLogLevel>() {
+        (*f.0)(status)
+    } else {
+        default_error_log_level(status)
+    };
+    if let Some(level) = level {
+        log::log!(level, "Error in response ({}): {:?}", status, err);
+    }
+}
+
+/// Runtime error renderer, configured with `App::error_renderer()`
+/// or `Scope::error_renderer()`
+#[derive(Clone)]
+pub(super) struct ErrorRendererFn(
+    pub(super) Rc<dyn Fn(&HttpRequest, &dyn fmt::Display, Response) -> Response>,
+);
+
+/// Apply runtime error renderer to the error response
+///
+/// Renderer of the matched scope is used, otherwise renderer of application.
+pub(super) fn render_error<E: fmt::Display>(
+    req: &HttpRequest,
+    err: &E,
+    res: Response,
+) -> Response {
+    let f = req
+        .extensions()
+        .get::<ErrorRendererFn>()
+        .or_else(|| req.app_data::<ErrorRendererFn>())
+        .cloned();
+    if let Some(f) = f {
+        (*f.0)(req, err, res)
+    } else {
+        res
+    }
+}
+
 /// An service http response
 pub struct WebResponse {
     request: HttpRequest,
@@ -24,13 +79,9 @@ impl WebResponse {
         request: HttpRequest,
     ) -> Self {
         let err = err.into();
-        let res: Response = err.error_response(&request);
+        let res = render_error(&request, &err, err.error_response(&request));
 
-        if res.head().status == StatusCode::INTERNAL_SERVER_ERROR {
-            log::error!("Internal Server Error: {:?}", err);
-        } else {
-            log::debug!("Error in response: {:?}", err);
-        }
+        log_error(&request, res.head().status, &err);
 
         WebResponse {
             request,
@@ -151,9 +202,44 @@ impl fmt::Debug for WebResponse {
 
 #[cfg(test)]
 mod tests {
+    use std::{cell::RefCell, sync::Once};
+
     use crate::http::{self, StatusCode};
-    use crate::web::test::TestRequest;
-    use crate::web::{DefaultError, HttpResponse};
+    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::{self, error, App, DefaultError, HttpResponse};
+
+    thread_local! {
+        static RECORDS: RefCell<Vec<log::Level>> = RefCell::new(Vec::new());
+    }
+
+    /// Logger that captures levels of error log records of current thread
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            if record.target() == "ntex::web::response" {
+                RECORDS.with(|records| records.borrow_mut().push(record.level()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn init_logger() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&TestLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+    }
+
+    fn take() -> Vec<log::Level> {
+        RECORDS.with(|records| records.borrow_mut().drain(..).collect())
+    }
 
     #[test]
     fn test_response() {
@@ -175,4 +261,74 @@ mod tests {
         });
         assert_eq!(res.response().status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
+
+    #[crate::rt_test]
+    async fn test_error_log_level() {
+        init_logger();
+        let app = || {
+            App::new()
+                .route(
+                    "/400",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(error::ErrorBadRequest("bad request"))
+                    }),
+                )
+                .route(
+                    "/500",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(error::ErrorInternalServerError("error"))
+                    }),
+                )
+                .route(
+                    "/502",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(error::ErrorBadGateway("bad gateway"))
+                    }),
+                )
+        };
+
+        // by default only internal server errors are logged at error level
+        take();
+        let srv = init_service(app()).await;
+        for (path, level) in &[
+            ("/400", log::Level::Debug),
+            ("/500", log::Level::Error),
+            ("/502", log::Level::Debug),
+        ] {
+            let req = TestRequest::with_uri(path).to_request();
+            let _ = call_service(&srv, req).await;
+            assert_eq!(take(), vec![*level]);
+        }
+
+        // all server errors at error level
+        let srv = init_service(app().error_log_level(|status| {
+            if status.is_server_error() {
+                Some(log::Level::Error)
+            } else {
+                Some(log::Level::Debug)
+            }
+        }))
+        .await;
+        let req = TestRequest::with_uri("/502").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(take(), vec![log::Level::Error]);
+
+        let srv = init_service(app().error_log_level(|status| {
+            if status.is_client_error() {
+                None
+            } else {
+                Some(log::Level::Warn)
+            }
+        }))
+        .await;
+        let req = TestRequest::with_uri("/400").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(take().is_empty());
+
+        let req = TestRequest::with_uri("/502").to_request();
+        let _ = call_service(&srv, req).await;
+        assert_eq!(take(), vec![log::Level::Warn]);
+    }
 }
@@ -1,6 +1,10 @@
-use std::{future::Future, mem, pin::Pin, rc::Rc, task::Context, task::Poll};
+use std::task::{Context, Poll};
+use std::{convert::Infallible, future::Future, mem, pin::Pin, rc::Rc};
 
-use crate::{http::Method, util::Ready, Service, ServiceFactory};
+use crate::http::header::HeaderName;
+use crate::time::{Millis, Seconds};
+use crate::util::{timeout::TimeoutError, Ready};
+use crate::{http::Method, Service, ServiceFactory};
 
 use super::error::ErrorRenderer;
 use super::error_default::DefaultError;
@@ -20,6 +24,7 @@ pub struct Route<Err: ErrorRenderer = DefaultError> {
     handler: Box<dyn HandlerFn<Err>>,
     methods: Vec<Method>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    timeout: Option<(Millis, fn() -> Err::Container)>,
 }
 
 impl<Err: ErrorRenderer> Route<Err> {
@@ -31,9 +36,17 @@ impl<Err: ErrorRenderer> Route<Err> {
             })),
             methods: Vec::new(),
             guards: Rc::new(Vec::new()),
+            timeout: None,
         }
     }
 
+    /// Http methods allowed by the route
+    pub(super) fn methods(&self) -> impl Iterator<Item = &Method> {
+        self.methods
+            .iter()
+            .chain(self.guards.iter().filter_map(|f| f.method()))
+    }
+
     pub(super) fn take_guards(&mut self) -> Vec<Box<dyn Guard>> {
         for m in &self.methods {
             Rc::get_mut(&mut self.guards)
@@ -49,6 +62,7 @@ impl<Err: ErrorRenderer> Route<Err> {
             handler: self.handler.clone_handler(),
             guards: self.guards.clone(),
             methods: self.methods.clone(),
+            timeout: self.timeout,
         }
     }
 }
@@ -71,16 +85,24 @@ pub struct RouteService<Err: ErrorRenderer> {
     handler: Box<dyn HandlerFn<Err>>,
     methods: Vec<Method>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    timeout: Option<(Millis, fn() -> Err::Container)>,
 }
 
 impl<Err: ErrorRenderer> RouteService<Err> {
+    /// Http methods allowed by the route
+    pub(super) fn methods(&self) -> impl Iterator<Item = &Method> {
+        self.methods
+            .iter()
+            .chain(self.guards.iter().filter_map(|f| f.method()))
+    }
+
     pub fn check(&self, req: &mut WebRequest<Err>) -> bool {
         if !self.methods.is_empty() && !self.methods.contains(&req.head().method) {
             return false;
         }
 
         for f in self.guards.iter() {
-            if !f.check(req.head()) {
+            if !f.check(req.head()) || !f.check_path(req.match_info()) {
                 return false;
             }
         }
@@ -101,7 +123,36 @@ impl<Err: ErrorRenderer> Service for RouteService<Err> {
 
     #[inline]
     fn call(&self, req: WebRequest<Err>) -> Self::Future {
-        self.handler.call(req)
+        if let Some((timeout, err)) = self.timeout {
+            self.handler.call_timeout(req, timeout, err)
+        } else if let Some(default) = req.app_data::<DefaultTimeout<Err>>() {
+            let (timeout, err) = (default.timeout(&req), default.err);
+            self.handler.call_timeout(req, timeout, err)
+        } else {
+            self.handler.call(req)
+        }
+    }
+}
+
+/// Request header that shortens application's default timeout
+const REQUEST_TIMEOUT: HeaderName = HeaderName::from_static("request-timeout");
+
+/// Application's default handler timeout
+pub(super) struct DefaultTimeout<Err: ErrorRenderer> {
+    pub(super) timeout: Millis,
+    pub(super) err: fn() -> Err::Container,
+}
+
+impl<Err: ErrorRenderer> DefaultTimeout<Err> {
+    /// Default timeout capped by `Request-Timeout` header, in seconds
+    fn timeout(&self, req: &WebRequest<Err>) -> Millis {
+        req.headers()
+            .get(&REQUEST_TIMEOUT)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.trim().parse::<f64>().ok())
+            .filter(|secs| secs.is_finite() && *secs > 0.0)
+            .map(|secs| Millis(((secs * 1000.0).ceil() as u64).min(self.timeout.0)))
+            .unwrap_or(self.timeout)
     }
 }
 
@@ -142,6 +193,37 @@ impl<Err: ErrorRenderer> Route<Err> {
         self
     }
 
+    /// Set handler timeout.
+    ///
+    /// If handler does not complete within specified duration, handler's
+    /// future is dropped and *504 Gateway Timeout* response is returned.
+    /// Timeout error is rendered with route's error renderer.
+    ///
+    /// ```rust
+    /// use ntex::{time::Seconds, web};
+    ///
+    /// fn main() {
+    ///     let app = web::App::new().service(
+    ///         web::resource("/index.html").route(
+    ///             web::get()
+    ///                 .timeout(Seconds(5))
+    ///                 .to(|| async { web::HttpResponse::Ok() }),
+    ///         ),
+    ///     );
+    /// }
+    /// ```
+    pub fn timeout(mut self, dur: Seconds) -> Self
+    where
+        TimeoutError<Infallible>: Into<Err::Container>,
+    {
+        self.timeout = if dur.is_zero() {
+            None
+        } else {
+            Some((dur.into(), || TimeoutError::<Infallible>::Timeout.into()))
+        };
+        self
+    }
+
     /// Set handler function, use request extractors for parameters.
     ///
     /// ```rust
@@ -274,8 +356,10 @@ array_routes!(12, a, b, c, d, e, f, g, h, i, j, k, l);
 
 #[cfg(test)]
 mod tests {
+    use std::{cell::Cell, rc::Rc};
+
     use crate::http::{Method, StatusCode};
-    use crate::time::{sleep, Millis};
+    use crate::time::{sleep, Millis, Seconds};
     use crate::util::Bytes;
     use crate::web::test::{call_service, init_service, read_body, TestRequest};
     use crate::web::{self, error, App, DefaultError, HttpResponse};
@@ -351,4 +435,54 @@ mod tests {
         let body = read_body(resp).await;
         assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
     }
+
+    #[crate::rt_test]
+    async fn test_route_timeout() {
+        struct OnDrop(Rc<Cell<bool>>);
+
+        impl Drop for OnDrop {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(false));
+        let dropped2 = dropped.clone();
+
+        let srv = init_service(
+            App::new()
+                .route(
+                    "/slow",
+                    web::get().timeout(Seconds(1)).to(move || {
+                        let guard = OnDrop(dropped2.clone());
+                        async move {
+                            sleep(Seconds(2)).await;
+                            drop(guard);
+                            HttpResponse::Ok()
+                        }
+                    }),
+                )
+                .route(
+                    "/fast",
+                    web::get()
+                        .timeout(Seconds(1))
+                        .to(|| async { HttpResponse::Ok().body("fast") }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/slow").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(dropped.get());
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"Service call timeout")
+        );
+
+        let req = TestRequest::with_uri("/fast").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"fast"));
+    }
 }
@@ -1,8 +1,7 @@
-use std::{
-    cell::RefCell, fmt, future::Future, pin::Pin, rc::Rc, task::Context, task::Poll,
-};
+use std::task::{Context, Poll};
+use std::{cell::RefCell, fmt, future::Future, marker::PhantomData, pin::Pin, rc::Rc};
 
-use crate::http::Response;
+use crate::http::{Method, Response};
 use crate::router::{IntoPattern, ResourceDef, ResourceInfo, Router};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{pipeline_factory, PipelineFactory};
@@ -10,17 +9,19 @@ use crate::service::{Identity, IntoServiceFactory, Service, ServiceFactory, Tran
 use crate::util::{Either, Extensions, Ready};
 
 use super::app::{Filter, Stack};
+use super::app_service::{check_path, matched_methods, method_not_allowed};
 use super::config::ServiceConfig;
-use super::dev::{WebServiceConfig, WebServiceFactory};
+use super::dev::{insert_slesh, WebServiceConfig, WebServiceFactory};
 use super::error::ErrorRenderer;
 use super::guard::Guard;
 use super::request::WebRequest;
 use super::resource::Resource;
-use super::response::WebResponse;
+use super::response::{ErrorRendererFn, WebResponse};
 use super::rmap::ResourceMap;
 use super::route::Route;
 use super::service::{AppServiceFactory, ServiceFactoryWrapper};
 use super::types::Data;
+use super::{HttpRequest, HttpResponse};
 
 type Guards = Vec<Box<dyn Guard>>;
 type HttpService<Err: ErrorRenderer> =
@@ -69,6 +70,7 @@ pub struct Scope<Err: ErrorRenderer, M = Identity, T = Filter<Err>> {
     default: Rc<RefCell<Option<Rc<HttpNewService<Err>>>>>,
     external: Vec<ResourceDef>,
     case_insensitive: bool,
+    error_renderer: Option<ErrorRendererFn>,
 }
 
 impl<Err: ErrorRenderer> Scope<Err> {
@@ -84,6 +86,7 @@ impl<Err: ErrorRenderer> Scope<Err> {
             default: Rc::new(RefCell::new(None)),
             external: Vec::new(),
             case_insensitive: false,
+            error_renderer: None,
         }
     }
 }
@@ -127,6 +130,9 @@ where
     /// Set or override application data. Application data could be accessed
     /// by using `Data<T>` extractor where `T` is data type.
     ///
+    /// Data is available only for services of the scope and shadows data
+    /// of the same type registered by `App::data()` or by outer scopes.
+    ///
     /// ```rust
     /// use std::cell::Cell;
     /// use ntex::web::{self, App, HttpResponse};
@@ -207,7 +213,7 @@ where
     {
         let mut cfg = ServiceConfig::new();
         f(&mut cfg);
-        self.services.extend(cfg.services);
+        self.services.extend(cfg.take_services());
         self.external.extend(cfg.external);
 
         if !cfg.data.is_empty() {
@@ -287,6 +293,50 @@ where
         )
     }
 
+    /// Configure named route for a specific path.
+    ///
+    /// This is same as `Scope::route()` method, but registered resource
+    /// gets a name, so it could be used in `HttpRequest::url_for()`.
+    pub fn route_named(self, name: &str, path: &str, mut route: Route<Err>) -> Self {
+        self.service(
+            Resource::new(path)
+                .name(name)
+                .add_guards(route.take_guards())
+                .route(route),
+        )
+    }
+
+    /// Set runtime error renderer for the scope.
+    ///
+    /// Errors are rendered with scope's `Err` renderer first, use
+    /// `web::with_renderer()` for scope with different renderer type.
+    /// Then renderer receives request, error and rendered response, and
+    /// returns final response. Renderer of the innermost matched scope is
+    /// used, otherwise renderer set by `App::error_renderer()`. Errors of
+    /// scope's middlewares and filters are rendered with scope's renderer.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, App, HttpRequest, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::scope("/api")
+    ///             .error_renderer(|_: &HttpRequest, err, res: HttpResponse| {
+    ///                 HttpResponse::build(res.status())
+    ///                     .json(&serde_json::json!({"error": err.to_string()}))
+    ///             })
+    ///             .route("/index.html", web::get().to(|| async { "Welcome!" })),
+    ///     );
+    /// }
+    /// ```
+    pub fn error_renderer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HttpRequest, &dyn fmt::Display, HttpResponse) -> HttpResponse + 'static,
+    {
+        self.error_renderer = Some(ErrorRendererFn(Rc::new(f)));
+        self
+    }
+
     /// Default service to be used if no matching route could be found.
     ///
     /// If default resource is not registered, app's default resource is being used.
@@ -352,6 +402,7 @@ where
             default: self.default,
             external: self.external,
             case_insensitive: self.case_insensitive,
+            error_renderer: self.error_renderer,
         }
     }
 
@@ -376,6 +427,7 @@ where
             default: self.default,
             external: self.external,
             case_insensitive: self.case_insensitive,
+            error_renderer: self.error_renderer,
         }
     }
 }
@@ -427,6 +479,7 @@ where
             data: self.data.take().map(Rc::new),
             default: self.default.clone(),
             case_insensitive: self.case_insensitive,
+            case_insensitive_capture: config.case_insensitive_capture(),
             services: Rc::new(
                 cfg.into_services()
                     .1
@@ -461,10 +514,24 @@ where
                 middleware: Rc::new(self.middleware),
                 filter: self.filter,
                 routing: router_factory,
+                error_renderer: self.error_renderer.take(),
             },
             Some(Rc::new(rmap)),
         )
     }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        let mut items = Vec::new();
+        for prefix in insert_slesh(self.rdef.clone()) {
+            let prefix = prefix.trim_end_matches('/');
+            for srv in &self.services {
+                for (path, methods) in srv.describe() {
+                    items.push((format!("{}{}", prefix, path), methods));
+                }
+            }
+        }
+        items
+    }
 }
 
 /// Scope service
@@ -472,6 +539,7 @@ struct ScopeServiceFactory<M, F, Err: ErrorRenderer> {
     middleware: Rc<M>,
     filter: F,
     routing: ScopeRouterFactory<Err>,
+    error_renderer: Option<ErrorRendererFn>,
 }
 
 impl<M, F, Err> ServiceFactory for ScopeServiceFactory<M, F, Err>
@@ -495,7 +563,7 @@ where
     type Request = WebRequest<Err>;
     type Response = WebResponse;
     type Error = Err::Container;
-    type Service = M::Service;
+    type Service = ScopeRendererService<M::Service, Err>;
     type InitError = ();
     type Future = Pin<Box<dyn Future<Output = Result<Self::Service, Self::InitError>>>>;
 
@@ -503,15 +571,77 @@ where
         let filter_fut = self.filter.new_service(());
         let routing_fut = self.routing.new_service(());
         let middleware = self.middleware.clone();
+        let error_renderer = self.error_renderer.clone();
         Box::pin(async move {
-            Ok(middleware.new_transform(ScopeService {
-                filter: filter_fut.await?,
-                routing: Rc::new(routing_fut.await?),
-            }))
+            Ok(ScopeRendererService {
+                error_renderer,
+                service: middleware.new_transform(ScopeService {
+                    filter: filter_fut.await?,
+                    routing: Rc::new(routing_fut.await?),
+                }),
+                _t: PhantomData,
+            })
         })
     }
 }
 
+/// Sets scope's error renderer before scope middlewares, and renders errors
+/// of scope middlewares and filters with it
+struct ScopeRendererService<S, Err> {
+    service: S,
+    error_renderer: Option<ErrorRendererFn>,
+    _t: PhantomData<Err>,
+}
+
+impl<S, Err> Service for ScopeRendererService<S, Err>
+where
+    S: Service<
+        Request = WebRequest<Err>,
+        Response = WebResponse,
+        Error = Err::Container,
+    >,
+    S::Future: 'static,
+    Err: ErrorRenderer,
+{
+    type Request = WebRequest<Err>;
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Future = Either<S::Future, BoxResponse<Err>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<Err>) -> Self::Future {
+        if let Some(ref f) = self.error_renderer {
+            req.extensions_mut().insert(f.clone());
+
+            // request is consumed by middlewares, keep copy for error rendering
+            let copy = req.copy();
+            let f = f.clone();
+            let fut = self.service.call(req);
+            Either::Right(Box::pin(async move {
+                match fut.await {
+                    Ok(res) => Ok(res),
+                    Err(err) => {
+                        let req = copy.request();
+                        req.extensions_mut().insert(f);
+                        Ok(WebResponse::from_err::<Err, _>(err, req))
+                    }
+                }
+            }))
+        } else {
+            Either::Left(self.service.call(req))
+        }
+    }
+}
+
 pub struct ScopeService<F, Err: ErrorRenderer> {
     filter: F,
     routing: Rc<ScopeRouter<Err>>,
@@ -593,6 +723,7 @@ struct ScopeRouterFactory<Err: ErrorRenderer> {
     services: Rc<Vec<(ResourceDef, HttpNewService<Err>, RefCell<Option<Guards>>)>>,
     default: Rc<RefCell<Option<Rc<HttpNewService<Err>>>>>,
     case_insensitive: bool,
+    case_insensitive_capture: bool,
 }
 
 impl<Err: ErrorRenderer> ServiceFactory for ScopeRouterFactory<Err> {
@@ -607,6 +738,7 @@ impl<Err: ErrorRenderer> ServiceFactory for ScopeRouterFactory<Err> {
     fn new_service(&self, _: ()) -> Self::Future {
         let services = self.services.clone();
         let case_insensitive = self.case_insensitive;
+        let case_insensitive_capture = self.case_insensitive_capture;
         let data = self.data.clone();
         let default_fut = self
             .default
@@ -622,7 +754,9 @@ impl<Err: ErrorRenderer> ServiceFactory for ScopeRouterFactory<Err> {
             }
             for (path, factory, guards) in &mut services.iter() {
                 let service = factory.new_service(()).await?;
-                router.rdef(path.clone(), service).2 = guards.borrow_mut().take();
+                let pattern = path.pattern().to_string();
+                router.rdef(path.clone(), (service, pattern)).2 =
+                    guards.borrow_mut().take();
             }
 
             let default = if let Some(fut) = default_fut {
@@ -634,6 +768,7 @@ impl<Err: ErrorRenderer> ServiceFactory for ScopeRouterFactory<Err> {
             Ok(ScopeRouter {
                 data,
                 default,
+                case_insensitive_capture,
                 router: router.finish(),
                 _ready: None,
             })
@@ -643,8 +778,9 @@ impl<Err: ErrorRenderer> ServiceFactory for ScopeRouterFactory<Err> {
 
 struct ScopeRouter<Err: ErrorRenderer> {
     data: Option<Rc<Extensions>>,
-    router: Router<HttpService<Err>, Vec<Box<dyn Guard>>>,
+    router: Router<(HttpService<Err>, String), Vec<Box<dyn Guard>>>,
     default: Option<HttpService<Err>>,
+    case_insensitive_capture: bool,
     _ready: Option<(WebRequest<Err>, ResourceInfo)>,
 }
 
@@ -660,24 +796,37 @@ impl<Err: ErrorRenderer> Service for ScopeRouter<Err> {
     }
 
     fn call(&self, mut req: WebRequest<Err>) -> Self::Future {
-        let res = self.router.recognize_checked(&mut req, |req, guards| {
-            if let Some(guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
+        let res = self.router.recognize_validated(
+            &mut req,
+            |req, guards| {
+                if let Some(guards) = guards {
+                    for f in guards {
+                        if !f.check(req.head()) {
+                            return false;
+                        }
                     }
                 }
-            }
-            true
-        });
+                true
+            },
+            check_path,
+        );
 
-        if let Some((srv, _info)) = res {
+        if let Some(((srv, pattern), _info)) = res {
+            req.push_match_pattern(pattern);
+            if self.case_insensitive_capture {
+                req.match_info_mut().lowercase_segments();
+            }
             if let Some(ref data) = self.data {
                 req.set_data_container(data.clone());
             }
             Either::Left(srv.call(req))
         } else if let Some(ref default) = self.default {
-            Either::Left(default.call(req))
+            let methods = matched_methods(&self.router, &mut req);
+            req.set_matched_methods(methods);
+            match method_not_allowed(req) {
+                Ok(res) => Either::Right(Ready::Ok(res)),
+                Err(req) => Either::Left(default.call(req)),
+            }
         } else {
             let req = req.into_parts().0;
             Either::Right(Ready::Ok(WebResponse::new(
@@ -697,10 +846,93 @@ mod tests {
     use crate::util::{Bytes, Ready};
     use crate::web::middleware::DefaultHeaders;
     use crate::web::request::WebRequest;
-    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
     use crate::web::DefaultError;
     use crate::web::{self, guard, App, HttpRequest, HttpResponse};
 
+    #[crate::rt_test]
+    async fn test_scope_error_renderer() {
+        async fn item(id: web::types::Path<u32>) -> Result<String, web::Error> {
+            if *id == 0 {
+                Err(web::error::ErrorBadRequest("zero id").into())
+            } else {
+                Ok(id.to_string())
+            }
+        }
+
+        let srv = init_service(
+            App::new()
+                .error_renderer(|_: &HttpRequest, err, res: HttpResponse| {
+                    HttpResponse::build(res.status())
+                        .content_type("text/html")
+                        .body(format!("<p>{}</p>", err))
+                })
+                .service(
+                    web::scope("/api")
+                        .error_renderer(|_: &HttpRequest, err, res: HttpResponse| {
+                            HttpResponse::build(res.status())
+                                .json(&serde_json::json!({ "error": err.to_string() }))
+                        })
+                        .filter(fn_service(|req: WebRequest<DefaultError>| async move {
+                            if req.headers().contains_key("x-fail") {
+                                Err(web::error::ErrorBadRequest("filter").into())
+                            } else {
+                                Ok(req)
+                            }
+                        }))
+                        .route("/item/{id}", web::get().to(item)),
+                )
+                .route("/item/{id}", web::get().to(item)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/api/item/0").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"{\"error\":\"zero id\"}")
+        );
+
+        // filter error
+        let req = TestRequest::with_uri("/api/item/1")
+            .header("x-fail", "1")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"{\"error\":\"filter\"}")
+        );
+
+        // extractor error
+        let req = TestRequest::with_uri("/api/item/abc").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+
+        let req = TestRequest::with_uri("/item/0").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("text/html")
+        );
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"<p>zero id</p>"));
+
+        let req = TestRequest::with_uri("/item/1").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+    }
+
     #[crate::rt_test]
     async fn test_scope() {
         let srv =
@@ -1246,6 +1478,37 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[crate::rt_test]
+    async fn test_scope_data_shadows_app_data() {
+        let srv = init_service(
+            App::new()
+                .data(1u32)
+                .service(web::scope("/local").data(2u32).route(
+                    "/t",
+                    web::get().to(|data: web::types::Data<u32>| async move {
+                        data.to_string()
+                    }),
+                ))
+                .route(
+                    "/t",
+                    web::get().to(|data: web::types::Data<u32>| async move {
+                        data.to_string()
+                    }),
+                ),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::with_uri("/local/t").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(read_body(resp).await, Bytes::from_static(b"2"));
+
+            let req = TestRequest::with_uri("/t").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+        }
+    }
+
     #[crate::rt_test]
     async fn test_override_app_data() {
         let srv = init_service(
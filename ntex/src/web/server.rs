@@ -217,6 +217,18 @@ where
         self
     }
 
+    /// Register callback for switching server to draining mode.
+    ///
+    /// Callback is invoked when server receives `SIGUSR1` signal.
+    /// See `ServerBuilder::on_drain()` and `middleware::Drain`.
+    pub fn on_drain<D>(mut self, f: D) -> Self
+    where
+        D: Fn() + Send + 'static,
+    {
+        self.builder = self.builder.on_drain(f);
+        self
+    }
+
     /// Timeout for graceful workers shutdown.
     ///
     /// After receiving a stop signal, workers have this much time to finish
@@ -1,7 +1,9 @@
-use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::{future::Future, marker::PhantomData, pin::Pin, rc::Rc};
 
+use crate::http::Method;
 use crate::router::{IntoPattern, ResourceDef};
-use crate::service::{boxed, IntoServiceFactory, ServiceFactory};
+use crate::service::{boxed, IntoServiceFactory, Service, ServiceFactory};
 use crate::util::Extensions;
 
 use super::config::AppConfig;
@@ -15,10 +17,20 @@ use super::types::data::DataFactory;
 
 pub trait WebServiceFactory<Err: ErrorRenderer> {
     fn register(self, config: &mut WebServiceConfig<Err>);
+
+    /// Describe registered resources, used by `App::debug_routes()`.
+    ///
+    /// Returns resource paths with methods of resource's routes,
+    /// empty list of methods means any method.
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        Vec::new()
+    }
 }
 
 pub(super) trait AppServiceFactory<Err: ErrorRenderer> {
     fn register(&mut self, config: &mut WebServiceConfig<Err>);
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)>;
 }
 
 pub(super) struct ServiceFactoryWrapper<T> {
@@ -43,11 +55,55 @@ where
             item.register(config)
         }
     }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        self.factory
+            .as_ref()
+            .map(|item| item.describe())
+            .unwrap_or_default()
+    }
+}
+
+/// Group of services registered by `ServiceConfig` with config's middlewares
+pub(super) struct ServiceConfigServices<Err: ErrorRenderer> {
+    pub(super) services: Vec<Box<dyn AppServiceFactory<Err>>>,
+    pub(super) middleware: Vec<ServiceMiddleware<Err>>,
+}
+
+impl<Err: ErrorRenderer> AppServiceFactory<Err> for ServiceConfigServices<Err> {
+    fn register(&mut self, config: &mut WebServiceConfig<Err>) {
+        let idx = config.services.len();
+        for srv in self.services.iter_mut() {
+            srv.register(config);
+        }
+
+        let middleware = &self.middleware;
+        let services: Vec<_> = config
+            .services
+            .drain(idx..)
+            .map(|(rdef, factory, guards, nested)| {
+                let factory = middleware.iter().fold(factory, |f, mw| mw(f));
+                (rdef, factory, guards, nested)
+            })
+            .collect();
+        config.services.extend(services);
+    }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        self.services
+            .iter()
+            .flat_map(|srv| srv.describe())
+            .collect()
+    }
 }
 
 type Guards = Vec<Box<dyn Guard>>;
-type HttpServiceFactory<Err: ErrorRenderer> =
+pub(super) type HttpService<Err: ErrorRenderer> =
+    boxed::BoxService<WebRequest<Err>, WebResponse, Err::Container>;
+pub(super) type HttpServiceFactory<Err: ErrorRenderer> =
     boxed::BoxServiceFactory<(), WebRequest<Err>, WebResponse, Err::Container, ()>;
+pub(super) type ServiceMiddleware<Err> =
+    Box<dyn Fn(HttpServiceFactory<Err>) -> HttpServiceFactory<Err>>;
 
 /// Application service configuration
 pub struct WebServiceConfig<Err: ErrorRenderer> {
@@ -61,6 +117,9 @@ pub struct WebServiceConfig<Err: ErrorRenderer> {
         Option<Rc<ResourceMap>>,
     )>,
     service_data: Rc<Vec<Box<dyn DataFactory>>>,
+    case_insensitive_capture: bool,
+    automatic_options: bool,
+    merge_trailing_slash: bool,
 }
 
 impl<Err: ErrorRenderer> WebServiceConfig<Err> {
@@ -76,9 +135,61 @@ impl<Err: ErrorRenderer> WebServiceConfig<Err> {
             service_data,
             root: true,
             services: Vec::new(),
+            case_insensitive_capture: false,
+            automatic_options: false,
+            merge_trailing_slash: false,
         }
     }
 
+    pub(crate) fn set_case_insensitive_capture(&mut self, val: bool) {
+        self.case_insensitive_capture = val;
+    }
+
+    /// Check if values of dynamic segments must be lowercased
+    pub(crate) fn case_insensitive_capture(&self) -> bool {
+        self.case_insensitive_capture
+    }
+
+    pub(crate) fn set_automatic_options(&mut self, val: bool) {
+        self.automatic_options = val;
+    }
+
+    /// Check if resources must respond to `OPTIONS` requests automatically
+    pub(crate) fn automatic_options(&self) -> bool {
+        self.automatic_options
+    }
+
+    pub(crate) fn set_merge_trailing_slash(&mut self, val: bool) {
+        self.merge_trailing_slash = val;
+    }
+
+    /// Add patterns with toggled trailing slash, if paths must match
+    /// with or without trailing slash.
+    ///
+    /// Original patterns are kept last, so resource pattern is not changed.
+    pub(crate) fn merge_trailing_slash(&self, patterns: Vec<String>) -> Vec<String> {
+        if !self.merge_trailing_slash {
+            return patterns;
+        }
+
+        let mut merged: Vec<String> = patterns
+            .iter()
+            .filter_map(|p| {
+                // root path and tail segments are not changed
+                if p.ends_with('*') || (self.root && p.len() <= 1) {
+                    None
+                } else if let Some(p) = p.strip_suffix('/') {
+                    Some(p.to_string())
+                } else {
+                    Some(format!("{}/", p))
+                }
+            })
+            .filter(|p| !patterns.contains(p))
+            .collect();
+        merged.extend(patterns);
+        merged
+    }
+
     /// Check if root is beeing configured
     pub fn is_root(&self) -> bool {
         self.root
@@ -105,6 +216,9 @@ impl<Err: ErrorRenderer> WebServiceConfig<Err> {
             services: Vec::new(),
             root: false,
             service_data: self.service_data.clone(),
+            case_insensitive_capture: self.case_insensitive_capture,
+            automatic_options: self.automatic_options,
+            merge_trailing_slash: self.merge_trailing_slash,
         }
     }
 
@@ -262,15 +376,149 @@ where
         };
 
         let mut rdef = if config.is_root() || !self.rdef.is_empty() {
-            ResourceDef::new(insert_slesh(self.rdef))
+            ResourceDef::new(config.merge_trailing_slash(insert_slesh(self.rdef)))
         } else {
-            ResourceDef::new(self.rdef)
+            ResourceDef::new(config.merge_trailing_slash(self.rdef))
         };
         if let Some(ref name) = self.name {
             *rdef.name_mut() = name.clone();
         }
         config.register_service(rdef, guards, self.srv, None)
     }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        insert_slesh(self.rdef.clone())
+            .into_iter()
+            .map(|path| (path, Vec::new()))
+            .collect()
+    }
+}
+
+/// Web service that renders errors with different error renderer.
+///
+/// See [`with_renderer()`](../fn.with_renderer.html)
+pub struct WithRenderer<Err, F> {
+    factory: F,
+    _t: PhantomData<Err>,
+}
+
+impl<Err, F> WithRenderer<Err, F>
+where
+    Err: ErrorRenderer,
+    F: WebServiceFactory<Err>,
+{
+    pub(super) fn new(_: Err, factory: F) -> Self {
+        WithRenderer {
+            factory,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<Err, Err2, F> WebServiceFactory<Err> for WithRenderer<Err2, F>
+where
+    Err: ErrorRenderer,
+    Err2: ErrorRenderer,
+    F: WebServiceFactory<Err2>,
+{
+    fn register(self, config: &mut WebServiceConfig<Err>) {
+        let mut cfg = WebServiceConfig {
+            config: config.config.clone(),
+            root: config.root,
+            default: Rc::new(boxed::factory(RenderErrorsFactory::<Err, Err2> {
+                factory: config.default.clone(),
+                _t: PhantomData,
+            })),
+            services: Vec::new(),
+            service_data: config.service_data.clone(),
+            case_insensitive_capture: config.case_insensitive_capture,
+            automatic_options: config.automatic_options,
+            merge_trailing_slash: config.merge_trailing_slash,
+        };
+        self.factory.register(&mut cfg);
+
+        for (rdef, factory, guards, nested) in cfg.services {
+            let factory = RenderErrorsFactory::<Err2, Err> {
+                factory: Rc::new(factory),
+                _t: PhantomData,
+            };
+            config
+                .services
+                .push((rdef, boxed::factory(factory), guards, nested));
+        }
+    }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        self.factory.describe()
+    }
+}
+
+/// Service factory for `Err2` requests, renders errors with `Err` renderer
+struct RenderErrorsFactory<Err: ErrorRenderer, Err2> {
+    factory: Rc<HttpServiceFactory<Err>>,
+    _t: PhantomData<Err2>,
+}
+
+impl<Err, Err2> ServiceFactory for RenderErrorsFactory<Err, Err2>
+where
+    Err: ErrorRenderer,
+    Err2: ErrorRenderer,
+{
+    type Config = ();
+    type Request = WebRequest<Err2>;
+    type Response = WebResponse;
+    type Error = Err2::Container;
+    type InitError = ();
+    type Service = RenderErrors<Err, Err2>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Service, ()>>>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let fut = self.factory.new_service(());
+        Box::pin(async move {
+            Ok(RenderErrors {
+                service: fut.await?,
+                _t: PhantomData,
+            })
+        })
+    }
+}
+
+struct RenderErrors<Err: ErrorRenderer, Err2> {
+    service: HttpService<Err>,
+    _t: PhantomData<Err2>,
+}
+
+impl<Err, Err2> Service for RenderErrors<Err, Err2>
+where
+    Err: ErrorRenderer,
+    Err2: ErrorRenderer,
+{
+    type Request = WebRequest<Err2>;
+    type Response = WebResponse;
+    type Error = Err2::Container;
+    type Future = Pin<Box<dyn Future<Output = Result<WebResponse, Err2::Container>>>>;
+
+    #[inline]
+    fn poll_ready(&self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<Err2>) -> Self::Future {
+        // request is consumed by service, keep copy for error rendering
+        let copy = req.copy();
+        let fut = self.service.call(req.cast());
+        Box::pin(async move {
+            match fut.await {
+                Ok(res) => Ok(res),
+                Err(err) => Ok(WebResponse::from_err::<Err, _>(err, copy.request())),
+            }
+        })
+    }
 }
 
 /// WebServiceFactory implementation for a Vec<T>
@@ -285,6 +533,10 @@ where
             service.register(config);
         }
     }
+
+    fn describe(&self) -> Vec<(String, Vec<Method>)> {
+        self.iter().flat_map(|service| service.describe()).collect()
+    }
 }
 
 macro_rules! tuple_web_service({$(($n:tt, $T:ident)),+} => {
@@ -296,6 +548,14 @@ macro_rules! tuple_web_service({$(($n:tt, $T:ident)),+} => {
                 self.$n.register(config);
             )+
         }
+
+        fn describe(&self) -> Vec<(String, Vec<Method>)> {
+            let mut items = Vec::new();
+            $(
+                items.extend(self.$n.describe());
+            )+
+            items
+        }
     }
 });
 
@@ -314,6 +574,10 @@ macro_rules! array_web_service({$num:tt, $($T:ident),+} => {
                 $T.register(config);
             )+
         }
+
+        fn describe(&self) -> Vec<(String, Vec<Method>)> {
+            self.iter().flat_map(|service| service.describe()).collect()
+        }
     }
 });
 
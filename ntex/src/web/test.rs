@@ -19,7 +19,7 @@ use crate::http::test::TestRequest as HttpTestRequest;
 use crate::http::{HttpService, Method, Payload, Request, StatusCode, Uri, Version};
 use crate::router::{Path, ResourceDef};
 use crate::service::{
-    map_config, IntoService, IntoServiceFactory, Service, ServiceFactory,
+    map_config, IntoService, IntoServiceFactory, Service, ServiceFactory, Transform,
 };
 use crate::time::{sleep, Millis, Seconds};
 use crate::util::{next, Bytes, BytesMut, Extensions, Ready};
@@ -125,6 +125,43 @@ where
     app.call(req).await.unwrap()
 }
 
+/// Applies middleware to a service and calls it with request.
+///
+/// Allows to test middleware in isolation, without building `App`.
+/// Use `ok_service()` or `default_service()` as inner service, or
+/// provide custom service.
+///
+/// ```rust
+/// use ntex::http::{header, StatusCode};
+/// use ntex::web::{middleware, test};
+///
+/// #[ntex::test]
+/// async fn test_default_headers() {
+///     let mw = middleware::DefaultHeaders::new().header("X-Version", "0.2");
+///     let req = test::TestRequest::default().to_srv_request();
+///
+///     let resp = test::test_middleware(mw, test::ok_service(), req).await;
+///     assert_eq!(resp.status(), StatusCode::OK);
+///     assert_eq!(resp.headers().get("x-version").unwrap(), "0.2");
+/// }
+/// ```
+pub async fn test_middleware<M, F, S, Err>(
+    mw: M,
+    service: F,
+    req: WebRequest<Err>,
+) -> WebResponse
+where
+    F: IntoService<S>,
+    S: Service<Request = WebRequest<Err>, Response = WebResponse>,
+    M: Transform<S>,
+    M::Service: Service<Request = WebRequest<Err>, Response = WebResponse>,
+    <M::Service as Service>::Error: fmt::Debug,
+{
+    let srv = mw.new_transform(service.into_service());
+    crate::util::poll_fn(|cx| srv.poll_ready(cx)).await.unwrap();
+    srv.call(req).await.unwrap()
+}
+
 /// Helper function that returns a response body of a TestRequest
 ///
 /// ```rust
@@ -991,6 +1028,31 @@ mod tests {
         assert_eq!(format!("{:?}", StreamType::Tcp), "StreamType::Tcp");
     }
 
+    #[crate::rt_test]
+    async fn test_test_middleware() {
+        let mw = web::middleware::DefaultHeaders::new().header("x-version", "0.2");
+        let req = TestRequest::default().to_srv_request();
+        let res = test_middleware(mw, ok_service(), req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("x-version").unwrap(), "0.2");
+
+        // inner service headers are preserved
+        let mw = web::middleware::DefaultHeaders::new().header("x-version", "0.2");
+        let req = TestRequest::default().to_srv_request();
+        let res = test_middleware(
+            mw,
+            |req: WebRequest<DefaultError>| async move {
+                Ok::<_, Infallible>(req.into_response(
+                    HttpResponse::Created().header("x-version", "0.3").finish(),
+                ))
+            },
+            req,
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers().get("x-version").unwrap(), "0.3");
+    }
+
     #[crate::rt_test]
     async fn test_request_methods() {
         let app = init_service(
@@ -0,0 +1,170 @@
+//! Tower service adapter
+//!
+//! `TowerService` exposes assembled application as a
+//! `tower_service::Service<http::Request<B>>`.
+//!
+//! Body conversions: request body is converted to `Bytes` and passed to
+//! the application as payload with a single chunk. Response body is loaded
+//! to memory and returned as `http::Response<Bytes>`, streaming responses
+//! are buffered completely.
+//!
+//! Error conversions: application errors are rendered to responses with
+//! `ResponseError::error_response()`, so adapter's future fails only if
+//! response body stream fails. Errors returned from service readiness
+//! check are converted to boxed errors with error's message.
+//!
+//! ```rust
+//! use ntex::web::{self, tower::TowerService, App};
+//!
+//! #[ntex::main]
+//! async fn main() {
+//!     let srv = TowerService::new(
+//!         App::new().route("/", web::get().to(|| async { "index" })),
+//!     )
+//!     .await
+//!     .unwrap();
+//! }
+//! ```
+use std::task::{Context, Poll};
+use std::{error::Error, future::Future, pin::Pin, rc::Rc};
+
+use crate::http::{error::ResponseError, h1, Request, Response};
+use crate::service::{IntoServiceFactory, Service, ServiceFactory};
+use crate::util::{next, Bytes, BytesMut};
+use crate::web::{config::AppConfig, WebResponse};
+
+/// Application service adapter for `tower_service::Service` trait.
+pub struct TowerService<S> {
+    srv: Rc<S>,
+}
+
+impl<S> TowerService<S>
+where
+    S: Service<Request = Request, Response = WebResponse>,
+{
+    /// Construct application service and wrap it to tower adapter.
+    pub async fn new<T, F>(app: T) -> Result<Self, F::InitError>
+    where
+        T: IntoServiceFactory<F>,
+        F: ServiceFactory<
+            Config = AppConfig,
+            Request = Request,
+            Response = WebResponse,
+            Error = S::Error,
+            Service = S,
+        >,
+    {
+        let srv = app.into_factory().new_service(AppConfig::default()).await?;
+        Ok(TowerService { srv: Rc::new(srv) })
+    }
+}
+
+impl<S> Clone for TowerService<S> {
+    fn clone(&self) -> Self {
+        TowerService {
+            srv: self.srv.clone(),
+        }
+    }
+}
+
+impl<S, B> tower_service::Service<http::Request<B>> for TowerService<S>
+where
+    S: Service<Request = Request, Response = WebResponse> + 'static,
+    S::Error: ResponseError,
+    B: Into<Bytes>,
+{
+    type Response = http::Response<Bytes>;
+    type Error = Box<dyn Error>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.srv.poll_ready(cx).map_err(|e| e.to_string().into())
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let (parts, body) = req.into_parts();
+
+        let mut payload = h1::Payload::empty();
+        let body = body.into();
+        if !body.is_empty() {
+            payload.unread_data(body);
+        }
+        let mut req = Request::with_payload(payload.into());
+        let head = req.head_mut();
+        head.method = parts.method;
+        head.uri = parts.uri;
+        head.version = parts.version;
+        for (name, value) in parts.headers.iter() {
+            head.headers.append(name.clone(), value.clone());
+        }
+
+        let srv = self.srv.clone();
+        Box::pin(async move {
+            let res: Response = match srv.call(req).await {
+                Ok(res) => res.into(),
+                Err(err) => err.error_response(),
+            };
+            let (res, mut body) = res.into_parts();
+
+            let mut buf = BytesMut::new();
+            while let Some(chunk) = next(&mut body).await {
+                buf.extend_from_slice(&chunk?);
+            }
+
+            let mut builder = http::Response::builder()
+                .status(res.status())
+                .version(res.head().version);
+            for (name, value) in res.headers() {
+                builder = builder.header(name, value);
+            }
+            Ok(builder.body(buf.freeze())?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::util::poll_fn;
+    use crate::web::{self, App, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_tower_service() {
+        let mut srv = TowerService::new(
+            App::new()
+                .route("/", web::get().to(|| async { "index" }))
+                .route(
+                    "/echo",
+                    web::post().to(|body: Bytes| async move {
+                        HttpResponse::Created().header("x-test", "111").body(body)
+                    }),
+                ),
+        )
+        .await
+        .unwrap();
+        poll_fn(|cx| {
+            tower_service::Service::<http::Request<Bytes>>::poll_ready(&mut srv, cx)
+        })
+        .await
+        .unwrap();
+
+        let req = http::Request::get("/").body(Bytes::new()).unwrap();
+        let res = tower_service::Service::call(&mut srv, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), &Bytes::from_static(b"index"));
+
+        let req = http::Request::post("/echo")
+            .header(header::CONTENT_LENGTH, "4")
+            .body("test")
+            .unwrap();
+        let res = tower_service::Service::call(&mut srv, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers().get("x-test").unwrap(), "111");
+        assert_eq!(res.body(), &Bytes::from_static(b"test"));
+
+        let req = http::Request::get("/missing").body(Vec::new()).unwrap();
+        let res = tower_service::Service::call(&mut srv, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,91 @@
+//! Request cancellation extractor
+use std::{future::Future, pin::Pin, task::Context, task::Poll};
+
+use crate::framed::OnDisconnect;
+use crate::http::{h1::ConnStats, Payload};
+use crate::util::Ready;
+use crate::web::error::ErrorRenderer;
+use crate::web::{FromRequest, HttpRequest};
+
+/// Cancellation token of the request.
+///
+/// Token is a future that resolves when client closes or resets
+/// connection, handlers could use it to abort long running work.
+/// Disconnects are detected for http/1 connections only, for other
+/// connections token never resolves.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::Cancellation, App, HttpResponse};
+/// use ntex::util::{select, Either};
+///
+/// async fn report(cancel: Cancellation) -> HttpResponse {
+///     let work = async {
+///         // long running work
+///         "report"
+///     };
+///     match select(work, cancel).await {
+///         Either::Left(report) => HttpResponse::Ok().body(report),
+///         // client is gone, response is not sent
+///         Either::Right(_) => HttpResponse::Ok().finish(),
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/report", web::get().to(report));
+/// }
+/// ```
+pub struct Cancellation {
+    stats: Option<ConnStats>,
+    fut: Option<OnDisconnect>,
+}
+
+impl Cancellation {
+    /// Check if client is disconnected.
+    pub fn is_cancelled(&self) -> bool {
+        self.stats
+            .as_ref()
+            .map(|stats| stats.is_disconnected())
+            .unwrap_or(false)
+    }
+}
+
+impl Future for Cancellation {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.fut {
+            Some(ref fut) => fut.poll_ready(cx),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for Cancellation {
+    type Error = Err::Container;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let stats = req.conn_stats().cloned();
+        let fut = stats.as_ref().map(|stats| stats.on_disconnect());
+        Ready::Ok(Cancellation { stats, fut })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::lazy;
+    use crate::web::test::{from_request, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_cancellation_not_available() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let mut cancel = from_request::<Cancellation>(&req, &mut pl).await.unwrap();
+        assert!(!cancel.is_cancelled());
+        let res = lazy(|cx| Pin::new(&mut cancel).poll(cx)).await;
+        assert!(res.is_pending());
+    }
+}
@@ -0,0 +1,122 @@
+//! Connection-level data extractor
+use std::{fmt, ops};
+
+use crate::http::{h1::ConnExtensions, Payload};
+use crate::util::Ready;
+use crate::web::error::{DataExtractorError, ErrorRenderer};
+use crate::web::{FromRequest, HttpRequest};
+
+/// Connection-level data extractor.
+///
+/// Extracts a clone of the value stored in connection extensions with
+/// `ConnExtensions::insert()`. Value persists across requests on the same
+/// keep-alive connection. If value is not set, extractor fails with
+/// *Internal Server Error* response, `Option<ConnData<T>>` could be used
+/// instead. Connection extensions are available for http/1 connections only.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::{ConnData, ConnExtensions}};
+///
+/// #[derive(Clone)]
+/// struct Session(String);
+///
+/// async fn login(ext: ConnExtensions) -> &'static str {
+///     ext.insert(Session("user".to_string()));
+///     "logged in"
+/// }
+///
+/// async fn index(session: Option<ConnData<Session>>) -> String {
+///     match session {
+///         Some(session) => format!("Welcome {}!", session.0),
+///         None => "Please login".to_string(),
+///     }
+/// }
+///
+/// fn main() {
+///     let app = web::App::new()
+///         .service(web::resource("/login").route(web::post().to(login)))
+///         .service(web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+pub struct ConnData<T>(T);
+
+impl<T> ConnData<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for ConnData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ConnData<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConnData").field(&self.0).finish()
+    }
+}
+
+impl<T: Clone + 'static, Err: ErrorRenderer> FromRequest<Err> for ConnData<T> {
+    type Error = DataExtractorError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(val) = req.conn_extensions().and_then(|ext| ext.get::<T>()) {
+            Ready::Ok(ConnData(val))
+        } else {
+            log::debug!(
+                "Failed to construct ConnData extractor. \
+                 Request path: {:?} (type: {})",
+                req.path(),
+                std::any::type_name::<T>(),
+            );
+            Ready::Err(DataExtractorError::NotConfigured)
+        }
+    }
+}
+
+/// Extract connection-level extensions.
+///
+/// Connection extensions are available for http/1 connections only,
+/// for other connections extractor fails with *Internal Server Error*
+/// response.
+impl<Err: ErrorRenderer> FromRequest<Err> for ConnExtensions {
+    type Error = DataExtractorError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(ext) = req.conn_extensions() {
+            Ready::Ok(ext.clone())
+        } else {
+            Ready::Err(DataExtractorError::NotConfigured)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{from_request, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_conn_data_not_available() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = from_request::<ConnExtensions>(&req, &mut pl).await;
+        assert!(matches!(res, Err(DataExtractorError::NotConfigured)));
+
+        let res = from_request::<ConnData<usize>>(&req, &mut pl).await;
+        assert!(matches!(res, Err(DataExtractorError::NotConfigured)));
+
+        let res = from_request::<Option<ConnData<usize>>>(&req, &mut pl).await;
+        assert!(res.unwrap().is_none());
+    }
+}
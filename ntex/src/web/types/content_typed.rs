@@ -0,0 +1,245 @@
+//! Content type dispatching extractor
+use std::{fmt, future::Future, ops, pin::Pin};
+
+use serde::de::DeserializeOwned;
+
+use crate::http::{HttpMessage, Payload};
+use crate::web::error::{ContentTypedError, ErrorRenderer};
+use crate::web::{FromRequest, HttpRequest};
+
+use super::{Form, Json, JsonConfig};
+
+/// Extract typed information from request's body, decoder is selected
+/// by request's `Content-Type` header.
+///
+/// Supported content types:
+///
+/// * json, `application/json` or any `+json` media type, also types
+///   accepted by [**JsonConfig**](struct.JsonConfig.html) predicate
+/// * `application/x-www-form-urlencoded`
+/// * messagepack, `application/msgpack`, `application/x-msgpack` or any
+///   `+msgpack` media type, requires `msgpack` feature
+///
+/// Other content types are rejected with `415 Unsupported Media Type`.
+/// Extraction is configured with `JsonConfig` and `FormConfig`, messagepack
+/// payload size is limited by [**PayloadConfig**](struct.PayloadConfig.html).
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// /// deserialize `Info` from json or urlencoded request's body
+/// async fn index(info: web::types::ContentTyped<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::post().to(index))
+///     );
+/// }
+/// ```
+pub struct ContentTyped<T>(pub T);
+
+impl<T> ContentTyped<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for ContentTyped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for ContentTyped<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ContentTyped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ContentTyped").field(&self.0).finish()
+    }
+}
+
+impl<T, Err> FromRequest<Err> for ContentTyped<T>
+where
+    T: DeserializeOwned + 'static,
+    Err: ErrorRenderer,
+{
+    type Error = ContentTypedError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let mime = match req.mime_type() {
+            Ok(Some(mime)) => mime,
+            _ => {
+                return Box::pin(async { Err(ContentTypedError::UnsupportedMediaType) })
+            }
+        };
+
+        #[cfg(feature = "msgpack")]
+        if is_msgpack(&mime) {
+            let fut =
+                <crate::util::Bytes as FromRequest<Err>>::from_request(req, payload);
+            return Box::pin(async move {
+                let body = fut.await?;
+                Ok(ContentTyped(rmp_serde::from_slice(&body)?))
+            });
+        }
+
+        if mime.essence_str() == mime::APPLICATION_WWW_FORM_URLENCODED.essence_str() {
+            let fut = <Form<T> as FromRequest<Err>>::from_request(req, payload);
+            Box::pin(async move { Ok(ContentTyped(fut.await?.into_inner())) })
+        } else if mime.subtype() == mime::JSON
+            || mime.suffix() == Some(mime::JSON)
+            || req
+                .app_data::<JsonConfig>()
+                .and_then(|cfg| cfg.content_type.as_ref())
+                .map_or(false, |predicate| predicate(mime))
+        {
+            let fut = <Json<T> as FromRequest<Err>>::from_request(req, payload);
+            Box::pin(async move { Ok(ContentTyped(fut.await?.into_inner())) })
+        } else {
+            Box::pin(async { Err(ContentTypedError::UnsupportedMediaType) })
+        }
+    }
+}
+
+#[cfg(feature = "msgpack")]
+/// Check if media type is messagepack
+fn is_msgpack(mime: &mime::Mime) -> bool {
+    mime.subtype() == "msgpack"
+        || mime.subtype() == "x-msgpack"
+        || mime.suffix().map_or(false, |s| s == "msgpack")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use crate::http::StatusCode;
+    use crate::util::Bytes;
+    use crate::web::test::{from_request, TestRequest};
+    use crate::web::{error::WebResponseError, DefaultError, HttpResponse};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Info {
+        hello: String,
+        counter: i64,
+    }
+
+    #[crate::rt_test]
+    async fn test_content_typed() {
+        let info = Info {
+            hello: "world".into(),
+            counter: 123,
+        };
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, "33")
+            .set_payload(Bytes::from_static(b"{\"hello\":\"world\",\"counter\":123}"))
+            .to_http_parts();
+        let s = from_request::<ContentTyped<Info>>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.into_inner(), info);
+
+        let (req, mut pl) =
+            TestRequest::with_header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .header(CONTENT_LENGTH, "23")
+                .set_payload(Bytes::from_static(b"hello=world&counter=123"))
+                .to_http_parts();
+        let s = from_request::<ContentTyped<Info>>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.into_inner(), info);
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "text/csv")
+            .header(CONTENT_LENGTH, "4")
+            .set_payload(Bytes::from_static(b"test"))
+            .to_http_parts();
+        let err = from_request::<ContentTyped<Info>>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let err = from_request::<ContentTyped<Info>>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ContentTypedError::UnsupportedMediaType));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[crate::rt_test]
+    async fn test_content_typed_msgpack() {
+        #[derive(serde::Serialize)]
+        struct Info2 {
+            hello: &'static str,
+            counter: i64,
+        }
+        let body = rmp_serde::to_vec_named(&Info2 {
+            hello: "world",
+            counter: 123,
+        })
+        .unwrap();
+
+        for ct in &["application/msgpack", "application/vnd.api+msgpack"] {
+            let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, *ct)
+                .header(CONTENT_LENGTH, body.len())
+                .set_payload(body.clone())
+                .to_http_parts();
+            let s = from_request::<ContentTyped<Info>>(&req, &mut pl)
+                .await
+                .unwrap();
+            assert_eq!(s.hello, "world");
+            assert_eq!(s.counter, 123);
+        }
+
+        let (req, mut pl) =
+            TestRequest::with_header(CONTENT_TYPE, "application/msgpack")
+                .header(CONTENT_LENGTH, "4")
+                .set_payload(Bytes::from_static(b"test"))
+                .to_http_parts();
+        let err = from_request::<ContentTyped<Info>>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ContentTypedError::MsgPack(_)));
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[crate::rt_test]
+    async fn test_content_typed_json_config() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "text/plain")
+            .header(CONTENT_LENGTH, "33")
+            .set_payload(Bytes::from_static(b"{\"hello\":\"world\",\"counter\":123}"))
+            .data(JsonConfig::default().content_type(|mime| mime == mime::TEXT_PLAIN))
+            .to_http_parts();
+        let s = from_request::<ContentTyped<Info>>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(s.counter, 123);
+    }
+}
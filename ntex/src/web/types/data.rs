@@ -72,6 +72,13 @@ impl<T> Data<T> {
         Data(Arc::new(state))
     }
 
+    /// Create new `Data` instance from existing `Arc`.
+    ///
+    /// All clones of the `Arc` share the same data.
+    pub fn from_arc(state: Arc<T>) -> Data<T> {
+        Data(state)
+    }
+
     /// Get reference to inner app data.
     pub fn get_ref(&self) -> &T {
         self.0.as_ref()
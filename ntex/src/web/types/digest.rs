@@ -0,0 +1,333 @@
+//! Digest verified body extractor
+use std::task::{Context, Poll};
+use std::{cell::RefCell, fmt, future::Future, ops, pin::Pin, rc::Rc};
+
+use sha2::Digest;
+
+use crate::http::{error, header::HeaderMap, Payload};
+use crate::util::{next, Bytes, BytesMut};
+use crate::web::error::{DigestError, ErrorRenderer, PayloadError};
+use crate::web::{FromRequest, HttpRequest};
+use crate::Stream;
+
+use super::PayloadConfig;
+
+const CONTENT_MD5: &str = "content-md5";
+const DIGEST: &str = "digest";
+
+/// Extract request's body and verify it against request's digest.
+///
+/// Expected digest is taken from `Content-MD5` header or from `Digest`
+/// header (RFC 3230). Supported `Digest` algorithms are `MD5` and
+/// `SHA-256`, other algorithms are ignored. Digest is computed over
+/// received body, before decompression, while body is being read.
+///
+/// Request is rejected with `400 Bad Request` if digest does not match,
+/// or request does not contain any supported digest. Requires `digest`
+/// feature.
+///
+/// [**PayloadConfig**](struct.PayloadConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web;
+///
+/// async fn index(body: web::types::VerifiedBody) -> String {
+///     format!("Body {:?}!", body)
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct VerifiedBody(pub Bytes);
+
+impl VerifiedBody {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Bytes {
+        self.0
+    }
+}
+
+impl ops::Deref for VerifiedBody {
+    type Target = Bytes;
+
+    fn deref(&self) -> &Bytes {
+        &self.0
+    }
+}
+
+impl fmt::Debug for VerifiedBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VerifiedBody").field(&self.0).finish()
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for VerifiedBody {
+    type Error = DigestError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let tmp;
+        let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
+            cfg
+        } else {
+            tmp = PayloadConfig::default();
+            &tmp
+        };
+
+        if let Err(e) = cfg.check_mimetype(req) {
+            return Box::pin(async move { Err(e.into()) });
+        }
+        let checks = match digests(req.headers()) {
+            Ok(checks) => Rc::new(RefCell::new(checks)),
+            Err(e) => return Box::pin(async move { Err(e) }),
+        };
+
+        let stream = DigestStream {
+            stream: payload.take(),
+            checks: checks.clone(),
+        };
+        #[cfg(feature = "compress")]
+        let mut stream =
+            crate::http::encoding::Decoder::from_headers(stream, req.headers());
+        #[cfg(not(feature = "compress"))]
+        let mut stream = stream;
+
+        let limit = cfg.limit;
+        Box::pin(async move {
+            let mut body = BytesMut::with_capacity(8192);
+
+            while let Some(item) = next(&mut stream).await {
+                let chunk = item.map_err(PayloadError::from)?;
+                if body.len() + chunk.len() > limit {
+                    return Err(PayloadError::from(error::PayloadError::Overflow).into());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            drop(stream);
+
+            for check in checks.borrow_mut().iter_mut() {
+                if !check.verify() {
+                    log::trace!("Request body digest mismatch");
+                    return Err(DigestError::Mismatch);
+                }
+            }
+            Ok(VerifiedBody(body.freeze()))
+        })
+    }
+}
+
+/// Supported digest algorithms
+enum Hasher {
+    Md5(md5::Md5),
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        match self {
+            Hasher::Md5(h) => h.finalize_reset().to_vec(),
+            Hasher::Sha256(h) => h.finalize_reset().to_vec(),
+        }
+    }
+}
+
+/// Expected digest of the body
+struct DigestCheck {
+    hasher: Hasher,
+    expected: Vec<u8>,
+}
+
+impl DigestCheck {
+    fn new(hasher: Hasher, value: &str) -> Result<Self, DigestError> {
+        let expected =
+            base64::decode(value.trim()).map_err(|_| DigestError::Malformed)?;
+        Ok(DigestCheck { hasher, expected })
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data)
+    }
+
+    fn verify(&mut self) -> bool {
+        self.hasher.finish() == self.expected
+    }
+}
+
+/// Collect expected digests from request headers
+fn digests(headers: &HeaderMap) -> Result<Vec<DigestCheck>, DigestError> {
+    let mut checks = Vec::new();
+
+    for val in headers.get_all(CONTENT_MD5) {
+        let val = val.to_str().map_err(|_| DigestError::Malformed)?;
+        checks.push(DigestCheck::new(Hasher::Md5(md5::Md5::new()), val)?);
+    }
+    for val in headers.get_all(DIGEST) {
+        let val = val.to_str().map_err(|_| DigestError::Malformed)?;
+        for item in val.split(',').filter(|item| !item.trim().is_empty()) {
+            let mut parts = item.splitn(2, '=');
+            let alg = parts.next().unwrap_or("").trim();
+            let value = parts.next().ok_or(DigestError::Malformed)?;
+
+            if alg.eq_ignore_ascii_case("md5") {
+                checks.push(DigestCheck::new(Hasher::Md5(md5::Md5::new()), value)?);
+            } else if alg.eq_ignore_ascii_case("sha-256") {
+                checks.push(DigestCheck::new(
+                    Hasher::Sha256(sha2::Sha256::new()),
+                    value,
+                )?);
+            }
+        }
+    }
+
+    if checks.is_empty() {
+        Err(DigestError::Missing)
+    } else {
+        Ok(checks)
+    }
+}
+
+/// Payload stream that feeds received chunks to digest checks
+struct DigestStream {
+    stream: Payload,
+    checks: Rc<RefCell<Vec<DigestCheck>>>,
+}
+
+impl Stream for DigestStream {
+    type Item = Result<Bytes, error::PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let item = Pin::new(&mut self.stream).poll_next(cx);
+        if let Poll::Ready(Some(Ok(ref chunk))) = item {
+            for check in self.checks.borrow_mut().iter_mut() {
+                check.update(chunk);
+            }
+        }
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use crate::http::StatusCode;
+    use crate::web::test::{from_request, TestRequest};
+    use crate::web::{error::WebResponseError, DefaultError, HttpResponse};
+
+    // md5 and sha-256 of "hello world"
+    const MD5: &str = "XrY7u+Ae7tCTyyK7j1rNww==";
+    const SHA256: &str = "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=";
+
+    #[crate::rt_test]
+    async fn test_verified_body() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_MD5, MD5)
+            .set_payload(Bytes::from_static(b"hello world"))
+            .to_http_parts();
+        let body = from_request::<VerifiedBody>(&req, &mut pl).await.unwrap();
+        assert_eq!(body.into_inner(), Bytes::from_static(b"hello world"));
+
+        let (req, mut pl) = TestRequest::with_header(
+            DIGEST,
+            format!("SHA-256={},unixsum=30637", SHA256),
+        )
+        .set_payload(Bytes::from_static(b"hello world"))
+        .to_http_parts();
+        let body = from_request::<VerifiedBody>(&req, &mut pl).await.unwrap();
+        assert_eq!(&body[..], b"hello world");
+
+        let (req, mut pl) =
+            TestRequest::with_header(DIGEST, format!("md5={}, sha-256={}", MD5, SHA256))
+                .set_payload(Bytes::from_static(b"hello world"))
+                .to_http_parts();
+        assert!(from_request::<VerifiedBody>(&req, &mut pl).await.is_ok());
+    }
+
+    #[crate::rt_test]
+    async fn test_verified_body_mismatch() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_MD5, MD5)
+            .set_payload(Bytes::from_static(b"hello world!"))
+            .to_http_parts();
+        let err = from_request::<VerifiedBody>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, DigestError::Mismatch));
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let (req, mut pl) =
+            TestRequest::with_header(DIGEST, format!("md5={}, sha-256={}", MD5, MD5))
+                .set_payload(Bytes::from_static(b"hello world"))
+                .to_http_parts();
+        let err = from_request::<VerifiedBody>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, DigestError::Mismatch));
+
+        let (req, mut pl) = TestRequest::with_header(DIGEST, "unixsum=30637")
+            .set_payload(Bytes::from_static(b"hello world"))
+            .to_http_parts();
+        let err = from_request::<VerifiedBody>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, DigestError::Missing));
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_MD5, "not base64!")
+            .set_payload(Bytes::from_static(b"hello world"))
+            .to_http_parts();
+        let err = from_request::<VerifiedBody>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, DigestError::Malformed));
+    }
+
+    #[crate::rt_test]
+    async fn test_verified_body_config() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_MD5, MD5)
+            .header(CONTENT_LENGTH, "11")
+            .set_payload(Bytes::from_static(b"hello world"))
+            .data(PayloadConfig::new(5))
+            .to_http_parts();
+        let err = from_request::<VerifiedBody>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_MD5, MD5)
+            .set_payload(Bytes::from_static(b"hello world"))
+            .data(PayloadConfig::default().mimetype(mime::APPLICATION_JSON))
+            .to_http_parts();
+        assert!(from_request::<VerifiedBody>(&req, &mut pl).await.is_err());
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_MD5, MD5)
+            .header(CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(b"hello world"))
+            .data(PayloadConfig::default().mimetype(mime::APPLICATION_JSON))
+            .to_http_parts();
+        assert!(from_request::<VerifiedBody>(&req, &mut pl).await.is_ok());
+    }
+}
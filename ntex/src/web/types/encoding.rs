@@ -0,0 +1,182 @@
+//! Request encoding extractor
+use std::{fmt, ops};
+
+use crate::http::header::{ContentEncoding, CONTENT_ENCODING};
+use crate::web::error::ErrorRenderer;
+use crate::web::{FromRequest, HttpRequest};
+use crate::{http::Payload, util::Ready};
+
+/// Extract content encodings declared by request's `Content-Encoding` header.
+///
+/// Encodings are listed in the order they were applied to the payload.
+/// Payload extractors decompress request's body but keep `Content-Encoding`
+/// header intact, so original encoding is available after decompression.
+/// Encodings that are not supported by payload decoders are reported as
+/// `Encoding::Unknown`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web;
+///
+/// async fn index(encoding: web::types::RequestEncoding, body: String) -> String {
+///     format!("Compressed: {}, body: {}", encoding.is_compressed(), body)
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct RequestEncoding(pub Vec<Encoding>);
+
+/// Content encoding declared by request
+#[derive(Clone, PartialEq, Debug)]
+pub enum Encoding {
+    /// Encoding supported by payload decoders
+    Known(ContentEncoding),
+    /// Encoding token as is, i.e. `zstd`
+    Unknown(String),
+}
+
+impl Encoding {
+    /// Check if payload is compressed, unknown encodings are treated as compressed
+    pub fn is_compressed(&self) -> bool {
+        match self {
+            Encoding::Known(enc) => enc.is_compressed(),
+            Encoding::Unknown(_) => true,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Encoding {
+    fn from(s: &'a str) -> Encoding {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("identity") {
+            Encoding::Known(ContentEncoding::Identity)
+        } else {
+            match ContentEncoding::from(s) {
+                ContentEncoding::Identity => Encoding::Unknown(s.to_string()),
+                enc => Encoding::Known(enc),
+            }
+        }
+    }
+}
+
+impl RequestEncoding {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> Vec<Encoding> {
+        self.0
+    }
+
+    /// Check if payload is compressed
+    pub fn is_compressed(&self) -> bool {
+        self.0.iter().any(|enc| enc.is_compressed())
+    }
+}
+
+impl ops::Deref for RequestEncoding {
+    type Target = [Encoding];
+
+    fn deref(&self) -> &[Encoding] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for RequestEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RequestEncoding").field(&self.0).finish()
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for RequestEncoding {
+    type Error = Err::Container;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let encodings = req
+            .headers()
+            .get_all(CONTENT_ENCODING)
+            .filter_map(|val| val.to_str().ok())
+            .flat_map(|val| val.split(','))
+            .filter(|val| !val.trim().is_empty())
+            .map(Encoding::from)
+            .collect();
+        Ready::Ok(RequestEncoding(encodings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{from_request, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_request_encoding() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let enc = from_request::<RequestEncoding>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert!(enc.is_empty());
+        assert!(!enc.is_compressed());
+
+        let (req, mut pl) =
+            TestRequest::with_header(CONTENT_ENCODING, "gzip").to_http_parts();
+        let enc = from_request::<RequestEncoding>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(&enc[..], &[Encoding::Known(ContentEncoding::Gzip)]);
+        assert!(enc.is_compressed());
+
+        let (req, mut pl) =
+            TestRequest::with_header(CONTENT_ENCODING, "gzip, br,, identity, zstd")
+                .to_http_parts();
+        let enc = from_request::<RequestEncoding>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(
+            enc.into_inner(),
+            vec![
+                Encoding::Known(ContentEncoding::Gzip),
+                Encoding::Known(ContentEncoding::Br),
+                Encoding::Known(ContentEncoding::Identity),
+                Encoding::Unknown("zstd".to_string()),
+            ]
+        );
+
+        // unknown encoding
+        let (req, mut pl) =
+            TestRequest::with_header(CONTENT_ENCODING, "x-custom").to_http_parts();
+        let enc = from_request::<RequestEncoding>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(&enc[..], &[Encoding::Unknown("x-custom".to_string())]);
+        assert!(enc.is_compressed());
+    }
+
+    #[cfg(feature = "compress")]
+    #[crate::rt_test]
+    async fn test_request_encoding_after_decompress() {
+        use std::io::Write;
+
+        use flate2::{write::GzEncoder, Compression};
+
+        use crate::util::Bytes;
+
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(b"hello").unwrap();
+        let body = enc.finish().unwrap();
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_ENCODING, "gzip")
+            .set_payload(body)
+            .to_http_parts();
+        let (body, enc) = from_request::<(Bytes, RequestEncoding)>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(body, Bytes::from_static(b"hello"));
+        assert_eq!(&enc[..], &[Encoding::Known(ContentEncoding::Gzip)]);
+    }
+}
@@ -0,0 +1,199 @@
+//! Feature flags extractor
+use std::sync::{Arc, RwLock};
+use std::{fmt, marker::PhantomData, ops};
+
+use crate::http::Payload;
+use crate::util::{HashSet, Ready};
+use crate::web::error::{DataExtractorError, ErrorRenderer};
+use crate::web::{FromRequest, HttpRequest};
+
+use super::Data;
+
+/// Set of enabled feature flags.
+///
+/// `FeatureFlags` is a handle to shared set, clones of the flags could
+/// be used for toggling flags at runtime, including from other threads.
+/// Flags are accessible to handlers with `Feature<T>` extractor
+/// if flags are registered with `App::data()` method.
+#[derive(Clone, Default)]
+pub struct FeatureFlags(Arc<RwLock<HashSet<String>>>);
+
+impl FeatureFlags {
+    /// Create empty `FeatureFlags` instance.
+    pub fn new() -> Self {
+        FeatureFlags::default()
+    }
+
+    /// Enable feature flag.
+    pub fn enable(&self, name: &str) {
+        self.0.write().unwrap().insert(name.to_string());
+    }
+
+    /// Disable feature flag.
+    pub fn disable(&self, name: &str) {
+        self.0.write().unwrap().remove(name);
+    }
+
+    /// Check if feature flag is enabled.
+    pub fn enabled(&self, name: &str) -> bool {
+        self.0.read().unwrap().contains(name)
+    }
+}
+
+impl fmt::Debug for FeatureFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FeatureFlags")
+            .field(&*self.0.read().unwrap())
+            .finish()
+    }
+}
+
+/// Feature flag name.
+pub trait FeatureName {
+    /// Name of the flag
+    const NAME: &'static str;
+}
+
+/// Extract state of the feature flag.
+///
+/// Flag name is defined by `FeatureName` implementation. Flags are read
+/// from `FeatureFlags` application data. If flags are not registered,
+/// using `Feature<T>` extractor would cause *Internal Server Error* response.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::{Feature, FeatureFlags, FeatureName}, App};
+///
+/// struct NewUi;
+///
+/// impl FeatureName for NewUi {
+///     const NAME: &'static str = "new-ui";
+/// }
+///
+/// async fn index(flag: Feature<NewUi>) -> &'static str {
+///     if flag.is_enabled() {
+///         "new ui"
+///     } else {
+///         "old ui"
+///     }
+/// }
+///
+/// fn main() {
+///     let flags = FeatureFlags::new();
+///     flags.enable("new-ui");
+///
+///     let app = App::new()
+///         .data(flags)
+///         .service(web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+pub struct Feature<T> {
+    enabled: bool,
+    _t: PhantomData<T>,
+}
+
+impl<T> Feature<T> {
+    /// Check if feature flag is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> bool {
+        self.enabled
+    }
+}
+
+impl<T> ops::Deref for Feature<T> {
+    type Target = bool;
+
+    fn deref(&self) -> &bool {
+        &self.enabled
+    }
+}
+
+impl<T: FeatureName> fmt::Debug for Feature<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Feature")
+            .field("name", &T::NAME)
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+impl<T: FeatureName, Err: ErrorRenderer> FromRequest<Err> for Feature<T> {
+    type Error = DataExtractorError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(flags) = req.app_data::<Data<FeatureFlags>>() {
+            Ready::Ok(Feature {
+                enabled: flags.enabled(T::NAME),
+                _t: PhantomData,
+            })
+        } else {
+            log::debug!(
+                "Failed to construct Feature extractor, FeatureFlags is not configured. \
+                 Request path: {:?}",
+                req.path()
+            );
+            Ready::Err(DataExtractorError::NotConfigured)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::Service;
+    use crate::util::Bytes;
+    use crate::web::test::{init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    struct Beta;
+
+    impl FeatureName for Beta {
+        const NAME: &'static str = "beta";
+    }
+
+    #[crate::rt_test]
+    async fn test_feature() {
+        let flags = FeatureFlags::new();
+        let srv = init_service(App::new().data(flags.clone()).service(
+            web::resource("/").to(|flag: Feature<Beta>| async move {
+                HttpResponse::Ok().body(format!("{}", flag.is_enabled()))
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"false"));
+
+        flags.enable("beta");
+        assert!(flags.enabled("beta"));
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"true"));
+
+        flags.disable("beta");
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"false"));
+    }
+
+    #[crate::rt_test]
+    async fn test_feature_not_configured() {
+        let srv = init_service(App::new().service(
+            web::resource("/").to(|_: Feature<Beta>| async { HttpResponse::Ok() }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
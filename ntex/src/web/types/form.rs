@@ -13,6 +13,8 @@ use crate::web::error::{ErrorRenderer, UrlencodedError, WebResponseError};
 use crate::web::responder::{Ready, Responder};
 use crate::web::{FromRequest, HttpRequest};
 
+use super::payload::PayloadLimit;
+
 /// Form data helper (`application/x-www-form-urlencoded`)
 ///
 /// Can be use to extract url-encoded data from the request body,
@@ -108,6 +110,7 @@ where
         let limit = req
             .app_data::<FormConfig>()
             .map(|c| c.limit)
+            .or_else(|| PayloadLimit::get(req))
             .unwrap_or(16384);
 
         let fut = UrlEncoded::new(req, payload).limit(limit);
@@ -0,0 +1,138 @@
+//! Idempotency key extractor
+use std::{fmt, ops};
+
+use crate::http::header::{HeaderMap, HeaderName};
+use crate::http::Payload;
+use crate::util::Ready;
+use crate::web::error::{ErrorRenderer, IdempotencyKeyError};
+use crate::web::{FromRequest, HttpRequest};
+
+/// `Idempotency-Key` header name
+pub(in crate::web) const IDEMPOTENCY_KEY: HeaderName =
+    HeaderName::from_static("idempotency-key");
+
+/// Max length of the idempotency key
+const MAX_LEN: usize = 255;
+
+/// Client provided idempotency key.
+///
+/// Key is extracted from `Idempotency-Key` request header. Key must be
+/// non-empty visible ascii string of at most 255 characters, otherwise
+/// extractor fails with *Bad Request* response. `Option<IdempotencyKey>`
+/// could be used if header is optional. Use `middleware::Idempotency`
+/// to replay responses of repeated requests.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::IdempotencyKey, App};
+///
+/// async fn payment(key: IdempotencyKey) -> String {
+///     format!("payment {}", key.as_str())
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/payment", web::post().to(payment));
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /// Parse idempotency key from request headers.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, IdempotencyKeyError> {
+        let val = headers
+            .get(&IDEMPOTENCY_KEY)
+            .ok_or(IdempotencyKeyError::Missing)?
+            .as_bytes();
+        if val.is_empty()
+            || val.len() > MAX_LEN
+            || !val.iter().all(|b| b.is_ascii_graphic())
+        {
+            Err(IdempotencyKeyError::Malformed)
+        } else {
+            // all bytes are ascii
+            Ok(IdempotencyKey(String::from_utf8_lossy(val).into_owned()))
+        }
+    }
+
+    /// Idempotency key value
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl ops::Deref for IdempotencyKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IdempotencyKey").field(&self.0).finish()
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for IdempotencyKey {
+    type Error = IdempotencyKeyError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match IdempotencyKey::from_headers(req.headers()) {
+            Ok(key) => Ready::Ok(key),
+            Err(e) => {
+                log::debug!(
+                    "Failed to construct IdempotencyKey extractor: {}. \
+                     Request path: {:?}",
+                    e,
+                    req.path()
+                );
+                Ready::Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{from_request, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_idempotency_key() {
+        let (req, mut pl) = TestRequest::default()
+            .header("idempotency-key", "8e03978e-40d5-43e8-bc93-6894a57f9324")
+            .to_http_parts();
+        let key = from_request::<IdempotencyKey>(&req, &mut pl).await.unwrap();
+        assert_eq!(key.as_str(), "8e03978e-40d5-43e8-bc93-6894a57f9324");
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = from_request::<IdempotencyKey>(&req, &mut pl).await;
+        assert_eq!(res.unwrap_err(), IdempotencyKeyError::Missing);
+        let res = from_request::<Option<IdempotencyKey>>(&req, &mut pl).await;
+        assert!(res.unwrap().is_none());
+
+        for val in &["", "key with spaces", &"x".repeat(256)] {
+            let (req, mut pl) = TestRequest::default()
+                .header("idempotency-key", *val)
+                .to_http_parts();
+            let res = from_request::<IdempotencyKey>(&req, &mut pl).await;
+            assert_eq!(res.unwrap_err(), IdempotencyKeyError::Malformed);
+        }
+    }
+}
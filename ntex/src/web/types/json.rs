@@ -12,6 +12,8 @@ use crate::web::error::{ErrorRenderer, JsonError, JsonPayloadError, WebResponseE
 use crate::web::responder::{Ready, Responder};
 use crate::web::{FromRequest, HttpRequest};
 
+use super::payload::PayloadLimit;
+
 /// Json helper
 ///
 /// Json can be used for two different purpose. First is for json response
@@ -172,7 +174,7 @@ where
         let (limit, ctype) = req
             .app_data::<JsonConfig>()
             .map(|c| (c.limit, c.content_type.clone()))
-            .unwrap_or((32768, None));
+            .unwrap_or_else(|| (PayloadLimit::get(req).unwrap_or(32768), None));
 
         let fut = JsonBody::new(req, payload, ctype).limit(limit);
         Box::pin(async move {
@@ -225,7 +227,7 @@ where
 #[derive(Clone)]
 pub struct JsonConfig {
     limit: usize,
-    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    pub(super) content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
 }
 
 impl JsonConfig {
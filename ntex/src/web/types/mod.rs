@@ -1,15 +1,40 @@
 //! Extractor types
 
+mod authorization;
+mod cancellation;
+mod conndata;
+mod content_typed;
 pub(in crate::web) mod data;
+#[cfg(feature = "digest")]
+mod digest;
+mod encoding;
+mod feature;
 pub(in crate::web) mod form;
+pub(in crate::web) mod idempotency;
 pub(in crate::web) mod json;
+mod multipart;
 mod path;
 pub(in crate::web) mod payload;
 mod query;
+mod stats;
+pub(in crate::web) mod trace;
 
+pub use self::authorization::{Authorization, AuthorizationConfig};
+pub use self::cancellation::Cancellation;
+pub use self::conndata::ConnData;
+pub use self::content_typed::ContentTyped;
 pub use self::data::Data;
+#[cfg(feature = "digest")]
+pub use self::digest::VerifiedBody;
+pub use self::encoding::{Encoding, RequestEncoding};
+pub use self::feature::{Feature, FeatureFlags, FeatureName};
 pub use self::form::{Form, FormConfig};
+pub use self::idempotency::IdempotencyKey;
 pub use self::json::{Json, JsonConfig};
-pub use self::path::Path;
+pub use self::multipart::{FromMultipart, MultipartFields, MultipartForm, TempFile};
+pub use self::path::{Path, PathConfig, PathParam};
 pub use self::payload::{Payload, PayloadConfig};
-pub use self::query::Query;
+pub use self::query::{Query, QueryConfig, QueryMulti};
+pub use self::trace::TraceContext;
+
+pub use crate::http::h1::{ConnExtensions, ConnStats};
@@ -0,0 +1,586 @@
+//! Multipart form extractor
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, future::Future, io, io::Write, ops, pin::Pin, str, str::FromStr};
+
+use mime::Mime;
+use nanorand::{Rng, WyRand};
+
+use crate::http::{header, HttpMessage, Payload};
+use crate::util::{Bytes, Either, Ready};
+use crate::web::error::{BlockingError, ErrorRenderer, MultipartError};
+use crate::web::{block, FromRequest, HttpRequest};
+
+/// Extract typed information from the `multipart/form-data` request body.
+///
+/// Form fields are mapped to `T` with the `FromMultipart` trait. Text
+/// fields are parsed with `FromStr`, uploaded files are stored to
+/// temporary files and are available as `TempFile`.
+///
+/// Whole body is loaded to memory before parsing, body size is limited
+/// with `PayloadConfig`, default limit is 256kB. Missing field or field
+/// that cannot be parsed results in *400 Bad Request* response.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, error, App};
+/// use ntex::web::types::{FromMultipart, MultipartFields, MultipartForm, TempFile};
+///
+/// struct Upload {
+///     title: String,
+///     file: TempFile,
+/// }
+///
+/// impl FromMultipart for Upload {
+///     fn from_multipart(
+///         fields: &mut MultipartFields,
+///     ) -> Result<Self, error::MultipartError> {
+///         Ok(Upload {
+///             title: fields.text("title")?,
+///             file: fields.file("file")?,
+///         })
+///     }
+/// }
+///
+/// async fn upload(form: MultipartForm<Upload>) -> String {
+///     format!("{}: {} bytes", form.title, form.file.size())
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/upload", web::post().to(upload));
+/// }
+/// ```
+pub struct MultipartForm<T>(pub T);
+
+impl<T> MultipartForm<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for MultipartForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for MultipartForm<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MultipartForm<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, Err> FromRequest<Err> for MultipartForm<T>
+where
+    T: FromMultipart + 'static,
+    Err: ErrorRenderer,
+{
+    type Error = MultipartError;
+    type Future = Either<
+        Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>,
+        Ready<Self, Self::Error>,
+    >;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let boundary = match boundary(req) {
+            Some(boundary) => boundary,
+            None => return Either::Right(Ready::Err(MultipartError::ContentType)),
+        };
+        let fut = <Bytes as FromRequest<Err>>::from_request(req, payload);
+        let path = req.path().to_string();
+
+        Either::Left(Box::pin(async move {
+            let res = async {
+                let body = fut.await?;
+                let mut fields = MultipartFields::default();
+                for part in parse(&body, &boundary)? {
+                    match part.file_name {
+                        Some(file_name) => {
+                            let file = TempFile::create(
+                                file_name,
+                                part.content_type,
+                                part.data,
+                            )
+                            .await?;
+                            fields.files.push((part.name, file));
+                        }
+                        None => fields.texts.push((part.name, part.data)),
+                    }
+                }
+                T::from_multipart(&mut fields)
+            }
+            .await;
+
+            match res {
+                Ok(item) => Ok(MultipartForm(item)),
+                Err(e) => {
+                    log::debug!(
+                        "Failed to deserialize MultipartForm from payload. \
+                         Request path: {}",
+                        path
+                    );
+                    Err(e)
+                }
+            }
+        }))
+    }
+}
+
+/// Construct a type from fields of the multipart form.
+pub trait FromMultipart: Sized {
+    /// Build value from form fields, fields are taken from the set.
+    fn from_multipart(fields: &mut MultipartFields) -> Result<Self, MultipartError>;
+}
+
+impl FromMultipart for MultipartFields {
+    fn from_multipart(fields: &mut MultipartFields) -> Result<Self, MultipartError> {
+        Ok(std::mem::take(fields))
+    }
+}
+
+/// Fields of the multipart form.
+///
+/// Parts with `filename` parameter are file fields, other parts are
+/// text fields. If form contains several fields with the same name,
+/// fields are taken in order.
+#[derive(Debug, Default)]
+pub struct MultipartFields {
+    texts: Vec<(String, Bytes)>,
+    files: Vec<(String, TempFile)>,
+}
+
+impl MultipartFields {
+    /// Take text field and parse it with `FromStr`.
+    pub fn text<T: FromStr>(&mut self, name: &str) -> Result<T, MultipartError> {
+        self.text_opt(name)?
+            .ok_or_else(|| MultipartError::MissingField(name.to_string()))
+    }
+
+    /// Take optional text field and parse it with `FromStr`.
+    pub fn text_opt<T: FromStr>(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<T>, MultipartError> {
+        match self.texts.iter().position(|(n, _)| n == name) {
+            Some(idx) => {
+                let (_, data) = self.texts.remove(idx);
+                str::from_utf8(&data)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .map(Some)
+                    .ok_or_else(|| MultipartError::Parse(name.to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Take uploaded file.
+    pub fn file(&mut self, name: &str) -> Result<TempFile, MultipartError> {
+        match self.files.iter().position(|(n, _)| n == name) {
+            Some(idx) => Ok(self.files.remove(idx).1),
+            None => Err(MultipartError::MissingField(name.to_string())),
+        }
+    }
+
+    /// Take all uploaded files with specified name.
+    pub fn files(&mut self, name: &str) -> Vec<TempFile> {
+        let (files, rest) = std::mem::take(&mut self.files)
+            .into_iter()
+            .partition(|(n, _)| n == name);
+        self.files = rest;
+        files.into_iter().map(|(_, file)| file).collect()
+    }
+}
+
+/// Uploaded file of the multipart form.
+///
+/// File is stored in the system temporary directory and is removed
+/// when `TempFile` is dropped, unless it is persisted.
+///
+/// Files are not streamed to disk, whole request body is loaded to memory
+/// and files are written after body is parsed. Upload size is limited by
+/// `PayloadConfig` limit, 256kB by default, i.e. use
+/// `.app_data(PayloadConfig::new(16 * 1024 * 1024))` for 16Mb uploads.
+pub struct TempFile {
+    path: PathBuf,
+    file_name: String,
+    content_type: Option<Mime>,
+    size: u64,
+    persisted: bool,
+}
+
+impl TempFile {
+    async fn create(
+        file_name: String,
+        content_type: Option<Mime>,
+        data: Bytes,
+    ) -> Result<TempFile, MultipartError> {
+        let size = data.len() as u64;
+        let res = block(move || {
+            let path = std::env::temp_dir().join(format!(
+                "ntex-upload-{:016x}",
+                WyRand::new().generate::<u64>()
+            ));
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            if let Err(e) = file.write_all(&data) {
+                let _ = fs::remove_file(&path);
+                return Err(e);
+            }
+            Ok(path)
+        })
+        .await;
+
+        match res {
+            Ok(path) => Ok(TempFile {
+                path,
+                file_name,
+                content_type,
+                size,
+                persisted: false,
+            }),
+            Err(BlockingError::Error(e)) => Err(MultipartError::Io(e)),
+            Err(BlockingError::Canceled) => Err(MultipartError::Io(io::Error::new(
+                io::ErrorKind::Other,
+                "Thread pool is gone",
+            ))),
+        }
+    }
+
+    /// Path of the temporary file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// File name sent by the client.
+    ///
+    /// Name is not sanitized and must not be used as a file system path.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Content type of the file part, if specified by the client
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// File size in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Move temporary file to a new location.
+    ///
+    /// This method blocks on file system operations.
+    pub fn persist<P: AsRef<Path>>(mut self, to: P) -> io::Result<()> {
+        let to = to.as_ref();
+        if fs::rename(&self.path, to).is_err() {
+            // temp dir could be on a different file system
+            fs::copy(&self.path, to)?;
+            let _ = fs::remove_file(&self.path);
+        }
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl fmt::Debug for TempFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TempFile")
+            .field("path", &self.path)
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Part of the multipart body
+struct Part {
+    name: String,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+    data: Bytes,
+}
+
+/// Boundary of `multipart/form-data` request
+fn boundary(req: &HttpRequest) -> Option<String> {
+    let mt = req.mime_type().ok()??;
+    if mt.type_() != mime::MULTIPART || mt.subtype() != mime::FORM_DATA {
+        return None;
+    }
+    mt.get_param(mime::BOUNDARY)
+        .map(|b| b.as_str().to_string())
+        .filter(|b| !b.is_empty())
+}
+
+fn parse(body: &Bytes, boundary: &str) -> Result<Vec<Part>, MultipartError> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+    let mut parts = Vec::new();
+
+    // skip preamble
+    let mut pos =
+        find(body, delimiter, 0).ok_or(MultipartError::Malformed)? + delimiter.len();
+    loop {
+        if body[pos..].starts_with(b"--") {
+            return Ok(parts);
+        } else if body[pos..].starts_with(b"\r\n") {
+            pos += 2;
+        } else {
+            return Err(MultipartError::Malformed);
+        }
+
+        let mut headers = [httparse::EMPTY_HEADER; 16];
+        let (len, headers) = match httparse::parse_headers(&body[pos..], &mut headers) {
+            Ok(httparse::Status::Complete(res)) => res,
+            _ => return Err(MultipartError::Malformed),
+        };
+        pos += len;
+
+        let mut disposition = None;
+        let mut content_type = None;
+        for hdr in headers.iter() {
+            let value = str::from_utf8(hdr.value).map_err(|_| MultipartError::Malformed);
+            if hdr
+                .name
+                .eq_ignore_ascii_case(header::CONTENT_DISPOSITION.as_str())
+            {
+                disposition = Some(content_disposition(value?)?);
+            } else if hdr.name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str()) {
+                content_type = value?.parse::<Mime>().ok();
+            }
+        }
+        let (name, file_name) = disposition.ok_or(MultipartError::Malformed)?;
+
+        let end = find(body, delimiter, pos)
+            .filter(|end| *end >= pos + 2 && &body[end - 2..*end] == b"\r\n")
+            .ok_or(MultipartError::Malformed)?;
+        parts.push(Part {
+            name,
+            file_name,
+            content_type,
+            data: body.slice(pos..end - 2),
+        });
+        pos = end + delimiter.len();
+    }
+}
+
+/// Find position of the needle in the haystack, starting from `from`
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|idx| idx + from)
+}
+
+/// Parse `name` and `filename` parameters of the `form-data` disposition
+fn content_disposition(value: &str) -> Result<(String, Option<String>), MultipartError> {
+    let (kind, mut rest) = match value.find(';') {
+        Some(idx) => (&value[..idx], &value[idx + 1..]),
+        None => (value, ""),
+    };
+    if !kind.trim().eq_ignore_ascii_case("form-data") {
+        return Err(MultipartError::Malformed);
+    }
+
+    let mut name = None;
+    let mut file_name = None;
+    while !rest.trim().is_empty() {
+        let idx = rest.find('=').ok_or(MultipartError::Malformed)?;
+        let param = rest[..idx].trim().to_ascii_lowercase();
+        let raw = rest[idx + 1..].trim_start();
+
+        let val;
+        if let Some(quoted) = raw.strip_prefix('"') {
+            // quoted string, backslash escapes next character
+            let mut s = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((_, '\\')) => {
+                        if let Some((_, c)) = chars.next() {
+                            s.push(c);
+                        }
+                    }
+                    Some((idx, '"')) => break idx,
+                    Some((_, c)) => s.push(c),
+                    None => return Err(MultipartError::Malformed),
+                }
+            };
+            val = s;
+            rest = quoted[end + 1..].trim_start();
+            rest = rest.strip_prefix(';').unwrap_or(rest);
+        } else {
+            let end = raw.find(';').unwrap_or(raw.len());
+            val = raw[..end].trim().to_string();
+            rest = raw.get(end + 1..).unwrap_or("");
+        }
+
+        match param.as_str() {
+            "name" => name = Some(val),
+            "filename" => file_name = Some(val),
+            _ => (),
+        }
+    }
+
+    name.map(|name| (name, file_name))
+        .ok_or(MultipartError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::CONTENT_TYPE;
+    use crate::http::StatusCode;
+    use crate::web::test::{call_service, from_request, init_service, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    #[derive(Debug)]
+    struct Upload {
+        title: String,
+        count: u32,
+        note: Option<String>,
+        file: TempFile,
+    }
+
+    impl FromMultipart for Upload {
+        fn from_multipart(fields: &mut MultipartFields) -> Result<Self, MultipartError> {
+            Ok(Upload {
+                title: fields.text("title")?,
+                count: fields.text("count")?,
+                note: fields.text_opt("note")?,
+                file: fields.file("file")?,
+            })
+        }
+    }
+
+    const BODY: &str = "preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        My \"file\"\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=count\r\n\
+        \r\n\
+        2\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a\\\"b;.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line1\r\nline2\r\n\
+        --XyZ--\r\n";
+
+    #[crate::rt_test]
+    async fn test_multipart_form() {
+        let (req, mut pl) = TestRequest::post()
+            .header(CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .set_payload(BODY)
+            .to_http_parts();
+        let form = from_request::<MultipartForm<Upload>>(&req, &mut pl)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(form.title, "My \"file\"");
+        assert_eq!(form.count, 2);
+        assert_eq!(form.note, None);
+        assert_eq!(form.file.file_name(), "a\"b;.txt");
+        assert_eq!(form.file.content_type(), Some(&mime::TEXT_PLAIN));
+        assert_eq!(form.file.size(), 12);
+
+        let path = form.file.path().to_path_buf();
+        assert_eq!(fs::read(&path).unwrap(), b"line1\r\nline2");
+        drop(form);
+        assert!(!path.exists());
+
+        // persisted file is kept
+        let (req, mut pl) = TestRequest::post()
+            .header(CONTENT_TYPE, "multipart/form-data; boundary=\"XyZ\"")
+            .set_payload(BODY)
+            .to_http_parts();
+        let mut fields = from_request::<MultipartForm<MultipartFields>>(&req, &mut pl)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            fields.text::<u32>("title").unwrap_err().to_string(),
+            "Cannot parse multipart field \"title\""
+        );
+        let files = fields.files("file");
+        assert_eq!(files.len(), 1);
+        let to = std::env::temp_dir().join(format!(
+            "ntex-test-{:016x}",
+            WyRand::new().generate::<u64>()
+        ));
+        let tmp = files[0].path().to_path_buf();
+        files.into_iter().next().unwrap().persist(&to).unwrap();
+        assert!(!tmp.exists());
+        assert_eq!(fs::read(&to).unwrap(), b"line1\r\nline2");
+        fs::remove_file(&to).unwrap();
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart_form_errors() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::post().to(|form: MultipartForm<Upload>| async move {
+                HttpResponse::Ok().body(form.title.clone())
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .set_payload(BODY)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // missing field
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .set_payload(
+                "--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nt\r\n--XyZ--",
+            )
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // unterminated body
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "multipart/form-data; boundary=XyZ")
+            .set_payload(
+                "--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nt",
+            )
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // wrong content type
+        let req = TestRequest::post()
+            .header(CONTENT_TYPE, "multipart/mixed; boundary=XyZ")
+            .set_payload(BODY)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,11 +1,13 @@
 //! Path extractor
-use std::{fmt, ops};
+use std::{fmt, ops, str::FromStr, sync::Arc};
 
 use serde::de;
 
+use crate::http::{Payload, Uri};
+use crate::router::{self, PathDeserializer};
+use crate::util::Ready;
 use crate::web::error::{ErrorRenderer, PathError};
 use crate::web::{FromRequest, HttpRequest};
-use crate::{http::Payload, router::PathDeserializer, util::Ready};
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 /// Extract typed information from the request's path.
@@ -158,6 +160,10 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Err(e) = validate(req) {
+            return Ready::Err(e);
+        }
+
         Ready::from(
             de::Deserialize::deserialize(PathDeserializer::new(req.match_info()))
                 .map(|inner| Path { inner })
@@ -173,6 +179,181 @@ where
     }
 }
 
+/// Extract single path segment parsed with `FromStr`.
+///
+/// Value of the first matched dynamic segment is parsed, segments of
+/// scope paths are matched first. Use `PathParam::named()` to parse
+/// segment by name, i.e. in resources with several dynamic segments.
+/// If value could not be parsed, extractor fails with *404 Not Found*.
+/// Use `guard::PathParam` guard to continue routing with next resource
+/// instead.
+///
+/// ```rust
+/// use ntex::web::{self, guard, types::PathParam, App};
+///
+/// async fn index(id: PathParam<u32>) -> String {
+///     format!("item {}", *id)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/item/{id}")
+///             .guard(guard::PathParam::<u32>("id"))
+///             .to(index),
+///     );
+/// }
+/// ```
+pub struct PathParam<T>(T);
+
+impl<T> PathParam<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: FromStr> PathParam<T> {
+    /// Parse value of the path segment with specified name.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, guard, types::PathParam, App, HttpRequest};
+    ///
+    /// async fn index(req: HttpRequest) -> Result<String, web::error::PathError> {
+    ///     let id = PathParam::<u32>::named(&req, "id")?;
+    ///     Ok(format!("item {}", *id))
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/{user}/item/{id}")
+    ///             .guard(guard::PathParam::<u32>("id"))
+    ///             .to(index),
+    ///     );
+    /// }
+    /// ```
+    pub fn named(req: &HttpRequest, name: &str) -> Result<Self, PathError> {
+        validate(req)?;
+        parse_param(req, req.match_info().get(name))
+    }
+}
+
+fn parse_param<T: FromStr>(
+    req: &HttpRequest,
+    val: Option<&str>,
+) -> Result<PathParam<T>, PathError> {
+    let res = if let Some(val) = val {
+        val.parse::<T>().map(PathParam).map_err(|_| {
+            PathError::Parse(format!(
+                "can not parse {:?} to {}",
+                val,
+                std::any::type_name::<T>()
+            ))
+        })
+    } else {
+        Err(PathError::Parse("path segment not found".to_string()))
+    };
+    if res.is_err() {
+        log::debug!(
+            "Failed during PathParam extractor parsing. Request path: {:?}",
+            req.path()
+        );
+    }
+    res
+}
+
+impl<T> ops::Deref for PathParam<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PathParam<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for PathParam<T>
+where
+    T: FromStr,
+{
+    type Error = PathError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Err(e) = validate(req) {
+            return Ready::Err(e);
+        }
+        let val = req.match_info().iter().next().map(|(_, val)| val);
+        Ready::from(parse_param(req, val))
+    }
+}
+
+/// Path extractor configuration
+///
+/// Validator inspects matched path segments before they are deserialized.
+/// If validator returns error, `Path` and `PathParam` extractors fail with
+/// *400 Bad Request*, error message is used as response body.
+///
+/// ```rust
+/// use ntex::web::{self, types::Path, App};
+///
+/// async fn index(id: Path<u32>) -> String {
+///     format!("item {}", *id)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/item/{id}")
+///             .app_data(web::types::PathConfig::default().validator(|path| {
+///                 match path.get("id").and_then(|id| id.parse::<u32>().ok()) {
+///                     Some(1..=1000) => Ok(()),
+///                     _ => Err("id must be in range 1..=1000".to_string()),
+///                 }
+///             }))
+///             .route(web::get().to(index)),
+///     );
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct PathConfig {
+    validator:
+        Option<Arc<dyn Fn(&router::Path<Uri>) -> Result<(), String> + Send + Sync>>,
+}
+
+impl PathConfig {
+    /// Set validator for matched path segments
+    pub fn validator<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&router::Path<Uri>) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validator = Some(Arc::new(f));
+        self
+    }
+}
+
+/// Run path validator if it is configured
+fn validate(req: &HttpRequest) -> Result<(), PathError> {
+    if let Some(validator) = req
+        .app_data::<PathConfig>()
+        .and_then(|c| c.validator.as_ref())
+    {
+        validator(req.match_info()).map_err(|e| {
+            log::debug!(
+                "Path validation failed: {}. Request path: {:?}",
+                e,
+                req.path()
+            );
+            PathError::Invalid(e)
+        })
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use derive_more::Display;
@@ -208,6 +389,94 @@ mod tests {
         assert!(from_request::<Path<MyStruct>>(&req, &mut pl).await.is_err());
     }
 
+    #[crate::rt_test]
+    async fn test_extract_path_param() {
+        let mut router = Router::<usize>::build();
+        router.path("/{value}/", 10).0.set_id(0);
+        let router = router.finish();
+
+        let mut req = TestRequest::with_uri("/32/").to_srv_request();
+        router.recognize(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+        let res = from_request::<PathParam<u8>>(&req, &mut pl).await.unwrap();
+        assert_eq!(res.into_inner(), 32);
+
+        let mut req = TestRequest::with_uri("/abc/").to_srv_request();
+        router.recognize(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+        let res = from_request::<PathParam<u8>>(&req, &mut pl).await;
+        assert!(matches!(res, Err(PathError::Parse(_))));
+
+        let (req, mut pl) = TestRequest::with_uri("/").to_http_parts();
+        let res = from_request::<PathParam<u8>>(&req, &mut pl).await;
+        assert!(res.is_err());
+    }
+
+    #[crate::rt_test]
+    async fn test_path_validator() {
+        use std::{cell::Cell, rc::Rc};
+
+        use crate::http::StatusCode;
+        use crate::util::Bytes;
+        use crate::web::test::{call_service, init_service, read_body};
+        use crate::web::{self, App};
+
+        let called = Rc::new(Cell::new(false));
+        let called2 = called.clone();
+        let srv = init_service(
+            App::new()
+                .app_data(PathConfig::default().validator(|path| {
+                    if path.get("id") == Some("0") {
+                        Err("id must not be 0".to_string())
+                    } else {
+                        Ok(())
+                    }
+                }))
+                .route(
+                    "/item/{id}",
+                    web::get().to(move |id: Path<u32>| {
+                        called2.set(true);
+                        async move { format!("item {}", *id) }
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/item/0").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"id must not be 0")
+        );
+        assert!(!called.get());
+
+        let req = TestRequest::with_uri("/item/10").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"item 10"));
+        assert!(called.get());
+    }
+
+    #[crate::rt_test]
+    async fn test_extract_path_optional_tail() {
+        let mut router = Router::<usize>::build();
+        router.path("/files/{path}*", 10).0.set_id(0);
+        let router = router.finish();
+
+        let mut req = TestRequest::with_uri("/files").to_srv_request();
+        router.recognize(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+        let res = from_request::<Path<String>>(&req, &mut pl).await.unwrap();
+        assert_eq!(res.as_str(), "");
+
+        let mut req = TestRequest::with_uri("/files/a/b/c").to_srv_request();
+        router.recognize(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+        let res = from_request::<Path<String>>(&req, &mut pl).await.unwrap();
+        assert_eq!(res.as_str(), "a/b/c");
+    }
+
     #[crate::rt_test]
     async fn test_tuple_extract() {
         let mut router = Router::<usize>::build();
@@ -1,11 +1,12 @@
 //! Payload/Bytes/String extractors
-use std::{future::Future, pin::Pin, str, task::Context, task::Poll};
+use std::{future::Future, io, pin::Pin, str, task::Context, task::Poll};
 
 use encoding_rs::UTF_8;
 use mime::Mime;
 
+use crate::codec::AsyncWrite;
 use crate::http::{error, header, HttpMessage};
-use crate::util::{next, Bytes, BytesMut, Either, Ready};
+use crate::util::{next, poll_fn, Bytes, BytesMut, Either, Ready};
 use crate::web::error::{ErrorRenderer, PayloadError};
 use crate::web::{FromRequest, HttpRequest};
 use crate::Stream;
@@ -46,6 +47,53 @@ impl Payload {
     pub fn into_inner(self) -> crate::http::Payload {
         self.0
     }
+
+    /// Write payload to an async writer.
+    ///
+    /// `progress` callback is called after each chunk is written with
+    /// the total number of bytes written so far. Payload size limit
+    /// is respected (see `Payload` extractor), payload that exceeds
+    /// limit fails with `PayloadError::Overflow` error. Returns total
+    /// number of written bytes.
+    ///
+    /// ```rust
+    /// use ntex::web::{self, error, types::Payload};
+    ///
+    /// async fn upload(body: Payload) -> Result<String, error::PayloadError> {
+    ///     let mut buf = Vec::new();
+    ///     let size = body
+    ///         .copy_to(&mut buf, |written| log::trace!("uploaded {} bytes", written))
+    ///         .await?;
+    ///     Ok(format!("uploaded {} bytes", size))
+    /// }
+    /// ```
+    pub async fn copy_to<W, F>(
+        mut self,
+        mut w: W,
+        mut progress: F,
+    ) -> Result<u64, error::PayloadError>
+    where
+        W: AsyncWrite + Unpin,
+        F: FnMut(u64),
+    {
+        let mut written = 0u64;
+        while let Some(chunk) = next(&mut self).await {
+            let mut chunk = chunk?;
+            while !chunk.is_empty() {
+                let n = poll_fn(|cx| Pin::new(&mut w).poll_write(cx, &chunk)).await?;
+                if n == 0 {
+                    return Err(error::PayloadError::Io(
+                        io::ErrorKind::WriteZero.into(),
+                    ));
+                }
+                let _ = chunk.split_to(n);
+                written += n as u64;
+            }
+            progress(written);
+        }
+        poll_fn(|cx| Pin::new(&mut w).poll_flush(cx)).await?;
+        Ok(written)
+    }
 }
 
 impl Stream for Payload {
@@ -62,6 +110,11 @@ impl Stream for Payload {
 
 /// Get request's payload stream
 ///
+/// Unlike `Bytes` and `String` extractors, payload stream is not limited
+/// by default. Limit is applied only if `App::payload_limit()` is set,
+/// `PayloadConfig` does not affect payload stream. Stream that exceeds
+/// limit fails with `PayloadError::Overflow` error.
+///
 /// ## Example
 ///
 /// ```rust
@@ -94,10 +147,21 @@ impl<Err: ErrorRenderer> FromRequest<Err> for Payload {
 
     #[inline]
     fn from_request(
-        _: &HttpRequest,
+        req: &HttpRequest,
         payload: &mut crate::http::Payload,
     ) -> Self::Future {
-        Ready::Ok(Payload(payload.take()))
+        if let Some(limit) = PayloadLimit::get(req) {
+            let stream = Limited {
+                limit,
+                size: 0,
+                stream: payload.take(),
+            };
+            Ready::Ok(Payload(crate::http::Payload::from(
+                Box::pin(stream) as crate::http::PayloadStream
+            )))
+        } else {
+            Ready::Ok(Payload(payload.take()))
+        }
     }
 }
 
@@ -141,7 +205,7 @@ impl<Err: ErrorRenderer> FromRequest<Err> for Bytes {
         let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
             cfg
         } else {
-            tmp = PayloadConfig::default();
+            tmp = PayloadConfig::from_app(req);
             &tmp
         };
 
@@ -198,7 +262,7 @@ impl<Err: ErrorRenderer> FromRequest<Err> for String {
         let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
             cfg
         } else {
-            tmp = PayloadConfig::default();
+            tmp = PayloadConfig::from_app(req);
             &tmp
         };
 
@@ -234,7 +298,7 @@ impl<Err: ErrorRenderer> FromRequest<Err> for String {
 /// Payload configuration for request's payload.
 #[derive(Clone, Debug)]
 pub struct PayloadConfig {
-    limit: usize,
+    pub(super) limit: usize,
     mimetype: Option<Mime>,
 }
 
@@ -253,6 +317,15 @@ impl PayloadConfig {
         self
     }
 
+    /// Default config, max size is set by `App::payload_limit()` if any
+    fn from_app(req: &HttpRequest) -> Self {
+        let mut cfg = PayloadConfig::default();
+        if let Some(limit) = PayloadLimit::get(req) {
+            cfg.limit = limit;
+        }
+        cfg
+    }
+
     /// Set required mime-type of the request. By default mime type is not
     /// enforced.
     pub fn mimetype(mut self, mt: Mime) -> Self {
@@ -260,7 +333,7 @@ impl PayloadConfig {
         self
     }
 
-    fn check_mimetype(&self, req: &HttpRequest) -> Result<(), PayloadError> {
+    pub(super) fn check_mimetype(&self, req: &HttpRequest) -> Result<(), PayloadError> {
         // check content-type
         if let Some(ref mt) = self.mimetype {
             match req.mime_type() {
@@ -292,6 +365,50 @@ impl Default for PayloadConfig {
     }
 }
 
+/// Application-wide max size of payload, set by `App::payload_limit()`
+///
+/// Used by payload extractors if more specific config is not set.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct PayloadLimit(pub(crate) usize);
+
+impl PayloadLimit {
+    pub(crate) fn get(req: &HttpRequest) -> Option<usize> {
+        req.app_data::<PayloadLimit>().map(|limit| limit.0)
+    }
+}
+
+/// Payload stream that fails if size of payload exceeds limit
+struct Limited {
+    stream: crate::http::Payload,
+    limit: usize,
+    size: usize,
+}
+
+impl Stream for Limited {
+    type Item = Result<Bytes, error::PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.size > self.limit {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut self.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.size += chunk.len();
+                if self.size > self.limit {
+                    Poll::Ready(Some(Err(error::PayloadError::Overflow)))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            res => res,
+        }
+    }
+}
+
 /// Future that resolves to a complete http message body.
 ///
 /// Load http message body.
@@ -407,9 +524,12 @@ impl Future for HttpMessageBody {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http::header;
+    use crate::http::{header, StatusCode};
     use crate::util::Bytes;
-    use crate::web::test::{from_request, TestRequest};
+    use crate::web::test::{
+        call_service, from_request, init_service, read_body, TestRequest,
+    };
+    use crate::web::{self, App, HttpResponse};
 
     #[crate::rt_test]
     async fn test_payload_config() {
@@ -517,4 +637,100 @@ mod tests {
             _ => unreachable!("error"),
         }
     }
+
+    #[crate::rt_test]
+    async fn test_payload_copy_to() {
+        let (mut sender, payload) = crate::http::h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"1111"));
+        sender.feed_data(Bytes::from_static(b"2222"));
+        sender.feed_data(Bytes::from_static(b"33"));
+        sender.feed_eof();
+
+        let mut buf = Vec::new();
+        let mut calls = Vec::new();
+        let size = Payload(crate::http::Payload::from(payload))
+            .copy_to(&mut buf, |written| calls.push(written))
+            .await
+            .unwrap();
+        assert_eq!(size, 10);
+        assert_eq!(buf, b"1111222233");
+        assert_eq!(calls, vec![4, 8, 10]);
+
+        // size limit
+        let srv = init_service(App::new().payload_limit(8).route(
+            "/",
+            web::post().to(|body: Payload| async move {
+                let mut buf = Vec::new();
+                match body.copy_to(&mut buf, |_| ()).await {
+                    Ok(size) => HttpResponse::Ok().body(format!("{}", size)),
+                    Err(_) => HttpResponse::PayloadTooLarge().finish(),
+                }
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload(Bytes::from_static(b"11112222"))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"8"));
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload(Bytes::from_static(b"111122223"))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // payload config does not limit stream
+        let srv = init_service(App::new().app_data(PayloadConfig::new(4)).route(
+            "/",
+            web::post().to(|body: Payload| async move {
+                let mut buf = Vec::new();
+                match body.copy_to(&mut buf, |_| ()).await {
+                    Ok(size) => HttpResponse::Ok().body(format!("{}", size)),
+                    Err(_) => HttpResponse::PayloadTooLarge().finish(),
+                }
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload(Bytes::from_static(b"11112"))
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"5"));
+    }
+
+    #[crate::rt_test]
+    async fn test_message_body_chunked() {
+        let req = TestRequest::with_header(header::TRANSFER_ENCODING, "chunked")
+            .to_http_request();
+
+        // decoded bytes within limit
+        let (mut sender, payload) = crate::http::h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"1111"));
+        sender.feed_data(Bytes::from_static(b"2222"));
+        sender.feed_eof();
+        let mut pl = crate::http::Payload::from(payload);
+        let res = HttpMessageBody::new(&req, &mut pl).limit(8).await;
+        assert_eq!(res.ok().unwrap(), Bytes::from_static(b"11112222"));
+
+        // cumulative size of chunks exceeds limit
+        let (mut sender, payload) = crate::http::h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"1111"));
+        sender.feed_data(Bytes::from_static(b"2222"));
+        sender.feed_data(Bytes::from_static(b"3"));
+        sender.feed_eof();
+        let mut pl = crate::http::Payload::from(payload);
+        let res = HttpMessageBody::new(&req, &mut pl).limit(8).await;
+        match res.err().unwrap() {
+            PayloadError::Payload(error::PayloadError::Overflow) => (),
+            _ => unreachable!("error"),
+        }
+    }
 }
@@ -133,6 +133,20 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let limit = req
+            .app_data::<QueryConfig>()
+            .map(|c| c.limit)
+            .unwrap_or(DEFAULT_LIMIT);
+
+        if pairs_overflow(req.query_string(), limit) {
+            log::debug!(
+                "Query has more than {} parameters. Request path: {:?}",
+                limit,
+                req.path()
+            );
+            return Ready::Err(QueryPayloadError::Overflow { limit });
+        }
+
         serde_urlencoded::from_str::<T>(req.query_string())
             .map(|val| Ready::Ok(Query(val)))
             .unwrap_or_else(move |e| {
@@ -148,12 +162,408 @@ where
     }
 }
 
+/// Extract typed information from the request's query, repeated keys
+/// are collected.
+///
+/// Unlike `Query`, keys could be repeated, i.e. `?tag=a&tag=b`, values of
+/// repeated keys are deserialized into sequence fields like `Vec<String>`.
+/// Sequence field also accepts single value, missing key deserializes to
+/// empty sequence only if field has `#[serde(default)]` attribute. Repeated
+/// key of a non-sequence field is a deserialization error. Empty values,
+/// i.e. `?tag=&tag=b` or `?tag`, are deserialized as empty strings.
+/// Extraction process is configured with `QueryConfig`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web;
+///
+/// #[derive(serde::Deserialize)]
+/// pub struct Filter {
+///    #[serde(default)]
+///    tag: Vec<String>,
+///    limit: u32,
+/// }
+///
+/// // The correct request for this handler would be `/items?tag=a&tag=b&limit=10`
+/// async fn index(filter: web::types::QueryMulti<Filter>) -> String {
+///     format!("tags: {:?}, limit: {}", filter.tag, filter.limit)
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().route("/items", web::get().to(index));
+/// }
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub struct QueryMulti<T>(pub T);
+
+impl<T> QueryMulti<T> {
+    /// Deconstruct to a inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// Get query parameters from the query string
+    pub fn from_query(query_str: &str) -> Result<Self, QueryPayloadError>
+    where
+        T: de::DeserializeOwned,
+    {
+        T::deserialize(multi::QueryDeserializer::new(query_str))
+            .map(QueryMulti)
+            .map_err(QueryPayloadError::Deserialize)
+    }
+}
+
+impl<T> ops::Deref for QueryMulti<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for QueryMulti<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for QueryMulti<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, Err> FromRequest<Err> for QueryMulti<T>
+where
+    T: de::DeserializeOwned,
+    Err: ErrorRenderer,
+{
+    type Error = QueryPayloadError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let limit = req
+            .app_data::<QueryConfig>()
+            .map(|c| c.limit)
+            .unwrap_or(DEFAULT_LIMIT);
+
+        if pairs_overflow(req.query_string(), limit) {
+            log::debug!(
+                "Query has more than {} parameters. Request path: {:?}",
+                limit,
+                req.path()
+            );
+            return Ready::Err(QueryPayloadError::Overflow { limit });
+        }
+
+        match QueryMulti::from_query(req.query_string()) {
+            Ok(val) => Ready::Ok(val),
+            Err(e) => {
+                log::debug!(
+                    "Failed during QueryMulti extractor deserialization. \
+                     Request path: {:?}",
+                    req.path()
+                );
+                Ready::Err(e)
+            }
+        }
+    }
+}
+
+/// Default max number of query parameters
+const DEFAULT_LIMIT: usize = 1024;
+
+/// Query extractor configuration
+///
+/// ```rust
+/// use ntex::web::{self, App};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// /// Custom configuration is used for this handler,
+/// /// query could contain at most 16 parameters
+/// async fn index(info: web::types::Query<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             // change `Query` extractor configuration
+///             .app_data(web::types::QueryConfig::default().limit(16))
+///             .route(web::get().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct QueryConfig {
+    limit: usize,
+}
+
+impl QueryConfig {
+    /// Change max number of query parameters. By default max number is 1024
+    ///
+    /// Requests with more parameters get *400 Bad Request* response,
+    /// query is not deserialized.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        QueryConfig {
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+/// Check if query contains more than `limit` key-value pairs
+fn pairs_overflow(query: &str, limit: usize) -> bool {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .nth(limit)
+        .is_some()
+}
+
+mod multi {
+    //! Deserializer of query pairs with repeated keys
+    use percent_encoding::percent_decode_str;
+    use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+    use serde::de::{self, Error as _, IntoDeserializer, Visitor};
+
+    /// Query deserializer, values of repeated keys are grouped
+    pub(super) struct QueryDeserializer(Vec<(String, Values)>);
+
+    impl QueryDeserializer {
+        pub(super) fn new(query: &str) -> Self {
+            let mut pairs: Vec<(String, Values)> = Vec::new();
+            for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+                let (key, val) = match pair.find('=') {
+                    Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+                    None => (pair, ""),
+                };
+                let (key, val) = (decode(key), decode(val));
+                match pairs.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, values)) => values.0.push(val),
+                    None => pairs.push((key, Values(vec![val]))),
+                }
+            }
+            QueryDeserializer(pairs)
+        }
+    }
+
+    /// Decode `application/x-www-form-urlencoded` component
+    fn decode(val: &str) -> String {
+        let val = val.replace('+', " ");
+        percent_decode_str(&val).decode_utf8_lossy().into_owned()
+    }
+
+    impl<'de> de::Deserializer<'de> for QueryDeserializer {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_map(MapDeserializer::new(
+                self.0.into_iter().map(|(k, v)| (Part(k), v)),
+            ))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    /// Values of the key
+    struct Values(Vec<String>);
+
+    impl Values {
+        fn single(mut self) -> Result<Part, Error> {
+            if self.0.len() == 1 {
+                Ok(Part(self.0.pop().unwrap()))
+            } else {
+                Err(Error::custom("duplicate query parameter"))
+            }
+        }
+    }
+
+    impl<'de> IntoDeserializer<'de, Error> for Values {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    macro_rules! single_value {
+        ($($method:ident)*) => {
+            $(
+                fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                    self.single()?.$method(visitor)
+                }
+            )*
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Values {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            if self.0.len() == 1 {
+                self.single()?.deserialize_any(visitor)
+            } else {
+                self.deserialize_seq(visitor)
+            }
+        }
+
+        fn deserialize_seq<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_seq(SeqDeserializer::new(self.0.into_iter().map(Part)))
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            self.single()?.deserialize_enum(name, variants, visitor)
+        }
+
+        single_value! {
+            deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+            deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32
+            deserialize_u64 deserialize_f32 deserialize_f64 deserialize_char
+            deserialize_str deserialize_string
+        }
+
+        serde::forward_to_deserialize_any! {
+            i128 u128 bytes byte_buf unit unit_struct tuple tuple_struct map struct
+            identifier ignored_any
+        }
+    }
+
+    /// Single decoded key or value
+    struct Part(String);
+
+    impl<'de> IntoDeserializer<'de, Error> for Part {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    macro_rules! parse_value {
+        ($($method:ident => $visit:ident,)*) => {
+            $(
+                fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                    match self.0.parse() {
+                        Ok(val) => visitor.$visit(val),
+                        Err(e) => Err(Error::custom(e)),
+                    }
+                }
+            )*
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Part {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_string(self.0)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            self.0
+                .into_deserializer()
+                .deserialize_enum(name, variants, visitor)
+        }
+
+        parse_value! {
+            deserialize_bool => visit_bool,
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_f32 => visit_f32,
+            deserialize_f64 => visit_f64,
+            deserialize_char => visit_char,
+        }
+
+        serde::forward_to_deserialize_any! {
+            i128 u128 str string bytes byte_buf unit unit_struct seq tuple
+            tuple_struct map struct identifier ignored_any
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use derive_more::Display;
 
     use super::*;
+    use crate::http::StatusCode;
     use crate::web::test::{from_request, TestRequest};
+    use crate::web::{DefaultError, HttpResponse, WebResponseError};
 
     #[derive(serde::Deserialize, Debug, Display)]
     struct Id {
@@ -194,4 +604,98 @@ mod tests {
         let s = s.into_inner();
         assert_eq!(s.id, "test1");
     }
+
+    #[crate::rt_test]
+    async fn test_query_config() {
+        #[derive(serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Params {
+            id: String,
+        }
+
+        let uri = format!(
+            "/?id=test&{}",
+            (0..2000)
+                .map(|i| format!("p{}=v", i))
+                .collect::<Vec<_>>()
+                .join("&")
+        );
+        let (req, mut pl) = TestRequest::with_uri(&uri).to_http_parts();
+        let res = from_request::<Query<Params>>(&req, &mut pl).await;
+        assert!(matches!(
+            res,
+            Err(QueryPayloadError::Overflow { limit: 1024 })
+        ));
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&res.err().unwrap(), &req);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let (req, mut pl) = TestRequest::with_uri("/?id=test&a=1&&b=2&c=3")
+            .data(QueryConfig::default().limit(4))
+            .to_http_parts();
+        let res = from_request::<Query<Params>>(&req, &mut pl).await;
+        assert_eq!(res.unwrap().id, "test");
+
+        let (req, mut pl) = TestRequest::with_uri("/?id=test&a=1&b=2&c=3&d=4")
+            .data(QueryConfig::default().limit(4))
+            .to_http_parts();
+        let res = from_request::<Query<Params>>(&req, &mut pl).await;
+        assert!(matches!(res, Err(QueryPayloadError::Overflow { limit: 4 })));
+    }
+
+    #[crate::rt_test]
+    async fn test_query_multi() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Params {
+            tag: Vec<String>,
+            x: u32,
+        }
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Opts {
+            #[serde(default)]
+            tag: Vec<String>,
+            ids: Option<Vec<u32>>,
+            name: Option<String>,
+        }
+
+        let (req, mut pl) = TestRequest::with_uri("/?tag=a&tag=b&x=1").to_http_parts();
+        let res = from_request::<QueryMulti<Params>>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(
+            res.into_inner(),
+            Params {
+                tag: vec!["a".to_string(), "b".to_string()],
+                x: 1
+            }
+        );
+
+        // single value of sequence field, empty values
+        let res = QueryMulti::<Params>::from_query("x=2&tag=a+b%21&tag=&tag").unwrap();
+        assert_eq!(res.tag, vec!["a b!", "", ""]);
+        let res = QueryMulti::<Params>::from_query("tag=a&x=2").unwrap();
+        assert_eq!(res.tag, vec!["a"]);
+
+        let res = QueryMulti::<Opts>::from_query("ids=1&ids=2&name=").unwrap();
+        assert_eq!(
+            res.0,
+            Opts {
+                tag: vec![],
+                ids: Some(vec![1, 2]),
+                name: Some(String::new())
+            }
+        );
+
+        // repeated key of non-sequence field
+        assert!(QueryMulti::<Params>::from_query("tag=a&x=1&x=2").is_err());
+        assert!(QueryMulti::<Params>::from_query("tag=a&x=a").is_err());
+        assert!(QueryMulti::<Params>::from_query("x=1").is_err());
+
+        let (req, mut pl) = TestRequest::with_uri("/?tag=a&tag=b&x=1")
+            .data(QueryConfig::default().limit(2))
+            .to_http_parts();
+        let res = from_request::<QueryMulti<Params>>(&req, &mut pl).await;
+        assert!(matches!(res, Err(QueryPayloadError::Overflow { limit: 2 })));
+    }
 }
@@ -0,0 +1,56 @@
+//! Connection statistics extractor
+use crate::http::{h1::ConnStats, Payload};
+use crate::util::Ready;
+use crate::web::error::{ConnStatsError, ErrorRenderer};
+use crate::web::{FromRequest, HttpRequest};
+
+/// Extract statistics of the current connection.
+///
+/// Statistics are available for http/1 connections only, for other
+/// connections extractor fails with *Internal Server Error* response,
+/// `Option<ConnStats>` could be used instead.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::ConnStats};
+///
+/// async fn index(stats: ConnStats) -> String {
+///     format!("Requests on connection: {}", stats.requests())
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::get().to(index))
+///     );
+/// }
+/// ```
+impl<Err: ErrorRenderer> FromRequest<Err> for ConnStats {
+    type Error = ConnStatsError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(stats) = req.conn_stats() {
+            Ready::Ok(stats.clone())
+        } else {
+            Ready::Err(ConnStatsError::NotAvailable)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{from_request, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_conn_stats_not_available() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = from_request::<ConnStats>(&req, &mut pl).await;
+        assert!(matches!(res, Err(ConnStatsError::NotAvailable)));
+
+        let res = from_request::<Option<ConnStats>>(&req, &mut pl).await;
+        assert!(res.unwrap().is_none());
+    }
+}
@@ -0,0 +1,294 @@
+//! W3C trace context extractor
+use std::fmt;
+
+use nanorand::{Rng, WyRand};
+
+use crate::http::{HeaderMap, Payload};
+use crate::util::Ready;
+use crate::web::error::{DataExtractorError, ErrorRenderer};
+use crate::web::{FromRequest, HttpRequest};
+
+/// `traceparent` header name
+pub(in crate::web) const TRACEPARENT: &str = "traceparent";
+/// `tracestate` header name
+pub(in crate::web) const TRACESTATE: &str = "tracestate";
+
+/// W3C trace context of the request.
+///
+/// Trace context is parsed from `traceparent` and `tracestate` request headers
+/// or generated if headers are absent or invalid. Each request gets a new
+/// span id, incoming span id is available via `parent_id()` method.
+/// Trace context is available to handlers if `middleware::TracePropagation`
+/// is registered, otherwise extractor fails with *Internal Server Error*.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, middleware, types::TraceContext, App};
+///
+/// async fn index(ctx: TraceContext) -> String {
+///     // `ctx.traceparent()` could be used for outbound calls
+///     format!("trace: {}", ctx.trace_id())
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::TracePropagation::new())
+///         .route("/index.html", web::get().to(index));
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    parent_id: Option<u64>,
+    flags: u8,
+    state: Option<String>,
+}
+
+impl TraceContext {
+    /// Generate new trace context.
+    pub fn new() -> Self {
+        let mut rng = WyRand::new();
+        let trace_id = loop {
+            let id = (u128::from(rng.generate::<u64>()) << 64)
+                | u128::from(rng.generate::<u64>());
+            if id != 0 {
+                break id;
+            }
+        };
+
+        TraceContext {
+            trace_id,
+            span_id: new_span_id(&mut rng),
+            parent_id: None,
+            flags: 0x01,
+            state: None,
+        }
+    }
+
+    /// Parse trace context from request headers.
+    ///
+    /// Returns `None` if `traceparent` header is absent or invalid.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut ctx = headers
+            .get(TRACEPARENT)
+            .and_then(|val| val.to_str().ok())
+            .and_then(Self::parse)?;
+        ctx.state = headers
+            .get(TRACESTATE)
+            .and_then(|val| val.to_str().ok())
+            .map(|val| val.trim().to_string())
+            .filter(|val| !val.is_empty());
+        Some(ctx)
+    }
+
+    /// Parse `traceparent` header value.
+    ///
+    /// New span id is generated, parsed parent id is available via
+    /// `parent_id()` method.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let mut parts = value.splitn(5, '-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+
+        if version.len() != 2
+            || trace_id.len() != 32
+            || parent_id.len() != 16
+            || flags.len() != 2
+        {
+            return None;
+        }
+        let version = parse_hex(version)?;
+        let trace_id = parse_hex(trace_id)?;
+        let parent_id = parse_hex(parent_id)? as u64;
+        let flags = parse_hex(flags)? as u8;
+
+        // version 255 is invalid, version 0 does not allow extra fields
+        if version == 0xff || (version == 0 && parts.next().is_some()) {
+            return None;
+        }
+        if trace_id == 0 || parent_id == 0 {
+            return None;
+        }
+
+        Some(TraceContext {
+            trace_id,
+            span_id: new_span_id(&mut WyRand::new()),
+            parent_id: Some(parent_id),
+            flags,
+            state: None,
+        })
+    }
+
+    /// Trace id, 32 lowercase hex characters.
+    pub fn trace_id(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    /// Span id of the current request, 16 lowercase hex characters.
+    pub fn span_id(&self) -> String {
+        format!("{:016x}", self.span_id)
+    }
+
+    /// Span id of the caller, if trace context is received from caller.
+    pub fn parent_id(&self) -> Option<String> {
+        self.parent_id.map(|id| format!("{:016x}", id))
+    }
+
+    /// Check if trace is sampled.
+    pub fn sampled(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    /// Trace state, value of `tracestate` header.
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// Value of `traceparent` header for the current span.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.span_id, self.flags
+        )
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        TraceContext::new()
+    }
+}
+
+impl fmt::Debug for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceContext")
+            .field("trace_id", &self.trace_id())
+            .field("span_id", &self.span_id())
+            .field("parent_id", &self.parent_id())
+            .field("sampled", &self.sampled())
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for TraceContext {
+    type Error = DataExtractorError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(ctx) = req.extensions().get::<TraceContext>() {
+            Ready::Ok(ctx.clone())
+        } else {
+            log::debug!(
+                "Failed to construct TraceContext extractor, \
+                 TracePropagation middleware is not registered. \
+                 Request path: {:?}",
+                req.path()
+            );
+            Ready::Err(DataExtractorError::NotConfigured)
+        }
+    }
+}
+
+fn new_span_id(rng: &mut WyRand) -> u64 {
+    loop {
+        let id = rng.generate::<u64>();
+        if id != 0 {
+            return id;
+        }
+    }
+}
+
+/// Parse lowercase hex value
+fn parse_hex(val: &str) -> Option<u128> {
+    if val
+        .bytes()
+        .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    {
+        u128::from_str_radix(val, 16).ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{HeaderName, HeaderValue};
+
+    #[test]
+    fn test_parse() {
+        let ctx = TraceContext::parse(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        )
+        .unwrap();
+        assert_eq!(ctx.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(ctx.parent_id().unwrap(), "00f067aa0ba902b7");
+        assert_ne!(ctx.span_id(), "00f067aa0ba902b7");
+        assert!(ctx.sampled());
+        assert!(ctx
+            .traceparent()
+            .starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(ctx.traceparent().ends_with("-01"));
+
+        // future versions could have extra fields
+        let ctx = TraceContext::parse(
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra",
+        )
+        .unwrap();
+        assert!(!ctx.sampled());
+
+        for val in &[
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-+bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            assert!(TraceContext::parse(val).is_none(), "{}", val);
+        }
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(TraceContext::from_headers(&headers).is_none());
+
+        headers.insert(
+            HeaderName::from_static(TRACEPARENT),
+            HeaderValue::from_static(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            ),
+        );
+        headers.insert(
+            HeaderName::from_static(TRACESTATE),
+            HeaderValue::from_static("congo=t61rcWkgMzE"),
+        );
+        let ctx = TraceContext::from_headers(&headers).unwrap();
+        assert_eq!(ctx.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(ctx.state(), Some("congo=t61rcWkgMzE"));
+    }
+
+    #[test]
+    fn test_new() {
+        let ctx = TraceContext::new();
+        assert!(ctx.parent_id().is_none());
+        assert!(ctx.state().is_none());
+        assert!(ctx.sampled());
+
+        // generated context must be valid
+        let parsed = TraceContext::parse(&ctx.traceparent()).unwrap();
+        assert_eq!(parsed.trace_id(), ctx.trace_id());
+        assert_eq!(parsed.parent_id().unwrap(), ctx.span_id());
+    }
+}
@@ -1,24 +1,29 @@
 //! Essentials helper functions and types for application registration.
-use std::fmt;
+use std::path::{Path, PathBuf};
+use std::task::{Context, Poll};
+use std::{error::Error, fmt, fs, io, pin::Pin, rc::Rc};
 
 use ntex_router::IntoPattern;
 
-use crate::http::body::MessageBody;
+use crate::http::body::{Body, Chunk, FlushableStream, MessageBody};
 use crate::http::error::{BlockingError, ResponseError};
 use crate::http::header::ContentEncoding;
 use crate::http::{Method, Request, Response};
-use crate::{IntoServiceFactory, Service, ServiceFactory};
+use crate::util::Bytes;
+use crate::{IntoServiceFactory, Service, ServiceFactory, Stream};
 
 use super::config::AppConfig;
 use super::error::ErrorRenderer;
 use super::extract::FromRequest;
+use super::guard;
 use super::handler::Handler;
+use super::httprequest::HttpRequest;
 use super::resource::Resource;
 use super::responder::Responder;
 use super::route::Route;
 use super::scope::Scope;
 use super::server::HttpServer;
-use super::service::WebServiceAdapter;
+use super::service::{WebServiceAdapter, WebServiceFactory, WithRenderer};
 use super::{HttpResponse, HttpResponseBuilder};
 
 /// Create resource for a specific path.
@@ -253,8 +258,131 @@ pub fn service<T: IntoPattern>(path: T) -> WebServiceAdapter {
     WebServiceAdapter::new(path)
 }
 
+/// Render errors of the service with different error renderer.
+///
+/// Application's error renderer type is used for all services by default.
+/// Services registered with `with_renderer()`, including nested services
+/// and middlewares, use `err` renderer type instead. Errors of the
+/// application's default service are rendered with application's renderer.
+/// Request's head is copied for each request, so errors could be rendered
+/// after request is consumed by the service.
+///
+/// ```rust
+/// use ntex::web::{self, App, DefaultError, HttpResponse};
+///
+/// let app = App::new().service(web::with_renderer(
+///     DefaultError,
+///     web::scope("/api")
+///         .route("/index.html", web::get().to(|| async { HttpResponse::Ok() })),
+/// ));
+/// ```
+pub fn with_renderer<Err, F>(err: Err, service: F) -> WithRenderer<Err, F>
+where
+    Err: ErrorRenderer,
+    F: WebServiceFactory<Err>,
+{
+    WithRenderer::new(err, service)
+}
+
+/// Create resource for serving Well-Known URIs from a directory.
+///
+/// Requests to `/.well-known/{path}` are served with files from `dir`,
+/// i.e. `/.well-known/acme-challenge/token` is served with file
+/// `dir/acme-challenge/token`. Only `GET` and `HEAD` requests are handled,
+/// *Not Found* response is returned if file does not exist.
+///
+/// Use `middleware::Exempt` to bypass global redirect or auth middlewares.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App};
+///
+/// let app = App::new()
+///     .wrap(middleware::Exempt::new(middleware::Logger::default()).well_known())
+///     .service(web::well_known("/var/www/.well-known"));
+/// ```
+pub fn well_known<P: AsRef<Path>, Err: ErrorRenderer>(dir: P) -> Resource<Err> {
+    let dir = Rc::new(dir.as_ref().to_path_buf());
+
+    Resource::new("/.well-known/{path}*").route(
+        route()
+            .guard(guard::Any(guard::Get()).or(guard::Head()))
+            .to(move |req: HttpRequest| {
+                let dir = dir.clone();
+                async move {
+                    let path =
+                        match well_known_path(&dir, req.match_info().query("path")) {
+                            Some(path) => path,
+                            None => return HttpResponse::NotFound().finish(),
+                        };
+
+                    let ct = match path.extension().and_then(|ext| ext.to_str()) {
+                        Some("txt") => "text/plain; charset=utf-8",
+                        Some("json") => "application/json",
+                        _ => "application/octet-stream",
+                    };
+                    match block(move || {
+                        if path.is_file() {
+                            fs::read(path)
+                        } else {
+                            Err(io::Error::from(io::ErrorKind::NotFound))
+                        }
+                    })
+                    .await
+                    {
+                        Ok(data) => HttpResponse::Ok().content_type(ct).body(data),
+                        Err(BlockingError::Error(e))
+                            if e.kind() == io::ErrorKind::NotFound =>
+                        {
+                            HttpResponse::NotFound().finish()
+                        }
+                        Err(e) => {
+                            log::error!("Cannot read well-known file: {:?}", e);
+                            HttpResponse::InternalServerError().finish()
+                        }
+                    }
+                }
+            }),
+    )
+}
+
+/// Build file path for well-known uri, path must not escape the directory
+fn well_known_path(dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut result = dir.to_path_buf();
+    let mut empty = true;
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            _ if segment.contains('\\') || segment.contains('\0') => return None,
+            _ => {
+                result.push(segment);
+                empty = false;
+            }
+        }
+    }
+    if empty {
+        None
+    } else {
+        Some(result)
+    }
+}
+
 /// Execute blocking function on a thread pool, returns future that resolves
 /// to result of the function execution.
+///
+/// Each worker uses its own blocking thread pool, max size of the pool
+/// could be configured with `ntex::rt::System::build().blocking_threads()`.
+///
+/// ```rust
+/// use ntex::web::{self, HttpResponse};
+///
+/// async fn index() -> Result<HttpResponse, web::Error> {
+///     // run blocking computation without stalling worker thread
+///     let res = web::block(|| Ok::<_, std::io::Error>((1..=10u64).product::<u64>()))
+///         .await?;
+///     Ok(HttpResponse::Ok().body(res.to_string()))
+/// }
+/// ```
 pub async fn block<F, I, E>(f: F) -> Result<I, BlockingError<E>>
 where
     F: FnOnce() -> Result<I, E> + Send + 'static,
@@ -267,6 +395,81 @@ where
     }
 }
 
+/// Create streaming response.
+///
+/// Each stream item is sent to the peer as a separate chunk, response uses
+/// chunked transfer encoding. Next item is requested from the stream only
+/// after previous item is flushed to the peer, so stream is not polled
+/// while peer is not able to receive more data. This is useful for
+/// server-sent events.
+///
+/// ```rust
+/// use ntex::{util::Bytes, web};
+///
+/// async fn events() -> web::HttpResponse {
+///     let items = vec![
+///         Ok::<_, std::io::Error>(Bytes::from_static(b"data: 1\n\n")),
+///         Ok(Bytes::from_static(b"data: 2\n\n")),
+///     ];
+///     let mut res = web::stream(futures::stream::iter(items));
+///     res.headers_mut().insert(
+///         ntex::http::header::CONTENT_TYPE,
+///         ntex::http::header::HeaderValue::from_static("text/event-stream"),
+///     );
+///     res
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().route("/events", web::get().to(events));
+/// }
+/// ```
+pub fn stream<S, E>(stream: S) -> HttpResponse
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+    E: Error + 'static,
+{
+    HttpResponse::Ok().body(Body::from_message(FlushableStream::new(FlushEach {
+        stream,
+        flush: false,
+    })))
+}
+
+/// Stream that yields flush marker after each item
+struct FlushEach<S> {
+    stream: S,
+    flush: bool,
+}
+
+impl<S, E> Stream for FlushEach<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    type Item = Result<Chunk, E>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.flush {
+            self.flush = false;
+            return Poll::Ready(Some(Ok(Chunk::Flush)));
+        }
+
+        loop {
+            return match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(item))) if item.is_empty() => continue,
+                Poll::Ready(Some(Ok(item))) => {
+                    self.flush = true;
+                    Poll::Ready(Some(Ok(Chunk::Data(item))))
+                }
+                Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
 /// Create new http server with application factory.
 ///
 /// ```rust,no_run
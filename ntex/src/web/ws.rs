@@ -1,20 +1,132 @@
 use std::{
-    error::Error as StdError, marker::PhantomData, pin::Pin, task::Context, task::Poll,
+    cell::Cell, convert::TryFrom, error::Error as StdError, marker::PhantomData,
+    pin::Pin, rc::Rc, task::Context, task::Poll,
 };
 
-pub use crate::ws::{CloseCode, CloseReason, Frame, Message};
+pub use crate::http::ws::HandshakeError;
+pub use crate::ws::{CloseCode, CloseReason, DeflateConfig, Frame, Message};
 
 use crate::http::body::{Body, BoxedBodyStream};
 use crate::http::error::PayloadError;
-use crate::http::ws::{handshake, HandshakeError};
+use crate::http::header::{HeaderValue, SEC_WEBSOCKET_EXTENSIONS};
+use crate::http::ws::handshake;
 use crate::service::{IntoServiceFactory, Service, ServiceFactory};
-use crate::web::{HttpRequest, HttpResponse};
+use crate::task::LocalWaker;
+use crate::time::{Millis, Sleep};
+use crate::web::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use crate::{channel::mpsc, rt, util::Bytes, ws, Sink, Stream};
 
 pub type WebSocketsSink =
     ws::StreamEncoder<mpsc::Sender<Result<Bytes, Box<dyn StdError>>>>;
 
+/// Websockets service configuration
+#[derive(Debug, Clone)]
+pub struct WebSocketsConfig {
+    close_timeout: Millis,
+    deflate: Option<DeflateConfig>,
+}
+
+impl Default for WebSocketsConfig {
+    fn default() -> Self {
+        WebSocketsConfig {
+            close_timeout: Millis::from_secs(5),
+            deflate: None,
+        }
+    }
+}
+
+impl WebSocketsConfig {
+    /// Create default websockets configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set close handshake timeout.
+    ///
+    /// Once `Close` frame is sent or received, dispatcher waits for
+    /// the close frame from the other side. If peer does not complete
+    /// close handshake within this time, connection get dropped. Wait
+    /// ends immediately if peer disconnects.
+    ///
+    /// By default close timeout is set to 5 seconds.
+    pub fn close_timeout<T: Into<Millis>>(mut self, timeout: T) -> Self {
+        self.close_timeout = timeout.into();
+        self
+    }
+
+    /// Enable per-message deflate compression.
+    ///
+    /// Compression is used only if client offers `permessage-deflate`
+    /// extension, otherwise messages are sent uncompressed.
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = Some(config);
+        self
+    }
+}
+
+/// Verify websocket handshake request and create handshake response.
+///
+/// Returns `101 Switching Protocols` response builder with computed
+/// `Sec-WebSocket-Accept` header, it could be used for custom handshakes.
+///
+/// ```rust
+/// use ntex::web::{self, ws, HttpRequest, HttpResponse};
+///
+/// async fn index(req: HttpRequest) -> Result<HttpResponse, ws::HandshakeError> {
+///     let mut res = ws::handshake_response(&req)?;
+///     Ok(res.header("x-custom", "value").finish())
+/// }
+/// ```
+pub fn handshake_response(
+    req: &HttpRequest,
+) -> Result<HttpResponseBuilder, HandshakeError> {
+    handshake(req.head())
+}
+
 /// Do websocket handshake and start websockets service.
+///
+/// Handshake request is verified, `101 Switching Protocols` response is
+/// returned on success and websockets service is started for the connection.
+/// Service receives decoded `Frame`s, messages returned by service are sent
+/// to peer, additional messages could be sent with `WebSocketsSink` passed
+/// as service factory config. Invalid handshake is rejected with
+/// `HandshakeError`, i.e. non-GET request is rejected with *405* response and
+/// request without `Sec-WebSocket-Key` header with *400* response.
+///
+/// ```rust
+/// use ntex::service::{fn_factory_with_config, fn_service};
+/// use ntex::web::{self, ws, App, HttpRequest};
+///
+/// async fn echo(frame: ws::Frame) -> Result<Option<ws::Message>, std::io::Error> {
+///     Ok(match frame {
+///         ws::Frame::Text(text) => Some(ws::Message::Text(
+///             String::from_utf8_lossy(&text).as_ref().into(),
+///         )),
+///         ws::Frame::Binary(bin) => Some(ws::Message::Binary(bin)),
+///         ws::Frame::Ping(msg) => Some(ws::Message::Pong(msg)),
+///         ws::Frame::Close(reason) => Some(ws::Message::Close(reason)),
+///         _ => None,
+///     })
+/// }
+///
+/// async fn index(
+///     req: HttpRequest,
+///     pl: web::types::Payload,
+/// ) -> Result<web::HttpResponse, web::Error> {
+///     ws::start::<_, _, _, web::Error>(
+///         req,
+///         pl,
+///         fn_factory_with_config(|_sink: ws::WebSocketsSink| async {
+///             Ok::<_, web::Error>(fn_service(echo))
+///         }),
+///     )
+///     .await
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/ws", web::get().to(index));
+/// }
+/// ```
 pub async fn start<T, F, S, Err>(
     req: HttpRequest,
     payload: S,
@@ -47,6 +159,40 @@ pub async fn start_with<T, F, S, Err, Tx, Rx>(
     rx: Rx,
     factory: F,
 ) -> Result<HttpResponse, Err>
+where
+    T: ServiceFactory<
+        Config = ws::StreamEncoder<Tx>,
+        Request = Frame,
+        Response = Option<Message>,
+    >,
+    T::Error: StdError + 'static,
+    T::InitError: 'static,
+    T::Service: 'static,
+    F: IntoServiceFactory<T>,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+    Err: From<T::InitError>,
+    Err: From<HandshakeError>,
+    Tx: Sink<Result<Bytes, Box<dyn StdError>>> + Clone + Unpin + 'static,
+    Tx::Error: StdError,
+    Rx: Stream<Item = Result<Bytes, Box<dyn StdError>>> + Unpin + 'static,
+{
+    start_with_config(req, payload, tx, rx, WebSocketsConfig::default(), factory).await
+}
+
+/// Do websocket handshake and start websockets service with custom configuration.
+///
+/// Close handshake is handled by dispatcher. Once `Close` message is returned
+/// from websockets service, dispatcher stops processing messages
+/// and waits for peer's `Close` frame, connection get dropped after
+/// peer's response or after close timeout.
+pub async fn start_with_config<T, F, S, Err, Tx, Rx>(
+    req: HttpRequest,
+    payload: S,
+    tx: Tx,
+    rx: Rx,
+    config: WebSocketsConfig,
+    factory: F,
+) -> Result<HttpResponse, Err>
 where
     T: ServiceFactory<
         Config = ws::StreamEncoder<Tx>,
@@ -65,10 +211,27 @@ where
     Rx: Stream<Item = Result<Bytes, Box<dyn StdError>>> + Unpin + 'static,
 {
     // ws handshake
-    let mut res = handshake(req.head())?;
+    let mut res = handshake_response(&req)?;
+
+    // negotiate per-message deflate
+    let mut codec = ws::Codec::new();
+    if let Some(ref deflate) = config.deflate {
+        let negotiated = req
+            .headers()
+            .get_all(SEC_WEBSOCKET_EXTENSIONS)
+            .filter_map(|hdr| hdr.to_str().ok())
+            .find_map(|hdr| deflate.negotiate(hdr));
+
+        if let Some(deflate) = negotiated {
+            if let Ok(hdr) = HeaderValue::try_from(deflate.to_string()) {
+                res.header(SEC_WEBSOCKET_EXTENSIONS, hdr);
+                codec = codec.deflate(deflate);
+            }
+        }
+    }
 
     // converter wraper from ws::Message to Bytes
-    let sink = ws::StreamEncoder::new(tx);
+    let sink = ws::StreamEncoder::with(tx, codec.clone());
 
     // create ws service
     let srv = factory
@@ -80,15 +243,26 @@ where
             e
         });
 
+    // close handshake state
+    let state = Rc::new(CloseState {
+        flags: Cell::new(CloseFlags::empty()),
+        waker: LocalWaker::new(),
+    });
+
     // start websockets service dispatcher
     rt::spawn(crate::util::stream::Dispatcher::new(
         // wrap bytes stream to ws::Frame's stream
-        MapStream {
-            stream: ws::StreamDecoder::new(payload),
-            _t: PhantomData,
+        CloseStream {
+            stream: MapStream {
+                stream: ws::StreamDecoder::with(payload, codec),
+                _t: PhantomData,
+            },
+            state: state.clone(),
+            timeout: config.close_timeout,
+            delay: None,
         },
         // converter wraper from ws::Message to Bytes
-        sink,
+        CloseSink { sink, state },
         // websockets handler service
         srv,
     ));
@@ -127,3 +301,184 @@ where
         self.stream.size_hint()
     }
 }
+
+bitflags::bitflags! {
+    struct CloseFlags: u8 {
+        /// Close frame has been sent to peer
+        const SENT     = 0b0000_0001;
+        /// Close frame has been received from peer
+        const RECEIVED = 0b0000_0010;
+    }
+}
+
+struct CloseState {
+    flags: Cell<CloseFlags>,
+    waker: LocalWaker,
+}
+
+impl CloseState {
+    fn insert(&self, flag: CloseFlags) {
+        let mut flags = self.flags.get();
+        flags.insert(flag);
+        self.flags.set(flags);
+        self.waker.wake();
+    }
+}
+
+/// Frames stream that tracks close handshake
+struct CloseStream<S> {
+    stream: S,
+    state: Rc<CloseState>,
+    timeout: Millis,
+    delay: Option<Sleep>,
+}
+
+impl<S> Stream for CloseStream<S>
+where
+    S: Stream<Item = Result<Frame, Box<dyn StdError>>> + Unpin,
+{
+    type Item = Result<Frame, Box<dyn StdError>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let flags = self.state.flags.get();
+
+        // close handshake is completed
+        if flags.contains(CloseFlags::SENT | CloseFlags::RECEIVED) {
+            log::trace!("Websockets close handshake is completed");
+            return Poll::Ready(None);
+        }
+
+        // close handshake is started, wait for other side
+        if !flags.is_empty() {
+            if self.delay.is_none() {
+                self.delay = Some(Sleep::new(self.timeout));
+            }
+            if self.delay.as_ref().unwrap().poll_elapsed(cx).is_ready() {
+                log::trace!("Websockets close handshake timeout, dropping connection");
+                return Poll::Ready(None);
+            }
+        }
+        self.state.waker.register(cx.waker());
+
+        match Pin::new(&mut self.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(Frame::Close(reason)))) => {
+                self.state.insert(CloseFlags::RECEIVED);
+                Poll::Ready(Some(Ok(Frame::Close(reason))))
+            }
+            // peer is disconnected, no need to wait for close frame
+            Poll::Ready(Some(Err(_))) | Poll::Ready(None) if !flags.is_empty() => {
+                log::trace!("Peer is disconnected during websockets close handshake");
+                self.delay = None;
+                Poll::Ready(None)
+            }
+            res => res,
+        }
+    }
+}
+
+/// Messages sink that tracks close handshake
+struct CloseSink<U> {
+    sink: U,
+    state: Rc<CloseState>,
+}
+
+impl<U, E> Sink<Result<Message, E>> for CloseSink<U>
+where
+    U: Sink<Result<Message, E>> + Unpin,
+{
+    type Error = U::Error;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sink).poll_ready(cx)
+    }
+
+    fn start_send(
+        mut self: Pin<&mut Self>,
+        item: Result<Message, E>,
+    ) -> Result<(), Self::Error> {
+        let flags = self.state.flags.get();
+
+        // no messages are allowed after close frame
+        if flags.contains(CloseFlags::SENT) {
+            log::trace!("Close frame is sent, ignore websockets message");
+            return Ok(());
+        }
+        if let Ok(Message::Close(_)) = item {
+            self.state.insert(CloseFlags::SENT);
+        }
+        Pin::new(&mut self.sink).start_send(item)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sink).poll_flush(cx)
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sink).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, Method, StatusCode};
+    use crate::web::test::TestRequest;
+
+    #[test]
+    fn test_handshake_response() {
+        let req = TestRequest::default()
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+            .to_http_request();
+        let res = handshake_response(&req).unwrap().finish();
+        assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(
+            res.headers().get(header::SEC_WEBSOCKET_ACCEPT).unwrap(),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(res.headers().get(header::UPGRADE).unwrap(), "websocket");
+
+        let req = TestRequest::default()
+            .method(Method::POST)
+            .to_http_request();
+        assert_eq!(
+            handshake_response(&req).err().unwrap(),
+            HandshakeError::GetMethodRequired
+        );
+
+        let req = TestRequest::default()
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .to_http_request();
+        assert_eq!(
+            handshake_response(&req).err().unwrap(),
+            HandshakeError::BadWebsocketKey
+        );
+
+        let req = TestRequest::default()
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "5")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+            .to_http_request();
+        assert_eq!(
+            handshake_response(&req).err().unwrap(),
+            HandshakeError::UnsupportedVersion
+        );
+    }
+}
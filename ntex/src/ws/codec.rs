@@ -3,6 +3,8 @@ use std::cell::Cell;
 use crate::codec::{Decoder, Encoder};
 use crate::util::{ByteString, Bytes, BytesMut};
 
+#[cfg(feature = "compress")]
+use super::deflate::{DeflateCodec, DeflateConfig};
 use super::frame::Parser;
 use super::proto::{CloseReason, OpCode};
 use super::ProtocolError;
@@ -55,6 +57,8 @@ pub enum Item {
 pub struct Codec {
     flags: Cell<Flags>,
     max_size: usize,
+    #[cfg(feature = "compress")]
+    deflate: Option<DeflateCodec>,
 }
 
 bitflags::bitflags! {
@@ -62,6 +66,7 @@ bitflags::bitflags! {
         const SERVER         = 0b0000_0001;
         const R_CONTINUATION = 0b0000_0010;
         const W_CONTINUATION = 0b0000_0100;
+        const R_COMPRESSED   = 0b0000_1000;
     }
 }
 
@@ -71,6 +76,8 @@ impl Codec {
         Codec {
             max_size: 65_536,
             flags: Cell::new(Flags::SERVER),
+            #[cfg(feature = "compress")]
+            deflate: None,
         }
     }
 
@@ -90,6 +97,90 @@ impl Codec {
         self
     }
 
+    #[cfg(feature = "compress")]
+    /// Enable per-message deflate compression.
+    ///
+    /// Configuration must be negotiated during websockets handshake.
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = Some(DeflateCodec::new(config));
+        self
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Option<Bytes>, ProtocolError> {
+        if let Some(ref deflate) = self.deflate {
+            deflate
+                .compress(data, self.flags.get().contains(Flags::SERVER))
+                .map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[cfg(not(feature = "compress"))]
+    fn compress(&self, _: &[u8]) -> Result<Option<Bytes>, ProtocolError> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "compress")]
+    fn decompress(
+        &self,
+        finished: bool,
+        rsv1: bool,
+        opcode: OpCode,
+        payload: Option<BytesMut>,
+    ) -> Result<Option<BytesMut>, ProtocolError> {
+        let deflate = if let Some(ref deflate) = self.deflate {
+            deflate
+        } else if rsv1 {
+            return Err(ProtocolError::UnexpectedRsv);
+        } else {
+            return Ok(payload);
+        };
+
+        // RSV1 bit is set on the first frame of compressed message
+        match opcode {
+            OpCode::Text | OpCode::Binary if rsv1 => {
+                if !finished {
+                    self.insert_flags(Flags::R_COMPRESSED);
+                }
+            }
+            OpCode::Continue
+                if !rsv1 && self.flags.get().contains(Flags::R_COMPRESSED) =>
+            {
+                if finished {
+                    self.remove_flags(Flags::R_COMPRESSED);
+                }
+            }
+            _ if rsv1 => return Err(ProtocolError::UnexpectedRsv),
+            _ => return Ok(payload),
+        }
+
+        deflate
+            .decompress(
+                payload.as_ref().map(|pl| &pl[..]).unwrap_or(&[]),
+                finished,
+                self.flags.get().contains(Flags::SERVER),
+                self.max_size,
+            )
+            .map(Some)
+    }
+
+    #[cfg(not(feature = "compress"))]
+    fn decompress(
+        &self,
+        _: bool,
+        rsv1: bool,
+        _: OpCode,
+        payload: Option<BytesMut>,
+    ) -> Result<Option<BytesMut>, ProtocolError> {
+        if rsv1 {
+            Err(ProtocolError::UnexpectedRsv)
+        } else {
+            Ok(payload)
+        }
+    }
+
     fn insert_flags(&self, f: Flags) {
         let mut flags = self.flags.get();
         flags.insert(f);
@@ -115,20 +206,40 @@ impl Encoder for Codec {
 
     fn encode(&self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
         match item {
-            Message::Text(txt) => Parser::write_message(
-                dst,
-                txt.as_ref(),
-                OpCode::Text,
-                true,
-                !self.flags.get().contains(Flags::SERVER),
-            ),
-            Message::Binary(bin) => Parser::write_message(
-                dst,
-                bin,
-                OpCode::Binary,
-                true,
-                !self.flags.get().contains(Flags::SERVER),
-            ),
+            Message::Text(txt) => match self.compress(txt.as_bytes())? {
+                Some(data) => Parser::write_frame(
+                    dst,
+                    data,
+                    OpCode::Text,
+                    true,
+                    true,
+                    !self.flags.get().contains(Flags::SERVER),
+                ),
+                None => Parser::write_message(
+                    dst,
+                    txt.as_ref(),
+                    OpCode::Text,
+                    true,
+                    !self.flags.get().contains(Flags::SERVER),
+                ),
+            },
+            Message::Binary(bin) => match self.compress(&bin)? {
+                Some(data) => Parser::write_frame(
+                    dst,
+                    data,
+                    OpCode::Binary,
+                    true,
+                    true,
+                    !self.flags.get().contains(Flags::SERVER),
+                ),
+                None => Parser::write_message(
+                    dst,
+                    bin,
+                    OpCode::Binary,
+                    true,
+                    !self.flags.get().contains(Flags::SERVER),
+                ),
+            },
             Message::Ping(txt) => Parser::write_message(
                 dst,
                 txt,
@@ -215,9 +326,14 @@ impl Decoder for Codec {
     type Error = ProtocolError;
 
     fn decode(&self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        match Parser::parse(src, self.flags.get().contains(Flags::SERVER), self.max_size)
-        {
-            Ok(Some((finished, opcode, payload))) => {
+        match Parser::parse_frame(
+            src,
+            self.flags.get().contains(Flags::SERVER),
+            self.max_size,
+        ) {
+            Ok(Some((finished, rsv1, opcode, payload))) => {
+                let payload = self.decompress(finished, rsv1, opcode, payload)?;
+
                 // handle continuation
                 if !finished {
                     match opcode {
@@ -0,0 +1,386 @@
+//! Per-message deflate extension (RFC 7692)
+use std::{cell::RefCell, fmt};
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+
+use super::ProtocolError;
+use crate::util::{Bytes, BytesMut};
+
+/// Extension name
+const EXTENSION: &str = "permessage-deflate";
+/// Tail of the sync flushed deflate block
+const TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+/// Max supported LZ77 window size
+const MAX_WINDOW_BITS: u8 = 15;
+
+/// Per-message deflate extension configuration
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DeflateConfig {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+}
+
+impl DeflateConfig {
+    /// Create default per-message deflate configuration
+    ///
+    /// By default both sides re-use compression context between messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Server resets compression context after each message
+    pub fn server_no_context_takeover(mut self) -> Self {
+        self.server_no_context_takeover = true;
+        self
+    }
+
+    /// Client resets compression context after each message
+    pub fn client_no_context_takeover(mut self) -> Self {
+        self.client_no_context_takeover = true;
+        self
+    }
+
+    /// Negotiate extension parameters with client's offer.
+    ///
+    /// `extensions` is a value of the `Sec-WebSocket-Extensions` request header.
+    /// Returns negotiated parameters for the first acceptable offer, result
+    /// should be used as a value for the response header.
+    pub fn negotiate(&self, extensions: &str) -> Option<DeflateConfig> {
+        extensions
+            .split(',')
+            .filter_map(|ext| Params::parse(ext)?.ok())
+            .find(|params| {
+                params.server_max_window_bits.is_none()
+                    || params.server_max_window_bits == Some(MAX_WINDOW_BITS)
+            })
+            .map(|params| DeflateConfig {
+                server_no_context_takeover: self.server_no_context_takeover
+                    || params.server_no_context_takeover,
+                client_no_context_takeover: self.client_no_context_takeover
+                    || params.client_no_context_takeover,
+            })
+    }
+
+    /// Check server's response for the offer.
+    ///
+    /// `extensions` is a value of the `Sec-WebSocket-Extensions` response header.
+    /// Returns `None` if response is not valid.
+    pub fn accept(&self, extensions: &str) -> Option<DeflateConfig> {
+        let mut result = None;
+        for ext in extensions.split(',') {
+            match Params::parse(ext) {
+                Some(Ok(params)) if result.is_none() => {
+                    // client_max_window_bits is not offered
+                    if params.client_max_window_bits.is_some()
+                        && params.client_max_window_bits != Some(MAX_WINDOW_BITS)
+                    {
+                        return None;
+                    }
+                    result = Some(DeflateConfig {
+                        server_no_context_takeover: params.server_no_context_takeover,
+                        client_no_context_takeover: self.client_no_context_takeover
+                            || params.client_no_context_takeover,
+                    });
+                }
+                _ => return None,
+            }
+        }
+        result
+    }
+}
+
+impl fmt::Display for DeflateConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", EXTENSION)?;
+        if self.server_no_context_takeover {
+            write!(f, "; server_no_context_takeover")?;
+        }
+        if self.client_no_context_takeover {
+            write!(f, "; client_no_context_takeover")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Params {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: Option<u8>,
+    client_max_window_bits: Option<u8>,
+}
+
+impl Params {
+    /// Parse extension parameters, returns `None` for unknown extensions
+    fn parse(ext: &str) -> Option<Result<Params, ()>> {
+        let mut items = ext.split(';').map(|s| s.trim());
+        if !items.next()?.eq_ignore_ascii_case(EXTENSION) {
+            return None;
+        }
+
+        let mut params = Params::default();
+        for item in items {
+            let (name, value) = if let Some(idx) = item.find('=') {
+                let value = item[idx + 1..].trim().trim_matches('"');
+                (item[..idx].trim(), Some(value))
+            } else {
+                (item, None)
+            };
+
+            match name {
+                "server_no_context_takeover"
+                    if value.is_none() && !params.server_no_context_takeover =>
+                {
+                    params.server_no_context_takeover = true
+                }
+                "client_no_context_takeover"
+                    if value.is_none() && !params.client_no_context_takeover =>
+                {
+                    params.client_no_context_takeover = true
+                }
+                "server_max_window_bits" if params.server_max_window_bits.is_none() => {
+                    match value.and_then(window_bits) {
+                        Some(bits) => params.server_max_window_bits = Some(bits),
+                        None => return Some(Err(())),
+                    }
+                }
+                "client_max_window_bits" if params.client_max_window_bits.is_none() => {
+                    match value {
+                        None => params.client_max_window_bits = Some(MAX_WINDOW_BITS),
+                        Some(val) => match window_bits(val) {
+                            Some(bits) => params.client_max_window_bits = Some(bits),
+                            None => return Some(Err(())),
+                        },
+                    }
+                }
+                _ => return Some(Err(())),
+            }
+        }
+        Some(Ok(params))
+    }
+}
+
+fn window_bits(val: &str) -> Option<u8> {
+    val.parse::<u8>()
+        .ok()
+        .filter(|bits| (8..=MAX_WINDOW_BITS).contains(bits))
+}
+
+/// Per-message deflate compression context
+pub(super) struct DeflateCodec {
+    config: DeflateConfig,
+    compress: RefCell<Compress>,
+    decompress: RefCell<Decompress>,
+}
+
+impl DeflateCodec {
+    pub(super) fn new(config: DeflateConfig) -> Self {
+        DeflateCodec {
+            config,
+            compress: RefCell::new(Compress::new(Compression::default(), false)),
+            decompress: RefCell::new(Decompress::new(false)),
+        }
+    }
+
+    /// Compress message payload
+    pub(super) fn compress(
+        &self,
+        data: &[u8],
+        server: bool,
+    ) -> Result<Bytes, ProtocolError> {
+        let mut compress = self.compress.borrow_mut();
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = compress.total_in();
+
+        loop {
+            let consumed = (compress.total_in() - start) as usize;
+            compress
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|_| ProtocolError::Compression)?;
+
+            // sync flush is completed if output buffer is not full
+            let consumed = (compress.total_in() - start) as usize;
+            if consumed == data.len() && out.len() < out.capacity() {
+                break;
+            }
+            out.reserve(out.capacity());
+        }
+
+        if out.ends_with(&TAIL) {
+            out.truncate(out.len() - TAIL.len());
+        }
+
+        let no_context_takeover = if server {
+            self.config.server_no_context_takeover
+        } else {
+            self.config.client_no_context_takeover
+        };
+        if no_context_takeover {
+            compress.reset();
+        }
+        Ok(Bytes::from(out))
+    }
+
+    /// Decompress message payload
+    pub(super) fn decompress(
+        &self,
+        data: &[u8],
+        finished: bool,
+        server: bool,
+        max_size: usize,
+    ) -> Result<BytesMut, ProtocolError> {
+        let mut decompress = self.decompress.borrow_mut();
+        let mut out = Vec::with_capacity(data.len() * 2 + 64);
+
+        inflate(&mut decompress, data, &mut out, max_size)?;
+        if finished {
+            inflate(&mut decompress, &TAIL, &mut out, max_size)?;
+
+            let no_context_takeover = if server {
+                self.config.client_no_context_takeover
+            } else {
+                self.config.server_no_context_takeover
+            };
+            if no_context_takeover {
+                decompress.reset(false);
+            }
+        }
+        Ok(BytesMut::from(&out[..]))
+    }
+}
+
+fn inflate(
+    decompress: &mut Decompress,
+    data: &[u8],
+    out: &mut Vec<u8>,
+    max_size: usize,
+) -> Result<(), ProtocolError> {
+    let start = decompress.total_in();
+
+    loop {
+        let consumed = (decompress.total_in() - start) as usize;
+        let len = out.len();
+        decompress
+            .decompress_vec(&data[consumed..], out, FlushDecompress::Sync)
+            .map_err(|_| ProtocolError::Compression)?;
+
+        if out.len() > max_size {
+            return Err(ProtocolError::Overflow);
+        }
+
+        let no_progress =
+            consumed == (decompress.total_in() - start) as usize && len == out.len();
+        let consumed = (decompress.total_in() - start) as usize;
+        if (consumed == data.len() || no_progress) && out.len() < out.capacity() {
+            return Ok(());
+        }
+        out.reserve(out.capacity());
+    }
+}
+
+impl Clone for DeflateCodec {
+    fn clone(&self) -> Self {
+        DeflateCodec::new(self.config)
+    }
+}
+
+impl fmt::Debug for DeflateCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeflateCodec")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let cfg = DeflateConfig::new();
+        assert_eq!(cfg.to_string(), "permessage-deflate");
+        assert_eq!(
+            cfg.negotiate("permessage-deflate; client_max_window_bits"),
+            Some(DeflateConfig::new())
+        );
+        assert_eq!(
+            cfg.negotiate("permessage-deflate; server_no_context_takeover"),
+            Some(DeflateConfig::new().server_no_context_takeover())
+        );
+        assert_eq!(
+            cfg.negotiate(
+                "permessage-deflate; server_max_window_bits=10, permessage-deflate"
+            ),
+            Some(DeflateConfig::new())
+        );
+        assert_eq!(
+            DeflateConfig::new()
+                .client_no_context_takeover()
+                .negotiate("x-webkit-deflate-frame, permessage-deflate")
+                .unwrap()
+                .to_string(),
+            "permessage-deflate; client_no_context_takeover"
+        );
+        assert_eq!(cfg.negotiate("permessage-deflate; unknown"), None);
+        assert_eq!(
+            cfg.negotiate("permessage-deflate; client_max_window_bits=16"),
+            None
+        );
+        assert_eq!(cfg.negotiate("x-webkit-deflate-frame"), None);
+    }
+
+    #[test]
+    fn test_accept() {
+        let cfg = DeflateConfig::new();
+        assert_eq!(cfg.accept("permessage-deflate"), Some(DeflateConfig::new()));
+        assert_eq!(
+            cfg.accept("permessage-deflate; server_no_context_takeover"),
+            Some(DeflateConfig::new().server_no_context_takeover())
+        );
+        assert_eq!(
+            cfg.accept("permessage-deflate; client_max_window_bits=10"),
+            None
+        );
+        assert_eq!(cfg.accept("permessage-deflate, permessage-deflate"), None);
+        assert_eq!(cfg.accept("x-webkit-deflate-frame"), None);
+    }
+
+    #[test]
+    fn test_compress() {
+        let server = DeflateCodec::new(DeflateConfig::new());
+        let client = DeflateCodec::new(DeflateConfig::new());
+
+        // RFC 7692 7.2.3.1
+        let data = server.compress(b"Hello", true).unwrap();
+        assert_eq!(&data[..], &[0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00][..]);
+        let data = client.decompress(&data, true, false, 1024).unwrap();
+        assert_eq!(&data[..], b"Hello");
+
+        // context takeover
+        let data = server.compress(b"Hello", true).unwrap();
+        assert!(data.len() < 7);
+        let data = client.decompress(&data, true, false, 1024).unwrap();
+        assert_eq!(&data[..], b"Hello");
+
+        let data = server.compress(b"Hello", true).unwrap();
+        assert!(matches!(
+            client.decompress(&data, true, false, 2),
+            Err(ProtocolError::Overflow)
+        ));
+    }
+
+    #[test]
+    fn test_no_context_takeover() {
+        let cfg = DeflateConfig::new().server_no_context_takeover();
+        let server = DeflateCodec::new(cfg);
+        let client = DeflateCodec::new(cfg);
+
+        for _ in 0..2 {
+            let data = server.compress(b"Hello", true).unwrap();
+            assert_eq!(&data[..], &[0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00][..]);
+            let data = client.decompress(&data, true, false, 1024).unwrap();
+            assert_eq!(&data[..], b"Hello");
+        }
+    }
+}
@@ -8,6 +8,8 @@ use std::io;
 use derive_more::{Display, From};
 
 mod codec;
+#[cfg(feature = "compress")]
+mod deflate;
 mod frame;
 mod mask;
 mod proto;
@@ -15,6 +17,8 @@ mod sink;
 mod stream;
 
 pub use self::codec::{Codec, Frame, Item, Message};
+#[cfg(feature = "compress")]
+pub use self::deflate::DeflateConfig;
 pub use self::frame::Parser;
 pub use self::proto::{hash_key, CloseCode, CloseReason, OpCode};
 pub use self::sink::WsSink;
@@ -59,6 +63,12 @@ pub enum ProtocolError {
     /// Unknown continuation fragment
     #[display(fmt = "Unknown continuation fragment.")]
     ContinuationFragment(OpCode),
+    /// Received frame with reserved bit set
+    #[display(fmt = "Received frame with reserved bit set")]
+    UnexpectedRsv,
+    /// Per-message compression error
+    #[display(fmt = "Per-message compression error")]
+    Compression,
 }
 
 impl std::error::Error for ProtocolError {}
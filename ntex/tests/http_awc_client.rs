@@ -12,7 +12,7 @@ use rand::Rng;
 use ntex::http::client::error::{JsonPayloadError, SendRequestError};
 use ntex::http::client::{Client, Connector};
 use ntex::http::test::server as test_server;
-use ntex::http::{header, HttpMessage, HttpService};
+use ntex::http::{error::ParseError, header, HttpMessage, HttpService};
 use ntex::service::{map_config, pipeline_factory, Service};
 use ntex::web::dev::AppConfig;
 use ntex::web::middleware::Compress;
@@ -187,6 +187,33 @@ async fn test_timeout() {
     }
 }
 
+#[ntex::test]
+async fn test_headers_limits() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|| async {
+            HttpResponse::Ok()
+                .header("x-a", "1")
+                .header("x-a", "2")
+                .header("x-a", "3")
+                .finish()
+        })))
+    });
+
+    let client = Client::build()
+        .connector(Connector::default().headers_limits(8, 3).finish())
+        .finish();
+    let response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let client = Client::build()
+        .connector(Connector::default().headers_limits(8, 2).finish())
+        .finish();
+    match client.get(srv.url("/")).send().await {
+        Err(SendRequestError::Response(ParseError::TooManyHeaders)) => (),
+        res => panic!("{:?}", res.map(|res| res.status())),
+    }
+}
+
 #[ntex::test]
 async fn test_timeout_override() {
     let srv = test::server(|| {
@@ -5,9 +5,9 @@ use std::sync::Arc;
 use futures::future::ok;
 use open_ssl::ssl::{SslAcceptor, SslConnector, SslFiletype, SslMethod, SslVerifyMode};
 
-use ntex::http::client::{Client, Connector};
+use ntex::http::client::{error::SendRequestError, Client, Connector};
 use ntex::http::test::server as test_server;
-use ntex::http::{HttpService, Version};
+use ntex::http::{error::ParseError, HttpService, StatusCode, Version};
 use ntex::service::{map_config, pipeline_factory, ServiceFactory};
 use ntex::time::Seconds;
 use ntex::web::{self, dev::AppConfig, App, HttpResponse};
@@ -83,3 +83,78 @@ async fn test_connection_reuse_h2() {
     // one connection
     assert_eq!(num.load(Ordering::Relaxed), 1);
 }
+
+#[ntex::test]
+async fn test_headers_limits_h2() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .headers_limits(8, 2)
+            .h2(map_config(
+                App::new().service(web::resource("/").route(web::to(|| async {
+                    HttpResponse::Ok()
+                        .header("x-a", "1")
+                        .header("x-a", "2")
+                        .header("x-a", "3")
+                        .finish()
+                }))),
+                |_| AppConfig::default(),
+            ))
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    // disable ssl verification
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_verify(SslVerifyMode::NONE);
+    let _ = builder
+        .set_alpn_protos(b"\x02h2\x08http/1.1")
+        .map_err(|e| log::error!("Cannot set alpn protocol: {:?}", e));
+    let connector = builder.build();
+
+    let client = Client::build()
+        .connector(
+            Connector::default()
+                .openssl(connector.clone())
+                .headers_limits(8, 3)
+                .finish(),
+        )
+        .finish();
+
+    // request headers
+    let response = client
+        .get(srv.surl("/"))
+        .header("x-b", "1")
+        .header("x-b", "2")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.version(), Version::HTTP_2);
+
+    let response = client
+        .get(srv.surl("/"))
+        .header("x-b", "1")
+        .header("x-b", "2")
+        .header("x-b", "3")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.status(),
+        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+    );
+
+    // response headers
+    let client = Client::build()
+        .connector(
+            Connector::default()
+                .openssl(connector)
+                .headers_limits(8, 2)
+                .finish(),
+        )
+        .finish();
+    match client.get(srv.surl("/")).send().await {
+        Err(SendRequestError::Response(ParseError::TooManyHeaders)) => (),
+        res => panic!("{:?}", res.map(|res| res.status())),
+    }
+}
@@ -455,3 +455,133 @@ async fn test_ssl_handshake_timeout() {
     let _ = stream.read_to_string(&mut data);
     assert!(data.is_empty());
 }
+
+#[ntex::test]
+async fn test_h2_server_header() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .server_header(Some("ntex"))
+            .h2(|_| {
+                ok::<_, io::Error>(
+                    Response::Ok().header(header::SERVER, "custom").finish(),
+                )
+            })
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv.srequest(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(header::SERVER).unwrap(), "ntex");
+
+    let srv = test_server(move || {
+        HttpService::build()
+            .server_header(None)
+            .h2(|_| {
+                ok::<_, io::Error>(
+                    Response::Ok().header(header::SERVER, "custom").finish(),
+                )
+            })
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv.srequest(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.headers().contains_key(header::SERVER));
+}
+
+#[ntex::test]
+async fn test_h2_date_header() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .date_header(false)
+            .h2(|_| ok::<_, io::Error>(Response::Ok().finish()))
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv.srequest(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.headers().contains_key(header::DATE));
+}
+
+#[ntex::test]
+async fn test_h2_window_size() {
+    let data = "HELLOWORLD".to_owned().repeat(512 * 1024);
+    let mut srv = test_server(move || {
+        HttpService::build()
+            .h2_initial_window_size(1024 * 1024)
+            .h2_initial_connection_window_size(4 * 1024 * 1024)
+            .h2(|mut req: Request| async move {
+                let body = load_body(req.take_payload())
+                    .await
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                Ok::<_, io::Error>(Response::Ok().body(body))
+            })
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv
+        .srequest(Method::POST, "/")
+        .send_body(data.clone())
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let body = srv.load_body(response).await.unwrap();
+    assert_eq!(body.len(), data.len());
+    assert_eq!(&body, data.as_bytes());
+}
+
+#[cfg(feature = "digest")]
+#[ntex::test]
+async fn test_h2_digest_trailer() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .h2(|_| {
+                let chunks = vec![
+                    Ok::<_, io::Error>(Bytes::from_static(b"hello ")),
+                    Ok(Bytes::from_static(b"world")),
+                ];
+                ok::<_, io::Error>(
+                    Response::Ok().header(header::TRAILER, "digest").body(
+                        body::Body::from_message(body::DigestBody::sha256(
+                            body::BodyStream::new(futures::stream::iter(chunks)),
+                        )),
+                    ),
+                )
+            })
+            .tcp()
+    });
+
+    // h2 with prior knowledge
+    let io = ntex::rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(io).await.unwrap();
+    ntex::rt::spawn(async move {
+        let _ = conn.await;
+    });
+
+    let req = http::Request::get("/").body(()).unwrap();
+    let (response, _) = client.send_request(req, true).unwrap();
+    let response = response.await.unwrap();
+    assert!(response.status().is_success());
+
+    let mut body = response.into_body();
+    let mut data = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        let _ = body.flow_control().release_capacity(chunk.len());
+        data.extend_from_slice(&chunk);
+    }
+    assert_eq!(&data[..], b"hello world");
+
+    let trailers = body.trailers().await.unwrap().unwrap();
+    let digest =
+        open_ssl::hash::hash(open_ssl::hash::MessageDigest::sha256(), &data).unwrap();
+    assert_eq!(
+        trailers.get("digest").unwrap().to_str().unwrap(),
+        format!("sha-256={}", base64::encode(digest))
+    );
+}
@@ -242,6 +242,37 @@ async fn test_http1_keepalive_close() {
     assert_eq!(res, 0);
 }
 
+#[ntex::test]
+async fn test_http1_response_close_connection() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                let mut res = Response::Ok().finish();
+                if req.path() == "/close" {
+                    res.close_connection();
+                }
+                future::ok::<_, io::Error>(res)
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let _ = stream.read(&mut data);
+    assert_eq!(&data[..17], b"HTTP/1.1 200 OK\r\n");
+
+    // pipelined request after close is not processed
+    let _ = stream.write_all(b"GET /close HTTP/1.1\r\n\r\nGET /test HTTP/1.1\r\n\r\n");
+    sleep(Millis(100)).await;
+    let mut data = Vec::new();
+    let _ = stream.read_to_end(&mut data);
+    let data = String::from_utf8(data).unwrap();
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.contains("connection: close\r\n"));
+    assert_eq!(data.matches("HTTP/1.1").count(), 1);
+}
+
 #[ntex::test]
 async fn test_http10_keepalive_default_close() {
     let srv = test_server(|| {
@@ -645,3 +676,234 @@ async fn test_h1_on_connect() {
     let response = srv.request(Method::GET, "/").send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[ntex::test]
+async fn test_h1_server_header() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .server_header(Some("ntex"))
+            .h1(|_| {
+                future::ok::<_, io::Error>(
+                    Response::Ok().header(header::SERVER, "custom").finish(),
+                )
+            })
+            .tcp()
+    });
+
+    let response = srv.request(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(header::SERVER).unwrap(), "ntex");
+
+    let srv = test_server(|| {
+        HttpService::build()
+            .server_header(None)
+            .h1(|_| {
+                future::ok::<_, io::Error>(
+                    Response::Ok().header(header::SERVER, "custom").finish(),
+                )
+            })
+            .tcp()
+    });
+
+    let response = srv.request(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.headers().contains_key(header::SERVER));
+}
+
+#[ntex::test]
+async fn test_h1_default_server_header() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .default_server_header("my-svc/1.0")
+            .h1(|req: Request| {
+                let res = if req.path() == "/custom" {
+                    Response::Ok().header(header::SERVER, "custom").finish()
+                } else {
+                    Response::Ok().finish()
+                };
+                future::ok::<_, io::Error>(res)
+            })
+            .tcp()
+    });
+
+    let response = srv.request(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(header::SERVER).unwrap(),
+        "my-svc/1.0"
+    );
+
+    let response = srv.request(Method::GET, "/custom").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(header::SERVER).unwrap(), "custom");
+}
+
+#[ntex::test]
+async fn test_h1_date_header() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .date_header(false)
+            .h1(|req: Request| {
+                let res = if req.path() == "/custom" {
+                    Response::Ok().header(header::DATE, "custom").finish()
+                } else {
+                    Response::Ok().finish()
+                };
+                future::ok::<_, io::Error>(res)
+            })
+            .tcp()
+    });
+
+    let response = srv.request(Method::GET, "/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.headers().contains_key(header::DATE));
+
+    let response = srv.request(Method::GET, "/custom").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(header::DATE).unwrap(), "custom");
+}
+
+#[ntex::test]
+async fn test_h1_title_case_headers() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .title_case_headers(true)
+            .h1(|_| {
+                future::ok::<_, io::Error>(
+                    Response::Ok()
+                        .header(header::CONTENT_TYPE, "text/plain")
+                        .finish(),
+                )
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(data.contains("\r\nContent-Type: text/plain\r\n"));
+    assert!(!data.contains("content-type"));
+}
+
+#[ntex::test]
+async fn test_h1_map_request() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .map_request(|mut req| {
+                if let Some(val) = req.headers().get("x-proprietary").cloned() {
+                    req.headers_mut().remove("x-proprietary");
+                    req.headers_mut().insert(header::AUTHORIZATION, val);
+                }
+                req
+            })
+            .h1(|req: Request| {
+                let res = if let Some(val) = req.headers().get(header::AUTHORIZATION) {
+                    assert!(!req.headers().contains_key("x-proprietary"));
+                    Response::Ok().body(val.to_str().unwrap().to_string())
+                } else {
+                    Response::BadRequest().finish()
+                };
+                future::ok::<_, io::Error>(res)
+            })
+            .tcp()
+    });
+
+    let response = srv
+        .request(Method::GET, "/")
+        .header("x-proprietary", "token")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let bytes = srv.load_body(response).await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"token"));
+
+    let response = srv.request(Method::GET, "/").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[ntex::test]
+async fn test_h1_write_stall_timeout() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .write_stall_timeout(Seconds(1))
+            .h1(|_| {
+                let body = Bytes::from(vec![b'x'; 32 * 1024 * 1024]);
+                future::ok::<_, io::Error>(Response::Ok().body(body))
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test/tests/test HTTP/1.1\r\n\r\n");
+
+    // slow reader, does not read anything
+    sleep(Millis(2500)).await;
+
+    let mut data = Vec::new();
+    let _ = stream.read_to_end(&mut data);
+    assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(data.len() < 32 * 1024 * 1024);
+}
+
+#[ntex::test]
+async fn test_h1_write_stall_timeout_progress() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .write_stall_timeout(Seconds(1))
+            .h1(|_| {
+                let body = Bytes::from(vec![b'x'; 4 * 1024 * 1024]);
+                future::ok::<_, io::Error>(Response::Ok().body(body))
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ =
+        stream.write_all(b"GET /test/tests/test HTTP/1.1\r\nconnection: close\r\n\r\n");
+
+    // slow reader, write makes progress so stall timer is re-armed
+    let mut data = Vec::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(data.ends_with(&[b'x'; 1024][..]));
+    assert!(data.len() > 4 * 1024 * 1024);
+}
+
+#[ntex::test]
+async fn test_h1_write_timeout() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .write_timeout(Seconds(1))
+            .h1(|_| {
+                let body = Bytes::from(vec![b'x'; 32 * 1024 * 1024]);
+                future::ok::<_, io::Error>(Response::Ok().body(body))
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET /test/tests/test HTTP/1.1\r\n\r\n");
+
+    // slow reader, reads continuously but does not finish in time
+    let mut total = 0;
+    let mut buf = vec![0; 16 * 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => total += n,
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(total > 0);
+    assert!(total < 32 * 1024 * 1024);
+}
@@ -3,7 +3,7 @@ use std::sync::{mpsc, Arc};
 use std::{io, io::Read, net, thread, time};
 
 use futures::future::{lazy, ok, FutureExt};
-use futures::SinkExt;
+use futures::{SinkExt, StreamExt};
 
 use ntex::codec::{BytesCodec, Framed};
 use ntex::rt::net::TcpStream;
@@ -280,3 +280,117 @@ fn test_panic_in_worker() {
     sys.stop();
     let _ = h.join();
 }
+
+#[test]
+fn test_max_connections() {
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        let mut sys = ntex::rt::System::new("test");
+        sys.exec(|| {
+            Server::build()
+                .workers(2)
+                .max_connections(2)
+                .disable_signals()
+                .bind("test", addr, move || {
+                    fn_service(|io: TcpStream| async move {
+                        let mut f = Framed::new(io, BytesCodec);
+                        f.send(Bytes::from_static(b"test")).await.unwrap();
+                        // keep connection open until peer disconnects
+                        let _ = f.next().await;
+                        Ok::<_, ()>(())
+                    })
+                })
+                .unwrap()
+                .start()
+        });
+        let _ = tx.send(ntex::rt::System::current());
+        let _ = sys.run();
+    });
+    let sys = rx.recv().unwrap();
+    thread::sleep(time::Duration::from_millis(300));
+
+    let mut buf = [0u8; 4];
+    let mut conn1 = net::TcpStream::connect(addr).unwrap();
+    conn1.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, b"test"[..]);
+    let mut conn2 = net::TcpStream::connect(addr).unwrap();
+    conn2.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, b"test"[..]);
+
+    // excess connection gets closed
+    let mut conn3 = net::TcpStream::connect(addr).unwrap();
+    conn3
+        .set_read_timeout(Some(time::Duration::from_millis(500)))
+        .unwrap();
+    assert_eq!(conn3.read(&mut buf).unwrap_or(0), 0);
+
+    // connection slot is released on close
+    drop(conn1);
+    thread::sleep(time::Duration::from_millis(200));
+    let mut conn4 = net::TcpStream::connect(addr).unwrap();
+    conn4.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, b"test"[..]);
+
+    drop(conn2);
+    sys.stop();
+    let _ = h.join();
+}
+
+#[test]
+fn test_stats_interval() {
+    use ntex::http::HttpService;
+    use ntex::service::map_config;
+    use ntex::web::{self, dev::AppConfig, App, HttpResponse};
+    use std::io::Write;
+
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let h = thread::spawn(move || {
+        let mut sys = ntex::rt::System::new("test");
+        let srv = sys.exec(|| {
+            Server::build()
+                .workers(1)
+                .disable_signals()
+                .stats_interval(ntex::time::Seconds(1))
+                .bind("test", addr, move || {
+                    HttpService::new(map_config(
+                        App::new()
+                            .route("/", web::get().to(|| async { HttpResponse::Ok() })),
+                        |_| AppConfig::default(),
+                    ))
+                    .tcp()
+                })
+                .unwrap()
+                .start()
+        });
+        let _ = tx.send((srv, ntex::rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+    thread::sleep(time::Duration::from_millis(300));
+
+    for path in &["/", "/", "/missing"] {
+        let mut conn = net::TcpStream::connect(addr).unwrap();
+        conn.write_all(
+            format!("GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path).as_bytes(),
+        )
+        .unwrap();
+        let mut buf = Vec::new();
+        conn.read_to_end(&mut buf).unwrap();
+        assert!(buf.starts_with(b"HTTP/1.1"));
+    }
+
+    // totals are updated by stats task
+    thread::sleep(time::Duration::from_millis(1500));
+    let stats = futures::executor::block_on(srv.stats()).unwrap();
+    assert_eq!(stats.requests(), 3);
+    assert_eq!(stats.status_class(2), 2);
+    assert_eq!(stats.status_class(4), 1);
+    assert_eq!(stats.status_class(5), 0);
+
+    sys.stop();
+    let _ = h.join();
+}
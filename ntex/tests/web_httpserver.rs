@@ -61,6 +61,62 @@ async fn test_run() {
     sys.stop();
 }
 
+#[cfg(unix)]
+#[ntex::test]
+async fn test_graceful_stop() {
+    let addr = TestServer::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut sys = ntex::rt::System::new("test");
+
+        let srv = sys.exec(|| {
+            HttpServer::new(|| {
+                App::new()
+                    .service(web::resource("/").to(|| async { HttpResponse::Ok() }))
+                    .service(web::resource("/slow").to(|| async {
+                        ntex::time::sleep(Seconds(30)).await;
+                        HttpResponse::Ok()
+                    }))
+            })
+            .workers(1)
+            .shutdown_timeout(Seconds(1))
+            .stop_runtime()
+            .disable_signals()
+            .bind(format!("{}", addr))
+            .unwrap()
+            .run()
+        });
+
+        let _ = tx.send((srv, ntex::rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, _) = rx.recv().unwrap();
+
+    let client = ntex::http::client::Client::new();
+    let response = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    // long request exceeds shutdown timeout
+    let slow = client
+        .get(format!("http://{}/slow", addr))
+        .timeout(Seconds(60))
+        .send();
+    let slow = ntex::rt::spawn(async move { slow.await.is_err() });
+    ntex::time::sleep(ntex::time::Millis(200)).await;
+
+    let start = std::time::Instant::now();
+    srv.stop(true).await;
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(900), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    assert!(slow.await.unwrap());
+}
+
 #[cfg(feature = "openssl")]
 fn ssl_acceptor() -> std::io::Result<SslAcceptorBuilder> {
     use open_ssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
@@ -82,6 +82,23 @@ impl futures::Stream for TestBody {
     }
 }
 
+/// Send `GET` request over raw connection, read response until it ends with `expected`
+fn request(stream: &mut std::net::TcpStream, path: &str, expected: &[u8]) -> Vec<u8> {
+    stream
+        .write_all(
+            format!("GET {} HTTP/1.1\r\nhost: localhost\r\n\r\n", path).as_bytes(),
+        )
+        .unwrap();
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+    while !data.ends_with(expected) {
+        let n = stream.read(&mut buf).unwrap();
+        assert!(n > 0);
+        data.extend_from_slice(&buf[..n]);
+    }
+    data
+}
+
 #[ntex::test]
 async fn test_body() {
     let srv = test::server(|| {
@@ -351,6 +368,48 @@ async fn test_body_br_streaming() {
     assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
 }
 
+#[ntex::test]
+async fn test_stream_flush_per_item() {
+    let (tx, rx) = futures::channel::mpsc::unbounded::<Result<Bytes, io::Error>>();
+    let rx = std::sync::Arc::new(std::sync::Mutex::new(Some(rx)));
+
+    let srv = test::server_with(test::config().h1(), move || {
+        let rx = rx.clone();
+        App::new().service(web::resource("/").route(web::to(move || {
+            let rx = rx.lock().unwrap().take().unwrap();
+            async move { web::stream(rx) }
+        })))
+    });
+
+    tx.unbounded_send(Ok(Bytes::from_static(b"data: 1\n\n")))
+        .unwrap();
+    let mut response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(TRANSFER_ENCODING).unwrap(),
+        "chunked"
+    );
+
+    // every chunk is received before next one is produced
+    let chunk = ntex::util::next(&mut response).await.unwrap().unwrap();
+    assert_eq!(chunk, Bytes::from_static(b"data: 1\n\n"));
+
+    sleep(Millis(50)).await;
+    tx.unbounded_send(Ok(Bytes::from_static(b"data: 2\n\n")))
+        .unwrap();
+    let chunk = ntex::util::next(&mut response).await.unwrap().unwrap();
+    assert_eq!(chunk, Bytes::from_static(b"data: 2\n\n"));
+
+    sleep(Millis(50)).await;
+    tx.unbounded_send(Ok(Bytes::from_static(b"data: 3\n\n")))
+        .unwrap();
+    let chunk = ntex::util::next(&mut response).await.unwrap().unwrap();
+    assert_eq!(chunk, Bytes::from_static(b"data: 3\n\n"));
+
+    drop(tx);
+    assert!(ntex::util::next(&mut response).await.is_none());
+}
+
 #[ntex::test]
 async fn test_head_binary() {
     let srv =
@@ -1071,6 +1130,121 @@ async fn test_slow_request() {
     assert!(data.starts_with("HTTP/1.1 408 Request Timeout"));
 }
 
+#[ntex::test]
+async fn test_conn_stats() {
+    use std::net;
+
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |stats: web::types::ConnStats| async move {
+                assert!(stats.bytes_read() > 0);
+                HttpResponse::Ok().body(format!("{}", stats.requests()))
+            },
+        )))
+    });
+
+    // requests on keep-alive connection
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    assert!(request(&mut stream, "/", b"\r\n\r\n1").starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(request(&mut stream, "/", b"\r\n\r\n2").starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(request(&mut stream, "/", b"\r\n\r\n3").starts_with(b"HTTP/1.1 200 OK\r\n"));
+
+    // new connection
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    assert!(request(&mut stream, "/", b"\r\n\r\n1").starts_with(b"HTTP/1.1 200 OK\r\n"));
+}
+
+#[ntex::test]
+async fn test_cancellation() {
+    use std::net;
+    use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
+
+    use ntex::util::{select, Either};
+
+    let started = Arc::new(AtomicBool::new(false));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let started2 = started.clone();
+    let cancelled2 = cancelled.clone();
+
+    let srv = test::server(move || {
+        let started = started2.clone();
+        let cancelled = cancelled2.clone();
+        App::new().service(web::resource("/").route(web::to(
+            move |cancel: web::types::Cancellation| {
+                let started = started.clone();
+                let cancelled = cancelled.clone();
+                async move {
+                    started.store(true, Ordering::Relaxed);
+                    match select(sleep(Seconds(10)), cancel).await {
+                        Either::Left(_) => (),
+                        Either::Right(_) => cancelled.store(true, Ordering::Relaxed),
+                    }
+                    HttpResponse::Ok().finish()
+                }
+            },
+        )))
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+        .unwrap();
+    for _ in 0..50 {
+        if started.load(Ordering::Relaxed) {
+            break;
+        }
+        sleep(Millis(20)).await;
+    }
+    assert!(started.load(Ordering::Relaxed));
+    assert!(!cancelled.load(Ordering::Relaxed));
+
+    // client disconnects during handler execution
+    drop(stream);
+    for _ in 0..50 {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        sleep(Millis(20)).await;
+    }
+    assert!(cancelled.load(Ordering::Relaxed));
+}
+
+#[ntex::test]
+async fn test_conn_data() {
+    use std::net;
+
+    #[derive(Clone)]
+    struct Session(String);
+
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/login").route(web::to(
+                |ext: web::types::ConnExtensions| async move {
+                    ext.insert(Session("user".to_string()));
+                    HttpResponse::Ok().body("ok")
+                },
+            )))
+            .service(web::resource("/").route(web::to(
+                |session: Option<web::types::ConnData<Session>>| async move {
+                    HttpResponse::Ok().body(
+                        session
+                            .map(|s| s.0.clone())
+                            .unwrap_or_else(|| "none".to_string()),
+                    )
+                },
+            )))
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    request(&mut stream, "/", b"\r\n\r\nnone");
+    request(&mut stream, "/login", b"\r\n\r\nok");
+    request(&mut stream, "/", b"\r\n\r\nuser");
+
+    // state is not shared with other connections
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    request(&mut stream, "/", b"\r\n\r\nnone");
+}
+
 #[ntex::test]
 async fn test_custom_error() {
     #[derive(Debug, Display)]
@@ -1138,3 +1312,166 @@ async fn test_custom_error() {
     let tp = response.headers().get(CONTENT_TYPE).unwrap();
     assert_eq!("application/json", tp.to_str().unwrap());
 }
+
+#[ntex::test]
+async fn test_with_renderer() {
+    #[derive(Debug, Display)]
+    struct TestError;
+
+    #[derive(Debug, Display)]
+    struct JsonContainer(Box<dyn WebResponseError<JsonRenderer>>);
+
+    impl ntex::web::ErrorContainer for JsonContainer {
+        fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+            self.0.error_response(req)
+        }
+    }
+
+    impl ntex::http::ResponseError for JsonContainer {}
+
+    impl From<TestError> for JsonContainer {
+        fn from(e: TestError) -> JsonContainer {
+            JsonContainer(Box::new(e))
+        }
+    }
+
+    struct JsonRenderer;
+
+    impl ntex::web::error::ErrorRenderer for JsonRenderer {
+        type Container = JsonContainer;
+    }
+
+    impl WebResponseError<JsonRenderer> for TestError {
+        fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+            HttpResponse::BadRequest()
+                .header(CONTENT_TYPE, "application/json")
+                .body("{\"error\":\"test\"}")
+        }
+    }
+
+    impl WebResponseError<web::DefaultError> for TestError {
+        fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+            HttpResponse::BadRequest()
+                .header(CONTENT_TYPE, "text/html")
+                .body("<p>test</p>")
+        }
+    }
+
+    async fn test_err() -> Result<HttpResponse, TestError> {
+        Err(TestError)
+    }
+
+    let srv = test::server_with(test::config().h1(), || {
+        App::new()
+            .service(web::with_renderer(
+                JsonRenderer,
+                web::scope("/api")
+                    .filter(ntex::fn_service(
+                        |req: web::WebRequest<JsonRenderer>| async move {
+                            if req.headers().contains_key("x-fail") {
+                                Err(TestError.into())
+                            } else {
+                                Ok(req)
+                            }
+                        },
+                    ))
+                    .route("/err", web::get().to(test_err)),
+            ))
+            .service(web::scope("/html").route("/err", web::get().to(test_err)))
+    });
+
+    let mut response = srv.get("/api/err").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"{\"error\":\"test\"}"));
+
+    // filter error is rendered with scope's renderer
+    let mut response = srv
+        .get("/api/err")
+        .header("x-fail", "1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"{\"error\":\"test\"}"));
+
+    let mut response = srv.get("/html/err").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), "text/html");
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"<p>test</p>"));
+
+    // unknown path is handled by app's default service
+    let response = srv.get("/api/unknown").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[ntex::test]
+async fn test_web_block() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/block").to(|| async {
+                let res = web::block(|| {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    Ok::<_, io::Error>("blocking")
+                })
+                .await?;
+                Ok::<_, web::Error>(HttpResponse::Ok().body(res))
+            }))
+            .service(web::resource("/").to(|| async { HttpResponse::Ok().body("fast") }))
+    });
+
+    let block = async { srv.get("/block").send().await.unwrap() };
+    let fast = async {
+        sleep(Millis(50)).await;
+        let start = std::time::Instant::now();
+        let response = srv.get("/").send().await.unwrap();
+        (response, start.elapsed())
+    };
+    let (mut block, (mut fast, elapsed)) = futures::join!(block, fast);
+
+    // blocking computation does not stall concurrent requests
+    assert!(elapsed < std::time::Duration::from_millis(300));
+    assert_eq!(fast.body().await.unwrap(), Bytes::from_static(b"fast"));
+    assert!(block.status().is_success());
+    assert_eq!(block.body().await.unwrap(), Bytes::from_static(b"blocking"));
+}
+
+#[ntex::test]
+async fn test_chunked_payload_limit() {
+    let srv = test::server(|| {
+        App::new().service(
+            web::resource("/")
+                .app_data(web::types::PayloadConfig::new(8))
+                .route(
+                    web::post()
+                        .to(|body: Bytes| async move { HttpResponse::Ok().body(body) }),
+                ),
+        )
+    });
+
+    let chunks = futures::stream::iter(vec![
+        Ok::<_, io::Error>(Bytes::from_static(b"1111")),
+        Ok(Bytes::from_static(b"2222")),
+    ]);
+    let mut response = srv.post("/").send_stream(chunks).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.body().await.unwrap(),
+        Bytes::from_static(b"11112222")
+    );
+
+    // chunks together exceed the limit
+    let chunks = futures::stream::iter(vec![
+        Ok::<_, io::Error>(Bytes::from_static(b"1111")),
+        Ok(Bytes::from_static(b"2222")),
+        Ok(Bytes::from_static(b"3333")),
+    ]);
+    let response = srv.post("/").send_stream(chunks).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -1,8 +1,10 @@
 use std::io;
 
 use futures::{SinkExt, StreamExt};
-use ntex::http::StatusCode;
+use ntex::channel::mpsc;
+use ntex::http::{client::Client, header, StatusCode};
 use ntex::service::{fn_factory_with_config, fn_service};
+use ntex::time::{timeout, Millis};
 use ntex::util::{ByteString, Bytes};
 use ntex::web::{self, test, ws, App, HttpRequest};
 
@@ -65,6 +67,50 @@ async fn web_ws() {
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
 
+#[ntex::test]
+async fn web_ws_handshake_errors() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, pl: web::types::Payload| async move {
+                ws::start::<_, _, _, web::Error>(
+                    req,
+                    pl,
+                    fn_factory_with_config(|_| async {
+                        Ok::<_, web::Error>(fn_service(service))
+                    }),
+                )
+                .await
+            },
+        )))
+    });
+
+    // upgrade requests are not kept alive, use new connection for each request
+    let res = Client::new()
+        .post(srv.url("/"))
+        .header(header::UPGRADE, "websocket")
+        .header(header::CONNECTION, "upgrade")
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers().get(header::ALLOW).unwrap(), "GET");
+
+    let res = Client::new()
+        .get(srv.url("/"))
+        .header(header::UPGRADE, "websocket")
+        .header(header::CONNECTION, "upgrade")
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = Client::new().get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
 #[ntex::test]
 async fn web_ws_client() {
     let srv = test::server(|| {
@@ -111,8 +157,284 @@ async fn web_ws_client() {
     let item = rx.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 
-    let item = rx.next().await.unwrap();
-    assert!(item.is_err());
+    // server completes close handshake and drops connection
+    assert!(rx.next().await.is_none());
 
     on_disconnect.await
 }
+
+#[ntex::test]
+async fn web_ws_close_handshake() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, pl: web::types::Payload| async move {
+                ws::start::<_, _, _, web::Error>(
+                    req,
+                    pl,
+                    fn_factory_with_config(|_| async {
+                        Ok::<_, web::Error>(fn_service(|msg| async move {
+                            match msg {
+                                ws::Frame::Text(_) => Ok::<_, io::Error>(Some(
+                                    ws::Message::Close(Some(ws::CloseCode::Away.into())),
+                                )),
+                                _ => Ok(None),
+                            }
+                        }))
+                    }),
+                )
+                .await
+            },
+        )))
+    });
+
+    // server initiates close handshake
+    let mut framed = srv.ws().await.unwrap().into_inner().1;
+    framed
+        .send(ws::Message::Text(ByteString::from_static("close")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Away.into())));
+
+    // server drops connection after response
+    framed
+        .send(ws::Message::Close(Some(ws::CloseCode::Away.into())))
+        .await
+        .unwrap();
+    assert!(framed.next().await.is_none());
+}
+
+#[ntex::test]
+async fn web_ws_close_timeout() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, pl: web::types::Payload| async move {
+                let (tx, rx) = mpsc::channel();
+                ws::start_with_config::<_, _, _, web::Error, _, _>(
+                    req,
+                    pl,
+                    tx,
+                    rx,
+                    ws::WebSocketsConfig::new().close_timeout(Millis(200)),
+                    fn_factory_with_config(|_| async {
+                        Ok::<_, web::Error>(fn_service(|_| async {
+                            Ok::<_, io::Error>(Some(ws::Message::Close(Some(
+                                ws::CloseCode::Normal.into(),
+                            ))))
+                        }))
+                    }),
+                )
+                .await
+            },
+        )))
+    });
+
+    let mut framed = srv.ws().await.unwrap().into_inner().1;
+    framed
+        .send(ws::Message::Text(ByteString::from_static("close")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
+
+    // peer does not respond, server drops connection after timeout
+    let res = timeout(Millis(2_000), framed.next()).await;
+    assert!(matches!(res, Ok(None)));
+}
+
+#[ntex::test]
+async fn web_ws_close_disconnect() {
+    struct OnDrop(std::sync::mpsc::Sender<()>);
+
+    impl Drop for OnDrop {
+        fn drop(&mut self) {
+            let _ = self.0.send(());
+        }
+    }
+
+    let (dropped_tx, dropped) = std::sync::mpsc::channel();
+    let srv = test::server(move || {
+        let dropped_tx = dropped_tx.clone();
+        App::new().service(web::resource("/").route(web::to(
+            move |req: HttpRequest, pl: web::types::Payload| {
+                let guard = std::rc::Rc::new(OnDrop(dropped_tx.clone()));
+                async move {
+                    let (tx, rx) = mpsc::channel();
+                    ws::start_with_config::<_, _, _, web::Error, _, _>(
+                        req,
+                        pl,
+                        tx,
+                        rx,
+                        ws::WebSocketsConfig::new().close_timeout(Millis(10_000)),
+                        fn_factory_with_config(move |_| {
+                            let guard = guard.clone();
+                            async move {
+                                Ok::<_, web::Error>(fn_service(move |_| {
+                                    let _ = &guard;
+                                    async {
+                                        Ok::<_, io::Error>(Some(ws::Message::Close(
+                                            Some(ws::CloseCode::Normal.into()),
+                                        )))
+                                    }
+                                }))
+                            }
+                        }),
+                    )
+                    .await
+                }
+            },
+        )))
+    });
+
+    let mut framed = srv.ws().await.unwrap().into_inner().1;
+    framed
+        .send(ws::Message::Text(ByteString::from_static("close")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
+
+    // peer drops connection, server does not wait for close timeout
+    drop(framed);
+    let res = dropped.recv_timeout(std::time::Duration::from_millis(2_000));
+    assert!(res.is_ok());
+}
+
+#[ntex::test]
+async fn web_ws_deflate() {
+    let srv = test::server(|| {
+        App::new()
+            .service(web::resource("/").route(web::to(
+                |req: HttpRequest, pl: web::types::Payload| async move {
+                    let (tx, rx) = mpsc::channel();
+                    ws::start_with_config::<_, _, _, web::Error, _, _>(
+                        req,
+                        pl,
+                        tx,
+                        rx,
+                        ws::WebSocketsConfig::new().deflate(ws::DeflateConfig::new()),
+                        fn_factory_with_config(|_| async {
+                            Ok::<_, web::Error>(fn_service(service))
+                        }),
+                    )
+                    .await
+                },
+            )))
+            .service(web::resource("/plain").route(web::to(
+                |req: HttpRequest, pl: web::types::Payload| async move {
+                    ws::start::<_, _, _, web::Error>(
+                        req,
+                        pl,
+                        fn_factory_with_config(|_| async {
+                            Ok::<_, web::Error>(fn_service(service))
+                        }),
+                    )
+                    .await
+                },
+            )))
+    });
+
+    // compression is negotiated
+    let conn = Client::new()
+        .ws(srv.url("/"))
+        .deflate(ws::DeflateConfig::new().client_no_context_takeover())
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(
+        conn.response()
+            .headers()
+            .get(header::SEC_WEBSOCKET_EXTENSIONS)
+            .unwrap(),
+        "permessage-deflate; client_no_context_takeover"
+    );
+
+    let mut framed = conn.into_inner().1;
+    let text = "text".repeat(1024);
+    for _ in 0..2 {
+        framed
+            .send(ws::Message::Text(ByteString::from(text.clone())))
+            .await
+            .unwrap();
+        let item = framed.next().await.unwrap().unwrap();
+        assert_eq!(item, ws::Frame::Text(Bytes::from(text.clone())));
+    }
+
+    framed
+        .send(ws::Message::Binary(Bytes::from_static(b"binary")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from_static(b"binary")));
+
+    // fallback to uncompressed messages
+    let conn = Client::new()
+        .ws(srv.url("/plain"))
+        .deflate(ws::DeflateConfig::new())
+        .connect()
+        .await
+        .unwrap();
+    assert!(!conn
+        .response()
+        .headers()
+        .contains_key(header::SEC_WEBSOCKET_EXTENSIONS));
+
+    let mut framed = conn.into_inner().1;
+    framed
+        .send(ws::Message::Text(ByteString::from_static("text")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"text")));
+}
+
+#[ntex::test]
+async fn web_ws_nodelay() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(
+            |req: HttpRequest, pl: web::types::Payload| async move {
+                let opts = req.socket_options().cloned().unwrap();
+                ws::start::<_, _, _, web::Error>(
+                    req,
+                    pl,
+                    fn_factory_with_config(move |_| {
+                        let opts = opts.clone();
+                        async move {
+                            Ok::<_, web::Error>(fn_service(move |msg| {
+                                let res = match msg {
+                                    ws::Frame::Text(text) => {
+                                        opts.set_nodelay(&text[..] == b"on").unwrap();
+                                        opts.nodelay().unwrap().to_string()
+                                    }
+                                    _ => "unexpected".to_string(),
+                                };
+                                async move {
+                                    Ok::<_, io::Error>(Some(ws::Message::Text(
+                                        res.into(),
+                                    )))
+                                }
+                            }))
+                        }
+                    }),
+                )
+                .await
+            },
+        )))
+    });
+
+    // toggle nodelay after upgrade
+    let mut framed = srv.ws().await.unwrap().into_inner().1;
+    framed
+        .send(ws::Message::Text(ByteString::from_static("off")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"false")));
+
+    framed
+        .send(ws::Message::Text(ByteString::from_static("on")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"true")));
+}